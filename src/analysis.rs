@@ -0,0 +1,431 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! Signal analysis, such as peak, RMS and loudness metering.
+
+use crate::{coef2gain_db, Biquad, BiquadCoefs};
+
+/// The default fall rate of the [PeakMeter].
+const PEAK_METER_DECAY_DB_PER_SEC: f32 = 20.0;
+/// The default window length of the [RmsMeter].
+const RMS_METER_WINDOW_MS: f32 = 300.0;
+/// The length of the blocks, that the [LoudnessMeter] sums up.
+const LOUDNESS_BLOCK_MS: f32 = 100.0;
+/// The number of blocks of the momentary loudness (400ms).
+const LOUDNESS_MOMENTARY_BLOCKS: usize = 4;
+/// The number of blocks of the short-term loudness (3s).
+const LOUDNESS_SHORT_TERM_BLOCKS: usize = 30;
+/// The lowest loudness the [LoudnessMeter] reports, for silence.
+const LOUDNESS_MIN_LUFS: f32 = -120.0;
+
+/// A peak level meter with instant attack and a falling display, like the
+/// peak meters of a mixing desk.
+///
+///```
+/// use synfx_dsp::PeakMeter;
+///
+/// let mut meter = PeakMeter::new();
+/// meter.set_sample_rate(1000.0);
+/// meter.set_decay_db_per_sec(20.0);
+///
+/// meter.process(-0.5);
+/// assert_eq!(meter.peak(), 0.5);
+///
+/// // Falls by 20dB within one second:
+/// for _ in 0..1000 {
+///     meter.process(0.0);
+/// }
+/// assert!((meter.peak_db() - (-6.02 - 20.0)).abs() < 0.01);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct PeakMeter {
+    srate: f32,
+    decay_db_per_sec: f32,
+    decay: f32,
+    peak: f32,
+}
+
+impl PeakMeter {
+    /// Creates a peak meter, that falls by 20dB per second.
+    pub fn new() -> Self {
+        let mut this = Self {
+            srate: 44100.0,
+            decay_db_per_sec: PEAK_METER_DECAY_DB_PER_SEC,
+            decay: 1.0,
+            peak: 0.0,
+        };
+        this.update_decay();
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.update_decay();
+    }
+
+    pub fn reset(&mut self) {
+        self.peak = 0.0;
+    }
+
+    /// Sets how fast the displayed peak falls, in decibels per second.
+    pub fn set_decay_db_per_sec(&mut self, decay_db_per_sec: f32) {
+        self.decay_db_per_sec = decay_db_per_sec.max(0.0);
+        self.update_decay();
+    }
+
+    fn update_decay(&mut self) {
+        self.decay = 10.0_f32.powf(-self.decay_db_per_sec / (20.0 * self.srate));
+    }
+
+    /// The current peak level.
+    #[inline]
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// The current peak level in decibels, see also [coef2gain_db].
+    #[inline]
+    pub fn peak_db(&self) -> f32 {
+        coef2gain_db(self.peak)
+    }
+
+    /// Measures the next sample and returns the peak level.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.peak = (self.peak * self.decay).max(input.abs());
+        self.peak
+    }
+
+    /// Measures a block of samples.
+    pub fn process_block(&mut self, block: &[f32]) {
+        for s in block {
+            self.process(*s);
+        }
+    }
+}
+
+impl Default for PeakMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An RMS level meter, that averages over a sliding rectangular window.
+///
+///```
+/// use synfx_dsp::RmsMeter;
+///
+/// let mut meter = RmsMeter::new();
+/// meter.set_sample_rate(44100.0);
+/// meter.set_window_ms(100.0);
+///
+/// for i in 0..44100 {
+///     meter.process((i as f32 * std::f32::consts::TAU * 441.0 / 44100.0).sin() * 0.5);
+/// }
+/// assert!((meter.rms() - 0.3536).abs() < 0.001);
+///
+/// // The window forgets the signal completely after its length:
+/// for _ in 0..4410 {
+///     meter.process(0.0);
+/// }
+/// assert!(meter.rms() < 0.0001);
+///```
+#[derive(Debug, Clone)]
+pub struct RmsMeter {
+    srate: f32,
+    window_ms: f32,
+    /// The squares of the samples within the window.
+    squares: Vec<f32>,
+    pos: usize,
+    sum: f64,
+}
+
+impl RmsMeter {
+    /// Creates an RMS meter with a 300ms window.
+    pub fn new() -> Self {
+        let mut this = Self {
+            srate: 44100.0,
+            window_ms: RMS_METER_WINDOW_MS,
+            squares: vec![],
+            pos: 0,
+            sum: 0.0,
+        };
+        this.update_window();
+        this
+    }
+
+    /// **Attention:** This allocates the window, don't call it from the audio thread.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.update_window();
+    }
+
+    pub fn reset(&mut self) {
+        self.squares.fill(0.0);
+        self.pos = 0;
+        self.sum = 0.0;
+    }
+
+    /// Sets the length of the averaging window in milliseconds.
+    ///
+    /// **Attention:** This allocates the window, don't call it from the audio thread.
+    pub fn set_window_ms(&mut self, window_ms: f32) {
+        self.window_ms = window_ms;
+        self.update_window();
+    }
+
+    fn update_window(&mut self) {
+        let len = ((self.window_ms * self.srate / 1000.0).round() as usize).max(1);
+        self.squares = vec![0.0; len];
+        self.reset();
+    }
+
+    /// The current RMS level.
+    #[inline]
+    pub fn rms(&self) -> f32 {
+        (self.sum.max(0.0) / self.squares.len() as f64).sqrt() as f32
+    }
+
+    /// The current RMS level in decibels, see also [coef2gain_db].
+    #[inline]
+    pub fn rms_db(&self) -> f32 {
+        coef2gain_db(self.rms())
+    }
+
+    /// Measures the next sample and returns the RMS level.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let sq = input * input;
+        self.sum += sq as f64 - self.squares[self.pos] as f64;
+        self.squares[self.pos] = sq;
+        self.pos = (self.pos + 1) % self.squares.len();
+        self.rms()
+    }
+
+    /// Measures a block of samples.
+    pub fn process_block(&mut self, block: &[f32]) {
+        for s in block {
+            self.process(*s);
+        }
+    }
+}
+
+impl Default for RmsMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The K-weighting filter design for arbitrary sample rates follows libebur128
+// Copyright (c) 2011 Jan Kokemüller
+// Under MIT License
+/// Returns the two K-weighting filters of ITU-R BS.1770, the high shelf, that models
+/// the acoustic effect of the head, and the high pass (RLB weighting).
+fn k_weighting_coefs(srate: f32) -> (BiquadCoefs, BiquadCoefs) {
+    let srate = srate as f64;
+
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (std::f64::consts::PI * f0 / srate).tan();
+    let vh = 10.0_f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = BiquadCoefs::new(
+        ((vh + vb * k / q + k * k) / a0) as f32,
+        (2.0 * (k * k - vh) / a0) as f32,
+        ((vh - vb * k / q + k * k) / a0) as f32,
+        (2.0 * (k * k - 1.0) / a0) as f32,
+        ((1.0 - k / q + k * k) / a0) as f32,
+    );
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (std::f64::consts::PI * f0 / srate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let highpass = BiquadCoefs::new(
+        1.0,
+        -2.0,
+        1.0,
+        (2.0 * (k * k - 1.0) / a0) as f32,
+        ((1.0 - k / q + k * k) / a0) as f32,
+    );
+
+    (shelf, highpass)
+}
+
+/// A loudness meter after EBU R128 (ITU-R BS.1770), which measures the momentary (400ms)
+/// and the short-term (3s) loudness in LUFS.
+///
+/// The channels are K-weighted with two [Biquad] filters, squared and summed.
+/// The sum is averaged in blocks of 100ms, so the loudness values are updated every
+/// 100ms. Before the windows are filled, the meter measures as if the input was preceded
+/// by silence. A mono signal is measured as a single channel, so it reads 3dB lower than
+/// the same signal on both channels of a stereo signal.
+///
+///```
+/// use synfx_dsp::{LoudnessMeter, gain_db2coef};
+///
+/// let mut meter = LoudnessMeter::new();
+/// meter.set_sample_rate(48000.0);
+///
+/// // A 1kHz sine at -23dBFS on both channels reads -23 LUFS:
+/// let amp = gain_db2coef(-23.0);
+/// for i in 0..(3 * 48000) {
+///     let s = (i as f32 * std::f32::consts::TAU * 1000.0 / 48000.0).sin() * amp;
+///     meter.process(s, s);
+/// }
+/// assert!((meter.momentary_lufs() + 23.0).abs() < 0.1);
+/// assert!((meter.short_term_lufs() + 23.0).abs() < 0.1);
+///
+/// // After one second of silence, only the short-term loudness remembers the sine:
+/// for _ in 0..48000 {
+///     meter.process(0.0, 0.0);
+/// }
+/// assert_eq!(meter.momentary_lufs(), -120.0);
+/// assert!((meter.short_term_lufs() - (-23.0 + 10.0 * (2.0_f32 / 3.0).log10())).abs() < 0.1);
+///```
+#[derive(Debug, Clone)]
+pub struct LoudnessMeter {
+    srate: f32,
+    /// The K-weighting filters, shelf and high pass, of the left and right channel.
+    filters: [[Biquad; 2]; 2],
+    block_len: usize,
+    block_pos: usize,
+    block_sum: f64,
+    /// The mean squares of the last blocks.
+    blocks: [f64; LOUDNESS_SHORT_TERM_BLOCKS],
+    block_idx: usize,
+}
+
+impl LoudnessMeter {
+    pub fn new() -> Self {
+        let mut this = Self {
+            srate: 44100.0,
+            filters: [[Biquad::new(); 2]; 2],
+            block_len: 1,
+            block_pos: 0,
+            block_sum: 0.0,
+            blocks: [0.0; LOUDNESS_SHORT_TERM_BLOCKS],
+            block_idx: 0,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        let (shelf, highpass) = k_weighting_coefs(srate);
+        for ch in self.filters.iter_mut() {
+            ch[0].set_coefs(shelf);
+            ch[1].set_coefs(highpass);
+        }
+        self.block_len = ((LOUDNESS_BLOCK_MS * srate / 1000.0).round() as usize).max(1);
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for ch in self.filters.iter_mut() {
+            ch[0].reset();
+            ch[1].reset();
+        }
+        self.block_pos = 0;
+        self.block_sum = 0.0;
+        self.blocks = [0.0; LOUDNESS_SHORT_TERM_BLOCKS];
+        self.block_idx = 0;
+    }
+
+    #[inline]
+    fn weight(&mut self, ch: usize, input: f32) -> f64 {
+        let [shelf, highpass] = &mut self.filters[ch];
+        highpass.tick(shelf.tick(input)) as f64
+    }
+
+    #[inline]
+    fn add(&mut self, sum_sq: f64) {
+        self.block_sum += sum_sq;
+        self.block_pos += 1;
+        if self.block_pos >= self.block_len {
+            self.blocks[self.block_idx] = self.block_sum / self.block_len as f64;
+            self.block_idx = (self.block_idx + 1) % LOUDNESS_SHORT_TERM_BLOCKS;
+            self.block_pos = 0;
+            self.block_sum = 0.0;
+        }
+    }
+
+    /// Measures the next stereo sample.
+    #[inline]
+    pub fn process(&mut self, left: f32, right: f32) {
+        let l = self.weight(0, left);
+        let r = self.weight(1, right);
+        self.add(l * l + r * r);
+    }
+
+    /// Measures the next sample of a mono signal.
+    #[inline]
+    pub fn process_mono(&mut self, input: f32) {
+        let s = self.weight(0, input);
+        self.add(s * s);
+    }
+
+    /// Returns the loudness of the average of the last `count` blocks.
+    fn loudness(&self, count: usize) -> f32 {
+        let sum: f64 = (1..=count)
+            .map(|i| {
+                self.blocks
+                    [(self.block_idx + LOUDNESS_SHORT_TERM_BLOCKS - i) % LOUDNESS_SHORT_TERM_BLOCKS]
+            })
+            .sum();
+        let mean = sum / count as f64;
+        if mean <= 0.0 {
+            LOUDNESS_MIN_LUFS
+        } else {
+            ((-0.691 + 10.0 * mean.log10()) as f32).max(LOUDNESS_MIN_LUFS)
+        }
+    }
+
+    /// The loudness of the last 400ms in LUFS.
+    pub fn momentary_lufs(&self) -> f32 {
+        self.loudness(LOUDNESS_MOMENTARY_BLOCKS)
+    }
+
+    /// The loudness of the last 3 seconds in LUFS.
+    pub fn short_term_lufs(&self) -> f32 {
+        self.loudness(LOUDNESS_SHORT_TERM_BLOCKS)
+    }
+}
+
+impl Default for LoudnessMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_k_weighting_matches_bs1770_at_48k() {
+        // The coefficients given in ITU-R BS.1770 for 48kHz:
+        let (shelf, highpass) = k_weighting_coefs(48000.0);
+        let expected_shelf = [
+            1.53512485958697,
+            -2.69169618940638,
+            1.19839281085285,
+            -1.69065929318241,
+            0.73248077421585,
+        ];
+        let expected_highpass = [1.0, -2.0, 1.0, -1.99004745483398, 0.99007225036621];
+
+        let c = [shelf.b0, shelf.b1, shelf.b2, shelf.a1, shelf.a2];
+        for (c, e) in c.iter().zip(expected_shelf.iter()) {
+            assert!((c - e).abs() < 1e-5, "{} {}", c, e);
+        }
+        let c = [highpass.b0, highpass.b1, highpass.b2, highpass.a1, highpass.a2];
+        for (c, e) in c.iter().zip(expected_highpass.iter()) {
+            assert!((c - e).abs() < 1e-5, "{} {}", c, e);
+        }
+    }
+}
@@ -3,28 +3,41 @@
 // See README.md and COPYING for details.
 
 //! Various approximations and faster implementations of trigonometric functions.
+
+use std::sync::OnceLock;
+
 ///
 /// Note: The [fast_cos] and [fast_sin] functions are only barely faster than
 /// the Rust builtin `sin` and `cos` functions.
 
 /// Logarithmic table size of the table in [fast_cos] / [fast_sin].
-static FAST_COS_TAB_LOG2_SIZE: usize = 9;
+const FAST_COS_TAB_LOG2_SIZE: usize = 9;
 /// Table size of the table in [fast_cos] / [fast_sin].
-static FAST_COS_TAB_SIZE: usize = 1 << FAST_COS_TAB_LOG2_SIZE; // =512
-/// The wave table of [fast_cos] / [fast_sin].
-static mut FAST_COS_TAB: [f32; 513] = [0.0; 513];
+const FAST_COS_TAB_SIZE: usize = 1 << FAST_COS_TAB_LOG2_SIZE; // =512
+/// The wave table of [fast_cos] / [fast_sin], it is generated on first use.
+static FAST_COS_TAB: OnceLock<[f32; FAST_COS_TAB_SIZE + 1]> = OnceLock::new();
+
+/// Returns the cosine wave table, generating it if this is the first call.
+#[inline]
+fn cos_tab() -> &'static [f32; FAST_COS_TAB_SIZE + 1] {
+    FAST_COS_TAB.get_or_init(|| {
+        let mut tab = [0.0; FAST_COS_TAB_SIZE + 1];
+        for (i, v) in tab.iter_mut().enumerate() {
+            let phase: f32 = (i as f32) * ((std::f32::consts::TAU) / (FAST_COS_TAB_SIZE as f32));
+            *v = phase.cos();
+        }
+        tab
+    })
+}
 
 /// Initializes the cosine wave table for [fast_cos] and [fast_sin].
+///
+/// Calling this is optional, the table is also generated on the first call of
+/// [fast_cos]. Calling it once on process initialization keeps
+/// the table generation out of your audio thread. It is safe to call this
+/// multiple times and from multiple threads.
 pub fn init_cos_tab() {
-    for i in 0..(FAST_COS_TAB_SIZE + 1) {
-        let phase: f32 = (i as f32) * ((std::f32::consts::TAU) / (FAST_COS_TAB_SIZE as f32));
-        unsafe {
-            // XXX: note: mutable statics can be mutated by multiple
-            //      threads: aliasing violations or data races
-            //      will cause undefined behavior
-            FAST_COS_TAB[i] = phase.cos();
-        }
-    }
+    cos_tab();
 }
 
 /// Internal phase increment/scaling for [fast_cos].
@@ -33,7 +46,7 @@ const PHASE_SCALE: f32 = 1.0_f32 / (std::f32::consts::TAU);
 /// A faster implementation of cosine. It's not that much faster than
 /// Rust's built in cosine function. But YMMV.
 ///
-/// Don't forget to call [init_cos_tab] before using this!
+/// See [init_cos_tab] for initializing the table ahead of time.
 ///
 ///```
 /// use synfx_dsp::*;
@@ -53,21 +66,17 @@ pub fn fast_cos(mut x: f32) -> f32 {
     let fract = index.fract();
     let index = index.floor() as usize;
 
-    unsafe {
-        // XXX: note: mutable statics can be mutated by multiple
-        //      threads: aliasing violations or data races
-        //      will cause undefined behavior
-        let left = FAST_COS_TAB[index as usize];
-        let right = FAST_COS_TAB[index as usize + 1];
+    let tab = cos_tab();
+    let left = tab[index];
+    let right = tab[index + 1];
 
-        return left + (right - left) * fract;
-    }
+    left + (right - left) * fract
 }
 
 /// A faster implementation of sine. It's not that much faster than
 /// Rust's built in sine function. But YMMV.
 ///
-/// Don't forget to call [init_cos_tab] before using this!
+/// See [init_cos_tab] for initializing the table ahead of time.
 ///
 ///```
 /// use synfx_dsp::*;
@@ -5,7 +5,9 @@
 /*! Implements some atomic data structures useful for DSP.
 */
 
+use crate::RampValue;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
 
 // Implementation from vst-rs
 // https://github.com/RustAudio/vst-rs/blob/master/src/util/atomic_float.rs
@@ -126,3 +128,128 @@ impl From<AtomicFloatPair> for (f32, f32) {
         value.get()
     }
 }
+
+/// The default smoothing time of a [SmoothedParam] in milliseconds.
+pub const SMOOTHED_PARAM_DEFAULT_MS: f32 = 10.0;
+
+/// A parameter, that is set from another thread (eg. the GUI) and smoothed in the audio thread.
+///
+/// The target value is stored in an [AtomicFloat], which is shared with the
+/// [SmoothedParamHandle]s returned by [SmoothedParam::handle]. Whenever the audio thread
+/// sees a new target in [SmoothedParam::next], it ramps linearly to it with a [RampValue]
+/// over the smoothing time, so that parameter changes do not click.
+///
+///```
+/// use synfx_dsp::SmoothedParam;
+///
+/// let mut param = SmoothedParam::new(0.0);
+/// param.set_sample_rate(1000.0);
+/// param.set_smooth_ms(10.0);
+///
+/// let handle = param.handle();
+/// std::thread::spawn(move || handle.set(1.0)).join().unwrap();
+///
+/// // Ramps to the new target within 10 samples:
+/// let out: Vec<f32> = (0..20).map(|_| param.next()).collect();
+/// assert!((out[4] - 0.5).abs() < 0.0001);
+/// assert_eq!(out[19], 1.0);
+///
+/// // Reset jumps directly to the target:
+/// param.set(0.25);
+/// param.reset();
+/// assert_eq!(param.next(), 0.25);
+///```
+#[derive(Debug)]
+pub struct SmoothedParam {
+    target: Arc<AtomicFloat>,
+    ramp: RampValue<f32>,
+    /// The target the ramp is currently moving to.
+    last_target: f32,
+    smooth_ms: f32,
+}
+
+impl SmoothedParam {
+    /// Creates a new parameter, with the initial value `value`.
+    pub fn new(value: f32) -> Self {
+        let mut ramp = RampValue::new();
+        ramp.set_target(value, 0.0);
+
+        Self {
+            target: Arc::new(AtomicFloat::new(value)),
+            ramp,
+            last_target: value,
+            smooth_ms: SMOOTHED_PARAM_DEFAULT_MS,
+        }
+    }
+
+    /// Returns a handle to set the target value from another thread.
+    pub fn handle(&self) -> SmoothedParamHandle {
+        SmoothedParamHandle { target: self.target.clone() }
+    }
+
+    /// Jumps directly to the current target value.
+    pub fn reset(&mut self) {
+        self.last_target = self.target.get();
+        self.ramp.set_target(self.last_target, 0.0);
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.ramp.set_sample_rate(srate);
+    }
+
+    /// Sets the time in milliseconds it takes to ramp to a new target value.
+    /// Changes apply to the next new target value.
+    pub fn set_smooth_ms(&mut self, smooth_ms: f32) {
+        self.smooth_ms = smooth_ms.max(0.0);
+    }
+
+    /// Sets a new target value.
+    #[inline]
+    pub fn set(&self, value: f32) {
+        self.target.set(value);
+    }
+
+    /// Returns the target value.
+    #[inline]
+    pub fn target(&self) -> f32 {
+        self.target.get()
+    }
+
+    /// Returns the current smoothed value.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.ramp.value()
+    }
+
+    /// Returns the next smoothed value, call this once per sample.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let target = self.target.get();
+        if target != self.last_target {
+            self.last_target = target;
+            self.ramp.set_target(target, self.smooth_ms);
+        }
+
+        self.ramp.next()
+    }
+}
+
+/// A handle to set the target value of a [SmoothedParam] from another thread.
+#[derive(Debug, Clone)]
+pub struct SmoothedParamHandle {
+    target: Arc<AtomicFloat>,
+}
+
+impl SmoothedParamHandle {
+    /// Sets a new target value.
+    #[inline]
+    pub fn set(&self, value: f32) {
+        self.target.set(value);
+    }
+
+    /// Returns the target value.
+    #[inline]
+    pub fn get(&self) -> f32 {
+        self.target.get()
+    }
+}
@@ -12,7 +12,8 @@
 
 //! A biquad filter implementation.
 ///
-/// It is unfortunately still missing some coefficient calculations for some types of filters.
+/// Besides the Butterworth filters, [BiquadCoefs] offers the filters from the
+/// Audio EQ Cookbook by Robert Bristow-Johnson.
 use std::f32::consts::*;
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -47,6 +48,29 @@ impl BiquadCoefs {
         BiquadCoefs { a1, a2, b0, b1, b2 }
     }
 
+    /// Returns settings for a Butterworth highpass filter.
+    /// Cutoff is the -3 dB point of the filter in Hz.
+    #[inline]
+    pub fn butter_highpass(sample_rate: f32, cutoff: f32) -> BiquadCoefs {
+        let f = (cutoff * PI / sample_rate).tan();
+        let a0r = 1.0 / (1.0 + SQRT_2 * f + f * f);
+        let a1 = (2.0 * f * f - 2.0) * a0r;
+        let a2 = (1.0 - SQRT_2 * f + f * f) * a0r;
+        let b0 = a0r;
+        let b1 = -2.0 * b0;
+        let b2 = b0;
+        BiquadCoefs { a1, a2, b0, b1, b2 }
+    }
+
+    /// Returns settings for a second order all-pass filter, that has the same phase
+    /// response as the sum of the outputs of a 4th order Linkwitz-Riley crossover
+    /// at `cutoff` Hz. Used to phase align parallel signal paths with such a crossover.
+    #[inline]
+    pub fn butter_allpass(sample_rate: f32, cutoff: f32) -> BiquadCoefs {
+        let lp = Self::butter_lowpass(sample_rate, cutoff);
+        BiquadCoefs { a1: lp.a1, a2: lp.a2, b0: lp.a2, b1: lp.a1, b2: 1.0 }
+    }
+
     /// Returns the Q for cascading a butterworth filter:
     pub fn calc_cascaded_butter_q(order: usize, casc_idx: usize) -> f32 {
         let order = order as f32;
@@ -81,6 +105,163 @@ impl BiquadCoefs {
         BiquadCoefs { a1, a2, b0, b1, b2 }
     }
 
+    // The following filters are taken from the Audio EQ Cookbook by Robert Bristow-Johnson:
+    // https://www.w3.org/TR/audio-eq-cookbook/
+
+    /// Calculates `cos(w0)` and `alpha` of the cookbook formulas.
+    #[inline]
+    fn rbj_params(sample_rate: f32, q: f32, freq: f32) -> (f32, f32) {
+        let w0 = TAU * freq / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * q))
+    }
+
+    /// Normalizes the cookbook coefficients by `a0`.
+    #[inline]
+    fn rbj_normalize(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> BiquadCoefs {
+        let a0r = 1.0 / a0;
+        BiquadCoefs { b0: b0 * a0r, b1: b1 * a0r, b2: b2 * a0r, a1: a1 * a0r, a2: a2 * a0r }
+    }
+
+    /// Returns settings for a highpass filter with a specific q.
+    /// The cutoff frequency is given in Hz.
+    #[inline]
+    pub fn highpass(sample_rate: f32, q: f32, cutoff: f32) -> BiquadCoefs {
+        let (cos, alpha) = Self::rbj_params(sample_rate, q, cutoff);
+        let b0 = (1.0 + cos) * 0.5;
+        Self::rbj_normalize(b0, -(1.0 + cos), b0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// Returns settings for a bandpass filter with constant skirt gain, the
+    /// peak gain is `q`. The center frequency is given in Hz.
+    #[inline]
+    pub fn bandpass_skirt(sample_rate: f32, q: f32, center: f32) -> BiquadCoefs {
+        let (cos, alpha) = Self::rbj_params(sample_rate, q, center);
+        let b0 = q * alpha;
+        Self::rbj_normalize(b0, 0.0, -b0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// Returns settings for a bandpass filter with a constant peak gain of 0dB.
+    /// The center frequency is given in Hz.
+    #[inline]
+    pub fn bandpass_peak(sample_rate: f32, q: f32, center: f32) -> BiquadCoefs {
+        let (cos, alpha) = Self::rbj_params(sample_rate, q, center);
+        Self::rbj_normalize(alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// Returns settings for a notch filter. The center frequency is given in Hz.
+    #[inline]
+    pub fn notch(sample_rate: f32, q: f32, center: f32) -> BiquadCoefs {
+        let (cos, alpha) = Self::rbj_params(sample_rate, q, center);
+        Self::rbj_normalize(1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    /// Returns settings for a second order all-pass filter, whose phase shift
+    /// is 180 degrees at `center` Hz.
+    #[inline]
+    pub fn allpass(sample_rate: f32, q: f32, center: f32) -> BiquadCoefs {
+        let (cos, alpha) = Self::rbj_params(sample_rate, q, center);
+        Self::rbj_normalize(
+            1.0 - alpha,
+            -2.0 * cos,
+            1.0 + alpha,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    /// Returns settings for a peaking EQ filter, which boosts or cuts
+    /// by `gain_db` decibels around the `center` frequency in Hz.
+    ///
+    ///```
+    /// use synfx_dsp::{Biquad, BiquadCoefs};
+    ///
+    /// // Measures the gain of the filter for a sine at `freq` Hz:
+    /// let gain_at = |coefs: BiquadCoefs, freq: f32| {
+    ///     let mut bq = Biquad::new();
+    ///     bq.set_coefs(coefs);
+    ///     let mut peak: f32 = 0.0;
+    ///     for i in 0..44100 {
+    ///         let out = bq.tick((i as f32 * freq * std::f32::consts::TAU / 44100.0).sin());
+    ///         if i > 22050 {
+    ///             peak = peak.max(out.abs());
+    ///         }
+    ///     }
+    ///     peak
+    /// };
+    ///
+    /// let eq = BiquadCoefs::peaking_eq(44100.0, 1.0, 1000.0, 6.0);
+    /// assert!((gain_at(eq, 1000.0) - 1.995).abs() < 0.01);
+    /// assert!((gain_at(eq, 50.0) - 1.0).abs() < 0.02);
+    ///
+    /// let shelf = BiquadCoefs::low_shelf(44100.0, 0.707, 1000.0, -12.0);
+    /// assert!((gain_at(shelf, 50.0) - 0.251).abs() < 0.01);
+    /// assert!((gain_at(shelf, 10000.0) - 1.0).abs() < 0.01);
+    ///
+    /// let shelf = BiquadCoefs::high_shelf(44100.0, 0.707, 1000.0, 12.0);
+    /// assert!((gain_at(shelf, 50.0) - 1.0).abs() < 0.01);
+    /// assert!((gain_at(shelf, 15000.0) - 3.981).abs() < 0.05);
+    ///
+    /// let notch = BiquadCoefs::notch(44100.0, 2.0, 1000.0);
+    /// assert!(gain_at(notch, 1000.0) < 0.01);
+    ///
+    /// let bp = BiquadCoefs::bandpass_peak(44100.0, 2.0, 1000.0);
+    /// assert!((gain_at(bp, 1000.0) - 1.0).abs() < 0.01);
+    ///
+    /// let hp = BiquadCoefs::highpass(44100.0, 0.707, 1000.0);
+    /// assert!(gain_at(hp, 100.0) < 0.02);
+    /// assert!((gain_at(hp, 10000.0) - 1.0).abs() < 0.01);
+    ///```
+    #[inline]
+    pub fn peaking_eq(sample_rate: f32, q: f32, center: f32, gain_db: f32) -> BiquadCoefs {
+        let (cos, alpha) = Self::rbj_params(sample_rate, q, center);
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        Self::rbj_normalize(
+            1.0 + alpha * a,
+            -2.0 * cos,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos,
+            1.0 - alpha / a,
+        )
+    }
+
+    /// Returns settings for a low shelf filter, which boosts or cuts the frequencies
+    /// below `freq` Hz by `gain_db` decibels. A `q` of 0.707 gives the steepest slope
+    /// without overshoot.
+    #[inline]
+    pub fn low_shelf(sample_rate: f32, q: f32, freq: f32, gain_db: f32) -> BiquadCoefs {
+        let (cos, alpha) = Self::rbj_params(sample_rate, q, freq);
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let sa = 2.0 * a.sqrt() * alpha;
+        Self::rbj_normalize(
+            a * ((a + 1.0) - (a - 1.0) * cos + sa),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+            a * ((a + 1.0) - (a - 1.0) * cos - sa),
+            (a + 1.0) + (a - 1.0) * cos + sa,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+            (a + 1.0) + (a - 1.0) * cos - sa,
+        )
+    }
+
+    /// Returns settings for a high shelf filter, which boosts or cuts the frequencies
+    /// above `freq` Hz by `gain_db` decibels. A `q` of 0.707 gives the steepest slope
+    /// without overshoot.
+    #[inline]
+    pub fn high_shelf(sample_rate: f32, q: f32, freq: f32, gain_db: f32) -> BiquadCoefs {
+        let (cos, alpha) = Self::rbj_params(sample_rate, q, freq);
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let sa = 2.0 * a.sqrt() * alpha;
+        Self::rbj_normalize(
+            a * ((a + 1.0) + (a - 1.0) * cos + sa),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - sa),
+            (a + 1.0) - (a - 1.0) * cos + sa,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - sa,
+        )
+    }
+
     /// Returns settings for a constant-gain bandpass resonator.
     /// The center frequency is given in Hz.
     /// Bandwidth is the difference in Hz between -3 dB points of the filter response.
@@ -95,6 +276,23 @@ impl BiquadCoefs {
         BiquadCoefs { a1, a2, b0, b1, b2 }
     }
 
+    /// Returns settings for a first order Thiran all-pass filter with a group delay
+    /// of `delay` samples at low frequencies. `delay` should be in the range 0.5 to 1.5.
+    #[inline]
+    pub fn thiran_allpass1(delay: f32) -> BiquadCoefs {
+        let a1 = (1.0 - delay) / (1.0 + delay);
+        BiquadCoefs { a1, a2: 0.0, b0: a1, b1: 1.0, b2: 0.0 }
+    }
+
+    /// Returns settings for a second order Thiran all-pass filter with a group delay
+    /// of `delay` samples at low frequencies. `delay` should be in the range 1.5 to 2.5.
+    #[inline]
+    pub fn thiran_allpass2(delay: f32) -> BiquadCoefs {
+        let a1 = -2.0 * (delay - 2.0) / (delay + 1.0);
+        let a2 = ((delay - 1.0) * (delay - 2.0)) / ((delay + 1.0) * (delay + 2.0));
+        BiquadCoefs { a1, a2, b0: a2, b1: a1, b2: 1.0 }
+    }
+
     //    /// Frequency response at frequency `omega` expressed as fraction of sampling rate.
     //    pub fn response(&self, omega: f64) -> Complex64 {
     //        let z1 = Complex64::from_polar(1.0, -TAU * omega);
@@ -160,6 +358,43 @@ impl Biquad {
         //   s1 = s2 + b1 * x0 - a1 * y0
         //   s2 = b2 * x0 - a2 * y0
     }
+
+    /// Processes a block of samples. Only `min(input.len(), output.len())`
+    /// samples are processed.
+    ///
+    ///```
+    /// use synfx_dsp::{Biquad, BiquadCoefs};
+    ///
+    /// let mut bq_a = Biquad::new();
+    /// bq_a.set_coefs(BiquadCoefs::butter_lowpass(44100.0, 1000.0));
+    /// let mut bq_b = bq_a;
+    ///
+    /// let input: Vec<f32> = (0..64).map(|i| if i % 8 < 4 { 1.0 } else { -1.0 }).collect();
+    /// let mut output = vec![0.0; 64];
+    /// bq_a.process_block(&input[..], &mut output[..]);
+    ///
+    /// for (i, o) in input.iter().zip(output.iter()) {
+    ///     assert_eq!(bq_b.tick(*i), *o);
+    /// }
+    ///```
+    #[inline]
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        let BiquadCoefs { b0, b1, b2, a1, a2 } = self.coefs;
+        let (mut x1, mut x2, mut y1, mut y2) = (self.x1, self.x2, self.y1, self.y2);
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            let x0 = *i;
+            let y0 = b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+            x2 = x1;
+            x1 = x0;
+            y2 = y1;
+            y1 = y0;
+            *o = y0;
+        }
+        self.x1 = x1;
+        self.x2 = x2;
+        self.y1 = y1;
+        self.y2 = y2;
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -199,3 +434,694 @@ impl ButterLowpass {
         self.biquad.tick(input)
     }
 }
+
+/// The minimum delay in samples of [FractionalDelayAllpass].
+pub const FRAC_DELAY_ALLPASS_MIN: f32 = 0.5;
+/// The maximum delay in samples of [FractionalDelayAllpass].
+pub const FRAC_DELAY_ALLPASS_MAX: f32 = 2.5;
+
+/// A fractional sample delay, based on Thiran all-pass filters.
+///
+/// In contrast to an interpolated delay line (like [crate::DelayBuffer]), the magnitude
+/// spectrum of the signal is left untouched. Only the phase is shifted. This is useful for
+/// time aligning multiple signal paths (eg. multi mic recordings or crossover bands).
+///
+/// The usable delay range is [FRAC_DELAY_ALLPASS_MIN] to [FRAC_DELAY_ALLPASS_MAX] samples.
+/// Delays below 1.5 samples use a first order all-pass, longer delays a second order one.
+/// The delay is exact at low frequencies and deviates towards the Nyquist frequency.
+/// For longer delays combine this with a [crate::DelayBuffer] for the integer part
+/// of the delay.
+///
+///```
+/// use synfx_dsp::FractionalDelayAllpass;
+///
+/// let mut ap = FractionalDelayAllpass::new();
+/// ap.set_delay_samples(1.3);
+///
+/// // An all-pass filter preserves the energy of an impulse:
+/// let mut energy = 0.0;
+/// for i in 0..1000 {
+///     let out = ap.tick(if i == 0 { 1.0 } else { 0.0 });
+///     energy += out * out;
+/// }
+/// assert!((energy - 1.0_f32).abs() < 0.001);
+///```
+#[derive(Debug, Copy, Clone)]
+pub struct FractionalDelayAllpass {
+    biquad: Biquad,
+    delay: f32,
+}
+
+impl FractionalDelayAllpass {
+    /// Creates a new all-pass delay with a delay of 1 sample.
+    pub fn new() -> Self {
+        let mut this = Self { biquad: Biquad::new(), delay: 0.0 };
+        this.set_delay_samples(1.0);
+        this
+    }
+
+    /// Reset the internal state of the filter.
+    pub fn reset(&mut self) {
+        self.biquad.reset();
+    }
+
+    /// Returns the current delay in samples.
+    #[inline]
+    pub fn delay_samples(&self) -> f32 {
+        self.delay
+    }
+
+    /// Sets the delay in samples. The value is clamped to the range
+    /// [FRAC_DELAY_ALLPASS_MIN] to [FRAC_DELAY_ALLPASS_MAX].
+    #[inline]
+    pub fn set_delay_samples(&mut self, delay: f32) {
+        let delay = delay.clamp(FRAC_DELAY_ALLPASS_MIN, FRAC_DELAY_ALLPASS_MAX);
+        if delay == self.delay {
+            return;
+        }
+        self.delay = delay;
+
+        if delay < 1.5 {
+            self.biquad.set_coefs(BiquadCoefs::thiran_allpass1(delay));
+        } else {
+            self.biquad.set_coefs(BiquadCoefs::thiran_allpass2(delay));
+        }
+    }
+
+    /// Processes the next sample.
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> f32 {
+        self.biquad.tick(input)
+    }
+
+    /// Processes a block of samples. Only `min(input.len(), output.len())`
+    /// samples are processed.
+    #[inline]
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        self.biquad.process_block(input, output)
+    }
+}
+
+impl Default for FractionalDelayAllpass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A 4th order Linkwitz-Riley crossover, which splits a signal into a low and a high band.
+///
+/// Both bands are -6 dB at the crossover frequency and are in phase, so that their sum
+/// has a flat magnitude response. The sum is not identical to the input though,
+/// it has the phase response of a second order all-pass (see [BiquadCoefs::butter_allpass]).
+///
+///```
+/// use synfx_dsp::LinkwitzRileyCrossover;
+///
+/// let mut xover = LinkwitzRileyCrossover::new();
+/// xover.set_sample_rate(44100.0);
+/// xover.set_freq(1000.0);
+///
+/// // The summed bands preserve the energy of an impulse:
+/// let mut energy = 0.0;
+/// for i in 0..44100 {
+///     let (lo, hi) = xover.tick(if i == 0 { 1.0 } else { 0.0 });
+///     energy += (lo + hi) * (lo + hi);
+/// }
+/// assert!((energy - 1.0_f32).abs() < 0.001);
+///```
+#[derive(Debug, Copy, Clone)]
+pub struct LinkwitzRileyCrossover {
+    lp: [Biquad; 2],
+    hp: [Biquad; 2],
+    sample_rate: f32,
+    freq: f32,
+}
+
+impl LinkwitzRileyCrossover {
+    pub fn new() -> Self {
+        let mut this = Self {
+            lp: [Biquad::new(); 2],
+            hp: [Biquad::new(); 2],
+            sample_rate: 44100.0,
+            freq: 1000.0,
+        };
+        this.set_freq(1000.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.sample_rate = srate;
+        self.set_freq(self.freq);
+    }
+
+    pub fn reset(&mut self) {
+        for bq in self.lp.iter_mut().chain(self.hp.iter_mut()) {
+            bq.reset();
+        }
+    }
+
+    /// Sets the crossover frequency in Hz.
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq;
+        let lp = BiquadCoefs::butter_lowpass(self.sample_rate, freq);
+        let hp = BiquadCoefs::butter_highpass(self.sample_rate, freq);
+        for bq in self.lp.iter_mut() {
+            bq.set_coefs(lp);
+        }
+        for bq in self.hp.iter_mut() {
+            bq.set_coefs(hp);
+        }
+    }
+
+    /// Returns the crossover frequency in Hz.
+    pub fn freq(&self) -> f32 {
+        self.freq
+    }
+
+    /// Splits the input sample and returns the `(low, high)` bands.
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> (f32, f32) {
+        let lo = self.lp[0].tick(input);
+        let hi = self.hp[0].tick(input);
+        (self.lp[1].tick(lo), self.hp[1].tick(hi))
+    }
+}
+
+impl Default for LinkwitzRileyCrossover {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A 4th order Linkwitz-Riley crossover, see [LinkwitzRileyCrossover].
+pub type CrossoverLR4 = LinkwitzRileyCrossover;
+
+/// A 2nd order Linkwitz-Riley crossover, which splits a signal into a low and a high band.
+/// In comparison to the [CrossoverLR4] the slopes are only 12dB per octave, but the
+/// filters ring less.
+///
+/// Both bands are -6 dB at the crossover frequency. The high band is inverted,
+/// so that the bands are in phase and their sum has a flat magnitude response.
+/// The sum has the phase response of a first order all-pass.
+///
+///```
+/// use synfx_dsp::CrossoverLR2;
+///
+/// let mut xover = CrossoverLR2::new();
+/// xover.set_sample_rate(44100.0);
+/// xover.set_freq(1000.0);
+///
+/// // The summed bands preserve the energy of an impulse:
+/// let mut energy = 0.0;
+/// for i in 0..44100 {
+///     let (lo, hi) = xover.tick(if i == 0 { 1.0 } else { 0.0 });
+///     energy += (lo + hi) * (lo + hi);
+/// }
+/// assert!((energy - 1.0_f32).abs() < 0.001);
+///```
+#[derive(Debug, Copy, Clone)]
+pub struct CrossoverLR2 {
+    lp: Biquad,
+    hp: Biquad,
+    sample_rate: f32,
+    freq: f32,
+}
+
+impl CrossoverLR2 {
+    pub fn new() -> Self {
+        let mut this =
+            Self { lp: Biquad::new(), hp: Biquad::new(), sample_rate: 44100.0, freq: 1000.0 };
+        this.set_freq(1000.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.sample_rate = srate;
+        self.set_freq(self.freq);
+    }
+
+    pub fn reset(&mut self) {
+        self.lp.reset();
+        self.hp.reset();
+    }
+
+    /// Sets the crossover frequency in Hz.
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq;
+        // A Q of 0.5 gives two cascaded first order Butterworth filters:
+        self.lp.set_coefs(BiquadCoefs::lowpass(self.sample_rate, 0.5, freq));
+        self.hp.set_coefs(BiquadCoefs::highpass(self.sample_rate, 0.5, freq));
+    }
+
+    /// Returns the crossover frequency in Hz.
+    pub fn freq(&self) -> f32 {
+        self.freq
+    }
+
+    /// Splits the input sample and returns the `(low, high)` bands.
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> (f32, f32) {
+        (self.lp.tick(input), -self.hp.tick(input))
+    }
+}
+
+impl Default for CrossoverLR2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits a signal into `BANDS` frequency bands, for processing them independently,
+/// like in multiband compressors or distortions. `BANDS` must be at least 1.
+///
+/// The bands are split off one after another with [LinkwitzRileyCrossover]s, starting
+/// from the lowest. Each band is then passed through the all-pass filters of the crossovers
+/// above it, which it did not pass through. This way all bands end up with the same phase
+/// response, and [MultibandSplitter::sum] of the unprocessed bands has a flat magnitude
+/// response for any number of bands.
+///
+///```
+/// use synfx_dsp::MultibandSplitter;
+///
+/// let mut mb = MultibandSplitter::<4>::new();
+/// mb.set_sample_rate(44100.0);
+/// mb.set_crossovers(&[200.0, 1000.0, 5000.0]);
+///
+/// // Measure the summed output amplitude at some frequencies:
+/// for freq in [50.0, 200.0, 600.0, 1000.0, 3000.0, 5000.0, 12000.0] {
+///     mb.reset();
+///     let mut max: f32 = 0.0;
+///     for i in 0..44100 {
+///         let input = (i as f32 * std::f32::consts::TAU * freq / 44100.0).sin();
+///         let bands = mb.split(input);
+///         let out = mb.sum(&bands);
+///         if i > 22050 {
+///             max = max.max(out.abs());
+///         }
+///     }
+///     assert!((max - 1.0).abs() < 0.01, "freq={} max={}", freq, max);
+/// }
+///```
+#[derive(Debug, Copy, Clone)]
+pub struct MultibandSplitter<const BANDS: usize> {
+    /// Crossover `i` splits band `i` from the bands above it, the last one is unused.
+    xovers: [LinkwitzRileyCrossover; BANDS],
+    /// `comp[b][k]` compensates the phase of crossover `k` on band `b` (for `b < k`).
+    comp: [[Biquad; BANDS]; BANDS],
+}
+
+impl<const BANDS: usize> MultibandSplitter<BANDS> {
+    /// Creates a splitter with crossovers evenly spaced in octaves
+    /// between 100 Hz and 10 kHz.
+    pub fn new() -> Self {
+        let mut this = Self {
+            xovers: [LinkwitzRileyCrossover::new(); BANDS],
+            comp: [[Biquad::new(); BANDS]; BANDS],
+        };
+
+        let mut freqs = [0.0; BANDS];
+        let n = BANDS.saturating_sub(1);
+        for (i, freq) in freqs.iter_mut().enumerate().take(n) {
+            let octaves = if n > 1 {
+                (i as f32 / (n - 1) as f32) * 100.0_f32.log2()
+            } else {
+                0.5 * 100.0_f32.log2()
+            };
+            *freq = 100.0 * octaves.exp2();
+        }
+        this.set_crossovers(&freqs[0..n]);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        for xover in self.xovers.iter_mut() {
+            xover.set_sample_rate(srate);
+        }
+        self.update_compensation();
+    }
+
+    pub fn reset(&mut self) {
+        for xover in self.xovers.iter_mut() {
+            xover.reset();
+        }
+        for bq in self.comp.iter_mut().flatten() {
+            bq.reset();
+        }
+    }
+
+    /// Sets the crossover frequencies in Hz, in ascending order. Only the first
+    /// `BANDS - 1` frequencies are used.
+    pub fn set_crossovers(&mut self, freqs: &[f32]) {
+        for (xover, freq) in self.xovers.iter_mut().zip(freqs.iter()).take(BANDS.saturating_sub(1))
+        {
+            xover.set_freq(*freq);
+        }
+        self.update_compensation();
+    }
+
+    fn update_compensation(&mut self) {
+        for k in 0..BANDS.saturating_sub(1) {
+            let xover = &self.xovers[k];
+            let coefs = BiquadCoefs::butter_allpass(xover.sample_rate, xover.freq);
+            for b in 0..k {
+                self.comp[b][k].set_coefs(coefs);
+            }
+        }
+    }
+
+    /// Splits the input sample into the bands, lowest band first.
+    #[inline]
+    pub fn split(&mut self, input: f32) -> [f32; BANDS] {
+        let mut bands = [0.0; BANDS];
+        let mut rest = input;
+
+        for (band, xover) in
+            bands.iter_mut().zip(self.xovers.iter_mut()).take(BANDS.saturating_sub(1))
+        {
+            let (lo, hi) = xover.tick(rest);
+            *band = lo;
+            rest = hi;
+        }
+        if BANDS > 0 {
+            bands[BANDS - 1] = rest;
+        }
+
+        for (b, band) in bands.iter_mut().enumerate() {
+            for k in (b + 1)..BANDS.saturating_sub(1) {
+                *band = self.comp[b][k].tick(*band);
+            }
+        }
+
+        bands
+    }
+
+    /// Sums the (processed) bands back together.
+    #[inline]
+    pub fn sum(&self, bands: &[f32; BANDS]) -> f32 {
+        bands.iter().sum()
+    }
+}
+
+impl<const BANDS: usize> Default for MultibandSplitter<BANDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The settings of one band of a [ParametricEq].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EqBand {
+    /// Disabled bands are bypassed.
+    pub enabled: bool,
+    /// The center frequency of a peaking band or the corner frequency of a shelf in Hz.
+    pub freq: f32,
+    /// The quality factor, higher values make a peaking band narrower. Shelves
+    /// don't overshoot with values up to 0.707.
+    pub q: f32,
+    /// The boost (positive) or cut (negative) in decibels.
+    pub gain_db: f32,
+}
+
+impl EqBand {
+    /// Creates an enabled band.
+    pub fn new(freq: f32, q: f32, gain_db: f32) -> Self {
+        Self { enabled: true, freq, q, gain_db }
+    }
+}
+
+/// The filter type of a band of a [ParametricEq].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum EqBandKind {
+    LowShelf,
+    Peak,
+    HighShelf,
+}
+
+impl EqBandKind {
+    #[inline]
+    fn coefs(&self, srate: f32, band: &EqBand) -> BiquadCoefs {
+        // Keep the frequency away from Nyquist, where the filters become unstable:
+        let freq = band.freq.clamp(1.0, 0.49 * srate);
+        let q = band.q.max(0.01);
+        match self {
+            EqBandKind::LowShelf => BiquadCoefs::low_shelf(srate, q, freq, band.gain_db),
+            EqBandKind::Peak => BiquadCoefs::peaking_eq(srate, q, freq, band.gain_db),
+            EqBandKind::HighShelf => BiquadCoefs::high_shelf(srate, q, freq, band.gain_db),
+        }
+    }
+}
+
+/// The frequency of the low shelf after [ParametricEq::new].
+const EQ_DEFAULT_LOW_SHELF_HZ: f32 = 80.0;
+/// The frequency of the high shelf after [ParametricEq::new].
+const EQ_DEFAULT_HIGH_SHELF_HZ: f32 = 12000.0;
+
+/// A parametric equalizer, built from a chain of [Biquad] filters: A low shelf,
+/// `N` peaking bands and a high shelf. See also [BiquadCoefs::low_shelf],
+/// [BiquadCoefs::peaking_eq] and [BiquadCoefs::high_shelf].
+///
+/// The coefficients of a band are recalculated right away when it is changed.
+/// This does not allocate or lock, so the bands can be changed from the audio thread,
+/// for instance for automation. The biquads are in Direct Form I, which copes well
+/// with changing coefficients.
+///
+///```
+/// use synfx_dsp::{EqBand, ParametricEq};
+///
+/// let mut eq = ParametricEq::<3>::new();
+/// eq.set_sample_rate(44100.0);
+/// eq.set_low_shelf(EqBand::new(100.0, 0.707, -12.0));
+/// eq.set_peak(1, EqBand::new(1000.0, 2.0, 6.0));
+/// eq.set_high_shelf(EqBand::new(10000.0, 0.707, 6.0));
+///
+/// // Measures the output amplitude of a sine at `freq`:
+/// let mut amp_at = |eq: &mut ParametricEq<3>, freq: f32| {
+///     eq.reset();
+///     let mut max: f32 = 0.0;
+///     for i in 0..44100 {
+///         let out = eq.process((i as f32 * std::f32::consts::TAU * freq / 44100.0).sin());
+///         if i > 22050 {
+///             max = max.max(out.abs());
+///         }
+///     }
+///     max
+/// };
+///
+/// assert!((amp_at(&mut eq, 20.0) - 0.251).abs() < 0.01); // -12dB
+/// assert!((amp_at(&mut eq, 1000.0) - 1.995).abs() < 0.05); // +6dB
+/// assert!((amp_at(&mut eq, 18000.0) - 1.995).abs() < 0.05); // +6dB
+///
+/// // Disabled bands are bypassed:
+/// let mut band = *eq.peak(1);
+/// band.enabled = false;
+/// eq.set_peak(1, band);
+/// assert!((amp_at(&mut eq, 1000.0) - 1.0).abs() < 0.02);
+///```
+#[derive(Debug, Copy, Clone)]
+pub struct ParametricEq<const N: usize> {
+    srate: f32,
+    low_shelf: (EqBand, Biquad),
+    peaks: [(EqBand, Biquad); N],
+    high_shelf: (EqBand, Biquad),
+}
+
+impl<const N: usize> ParametricEq<N> {
+    /// Creates a flat equalizer. The shelves are at 80Hz and 12kHz, the peaking bands
+    /// are spread evenly in octaves between them. All bands are enabled with
+    /// a gain of 0dB.
+    pub fn new() -> Self {
+        let mut this = Self {
+            srate: 44100.0,
+            low_shelf: (EqBand::new(EQ_DEFAULT_LOW_SHELF_HZ, 0.707, 0.0), Biquad::new()),
+            peaks: [(EqBand::new(1000.0, 1.0, 0.0), Biquad::new()); N],
+            high_shelf: (EqBand::new(EQ_DEFAULT_HIGH_SHELF_HZ, 0.707, 0.0), Biquad::new()),
+        };
+
+        let octaves = (EQ_DEFAULT_HIGH_SHELF_HZ / EQ_DEFAULT_LOW_SHELF_HZ).log2();
+        for (i, (band, _)) in this.peaks.iter_mut().enumerate() {
+            band.freq =
+                EQ_DEFAULT_LOW_SHELF_HZ * (octaves * (i + 1) as f32 / (N + 1) as f32).exp2();
+        }
+
+        this.recalc();
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.recalc();
+    }
+
+    pub fn reset(&mut self) {
+        self.low_shelf.1.reset();
+        self.high_shelf.1.reset();
+        for (_, bq) in self.peaks.iter_mut() {
+            bq.reset();
+        }
+    }
+
+    /// Returns the settings of the low shelf.
+    pub fn low_shelf(&self) -> &EqBand {
+        &self.low_shelf.0
+    }
+
+    /// Returns the settings of the peaking band at `index`.
+    ///
+    /// Panics if `index` is not below `N`.
+    pub fn peak(&self, index: usize) -> &EqBand {
+        &self.peaks[index].0
+    }
+
+    /// Returns the settings of the high shelf.
+    pub fn high_shelf(&self) -> &EqBand {
+        &self.high_shelf.0
+    }
+
+    /// Sets the low shelf and recalculates its coefficients.
+    #[inline]
+    pub fn set_low_shelf(&mut self, band: EqBand) {
+        Self::update_band(&mut self.low_shelf, band, self.srate, EqBandKind::LowShelf);
+    }
+
+    /// Sets the peaking band at `index` and recalculates its coefficients.
+    ///
+    /// Panics if `index` is not below `N`.
+    #[inline]
+    pub fn set_peak(&mut self, index: usize, band: EqBand) {
+        Self::update_band(&mut self.peaks[index], band, self.srate, EqBandKind::Peak);
+    }
+
+    /// Sets the high shelf and recalculates its coefficients.
+    #[inline]
+    pub fn set_high_shelf(&mut self, band: EqBand) {
+        Self::update_band(&mut self.high_shelf, band, self.srate, EqBandKind::HighShelf);
+    }
+
+    /// Stores `band` and recalculates the coefficients if it changed.
+    #[inline]
+    fn update_band(slot: &mut (EqBand, Biquad), band: EqBand, srate: f32, kind: EqBandKind) {
+        if slot.0 == band {
+            return;
+        }
+        if band.enabled && !slot.0.enabled {
+            // The state of a bypassed filter is stale:
+            slot.1.reset();
+        }
+        slot.0 = band;
+        slot.1.set_coefs(kind.coefs(srate, &band));
+    }
+
+    /// Recalculates the coefficients of all bands.
+    fn recalc(&mut self) {
+        let srate = self.srate;
+        self.low_shelf.1.set_coefs(EqBandKind::LowShelf.coefs(srate, &self.low_shelf.0));
+        for (band, bq) in self.peaks.iter_mut() {
+            bq.set_coefs(EqBandKind::Peak.coefs(srate, band));
+        }
+        self.high_shelf.1.set_coefs(EqBandKind::HighShelf.coefs(srate, &self.high_shelf.0));
+    }
+
+    /// Processes the next sample through all enabled bands.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let mut s = input;
+        if self.low_shelf.0.enabled {
+            s = self.low_shelf.1.tick(s);
+        }
+        for (band, bq) in self.peaks.iter_mut() {
+            if band.enabled {
+                s = bq.tick(s);
+            }
+        }
+        if self.high_shelf.0.enabled {
+            s = self.high_shelf.1.tick(s);
+        }
+        s
+    }
+}
+
+impl<const N: usize> Default for ParametricEq<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A 3 band crossover with 4th order Linkwitz-Riley filters, which splits a signal into
+/// a low, mid and high band. The bands are phase aligned, so that their sum has a flat
+/// magnitude response. This is a [MultibandSplitter] with 3 bands.
+///
+///```
+/// use synfx_dsp::Crossover3Band;
+///
+/// let mut xover = Crossover3Band::new();
+/// xover.set_sample_rate(44100.0);
+/// xover.set_freqs(200.0, 2000.0);
+///
+/// // Measures the amplitude of the bands and their sum for a sine at `freq`:
+/// let mut amps = |freq: f32| {
+///     xover.reset();
+///     let mut max = [0.0_f32; 4];
+///     for i in 0..44100 {
+///         let input = (i as f32 * std::f32::consts::TAU * freq / 44100.0).sin();
+///         let (lo, mid, hi) = xover.tick(input);
+///         if i > 22050 {
+///             for (m, s) in max.iter_mut().zip([lo, mid, hi, lo + mid + hi]) {
+///                 *m = m.max(s.abs());
+///             }
+///         }
+///     }
+///     max
+/// };
+///
+/// let [lo, mid, hi, sum] = amps(50.0);
+/// assert!(lo > 0.99 && mid < 0.1 && hi < 0.01);
+/// assert!((sum - 1.0).abs() < 0.01);
+///
+/// let [lo, mid, hi, sum] = amps(630.0);
+/// assert!(mid > 0.8 && lo < 0.2 && hi < 0.2);
+/// assert!((sum - 1.0).abs() < 0.01);
+///
+/// let [lo, mid, hi, sum] = amps(10000.0);
+/// assert!(hi > 0.99 && lo < 0.01 && mid < 0.1);
+/// assert!((sum - 1.0).abs() < 0.01);
+///```
+#[derive(Debug, Copy, Clone)]
+pub struct Crossover3Band {
+    splitter: MultibandSplitter<3>,
+}
+
+impl Crossover3Band {
+    /// Creates a crossover with the bands split at 200Hz and 2kHz.
+    pub fn new() -> Self {
+        let mut this = Self { splitter: MultibandSplitter::new() };
+        this.set_freqs(200.0, 2000.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.splitter.set_sample_rate(srate);
+    }
+
+    pub fn reset(&mut self) {
+        self.splitter.reset();
+    }
+
+    /// Sets the crossover frequencies in Hz, between the low and mid band and
+    /// between the mid and high band.
+    pub fn set_freqs(&mut self, low_mid: f32, mid_high: f32) {
+        self.splitter.set_crossovers(&[low_mid, mid_high]);
+    }
+
+    /// Splits the input sample and returns the `(low, mid, high)` bands.
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> (f32, f32, f32) {
+        let [lo, mid, hi] = self.splitter.split(input);
+        (lo, mid, hi)
+    }
+}
+
+impl Default for Crossover3Band {
+    fn default() -> Self {
+        Self::new()
+    }
+}
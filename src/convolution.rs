@@ -0,0 +1,281 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! Convolution with long impulse responses, for cabinet simulations and convolution reverbs.
+
+use crate::fft::Fft;
+
+/// The smallest partition size of the [Convolver].
+const CONV_MIN_BLOCK: usize = 16;
+/// The largest partition size of the [Convolver].
+const CONV_MAX_BLOCK: usize = 8192;
+
+/// A zero latency convolution engine for impulse responses up to a few seconds,
+/// for cabinet simulations and convolution reverbs.
+///
+/// The impulse response is split into partitions of the block size. The first
+/// partition is convolved directly in the time domain, so that the output has no latency.
+/// The remaining partitions are convolved with a uniformly partitioned overlap-save FFT
+/// convolution, which is calculated whenever a block of input samples is complete.
+///
+/// Smaller block sizes spread the CPU load more evenly, larger block sizes are cheaper
+/// for long impulse responses, as the cost of the direct convolution grows
+/// with the block size and the cost of the FFT part with the number of partitions.
+///
+///```
+/// use synfx_dsp::Convolver;
+///
+/// let ir: Vec<f32> = (0..1000).map(|i| 0.999_f32.powi(i) * if i % 2 == 0 { 1.0 } else { -0.5 }).collect();
+///
+/// let mut conv = Convolver::new(64);
+/// conv.set_ir(&ir[..]);
+///
+/// // An impulse reproduces the impulse response, without latency:
+/// let mut input = vec![0.0; 1200];
+/// input[0] = 1.0;
+/// let mut output = vec![0.0; 1200];
+/// conv.process_block(&input[..], &mut output[..]);
+///
+/// for (o, i) in output.iter().zip(ir.iter()) {
+///     assert!((o - i).abs() < 0.0001);
+/// }
+/// assert!(output[1000..].iter().all(|s| s.abs() < 0.0001));
+///```
+#[derive(Debug, Clone)]
+pub struct Convolver {
+    block: usize,
+    fft: Fft<f32>,
+    ir_len: usize,
+
+    /// The first partition of the impulse response, for the direct convolution.
+    head: Vec<f32>,
+    /// The input history of the direct convolution, stored twice for contiguous access.
+    head_hist: Vec<f32>,
+    head_pos: usize,
+
+    /// The number of FFT partitions.
+    parts: usize,
+    /// The spectra (bins 0 to block size) of the FFT partitions.
+    parts_re: Vec<f32>,
+    parts_im: Vec<f32>,
+    /// The frequency domain delay line with the spectra of the past input blocks.
+    fdl_re: Vec<f32>,
+    fdl_im: Vec<f32>,
+    fdl_pos: usize,
+
+    /// The previous and the current input block.
+    input: Vec<f32>,
+    /// The output of the FFT partitions for the current block.
+    tail_out: Vec<f32>,
+    pos: usize,
+
+    work_re: Vec<f32>,
+    work_im: Vec<f32>,
+    acc_re: Vec<f32>,
+    acc_im: Vec<f32>,
+}
+
+impl Convolver {
+    /// Creates a new convolver without an impulse response, which outputs silence.
+    ///
+    /// * `block_size` - The partition size, it is rounded up to the next power of two
+    ///   in the range 16 to 8192. Typical sizes are 64 to 512.
+    pub fn new(block_size: usize) -> Self {
+        let block = block_size.clamp(CONV_MIN_BLOCK, CONV_MAX_BLOCK).next_power_of_two();
+        let n = 2 * block;
+
+        Self {
+            block,
+            fft: Fft::new(n),
+            ir_len: 0,
+
+            head: vec![],
+            head_hist: vec![0.0; 2 * block],
+            head_pos: 0,
+
+            parts: 0,
+            parts_re: vec![],
+            parts_im: vec![],
+            fdl_re: vec![],
+            fdl_im: vec![],
+            fdl_pos: 0,
+
+            input: vec![0.0; n],
+            tail_out: vec![0.0; block],
+            pos: 0,
+
+            work_re: vec![0.0; n],
+            work_im: vec![0.0; n],
+            acc_re: vec![0.0; block + 1],
+            acc_im: vec![0.0; block + 1],
+        }
+    }
+
+    /// Returns the partition size.
+    pub fn block_size(&self) -> usize {
+        self.block
+    }
+
+    /// Returns the length of the impulse response.
+    pub fn ir_len(&self) -> usize {
+        self.ir_len
+    }
+
+    /// Sets the impulse response and resets the convolver.
+    ///
+    /// **Attention:** This allocates and transforms the whole impulse response,
+    /// don't call it from the audio thread.
+    pub fn set_ir(&mut self, ir: &[f32]) {
+        let block = self.block;
+        let bins = block + 1;
+
+        self.ir_len = ir.len();
+        self.head = ir[..ir.len().min(block)].to_vec();
+
+        let tail = if ir.len() > block { &ir[block..] } else { &[][..] };
+        self.parts = tail.len().div_ceil(block);
+        self.parts_re = vec![0.0; self.parts * bins];
+        self.parts_im = vec![0.0; self.parts * bins];
+
+        for (p, chunk) in tail.chunks(block).enumerate() {
+            self.work_re.fill(0.0);
+            self.work_im.fill(0.0);
+            self.work_re[..chunk.len()].copy_from_slice(chunk);
+            self.fft.transform(&mut self.work_re, &mut self.work_im, false);
+
+            self.parts_re[p * bins..(p + 1) * bins].copy_from_slice(&self.work_re[..bins]);
+            self.parts_im[p * bins..(p + 1) * bins].copy_from_slice(&self.work_im[..bins]);
+        }
+
+        self.fdl_re = vec![0.0; self.parts * bins];
+        self.fdl_im = vec![0.0; self.parts * bins];
+        self.reset();
+    }
+
+    /// Clears the input history, so that the reverb tail of the previous input stops.
+    pub fn reset(&mut self) {
+        self.head_hist.fill(0.0);
+        self.head_pos = 0;
+        self.fdl_re.fill(0.0);
+        self.fdl_im.fill(0.0);
+        self.fdl_pos = 0;
+        self.input.fill(0.0);
+        self.tail_out.fill(0.0);
+        self.pos = 0;
+    }
+
+    /// Calculates the output of the FFT partitions for the next block,
+    /// after the current input block is complete.
+    fn process_tail(&mut self) {
+        let block = self.block;
+        let bins = block + 1;
+        let n = 2 * block;
+
+        if self.parts == 0 {
+            return;
+        }
+
+        // Transform the previous and the current input block into the delay line:
+        self.work_re.copy_from_slice(&self.input);
+        self.work_im.fill(0.0);
+        self.fft.transform(&mut self.work_re, &mut self.work_im, false);
+        let slot = self.fdl_pos * bins;
+        self.fdl_re[slot..slot + bins].copy_from_slice(&self.work_re[..bins]);
+        self.fdl_im[slot..slot + bins].copy_from_slice(&self.work_im[..bins]);
+
+        // Multiply every partition with the input block it belongs to:
+        self.acc_re.fill(0.0);
+        self.acc_im.fill(0.0);
+        for p in 0..self.parts {
+            let slot = ((self.fdl_pos + self.parts - p) % self.parts) * bins;
+            let x_re = &self.fdl_re[slot..slot + bins];
+            let x_im = &self.fdl_im[slot..slot + bins];
+            let h_re = &self.parts_re[p * bins..(p + 1) * bins];
+            let h_im = &self.parts_im[p * bins..(p + 1) * bins];
+
+            for k in 0..bins {
+                self.acc_re[k] += x_re[k] * h_re[k] - x_im[k] * h_im[k];
+                self.acc_im[k] += x_re[k] * h_im[k] + x_im[k] * h_re[k];
+            }
+        }
+        self.fdl_pos = (self.fdl_pos + 1) % self.parts;
+
+        // The spectrum of a real signal is conjugate symmetric:
+        self.work_re[..bins].copy_from_slice(&self.acc_re);
+        self.work_im[..bins].copy_from_slice(&self.acc_im);
+        for k in bins..n {
+            self.work_re[k] = self.acc_re[n - k];
+            self.work_im[k] = -self.acc_im[n - k];
+        }
+        self.fft.transform(&mut self.work_re, &mut self.work_im, true);
+
+        // Overlap-save, only the second half is free of circular wrap around:
+        let scale = 1.0 / n as f32;
+        for (o, s) in self.tail_out.iter_mut().zip(self.work_re[block..].iter()) {
+            *o = s * scale;
+        }
+    }
+
+    /// Convolves the next input sample and returns the next output sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let block = self.block;
+
+        self.head_hist[self.head_pos] = input;
+        self.head_hist[self.head_pos + block] = input;
+        let newest = self.head_pos + block;
+        let mut out = 0.0;
+        for (k, h) in self.head.iter().enumerate() {
+            out += h * self.head_hist[newest - k];
+        }
+        self.head_pos = (self.head_pos + 1) % block;
+
+        out += self.tail_out[self.pos];
+
+        self.input[block + self.pos] = input;
+        self.pos += 1;
+        if self.pos == block {
+            self.process_tail();
+            self.input.copy_within(block.., 0);
+            self.pos = 0;
+        }
+
+        out
+    }
+
+    /// Processes a block of samples. Only `min(input.len(), output.len())` samples are
+    /// processed. The length does not need to match the block size of the convolver.
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = self.process(*i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_convolver_matches_direct_convolution() {
+        let mut rng = crate::Rng::new();
+        let ir: Vec<f32> = (0..700).map(|_| rng.next() - 0.5).collect();
+        let input: Vec<f32> = (0..3000).map(|_| rng.next() - 0.5).collect();
+
+        let mut conv = Convolver::new(32);
+        conv.set_ir(&ir[..]);
+
+        let mut output = vec![0.0; input.len()];
+        // Process in blocks, that don't line up with the partitions:
+        for (i, o) in input.chunks(45).zip(output.chunks_mut(45)) {
+            conv.process_block(i, o);
+        }
+
+        for (t, o) in output.iter().enumerate() {
+            let direct: f32 =
+                ir.iter().enumerate().take(t + 1).map(|(k, h)| h * input[t - k]).sum();
+            assert!((o - direct).abs() < 0.001, "t={} out={} direct={}", t, o, direct);
+        }
+    }
+}
@@ -72,6 +72,25 @@ const DAT_PLATE_DIFFUSION2: f64 = 0.5;
 const DAT_LFO_EXCURSION_MS: f64 = 16.0 / DAT_SAMPLES_PER_MS;
 const DAT_LFO_EXCURSION_MOD_MAX: f64 = 16.0;
 
+const DAT_FREEZE_FADE_MS: f64 = 50.0;
+
+const DAT_LOW_SHELF_HZ: f64 = 250.0;
+const DAT_HIGH_SHELF_HZ: f64 = 4000.0;
+const DAT_DECAY_MULT_MIN: f64 = 0.25;
+const DAT_DECAY_MULT_MAX: f64 = 4.0;
+
+/// Default smoothing time of the parameters, see [DattorroReverb::set_param_smoothing_ms].
+/// The smoothing is off by default, so that the parameters are used as they are.
+const DAT_PARAM_SMOOTH_MS: f64 = 0.0;
+/// Smoothed parameters closer than this to their target jump to it, which ends the ramp.
+const DAT_PARAM_SMOOTH_SNAP: f64 = 1e-9;
+
+/// The maximum pre-delay time, limited by the size of the pre-delay buffer.
+const DAT_PRE_DELAY_MAX_MS: f64 = 5000.0;
+
+/// The maximum stereo width of the [DattorroReverb] output.
+const DAT_STEREO_WIDTH_MAX: f64 = 2.0;
+
 /// Dattorro plate reverb implementation.
 #[derive(Debug, Clone)]
 pub struct DattorroReverb {
@@ -82,15 +101,19 @@ pub struct DattorroReverb {
 
     lfos: [TriSawLFO<f64>; 4],
 
-    input_hpf: OnePoleHPF<f64>,
-    input_lpf: OnePoleLPF<f64>,
+    /// The input filters, pre-delays and diffusers of the left and right channel.
+    /// Only the left ones are used for the summed input if true stereo is disabled.
+    input_hpf: [OnePoleHPF<f64>; 2],
+    input_lpf: [OnePoleLPF<f64>; 2],
 
-    pre_delay: DelayBuffer<f64>,
-    input_apfs: [(AllPass<f64>, f64, f64); 4],
+    pre_delay: [DelayBuffer<f64>; 2],
+    input_apfs: [[(AllPass<f64>, f64, f64); 4]; 2],
 
     apf1: [(AllPass<f64>, f64, f64); 2],
     hpf: [OnePoleHPF<f64>; 2],
     lpf: [OnePoleLPF<f64>; 2],
+    low_shelf: [OnePoleLPF<f64>; 2],
+    high_shelf: [OnePoleLPF<f64>; 2],
     apf2: [(AllPass<f64>, f64, f64); 2],
     delay1: [(DelayBuffer<f64>, f64); 2],
     delay2: [(DelayBuffer<f64>, f64); 2],
@@ -98,6 +121,26 @@ pub struct DattorroReverb {
     left_sum: f64,
     right_sum: f64,
 
+    true_stereo: bool,
+    stereo_width: f64,
+
+    srate: f64,
+    freeze: bool,
+    freeze_fade_ms: f64,
+    freeze_inc: f64,
+    /// Crossfade between normal operation (0.0) and frozen tank (1.0).
+    freeze_amt: f64,
+
+    low_decay_mult: f64,
+    high_decay_mult: f64,
+
+    /// The smoothed parameters that are used for processing.
+    smoothed: DattorroReverbParamsDefault,
+    param_smooth_ms: f64,
+    param_smooth_coef: f64,
+    /// Whether [DattorroReverb::smoothed] has been initialized since the last reset.
+    smoothed_init: bool,
+
     dbg_count: usize,
 }
 
@@ -140,15 +183,17 @@ impl DattorroReverb {
 
             lfos: [TriSawLFO::new(); 4],
 
-            input_hpf: OnePoleHPF::new(),
-            input_lpf: OnePoleLPF::new(),
+            input_hpf: [OnePoleHPF::new(); 2],
+            input_lpf: [OnePoleLPF::new(); 2],
 
-            pre_delay: DelayBuffer::new(),
+            pre_delay: [DelayBuffer::new(), DelayBuffer::new()],
             input_apfs: Default::default(),
 
             apf1: Default::default(),
             hpf: [OnePoleHPF::new(); 2],
             lpf: [OnePoleLPF::new(); 2],
+            low_shelf: [OnePoleLPF::new(); 2],
+            high_shelf: [OnePoleLPF::new(); 2],
             apf2: Default::default(),
             delay1: Default::default(),
             delay2: Default::default(),
@@ -156,25 +201,49 @@ impl DattorroReverb {
             left_sum: 0.0,
             right_sum: 0.0,
 
+            true_stereo: false,
+            stereo_width: 1.0,
+
+            srate: 44100.0,
+            freeze: false,
+            freeze_fade_ms: DAT_FREEZE_FADE_MS,
+            freeze_inc: 0.0,
+            freeze_amt: 0.0,
+
+            low_decay_mult: 1.0,
+            high_decay_mult: 1.0,
+
+            smoothed: DattorroReverbParamsDefault::new(),
+            param_smooth_ms: DAT_PARAM_SMOOTH_MS,
+            param_smooth_coef: 1.0,
+            smoothed_init: false,
+
             dbg_count: 0,
         };
 
         this.reset();
+        this.set_freeze_fade_ms(DAT_FREEZE_FADE_MS);
+        this.set_param_smoothing_ms(DAT_PARAM_SMOOTH_MS);
 
         this
     }
 
     pub fn reset(&mut self) {
-        self.input_lpf.reset();
-        self.input_hpf.reset();
+        for ch in 0..2 {
+            self.input_lpf[ch].reset();
+            self.input_hpf[ch].reset();
 
-        self.input_lpf.set_freq(22000.0);
-        self.input_hpf.set_freq(0.0);
+            self.input_lpf[ch].set_freq(22000.0);
+            self.input_hpf[ch].set_freq(0.0);
 
-        self.input_apfs[0] = (AllPass::new(), DAT_INPUT_APF_TIMES_MS[0], DAT_INPUT_DIFFUSION1);
-        self.input_apfs[1] = (AllPass::new(), DAT_INPUT_APF_TIMES_MS[1], DAT_INPUT_DIFFUSION1);
-        self.input_apfs[2] = (AllPass::new(), DAT_INPUT_APF_TIMES_MS[2], DAT_INPUT_DIFFUSION2);
-        self.input_apfs[3] = (AllPass::new(), DAT_INPUT_APF_TIMES_MS[3], DAT_INPUT_DIFFUSION2);
+            let apfs = &mut self.input_apfs[ch];
+            apfs[0] = (AllPass::new(), DAT_INPUT_APF_TIMES_MS[0], DAT_INPUT_DIFFUSION1);
+            apfs[1] = (AllPass::new(), DAT_INPUT_APF_TIMES_MS[1], DAT_INPUT_DIFFUSION1);
+            apfs[2] = (AllPass::new(), DAT_INPUT_APF_TIMES_MS[2], DAT_INPUT_DIFFUSION2);
+            apfs[3] = (AllPass::new(), DAT_INPUT_APF_TIMES_MS[3], DAT_INPUT_DIFFUSION2);
+
+            self.pre_delay[ch].reset();
+        }
 
         self.apf1[0] = (AllPass::new(), DAT_LEFT_APF1_TIME_MS, -DAT_PLATE_DIFFUSION1);
         self.apf1[1] = (AllPass::new(), DAT_RIGHT_APF1_TIME_MS, -DAT_PLATE_DIFFUSION1);
@@ -196,6 +265,15 @@ impl DattorroReverb {
         self.hpf[0].set_freq(0.0);
         self.hpf[1].set_freq(0.0);
 
+        for shelf in self.low_shelf.iter_mut() {
+            shelf.reset();
+            shelf.set_freq(DAT_LOW_SHELF_HZ);
+        }
+        for shelf in self.high_shelf.iter_mut() {
+            shelf.reset();
+            shelf.set_freq(DAT_HIGH_SHELF_HZ);
+        }
+
         self.lfos[0].set(DAT_LFO_FREQS_HZ[0], 0.5);
         self.lfos[0].set_phase_offs(0.0);
         self.lfos[0].reset();
@@ -214,18 +292,280 @@ impl DattorroReverb {
         self.out_dc_block[0].reset();
         self.out_dc_block[1].reset();
 
-        self.pre_delay.reset();
-
         self.left_sum = 0.0;
         self.right_sum = 0.0;
 
+        self.freeze = false;
+        self.freeze_amt = 0.0;
+
+        self.smoothed_init = false;
+
         self.set_time_scale(1.0);
     }
 
+    /// Sets the smoothing time of the parameters, to prevent zipper noise when they
+    /// are automated or modulated. The parameters are read from the [DattorroReverbParams]
+    /// with every call to [DattorroReverb::process] and are smoothed with a one-pole filter.
+    /// The first call after [DattorroReverb::reset] takes the parameters as they are.
+    /// The default is 0.0, which disables the smoothing, 20ms is a good value for automation.
+    /// While a parameter ramps, the values derived from it (like the delay times
+    /// for the time scale) are recalculated every sample, until it reaches its target.
+    ///
+    ///```
+    /// use synfx_dsp::{DattorroReverb, DattorroReverbParamsDefault};
+    ///
+    /// // Returns the largest jump between two output samples after doubling the size:
+    /// let max_step = |smooth_ms: f64| {
+    ///     let mut rev = DattorroReverb::new();
+    ///     rev.set_sample_rate(44100.0);
+    ///     rev.set_param_smoothing_ms(smooth_ms);
+    ///
+    ///     let mut params = DattorroReverbParamsDefault::new();
+    ///     params.set_time_scale(0.2);
+    ///
+    ///     let mut last = 0.0;
+    ///     let mut max_step: f64 = 0.0;
+    ///     for i in 0..44100 {
+    ///         if i == 22050 {
+    ///             params.set_time_scale(0.4);
+    ///         }
+    ///         let input = (i as f64 * 200.0 * std::f64::consts::TAU / 44100.0).sin();
+    ///         let (l, _r) = rev.process(&mut params, input, input);
+    ///         if i >= 22050 {
+    ///             max_step = max_step.max((l - last).abs());
+    ///         }
+    ///         last = l;
+    ///     }
+    ///     max_step
+    /// };
+    ///
+    /// assert!(max_step(20.0) < 0.5 * max_step(0.0));
+    ///```
+    pub fn set_param_smoothing_ms(&mut self, ms: f64) {
+        self.param_smooth_ms = ms.max(0.0);
+        let samples = self.param_smooth_ms * self.srate / 1000.0;
+        self.param_smooth_coef = if samples < 1.0 { 1.0 } else { 1.0 - (-1.0 / samples).exp() };
+    }
+
+    /// Moves the smoothed parameters towards the current values of `params`.
+    #[inline]
+    fn smooth_params(&mut self, params: &mut dyn DattorroReverbParams) {
+        let coef = if self.smoothed_init { self.param_smooth_coef } else { 1.0 };
+        self.smoothed_init = true;
+
+        // Without smoothing the parameters are taken exactly, otherwise the ramp
+        // ends once the value is close enough to the target:
+        let smooth = |v: &mut f64, target: f64| {
+            if coef >= 1.0 || (target - *v).abs() < DAT_PARAM_SMOOTH_SNAP {
+                *v = target;
+            } else {
+                *v += (target - *v) * coef;
+            }
+        };
+
+        let s = &mut self.smoothed;
+        smooth(&mut s.pre_delay_time_ms, params.pre_delay_time_ms());
+        smooth(&mut s.time_scale, params.time_scale());
+        smooth(&mut s.input_high_cutoff_hz, params.input_high_cutoff_hz());
+        smooth(&mut s.input_low_cutoff_hz, params.input_low_cutoff_hz());
+        smooth(&mut s.reverb_high_cutoff_hz, params.reverb_high_cutoff_hz());
+        smooth(&mut s.reverb_low_cutoff_hz, params.reverb_low_cutoff_hz());
+        smooth(&mut s.mod_speed, params.mod_speed());
+        smooth(&mut s.mod_depth, params.mod_depth());
+        smooth(&mut s.mod_shape, params.mod_shape());
+        smooth(&mut s.input_diffusion_mix, params.input_diffusion_mix());
+        smooth(&mut s.diffusion, params.diffusion());
+        smooth(&mut s.decay, params.decay());
+    }
+
+    /// Freezes the reverb tail. While frozen, the input to the tank is muted and the tank
+    /// feedback goes to unity with the damping filters bypassed, so the current tail
+    /// sustains indefinitely.
+    ///
+    /// Freezing and unfreezing is crossfaded over the time set with
+    /// [DattorroReverb::set_freeze_fade_ms]. The input mute and the feedback are driven
+    /// by the same ramp, which is applied equally to both channels of the tank, so the stereo
+    /// image of the tail is preserved during the transition.
+    ///
+    ///```
+    /// use synfx_dsp::{DattorroReverb, DattorroReverbParams};
+    ///
+    /// struct Params;
+    /// impl DattorroReverbParams for Params {
+    ///     fn pre_delay_time_ms(&self) -> f64 { 0.0 }
+    ///     fn time_scale(&self) -> f64 { 0.2 }
+    ///     fn input_high_cutoff_hz(&self) -> f64 { 0.0 }
+    ///     fn input_low_cutoff_hz(&self) -> f64 { 22000.0 }
+    ///     fn reverb_high_cutoff_hz(&self) -> f64 { 0.0 }
+    ///     fn reverb_low_cutoff_hz(&self) -> f64 { 5000.0 }
+    ///     fn mod_speed(&self) -> f64 { 0.1 }
+    ///     fn mod_depth(&self) -> f64 { 0.1 }
+    ///     fn mod_shape(&self) -> f64 { 0.5 }
+    ///     fn input_diffusion_mix(&self) -> f64 { 1.0 }
+    ///     fn diffusion(&self) -> f64 { 0.7 }
+    ///     fn decay(&self) -> f64 { 0.3 }
+    /// }
+    ///
+    /// let mut rev = DattorroReverb::new();
+    /// rev.set_sample_rate(44100.0);
+    /// rev.set_freeze_fade_ms(20.0);
+    ///
+    /// let mut params = Params;
+    /// for i in 0..4410 {
+    ///     let input = if i < 441 { 0.5 } else { 0.0 };
+    ///     rev.process(&mut params, input, input);
+    /// }
+    ///
+    /// rev.set_freeze(true);
+    /// let mut energy = 0.0;
+    /// for i in 0..(44100 * 2) {
+    ///     // The input is muted:
+    ///     let (l, r) = rev.process(&mut params, 1.0, 1.0);
+    ///     if i > 44100 {
+    ///         energy += l * l + r * r;
+    ///     }
+    /// }
+    /// // And the tail still sustains after 1 second:
+    /// assert!(energy > 1.0);
+    /// assert!(energy < 100000.0);
+    ///```
+    pub fn set_freeze(&mut self, freeze: bool) {
+        self.freeze = freeze;
+    }
+
+    /// Returns whether the reverb is (fading into being) frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.freeze
+    }
+
+    /// Sets the crossfade time for freezing and unfreezing the reverb,
+    /// see [DattorroReverb::set_freeze]. The default is 50ms.
+    pub fn set_freeze_fade_ms(&mut self, ms: f64) {
+        self.freeze_fade_ms = ms.max(0.0);
+        self.freeze_inc = 1.0 / (self.freeze_fade_ms * self.srate / 1000.0).max(1.0);
+    }
+
+    /// Multiplies the decay time of the low frequencies (below about 250Hz) of the tail,
+    /// relative to the decay set by [DattorroReverbParams::decay].
+    /// Values above 1.0 give a warmer, values below 1.0 a thinner tail.
+    /// The range is 0.25 to 4.0, the default is 1.0.
+    ///
+    /// The multiplier is applied with a shelving filter in the tank feedback.
+    /// Like the damping filters it is bypassed while the reverb is frozen.
+    ///
+    ///```
+    /// use synfx_dsp::{DattorroReverb, DattorroReverbParams, OnePoleLPF};
+    ///
+    /// struct Params;
+    /// impl DattorroReverbParams for Params {
+    ///     fn pre_delay_time_ms(&self) -> f64 { 0.0 }
+    ///     fn time_scale(&self) -> f64 { 0.2 }
+    ///     fn input_high_cutoff_hz(&self) -> f64 { 0.0 }
+    ///     fn input_low_cutoff_hz(&self) -> f64 { 22000.0 }
+    ///     fn reverb_high_cutoff_hz(&self) -> f64 { 0.0 }
+    ///     fn reverb_low_cutoff_hz(&self) -> f64 { 22000.0 }
+    ///     fn mod_speed(&self) -> f64 { 0.1 }
+    ///     fn mod_depth(&self) -> f64 { 0.1 }
+    ///     fn mod_shape(&self) -> f64 { 0.5 }
+    ///     fn input_diffusion_mix(&self) -> f64 { 1.0 }
+    ///     fn diffusion(&self) -> f64 { 0.7 }
+    ///     fn decay(&self) -> f64 { 0.5 }
+    /// }
+    ///
+    /// // Measures the energy of the low end of the tail after 1 second:
+    /// let low_tail = |low_mult: f64| {
+    ///     let mut rev = DattorroReverb::new();
+    ///     rev.set_sample_rate(44100.0);
+    ///     rev.set_low_decay_mult(low_mult);
+    ///     let mut lpf = OnePoleLPF::<f64>::new();
+    ///     lpf.set_sample_rate(44100.0);
+    ///     lpf.set_freq(200.0);
+    ///
+    ///     let mut energy = 0.0;
+    ///     for i in 0..(44100 * 2) {
+    ///         let input = if i < 441 { 0.5 } else { 0.0 };
+    ///         let (l, _r) = rev.process(&mut Params, input, input);
+    ///         let l = lpf.process(l);
+    ///         if i > 44100 {
+    ///             energy += l * l;
+    ///         }
+    ///     }
+    ///     energy
+    /// };
+    ///
+    /// assert!(low_tail(2.0) > 4.0 * low_tail(1.0));
+    /// assert!(low_tail(0.5) < 0.25 * low_tail(1.0));
+    ///```
+    pub fn set_low_decay_mult(&mut self, mult: f64) {
+        self.low_decay_mult = mult.clamp(DAT_DECAY_MULT_MIN, DAT_DECAY_MULT_MAX);
+    }
+
+    /// Multiplies the decay time of the high frequencies (above about 4kHz) of the tail,
+    /// see also [DattorroReverb::set_low_decay_mult].
+    /// The range is 0.25 to 4.0, the default is 1.0.
+    pub fn set_high_decay_mult(&mut self, mult: f64) {
+        self.high_decay_mult = mult.clamp(DAT_DECAY_MULT_MIN, DAT_DECAY_MULT_MAX);
+    }
+
+    /// Enables the true stereo mode. By default the left and right input are summed
+    /// before the input diffusion, which collapses stereo sources to the center.
+    /// In true stereo mode each channel has its own input filters, pre-delay and
+    /// input diffusion, and feeds its own half of the cross coupled tank.
+    /// That way the position of a source is kept in the early part of the tail.
+    ///
+    ///```
+    /// use synfx_dsp::{DattorroReverb, DattorroReverbParamsDefault};
+    ///
+    /// // Returns the energy of the left and right output for a hard left impulse:
+    /// let energy = |true_stereo: bool| {
+    ///     let mut rev = DattorroReverb::new();
+    ///     rev.set_sample_rate(44100.0);
+    ///     rev.set_true_stereo(true_stereo);
+    ///     let mut params = DattorroReverbParamsDefault::new();
+    ///
+    ///     let (mut el, mut er) = (0.0, 0.0);
+    ///     for i in 0..4410 {
+    ///         let input = if i == 0 { 1.0 } else { 0.0 };
+    ///         let (l, r) = rev.process(&mut params, input, 0.0);
+    ///         el += l * l;
+    ///         er += r * r;
+    ///     }
+    ///     (el, er)
+    /// };
+    ///
+    /// let (el, er) = energy(false);
+    /// assert!((el / er - 1.0).abs() < 0.5);
+    /// let (el, er) = energy(true);
+    /// assert!(el > 3.0 * er);
+    ///```
+    pub fn set_true_stereo(&mut self, true_stereo: bool) {
+        self.true_stereo = true_stereo;
+    }
+
+    pub fn is_true_stereo(&self) -> bool {
+        self.true_stereo
+    }
+
+    /// Sets the stereo width of the output, 0.0 is mono, 1.0 is the normal
+    /// width and up to 2.0 widens the output. The default is 1.0.
+    pub fn set_stereo_width(&mut self, width: f64) {
+        self.stereo_width = width.clamp(0.0, DAT_STEREO_WIDTH_MAX);
+    }
+
+    /// Applies the low and high decay multipliers as shelving filters to
+    /// the signal of the tank channel `ch`.
+    #[inline]
+    fn decay_shelves(&mut self, ch: usize, input: f64, low_gain: f64, high_gain: f64) -> f64 {
+        let low = self.low_shelf[ch].process(input);
+        let out = input + (low_gain - 1.0) * low;
+        let high = out - self.high_shelf[ch].process(out);
+        out + (high_gain - 1.0) * high
+    }
+
     #[inline]
     pub fn set_time_scale(&mut self, scale: f64) {
+        let scale = scale.max(0.1);
         if (self.last_scale - scale).abs() > std::f64::EPSILON {
-            let scale = scale.max(0.1);
             self.last_scale = scale;
 
             self.apf1[0].1 = DAT_LEFT_APF1_TIME_MS * scale;
@@ -241,6 +581,10 @@ impl DattorroReverb {
     }
 
     pub fn set_sample_rate(&mut self, srate: f64) {
+        self.srate = srate;
+        self.set_freeze_fade_ms(self.freeze_fade_ms);
+        self.set_param_smoothing_ms(self.param_smooth_ms);
+
         self.inp_dc_block[0].set_sample_rate(srate);
         self.inp_dc_block[1].set_sample_rate(srate);
         self.out_dc_block[0].set_sample_rate(srate);
@@ -251,15 +595,16 @@ impl DattorroReverb {
         self.lfos[2].set_sample_rate(srate);
         self.lfos[3].set_sample_rate(srate);
 
-        self.input_hpf.set_sample_rate(srate);
-        self.input_lpf.set_sample_rate(srate);
+        for ch in 0..2 {
+            self.input_hpf[ch].set_sample_rate(srate);
+            self.input_lpf[ch].set_sample_rate(srate);
 
-        self.pre_delay.set_sample_rate(srate);
+            self.pre_delay[ch].set_sample_rate(srate);
 
-        self.input_apfs[0].0.set_sample_rate(srate);
-        self.input_apfs[1].0.set_sample_rate(srate);
-        self.input_apfs[2].0.set_sample_rate(srate);
-        self.input_apfs[3].0.set_sample_rate(srate);
+            for (apf, _, _) in self.input_apfs[ch].iter_mut() {
+                apf.set_sample_rate(srate);
+            }
+        }
 
         self.apf1[0].0.set_sample_rate(srate);
         self.apf1[1].0.set_sample_rate(srate);
@@ -271,6 +616,10 @@ impl DattorroReverb {
         self.lpf[0].set_sample_rate(srate);
         self.lpf[1].set_sample_rate(srate);
 
+        for shelf in self.low_shelf.iter_mut().chain(self.high_shelf.iter_mut()) {
+            shelf.set_sample_rate(srate);
+        }
+
         self.delay1[0].0.set_sample_rate(srate);
         self.delay1[1].0.set_sample_rate(srate);
         self.delay2[0].0.set_sample_rate(srate);
@@ -306,12 +655,47 @@ impl DattorroReverb {
         (left_apf1_delay_ms, right_apf1_delay_ms, left_apf2_delay_ms, right_apf2_delay_ms)
     }
 
+    /// Runs the input of channel `ch` through the input filters, pre-delay and
+    /// input diffusion, and returns the signal that feeds the tank.
+    #[inline]
+    fn input_feed(
+        &mut self,
+        ch: usize,
+        params: &mut DattorroReverbParamsDefault,
+        input: f64,
+    ) -> f64 {
+        // DC outputs => LPF => HPF
+        self.input_lpf[ch].set_freq(params.input_low_cutoff_hz());
+        self.input_hpf[ch].set_freq(params.input_high_cutoff_hz());
+        let out_lpf = self.input_lpf[ch].process(input);
+        let out_hpf = self.input_hpf[ch].process(out_lpf);
+
+        // HPF => Pre-Delay, the delay is always fed, so that the pre-delay time
+        // can be moved up from 0.0 smoothly.
+        let pre_delay_ms = params.pre_delay_time_ms().clamp(0.0, DAT_PRE_DELAY_MAX_MS);
+        let out_pre_delay = self.pre_delay[ch].next_cubic(pre_delay_ms, out_hpf);
+        let out_pre_delay = if pre_delay_ms < 0.1 { out_hpf } else { out_pre_delay };
+
+        // Pre-Delay => 4 All-Pass filters
+        let mut diffused = out_pre_delay;
+        for (apf, time, g) in &mut self.input_apfs[ch] {
+            diffused = apf.next(*time, *g, diffused);
+        }
+
+        // Mix between diffused and pre-delayed intput for further processing
+        crossfade(out_pre_delay, diffused, params.input_diffusion_mix())
+    }
+
     pub fn process(
         &mut self,
         params: &mut dyn DattorroReverbParams,
         input_l: f64,
         input_r: f64,
     ) -> (f64, f64) {
+        self.smooth_params(params);
+        let mut smoothed = self.smoothed;
+        let params = &mut smoothed;
+
         // Some parameter setup...
         let timescale = 0.1 + (4.0 - 0.1) * params.time_scale();
         self.set_time_scale(timescale);
@@ -344,44 +728,53 @@ impl DattorroReverb {
         let input_r = self.inp_dc_block[0].next(input_r);
         let input_l = self.inp_dc_block[1].next(input_l);
 
-        // Sum of DC outputs => LPF => HPF
-        self.input_lpf.set_freq(params.input_low_cutoff_hz());
-        self.input_hpf.set_freq(params.input_high_cutoff_hz());
-        let out_lpf = self.input_lpf.process(input_r + input_l);
-        let out_hpf = self.input_hpf.process(out_lpf);
-
-        // HPF => Pre-Delay
-        let out_pre_delay = if params.pre_delay_time_ms() < 0.1 {
-            out_hpf
+        // In true stereo mode each channel has its own input path and feeds its own tank half.
+        // The feeds are doubled, so that centered sources have the same level as
+        // in the summed mode.
+        let (feed_l, feed_r) = if self.true_stereo {
+            let feed_l = self.input_feed(0, params, input_l);
+            let feed_r = self.input_feed(1, params, input_r);
+            (2.0 * feed_l, 2.0 * feed_r)
         } else {
-            self.pre_delay.next_cubic(params.pre_delay_time_ms(), out_hpf)
+            let feed = self.input_feed(0, params, input_r + input_l);
+            (feed, feed)
         };
 
-        // Pre-Delay => 4 All-Pass filters
-        let mut diffused = out_pre_delay;
-        for (apf, time, g) in &mut self.input_apfs {
-            diffused = apf.next(*time, *g, diffused);
+        // Ramp towards (un)freezing, the same ramp is used for both channels:
+        if self.freeze {
+            self.freeze_amt = (self.freeze_amt + self.freeze_inc).min(1.0);
+        } else {
+            self.freeze_amt = (self.freeze_amt - self.freeze_inc).max(0.0);
         }
-
-        // Mix between diffused and pre-delayed intput for further processing
-        let tank_feed = crossfade(out_pre_delay, diffused, params.input_diffusion_mix());
+        let freeze_amt = self.freeze_amt;
 
         // First tap for the output
-        self.left_sum += tank_feed;
-        self.right_sum += tank_feed;
+        self.left_sum += feed_l * (1.0 - freeze_amt);
+        self.right_sum += feed_r * (1.0 - freeze_amt);
 
         // Calculate tank decay of the left/right signal channels.
         let decay = 1.0 - params.decay().clamp(0.1, 0.9999);
         let decay = 1.0 - (decay * decay);
 
+        // The decay multipliers scale the decay times of the bands, a band with the
+        // decay multiplied by `m` needs a feedback gain of `decay^(1/m)`. The shelves
+        // are applied once for the two decay gain stages of each tank half.
+        let low_gain = (decay.powf(1.0 / self.low_decay_mult) / decay).powi(2);
+        let high_gain = (decay.powf(1.0 / self.high_decay_mult) / decay).powi(2);
+        let low_gain = crossfade(low_gain, 1.0, freeze_amt);
+        let high_gain = crossfade(high_gain, 1.0, freeze_amt);
+
+        let decay = crossfade(decay, 1.0, freeze_amt);
+
         // Left Sum => APF1 => Delay1 => LPF => HPF => APF2 => Delay2
         // And then send this over to the right sum.
         let left = self.left_sum;
         let left = self.apf1[0].0.next(left_apf1_delay_ms, self.apf1[0].2, left);
         let left_apf_tap = left;
         let left = self.delay1[0].0.next_cubic(self.delay1[0].1, left);
-        let left = self.lpf[0].process(left);
-        let left = self.hpf[0].process(left);
+        let left_filtered = self.hpf[0].process(self.lpf[0].process(left));
+        let left = crossfade(left_filtered, left, freeze_amt);
+        let left = self.decay_shelves(0, left, low_gain, high_gain);
         let left = left * decay;
         let left = self.apf2[0].0.next(left_apf2_delay_ms, self.apf2[0].2, left);
         let left = self.delay2[0].0.next_cubic(self.delay2[0].1, left);
@@ -408,8 +801,9 @@ impl DattorroReverb {
         let right = self.apf1[1].0.next(right_apf1_delay_ms, self.apf1[1].2, right);
         let right_apf_tap = right;
         let right = self.delay1[1].0.next_cubic(self.delay1[1].1, right);
-        let right = self.lpf[1].process(right);
-        let right = self.hpf[1].process(right);
+        let right_filtered = self.hpf[1].process(self.lpf[1].process(right));
+        let right = crossfade(right_filtered, right, freeze_amt);
+        let right = self.decay_shelves(1, right, low_gain, high_gain);
         let right = right * decay;
         let right = self.apf2[1].0.next(right_apf2_delay_ms, self.apf2[1].2, right);
         let right = self.delay2[1].0.next_cubic(self.delay2[1].1, right);
@@ -440,6 +834,207 @@ impl DattorroReverb {
 
         self.dbg_count += 1;
 
-        (left_out * 0.5, right_out * 0.5)
+        let mid = (left_out + right_out) * 0.5;
+        let side = (left_out - right_out) * 0.5 * self.stereo_width;
+
+        ((mid + side) * 0.5, (mid - side) * 0.5)
+    }
+}
+
+/// A plain implementation of [DattorroReverbParams] with setters,
+/// for when the parameters don't come from somewhere else.
+///
+///```
+/// use synfx_dsp::{DattorroReverb, DattorroReverbParams, DattorroReverbParamsDefault};
+///
+/// let mut params = DattorroReverbParamsDefault::new();
+/// params.set_pre_delay_time_ms(20.0);
+/// params.set_decay(0.7);
+/// params.set_diffusion(0.8);
+/// params.set_reverb_low_cutoff_hz(6000.0);
+/// params.set_mod_depth(0.3);
+/// params.set_mod_speed(0.2);
+/// assert_eq!(params.decay(), 0.7);
+///
+/// let mut rev = DattorroReverb::new();
+/// rev.set_sample_rate(44100.0);
+///
+/// let mut energy = 0.0;
+/// for i in 0..44100 {
+///     let input = if i == 0 { 1.0 } else { 0.0 };
+///     let (l, r) = rev.process(&mut params, input, input);
+///     energy += l * l + r * r;
+/// }
+/// assert!(energy > 0.01);
+///```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DattorroReverbParamsDefault {
+    pre_delay_time_ms: f64,
+    time_scale: f64,
+    input_high_cutoff_hz: f64,
+    input_low_cutoff_hz: f64,
+    reverb_high_cutoff_hz: f64,
+    reverb_low_cutoff_hz: f64,
+    mod_speed: f64,
+    mod_depth: f64,
+    mod_shape: f64,
+    input_diffusion_mix: f64,
+    diffusion: f64,
+    decay: f64,
+}
+
+impl DattorroReverbParamsDefault {
+    /// Creates the parameters for a medium sized plate without pre-delay and
+    /// without input filtering.
+    pub fn new() -> Self {
+        Self {
+            pre_delay_time_ms: 0.0,
+            time_scale: 0.5,
+            input_high_cutoff_hz: 0.0,
+            input_low_cutoff_hz: 22000.0,
+            reverb_high_cutoff_hz: 0.0,
+            reverb_low_cutoff_hz: 10000.0,
+            mod_speed: 0.0,
+            mod_depth: 0.2,
+            mod_shape: 0.5,
+            input_diffusion_mix: 1.0,
+            diffusion: 1.0,
+            decay: 0.5,
+        }
+    }
+
+    /// See [DattorroReverbParams::pre_delay_time_ms], range 0.0 to 5000.0.
+    pub fn set_pre_delay_time_ms(&mut self, ms: f64) {
+        self.pre_delay_time_ms = ms.clamp(0.0, DAT_PRE_DELAY_MAX_MS);
+    }
+
+    /// See [DattorroReverbParams::time_scale], range 0.0 to 1.0.
+    pub fn set_time_scale(&mut self, scale: f64) {
+        self.time_scale = scale.clamp(0.0, 1.0);
+    }
+
+    /// See [DattorroReverbParams::input_high_cutoff_hz].
+    pub fn set_input_high_cutoff_hz(&mut self, freq: f64) {
+        self.input_high_cutoff_hz = freq.clamp(0.0, 22000.0);
+    }
+
+    /// See [DattorroReverbParams::input_low_cutoff_hz].
+    pub fn set_input_low_cutoff_hz(&mut self, freq: f64) {
+        self.input_low_cutoff_hz = freq.clamp(0.0, 22000.0);
+    }
+
+    /// See [DattorroReverbParams::reverb_high_cutoff_hz].
+    pub fn set_reverb_high_cutoff_hz(&mut self, freq: f64) {
+        self.reverb_high_cutoff_hz = freq.clamp(0.0, 22000.0);
+    }
+
+    /// See [DattorroReverbParams::reverb_low_cutoff_hz].
+    pub fn set_reverb_low_cutoff_hz(&mut self, freq: f64) {
+        self.reverb_low_cutoff_hz = freq.clamp(0.0, 22000.0);
+    }
+
+    /// See [DattorroReverbParams::mod_speed], range 0.0 to 1.0.
+    pub fn set_mod_speed(&mut self, speed: f64) {
+        self.mod_speed = speed.clamp(0.0, 1.0);
+    }
+
+    /// See [DattorroReverbParams::mod_depth], range 0.0 to 1.0.
+    pub fn set_mod_depth(&mut self, depth: f64) {
+        self.mod_depth = depth.clamp(0.0, 1.0);
+    }
+
+    /// See [DattorroReverbParams::mod_shape], range 0.0 to 1.0.
+    pub fn set_mod_shape(&mut self, shape: f64) {
+        self.mod_shape = shape.clamp(0.0, 1.0);
+    }
+
+    /// See [DattorroReverbParams::input_diffusion_mix], range 0.0 to 1.0.
+    pub fn set_input_diffusion_mix(&mut self, mix: f64) {
+        self.input_diffusion_mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// See [DattorroReverbParams::diffusion], range 0.0 to 1.0.
+    pub fn set_diffusion(&mut self, diffusion: f64) {
+        self.diffusion = diffusion.clamp(0.0, 1.0);
+    }
+
+    /// See [DattorroReverbParams::decay], range 0.0 to 1.0.
+    pub fn set_decay(&mut self, decay: f64) {
+        self.decay = decay.clamp(0.0, 1.0);
+    }
+}
+
+impl Default for DattorroReverbParamsDefault {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DattorroReverbParams for DattorroReverbParamsDefault {
+    fn pre_delay_time_ms(&self) -> f64 {
+        self.pre_delay_time_ms
+    }
+    fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+    fn input_high_cutoff_hz(&self) -> f64 {
+        self.input_high_cutoff_hz
+    }
+    fn input_low_cutoff_hz(&self) -> f64 {
+        self.input_low_cutoff_hz
+    }
+    fn reverb_high_cutoff_hz(&self) -> f64 {
+        self.reverb_high_cutoff_hz
+    }
+    fn reverb_low_cutoff_hz(&self) -> f64 {
+        self.reverb_low_cutoff_hz
+    }
+    fn mod_speed(&self) -> f64 {
+        self.mod_speed
+    }
+    fn mod_depth(&self) -> f64 {
+        self.mod_depth
+    }
+    fn mod_shape(&self) -> f64 {
+        self.mod_shape
+    }
+    fn input_diffusion_mix(&self) -> f64 {
+        self.input_diffusion_mix
+    }
+    fn diffusion(&self) -> f64 {
+        self.diffusion
+    }
+    fn decay(&self) -> f64 {
+        self.decay
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_param_smoothing_is_off_by_default_and_settles() {
+        let mut params = DattorroReverbParamsDefault::new();
+        params.set_time_scale(0.2);
+
+        let mut rev = DattorroReverb::new();
+        for _ in 0..10 {
+            rev.process(&mut params, 0.0, 0.0);
+        }
+        params.set_time_scale(0.4);
+        rev.process(&mut params, 0.0, 0.0);
+        assert_eq!(rev.smoothed.time_scale, 0.4);
+
+        rev.set_param_smoothing_ms(20.0);
+        params.set_time_scale(0.2);
+        rev.process(&mut params, 0.0, 0.0);
+        assert!(rev.smoothed.time_scale > 0.2);
+
+        // The ramp ends, so the delay times are not recalculated forever:
+        for _ in 0..44100 {
+            rev.process(&mut params, 0.0, 0.0);
+        }
+        assert_eq!(rev.smoothed.time_scale, 0.2);
     }
 }
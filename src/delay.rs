@@ -4,7 +4,11 @@
 
 //! Interpolated delay line implementation and all-pass/comb filter implementations based on that.
 
-use crate::cubic_interpolate;
+use crate::{
+    crossfade, pan_constant_power, tanh_levien_f64, tempo_division_ms, OnePoleHPF, OnePoleLPF,
+    TriSawLFO,
+};
+use crate::{cubic_interpolate, SincTable};
 use crate::{f, Flt};
 
 /// Default size of the delay buffer: 5 seconds at 8 times 48kHz
@@ -71,6 +75,15 @@ impl<F: Flt> DelayBuffer<F> {
         res
     }
 
+    /// Combines [DelayBuffer::allpass_interpolate_at] and [DelayBuffer::feed]
+    /// into one convenient function.
+    #[inline]
+    pub fn next_allpass(&mut self, delay_time_ms: F, input: F, state: &mut AllpassTap<F>) -> F {
+        let res = self.allpass_interpolate_at(delay_time_ms, state);
+        self.feed(input);
+        res
+    }
+
     /// Combines [DelayBuffer::nearest_at] and [DelayBuffer::feed]
     /// into one convenient function.
     #[inline]
@@ -98,6 +111,12 @@ impl<F: Flt> DelayBuffer<F> {
         self.linear_interpolate_at(delay_time_ms)
     }
 
+    /// Shorthand for [DelayBuffer::allpass_interpolate_at].
+    #[inline]
+    pub fn tap_ap(&self, delay_time_ms: F, state: &mut AllpassTap<F>) -> F {
+        self.allpass_interpolate_at(delay_time_ms, state)
+    }
+
     /// Fetch a sample from the delay buffer at the given tim with linear interpolation.
     ///
     /// * `delay_time_ms` - Delay time in milliseconds.
@@ -167,6 +186,131 @@ impl<F: Flt> DelayBuffer<F> {
         res
     }
 
+    /// Fetch a sample from the delay buffer at the given time with windowed sinc interpolation.
+    /// See also [DelayBuffer::sinc_interpolate_at_s].
+    ///
+    /// * `delay_time_ms` - Delay time in milliseconds.
+    /// * `table` - The (shared) polyphase sinc table.
+    #[inline]
+    pub fn sinc_interpolate_at(&self, delay_time_ms: F, table: &SincTable) -> F {
+        self.sinc_interpolate_at_s((delay_time_ms * self.srate) / f(1000.0), table)
+    }
+
+    /// Fetch a sample from the delay buffer at the given offset with polyphase windowed
+    /// sinc interpolation. This is the highest quality interpolation, for pitch shifters
+    /// and tuned delays, but also the most expensive one.
+    ///
+    /// The interpolation needs `table.taps() / 2 - 1` samples after the interpolated position,
+    /// so shorter offsets are clamped to that.
+    ///
+    /// * `s_offs` - Sample offset in samples into the past of the [DelayBuffer]
+    ///   from the current write (or the "now") position.
+    /// * `table` - The (shared) polyphase sinc table.
+    ///
+    ///```
+    /// use synfx_dsp::{DelayBuffer, SincTable};
+    ///
+    /// let table = SincTable::new(32, 256);
+    /// let mut buf = DelayBuffer::new_with_size(1024);
+    /// for i in 0..1024 {
+    ///     buf.feed((i as f32 * 0.1).sin());
+    /// }
+    ///
+    /// // The sample 20.5 samples in the past:
+    /// let expected = ((1023.0 - 20.5) * 0.1_f32).sin();
+    /// assert!((buf.sinc_interpolate_at_s(20.5, &table) - expected).abs() < 0.001);
+    ///```
+    #[inline]
+    pub fn sinc_interpolate_at_s(&self, s_offs: F, table: &SincTable) -> F {
+        let half = table.taps() / 2;
+        let s_offs = s_offs.max(f((half - 1) as f64));
+        let offs = s_offs.floor().to_usize().unwrap_or(0);
+        let fract = s_offs.fract().to_f64().unwrap_or(0.0);
+
+        let pos = fract * (table.phases() as f64);
+        let phase = (pos as usize).min(table.phases() - 1);
+        let phase_fract = pos - (phase as f64);
+        let c0 = table.phase(phase);
+        let c1 = table.phase(phase + 1);
+
+        let first = offs + 1 - half;
+        let mut sum = f(0.0);
+        for (j, (a, b)) in c0.iter().zip(c1.iter()).enumerate() {
+            let coef = a + (b - a) * phase_fract;
+            sum = sum + self.at(first + j) * f(coef);
+        }
+        sum
+    }
+
+    /// Fetch a sample from the delay buffer at the given time with first order all-pass
+    /// interpolation. See also [DelayBuffer::allpass_interpolate_at_s].
+    ///
+    /// * `delay_time_ms` - Delay time in milliseconds.
+    /// * `state` - The state of the all-pass filter of this tap.
+    #[inline]
+    pub fn allpass_interpolate_at(&self, delay_time_ms: F, state: &mut AllpassTap<F>) -> F {
+        self.allpass_interpolate_at_s((delay_time_ms * self.srate) / f(1000.0), state)
+    }
+
+    /// Fetch a sample from the delay buffer at the given offset with first order all-pass
+    /// interpolation.
+    ///
+    /// In contrast to linear or cubic interpolation, the magnitude response is flat and
+    /// the high frequencies are not damped, only the phase response deviates towards the
+    /// Nyquist frequency. This makes it the interpolation of choice for tuned feedback
+    /// loops, like Karplus-Strong strings or flangers. As the all-pass filter is recursive,
+    /// every tap needs its own [AllpassTap] state, and it has to be read exactly once per
+    /// sample. Fast modulation of the offset causes transients, so this is less suited
+    /// for chorus or vibrato effects.
+    ///
+    /// Offsets below 0.5 samples are clamped to 0.5.
+    ///
+    /// * `s_offs` - Sample offset in samples into the past of the [DelayBuffer]
+    ///   from the current write (or the "now") position.
+    /// * `state` - The state of the all-pass filter of this tap.
+    ///
+    ///```
+    /// use synfx_dsp::{AllpassTap, DelayBuffer};
+    ///
+    /// let signal = |i: usize, w: f32| (i as f32 * w).sin();
+    /// let rms = |s: &[f32]| (s.iter().map(|s| s * s).sum::<f32>() / s.len() as f32).sqrt();
+    ///
+    /// let mut buf = DelayBuffer::new_with_size(1024);
+    /// let mut tap = AllpassTap::new();
+    /// let mut out = vec![];
+    /// for i in 0..2000 {
+    ///     buf.feed(signal(i, 0.05));
+    ///     out.push(buf.allpass_interpolate_at_s(10.3, &mut tap));
+    /// }
+    /// // The sample 10.3 samples in the past:
+    /// assert!((out[1999] - (1988.7 * 0.05_f32).sin()).abs() < 0.001);
+    ///
+    /// // High frequencies keep their amplitude, in contrast to linear interpolation:
+    /// let mut tap = AllpassTap::new();
+    /// let (mut ap, mut lin) = (vec![], vec![]);
+    /// for i in 0..2000 {
+    ///     buf.feed(signal(i, 2.5));
+    ///     ap.push(buf.allpass_interpolate_at_s(10.5, &mut tap));
+    ///     lin.push(buf.linear_interpolate_at_s(10.5));
+    /// }
+    /// assert!((rms(&ap[1000..]) - 0.707).abs() < 0.01);
+    /// assert!(rms(&lin[1000..]) < 0.3);
+    ///```
+    #[inline]
+    pub fn allpass_interpolate_at_s(&self, s_offs: F, state: &mut AllpassTap<F>) -> F {
+        // The fractional part is kept in the range 0.5 to 1.5, where the
+        // all-pass coefficient stays away from the unstable pole at -1.0:
+        let s_offs = s_offs.max(f(0.5));
+        let offs = (s_offs - f(0.5)).floor();
+        let fract = s_offs - offs;
+        let offs = offs.to_usize().unwrap_or(0);
+
+        let eta = (f::<F>(1.0) - fract) / (f::<F>(1.0) + fract);
+        let out = eta * self.at(offs) + self.at(offs + 1) - eta * state.y1;
+        state.y1 = out;
+        out
+    }
+
     /// Fetch a sample from the delay buffer at the given time without any interpolation.
     ///
     /// * `delay_time_ms` - Delay time in milliseconds.
@@ -191,6 +335,23 @@ impl<F: Flt> DelayBuffer<F> {
     }
 }
 
+/// The state of a first order all-pass interpolated read from a [DelayBuffer],
+/// see [DelayBuffer::allpass_interpolate_at_s]. Every tap needs its own state.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllpassTap<F: Flt> {
+    y1: F,
+}
+
+impl<F: Flt> AllpassTap<F> {
+    pub fn new() -> Self {
+        Self { y1: f(0.0) }
+    }
+
+    pub fn reset(&mut self) {
+        self.y1 = f(0.0);
+    }
+}
+
 /// Default size of the delay buffer: 1 seconds at 8 times 48kHz
 const DEFAULT_ALLPASS_COMB_SAMPLES: usize = 8 * 48000;
 
@@ -251,22 +412,63 @@ impl<F: Flt> AllPass<F> {
     }
 }
 
+/// Cutoff frequency of the DC servo in the feedback path of [Comb].
+const COMB_DC_SERVO_HZ: f32 = 5.0;
+
 #[derive(Debug, Clone)]
 pub struct Comb {
     delay: DelayBuffer<f32>,
+    feedback_safety: bool,
+    dc_servo: OnePoleHPF<f32>,
 }
 
 impl Comb {
     pub fn new() -> Self {
-        Self { delay: DelayBuffer::new_with_size(DEFAULT_ALLPASS_COMB_SAMPLES) }
+        let mut dc_servo = OnePoleHPF::new();
+        dc_servo.set_sample_rate(44100.0);
+        dc_servo.set_freq(COMB_DC_SERVO_HZ);
+
+        Self {
+            delay: DelayBuffer::new_with_size(DEFAULT_ALLPASS_COMB_SAMPLES),
+            feedback_safety: false,
+            dc_servo,
+        }
     }
 
     pub fn set_sample_rate(&mut self, srate: f32) {
         self.delay.set_sample_rate(srate);
+        self.dc_servo.set_sample_rate(srate);
     }
 
     pub fn reset(&mut self) {
         self.delay.reset();
+        self.dc_servo.reset();
+    }
+
+    /// Enables a DC servo (one pole high pass) and soft saturation
+    /// (see [crate::tanh_levien_f64]) on the fed back signal in [Comb::next_feedback].
+    /// This keeps the buffer bounded and free of DC, even with a feedback
+    /// gain near 1.0. Disabled by default.
+    ///
+    ///```
+    /// use synfx_dsp::Comb;
+    ///
+    /// let mut comb = Comb::new();
+    /// comb.set_sample_rate(44100.0);
+    /// comb.set_feedback_safety(true);
+    ///
+    /// // Feed a constant DC offset with (over) unity feedback:
+    /// let mut out = 0.0;
+    /// for _ in 0..44100 {
+    ///     out = comb.next_feedback(10.0, 1.01, 0.5);
+    /// }
+    /// assert!(out.abs() < 2.0);
+    ///```
+    pub fn set_feedback_safety(&mut self, enabled: bool) {
+        if enabled != self.feedback_safety {
+            self.dc_servo.reset();
+        }
+        self.feedback_safety = enabled;
     }
 
     #[inline]
@@ -279,10 +481,21 @@ impl Comb {
         self.delay.tap_n(time_ms)
     }
 
+    /// Scales the delayed signal `s` by the feedback gain `g`, applying the
+    /// feedback safety (see [Comb::set_feedback_safety]) if enabled.
+    #[inline]
+    fn feedback_signal(&mut self, s: f32, g: f32) -> f32 {
+        if self.feedback_safety {
+            tanh_levien_f64(self.dc_servo.process(s * g) as f64) as f32
+        } else {
+            s * g
+        }
+    }
+
     #[inline]
     pub fn next_feedback(&mut self, time: f32, g: f32, v: f32) -> f32 {
         let s = self.delay.cubic_interpolate_at(time);
-        let v = v + s * g;
+        let v = v + self.feedback_signal(s, g);
         self.delay.feed(v);
         v
     }
@@ -292,4 +505,1128 @@ impl Comb {
         let s = self.delay.next_cubic(time, v);
         v + s * g
     }
+
+    /// A comb filter with both a feedforward and a feedback path (IIR + FIR comb),
+    /// which gives a richer peak/notch structure for flangers and resonators:
+    ///
+    /// ```text
+    /// delayed = delay_line(time_ms)
+    /// y       = ff_gain * x + delayed
+    /// feed      x + fb_gain * delayed  into the delay line
+    /// ```
+    ///
+    /// With `ff_gain = -fb_gain` this is an all-pass filter.
+    /// The filter is only stable for `fb_gain` in the range -1.0 to 1.0 (exclusive), the closer
+    /// to 1.0 the longer the resonance rings. Enable [Comb::set_feedback_safety] if you need
+    /// `fb_gain` close to 1.0.
+    ///
+    /// * `time_ms` - Delay time in milliseconds.
+    /// * `ff_gain` - The gain of the direct (feedforward) signal in the output.
+    /// * `fb_gain` - The feedback gain.
+    /// * `v` - The new input sample to feed the filter.
+    ///
+    ///```
+    /// use synfx_dsp::Comb;
+    ///
+    /// let mut comb = Comb::new();
+    /// comb.set_sample_rate(1000.0);
+    ///
+    /// let out: Vec<f32> =
+    ///     (0..10).map(|i| comb.next_combined(2.0, 0.5, 0.5, if i == 0 { 1.0 } else { 0.0 })).collect();
+    /// assert_eq!(out, vec![0.5, 0.0, 0.0, 1.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.25]);
+    ///```
+    #[inline]
+    pub fn next_combined(&mut self, time_ms: f32, ff_gain: f32, fb_gain: f32, v: f32) -> f32 {
+        let delayed = self.delay.cubic_interpolate_at(time_ms);
+        let fb = self.feedback_signal(delayed, fb_gain);
+        self.delay.feed(v + fb);
+        ff_gain * v + delayed
+    }
+}
+
+/// A bidirectional waveguide built from two [DelayBuffer] rails, for physical modeling
+/// of tubes and strings (eg. bowed or blown instruments).
+///
+/// The right going rail runs from the excitation junction to the far termination,
+/// the left going rail runs back. At each termination the wave is inverted and passed through
+/// a one pole low pass filter, which models the frequency dependent losses
+/// of the reflection. The excitation signal is injected at the scattering junction
+/// at the near termination into the right going rail. The output is the wave arriving
+/// back at that junction.
+///
+///```
+/// use synfx_dsp::Waveguide;
+///
+/// let mut wg = Waveguide::new();
+/// wg.set_sample_rate(44100.0);
+/// wg.set_length_hz(220.0);
+/// wg.set_reflection(0.3);
+///
+/// // Pluck it once:
+/// wg.excite(1.0);
+///
+/// let mut peak: f32 = 0.0;
+/// for _ in 0..4410 {
+///     peak = peak.max(wg.next().abs());
+/// }
+/// assert!(peak > 0.1);
+///```
+#[derive(Debug, Clone)]
+pub struct Waveguide {
+    right: DelayBuffer<f32>,
+    left: DelayBuffer<f32>,
+    refl_right: OnePoleLPF<f32>,
+    refl_left: OnePoleLPF<f32>,
+    srate: f32,
+    freq: f32,
+    rail_samples: f32,
+    refl_gain: f32,
+    excitation: f32,
+}
+
+impl Waveguide {
+    /// Creates a new waveguide with about 1 second of space per rail.
+    pub fn new() -> Self {
+        let mut this = Self {
+            right: DelayBuffer::new_with_size(DEFAULT_ALLPASS_COMB_SAMPLES),
+            left: DelayBuffer::new_with_size(DEFAULT_ALLPASS_COMB_SAMPLES),
+            refl_right: OnePoleLPF::new(),
+            refl_left: OnePoleLPF::new(),
+            srate: 44100.0,
+            freq: 440.0,
+            rail_samples: 0.0,
+            refl_gain: 1.0,
+            excitation: 0.0,
+        };
+        this.set_sample_rate(44100.0);
+        this.set_reflection(0.0);
+        this
+    }
+
+    /// Sets the sample rate, this also recalculates the rail lengths.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.right.set_sample_rate(srate);
+        self.left.set_sample_rate(srate);
+        self.refl_right.set_sample_rate(srate);
+        self.refl_left.set_sample_rate(srate);
+        self.set_length_hz(self.freq);
+    }
+
+    /// Reset the rails, the reflection filters and any pending excitation.
+    pub fn reset(&mut self) {
+        self.right.reset();
+        self.left.reset();
+        self.refl_right.reset();
+        self.refl_left.reset();
+        self.excitation = 0.0;
+    }
+
+    /// Sets the length of the waveguide by the fundamental frequency in Hz it should resonate at.
+    /// The round trip through both rails takes one period of `freq`.
+    #[inline]
+    pub fn set_length_hz(&mut self, freq: f32) {
+        self.freq = freq.max(1.0);
+        // One sample per rail is already introduced by reading before feeding.
+        let max_samples = (DEFAULT_ALLPASS_COMB_SAMPLES - 4) as f32;
+        self.rail_samples = ((self.srate / (2.0 * self.freq)) - 1.0).clamp(1.0, max_samples);
+    }
+
+    /// Sets the damping of the reflections at the terminations.
+    ///
+    /// * `damping` - Range 0.0 to 1.0. At 0.0 the reflection filters are almost fully open
+    ///   and the waveguide rings for a long time, at 1.0 the reflections are dark and
+    ///   decay quickly.
+    #[inline]
+    pub fn set_reflection(&mut self, damping: f32) {
+        let damping = damping.clamp(0.0, 1.0);
+        let open = 1.0 - damping;
+        let cutoff = 100.0 + 19900.0 * open * open;
+        self.refl_right.set_freq(cutoff);
+        self.refl_left.set_freq(cutoff);
+        self.refl_gain = 0.999 - 0.05 * damping;
+    }
+
+    /// Injects an excitation signal at the scattering junction.
+    /// It is fed into the waveguide the next time [Waveguide::next] is called.
+    #[inline]
+    pub fn excite(&mut self, input: f32) {
+        self.excitation += input;
+    }
+
+    /// Computes the next sample of the waveguide, picked up at the scattering junction.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let right_out = self.right.cubic_interpolate_at_s(self.rail_samples);
+        let left_out = self.left.cubic_interpolate_at_s(self.rail_samples);
+
+        let refl_far = -self.refl_gain * self.refl_right.process(right_out);
+        let refl_near = -self.refl_gain * self.refl_left.process(left_out);
+
+        self.right.feed(refl_near + self.excitation);
+        self.left.feed(refl_far);
+        self.excitation = 0.0;
+
+        left_out
+    }
+}
+
+impl Default for Waveguide {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Time in milliseconds of the crossfade at the splice points of the [Looper].
+const LOOPER_SPLICE_FADE_MS: f32 = 5.0;
+
+/// A simple circular looper on top of a [DelayBuffer].
+///
+/// The loop is a delay line with a feedback of 1.0, that is as long as the loop.
+/// When recording, the input replaces the loop content. When overdubbing, the input is added
+/// to the loop content. Starting and stopping recording or overdubbing is crossfaded
+/// over a few milliseconds, so that the splice points of the loop don't click.
+///
+///```
+/// use synfx_dsp::Looper;
+///
+/// let mut looper = Looper::new();
+/// looper.set_sample_rate(1000.0);
+/// looper.set_loop_length_ms(100.0);
+///
+/// looper.record(true);
+/// for i in 0..100 {
+///     looper.process(i as f32 / 100.0);
+/// }
+/// looper.record(false);
+/// looper.play(true);
+///
+/// // The loop plays back what was recorded, with a short fade in at its start:
+/// let out: Vec<f32> = (0..100).map(|_| looper.process(0.0)).collect();
+/// assert!((out[50] - 0.5).abs() < 0.0001);
+/// assert!((out[99] - 0.99).abs() < 0.0001);
+///```
+#[derive(Debug, Clone)]
+pub struct Looper {
+    buf: DelayBuffer<f32>,
+    srate: f32,
+    loop_ms: f32,
+    loop_len: usize,
+    recording: bool,
+    overdubbing: bool,
+    playing: bool,
+    rec_gain: f32,
+    dub_gain: f32,
+    fade_inc: f32,
+}
+
+impl Looper {
+    /// Creates a looper with the capacity of a [DelayBuffer::new].
+    pub fn new() -> Self {
+        Self::new_with_size(DEFAULT_DELAY_BUFFER_SAMPLES)
+    }
+
+    /// Creates a looper with the given amount of samples capacity.
+    pub fn new_with_size(size: usize) -> Self {
+        let mut this = Self {
+            buf: DelayBuffer::new_with_size(size.max(2)),
+            srate: 44100.0,
+            loop_ms: 1000.0,
+            loop_len: 1,
+            recording: false,
+            overdubbing: false,
+            playing: false,
+            rec_gain: 0.0,
+            dub_gain: 0.0,
+            fade_inc: 0.0,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    /// Sets the sample rate, the loop length in samples is recalculated.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.buf.set_sample_rate(srate);
+        self.fade_inc = 1.0 / (LOOPER_SPLICE_FADE_MS * srate / 1000.0).max(1.0);
+        self.set_loop_length_ms(self.loop_ms);
+    }
+
+    /// Clears the loop contents and stops recording, overdubbing and playback.
+    pub fn reset(&mut self) {
+        self.buf.reset();
+        self.recording = false;
+        self.overdubbing = false;
+        self.playing = false;
+        self.rec_gain = 0.0;
+        self.dub_gain = 0.0;
+    }
+
+    /// Sets the length of the loop in milliseconds. It is limited by the capacity of the looper.
+    pub fn set_loop_length_ms(&mut self, loop_ms: f32) {
+        self.loop_ms = loop_ms;
+        let max_len = self.buf.data.len() - 1;
+        let len = (loop_ms * self.srate / 1000.0).round().max(1.0) as usize;
+        self.loop_len = len.min(max_len);
+    }
+
+    /// Returns the length of the loop in samples.
+    #[inline]
+    pub fn loop_length_samples(&self) -> usize {
+        self.loop_len
+    }
+
+    /// Start or stop recording. Recording replaces the loop contents with the input.
+    #[inline]
+    pub fn record(&mut self, enabled: bool) {
+        self.recording = enabled;
+    }
+
+    /// Start or stop overdubbing. Overdubbing adds the input to the loop contents.
+    #[inline]
+    pub fn overdub(&mut self, enabled: bool) {
+        self.overdubbing = enabled;
+    }
+
+    /// Start or stop the playback of the loop. The loop keeps running in the background
+    /// while playback is stopped.
+    #[inline]
+    pub fn play(&mut self, enabled: bool) {
+        self.playing = enabled;
+    }
+
+    /// Processes the next input sample and returns the loop output.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let rec_target = if self.recording { 1.0 } else { 0.0 };
+        let dub_target = if self.overdubbing { 1.0 } else { 0.0 };
+        self.rec_gain = approach(self.rec_gain, rec_target, self.fade_inc);
+        self.dub_gain = approach(self.dub_gain, dub_target, self.fade_inc);
+
+        let looped = self.buf.at(self.loop_len - 1);
+        let next = looped * (1.0 - self.rec_gain) + input * (self.rec_gain + self.dub_gain);
+        self.buf.feed(next);
+
+        if self.playing {
+            looped
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for Looper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Moves `value` by at most `inc` towards `target`.
+#[inline]
+fn approach(value: f32, target: f32, inc: f32) -> f32 {
+    if value < target {
+        (value + inc).min(target)
+    } else {
+        (value - inc).max(target)
+    }
+}
+
+/// The maximum relative delay time difference of [stereo_time_offset] per channel.
+const STEREO_TIME_MAX_OFFSET: f32 = 0.1;
+
+/// Derives slightly different delay times for the left and right channel of a stereo
+/// effect from one base delay time, which decorrelates the channels and widens the
+/// stereo image, for instance of a stereo echo.
+///
+/// The offset is relative to the base time, so it works for short chorus delays and
+/// long echos alike. The average of both times stays at `base_ms`.
+///
+/// * `base_ms` - The delay time in milliseconds.
+/// * `spread` - The stereo spread, 0.0 is mono and 1.0 makes the left channel 10% shorter
+///   and the right channel 10% longer. Negative values swap the sides, the range
+///   is -1.0 to 1.0.
+///
+///```
+/// use synfx_dsp::stereo_time_offset;
+///
+/// assert_eq!(stereo_time_offset(100.0, 0.0), (100.0, 100.0));
+/// assert_eq!(stereo_time_offset(100.0, 1.0), (90.0, 110.0));
+/// assert_eq!(stereo_time_offset(10.0, -0.5), (10.5, 9.5));
+///```
+#[inline]
+pub fn stereo_time_offset(base_ms: f32, spread: f32) -> (f32, f32) {
+    let offs = base_ms * spread.clamp(-1.0, 1.0) * STEREO_TIME_MAX_OFFSET;
+    (base_ms - offs, base_ms + offs)
+}
+
+/// Upper limit for the summed feedback of all taps of a [MultiTapDelay].
+const MULTI_TAP_MAX_FEEDBACK: f32 = 0.99;
+
+#[derive(Debug, Clone, Copy)]
+struct DelayTap {
+    time_ms: f32,
+    gain: f32,
+    feedback: f32,
+    /// Left and right output gain from the pan position, 1.0 in the center.
+    pan_gains: (f32, f32),
+}
+
+impl DelayTap {
+    fn new(time_ms: f32, gain: f32, feedback: f32) -> Self {
+        Self { time_ms, gain, feedback, pan_gains: (1.0, 1.0) }
+    }
+}
+
+/// A stereo multi tap delay, with a feedback amount per tap and an optional ping-pong mode.
+///
+/// Each tap reads from both channels of the delay line, the tap gain determines how
+/// loud the tap is in the (wet) output and the tap feedback how much of it is fed back
+/// into the delay line. The taps can be placed rhythmically with [MultiTapDelay::set_sync].
+///
+/// In ping-pong mode (see [MultiTapDelay::set_ping_pong]) the input is summed to mono
+/// and fed only into the left channel. The feedback of the left channel taps is routed
+/// into the right channel and vice versa, so each repetition alternates between the channels.
+///
+/// Each tap can be panned in the output with [MultiTapDelay::set_tap_pan]. Besides the
+/// feedback per tap, there is a shared feedback path (see [MultiTapDelay::set_shared_feedback]),
+/// which feeds the (panned) output of all taps back into the delay line.
+///
+/// To keep the feedback loop stable, the feedback of all taps and the shared feedback is
+/// scaled down if their (absolute) sum exceeds 0.99. This applies to both modes, because
+/// one round trip through the crossed channels in ping-pong mode has the same loop gain
+/// as two round trips through a single channel.
+///
+///```
+/// use synfx_dsp::MultiTapDelay;
+///
+/// let mut delay = MultiTapDelay::new();
+/// delay.set_sample_rate(1000.0);
+/// // A single tap, one quarter note at 120 BPM => 500ms:
+/// delay.set_sync(120.0, &[1.0]);
+/// delay.set_tap(0, 500.0, 1.0, 0.5);
+/// delay.set_ping_pong(true);
+///
+/// let mut left = vec![];
+/// let mut right = vec![];
+/// for i in 0..1200 {
+///     let input = if i == 0 { 1.0 } else { 0.0 };
+///     let (l, r) = delay.process(input, input);
+///     left.push(l);
+///     right.push(r);
+/// }
+///
+/// let peak = |v: &[f32]| {
+///     v.iter().enumerate().fold((0, 0.0), |(pi, pv), (i, s)| if *s > pv { (i, *s) } else { (pi, pv) })
+/// };
+///
+/// // The first echo is on the left, the second on the right:
+/// let (li, lv) = peak(&left);
+/// let (ri, rv) = peak(&right);
+/// assert!(li > 490 && li < 510);
+/// assert!(ri > 990 && ri < 1010);
+/// assert!((lv - 1.0).abs() < 0.01);
+/// assert!((rv - 0.5).abs() < 0.01);
+///```
+///
+/// Panned taps with a shared feedback path:
+///
+///```
+/// use synfx_dsp::MultiTapDelay;
+///
+/// let mut delay = MultiTapDelay::new();
+/// delay.set_sample_rate(1000.0);
+/// // A dotted 8th and a quarter note at 120 BPM => 375ms and 500ms:
+/// delay.set_sync(120.0, &[0.75, 1.0]);
+/// delay.set_tap_pan(0, -1.0);
+/// delay.set_tap_pan(1, 1.0);
+/// delay.set_shared_feedback(0.5);
+///
+/// let mut left = vec![];
+/// let mut right = vec![];
+/// for i in 0..1000 {
+///     let input = if i == 0 { 1.0 } else { 0.0 };
+///     let (l, r) = delay.process(input, input);
+///     left.push(l);
+///     right.push(r);
+/// }
+///
+/// // The taps only show up on their side (the delay line adds one sample of latency):
+/// assert!(left[376] > 1.0 && right[376].abs() < 0.001);
+/// assert!(right[501] > 1.0 && left[501].abs() < 0.001);
+/// // The left tap was fed back and is repeated by itself after another 375ms:
+/// assert!(left[752] > 0.5);
+///```
+#[derive(Debug, Clone)]
+pub struct MultiTapDelay {
+    left: DelayBuffer<f32>,
+    right: DelayBuffer<f32>,
+    taps: Vec<DelayTap>,
+    ping_pong: bool,
+    shared_feedback: f32,
+    fb_scale: f32,
+}
+
+impl MultiTapDelay {
+    /// Creates a multi tap delay without any taps, with about 5 seconds of capacity
+    /// at 8*48000Hz sample rate.
+    pub fn new() -> Self {
+        Self {
+            left: DelayBuffer::new(),
+            right: DelayBuffer::new(),
+            taps: vec![],
+            ping_pong: false,
+            shared_feedback: 0.0,
+            fb_scale: 1.0,
+        }
+    }
+
+    /// Sets the sample rate that is used for milliseconds => sample conversion.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.left.set_sample_rate(srate);
+        self.right.set_sample_rate(srate);
+    }
+
+    /// Clears the delay line contents. The taps are kept.
+    pub fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+    }
+
+    /// Returns the number of taps.
+    pub fn tap_count(&self) -> usize {
+        self.taps.len()
+    }
+
+    /// Removes all taps.
+    pub fn clear_taps(&mut self) {
+        self.taps.clear();
+        self.recalc();
+    }
+
+    /// Adds a new tap and returns its index.
+    ///
+    /// * `time_ms` - Delay time of the tap in milliseconds.
+    /// * `gain` - Gain of the tap in the output.
+    /// * `feedback` - Amount of the tap that is fed back into the delay line.
+    pub fn add_tap(&mut self, time_ms: f32, gain: f32, feedback: f32) -> usize {
+        self.taps.push(DelayTap::new(time_ms, gain, feedback));
+        self.recalc();
+        self.taps.len() - 1
+    }
+
+    /// Changes the tap at `index`. See also [MultiTapDelay::add_tap].
+    /// Does nothing if there is no such tap.
+    pub fn set_tap(&mut self, index: usize, time_ms: f32, gain: f32, feedback: f32) {
+        if let Some(tap) = self.taps.get_mut(index) {
+            tap.time_ms = time_ms;
+            tap.gain = gain;
+            tap.feedback = feedback;
+            self.recalc();
+        }
+    }
+
+    /// Pans the tap at `index` in the output. Does nothing if there is no such tap.
+    ///
+    /// * `pan` - The pan position, -1.0 is left, 0.0 center and 1.0 right.
+    ///   The constant power pan law is normalized to 1.0 in the center.
+    pub fn set_tap_pan(&mut self, index: usize, pan: f32) {
+        if let Some(tap) = self.taps.get_mut(index) {
+            let (l, r) = pan_constant_power(pan);
+            tap.pan_gains = (l * std::f32::consts::SQRT_2, r * std::f32::consts::SQRT_2);
+            self.recalc();
+        }
+    }
+
+    /// Sets the amount of the summed output of all taps that is fed back into the delay line.
+    pub fn set_shared_feedback(&mut self, feedback: f32) {
+        self.shared_feedback = feedback;
+        self.recalc();
+    }
+
+    /// Places the taps rhythmically. There will be one tap per entry in `divisions`,
+    /// existing taps keep their gain and feedback, new ones start with a gain of 1.0
+    /// and no feedback.
+    ///
+    /// * `bpm` - The tempo in beats (quarter notes) per minute.
+    /// * `divisions` - The delay time of each tap in beats, see also [crate::tempo_division_ms].
+    pub fn set_sync(&mut self, bpm: f32, divisions: &[f32]) {
+        self.taps.resize(divisions.len(), DelayTap::new(0.0, 1.0, 0.0));
+        for (tap, div) in self.taps.iter_mut().zip(divisions.iter()) {
+            tap.time_ms = tempo_division_ms(bpm, *div);
+        }
+        self.recalc();
+    }
+
+    /// Enables or disables the ping-pong mode, where the feedback alternates the channels.
+    pub fn set_ping_pong(&mut self, ping_pong: bool) {
+        self.ping_pong = ping_pong;
+    }
+
+    fn recalc(&mut self) {
+        let shared = self.shared_feedback.abs();
+        let fb_sum: f32 = self
+            .taps
+            .iter()
+            .map(|t| t.feedback.abs() + shared * t.gain.abs() * t.pan_gains.0.max(t.pan_gains.1))
+            .sum();
+        self.fb_scale =
+            if fb_sum > MULTI_TAP_MAX_FEEDBACK { MULTI_TAP_MAX_FEEDBACK / fb_sum } else { 1.0 };
+    }
+
+    /// Processes the next stereo input sample and returns the stereo (wet only) output.
+    #[inline]
+    pub fn process(&mut self, in_l: f32, in_r: f32) -> (f32, f32) {
+        let mut out_l = 0.0;
+        let mut out_r = 0.0;
+        let mut fb_l = 0.0;
+        let mut fb_r = 0.0;
+
+        for tap in self.taps.iter() {
+            let l = self.left.tap_c(tap.time_ms);
+            let r = self.right.tap_c(tap.time_ms);
+            out_l += l * tap.gain * tap.pan_gains.0;
+            out_r += r * tap.gain * tap.pan_gains.1;
+            fb_l += l * tap.feedback;
+            fb_r += r * tap.feedback;
+        }
+
+        fb_l = (fb_l + out_l * self.shared_feedback) * self.fb_scale;
+        fb_r = (fb_r + out_r * self.shared_feedback) * self.fb_scale;
+
+        if self.ping_pong {
+            self.left.feed((in_l + in_r) * 0.5 + fb_r);
+            self.right.feed(fb_l);
+        } else {
+            self.left.feed(in_l + fb_l);
+            self.right.feed(in_r + fb_r);
+        }
+
+        (out_l, out_r)
+    }
+}
+
+impl Default for MultiTapDelay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The capacity of each channel of a [ModulatedDelay], enough for the longest
+/// modulated delay time at 8*48000Hz.
+const MOD_DELAY_BUFFER_SAMPLES: usize = 65536;
+/// The maximum base delay time of a [ModulatedDelay] in milliseconds.
+const MOD_DELAY_MAX_MS: f32 = 100.0;
+/// The maximum modulation depth of a [ModulatedDelay] in milliseconds.
+const MOD_DELAY_MAX_DEPTH_MS: f32 = 50.0;
+/// The shortest delay time a [ModulatedDelay] is modulated down to in milliseconds.
+const MOD_DELAY_MIN_MS: f32 = 0.1;
+/// The maximum (absolute) feedback of a [ModulatedDelay].
+const MOD_DELAY_MAX_FEEDBACK: f32 = 0.99;
+
+/// A stereo modulated delay, which is the core of chorus, flanger and vibrato effects.
+///
+/// Each channel has its own delay line, whose delay time is modulated by a [TriSawLFO]
+/// (or an external modulation signal, see [ModulatedDelay::process_ext]).
+/// The LFO of the right channel can be phase shifted for a wider stereo image.
+/// The feedback path is damped by a low pass filter.
+///
+/// Typical settings are:
+///
+/// - Chorus: 15-30ms delay, 2-5ms depth, 0.3-1Hz rate, no feedback, 50% mix.
+/// - Flanger: 1-5ms delay, 1-4ms depth, 0.1-0.5Hz rate, high (also negative) feedback,
+///   50% mix.
+/// - Vibrato: 5ms delay, 1-3ms depth, 4-7Hz rate, no feedback, 100% mix.
+///
+/// See also [ModulatedDelay::new_chorus], [ModulatedDelay::new_flanger] and
+/// [ModulatedDelay::new_vibrato].
+///
+///```
+/// use synfx_dsp::ModulatedDelay;
+///
+/// let mut vib = ModulatedDelay::new_vibrato();
+/// vib.set_sample_rate(44100.0);
+///
+/// // A vibrato shifts the pitch of a sine up and down, which can be
+/// // observed by counting the zero crossings:
+/// let mut crossings = 0;
+/// let mut last = 0.0;
+/// for i in 0..44100 {
+///     let s = (i as f32 * 440.0 * std::f32::consts::TAU / 44100.0).sin();
+///     let (l, _r) = vib.process(s, s);
+///     if last < 0.0 && l >= 0.0 {
+///         crossings += 1;
+///     }
+///     last = l;
+/// }
+/// // Over full LFO cycles the average pitch stays the same:
+/// assert!((crossings as i32 - 440).abs() <= 2);
+///```
+#[derive(Debug, Clone)]
+pub struct ModulatedDelay {
+    delays: [DelayBuffer<f32>; 2],
+    lfos: [TriSawLFO<f32>; 2],
+    lpfs: [OnePoleLPF<f32>; 2],
+
+    delay_ms: f32,
+    depth_ms: f32,
+    rate_hz: f32,
+    feedback: f32,
+    mix: f32,
+}
+
+impl ModulatedDelay {
+    /// Creates a new modulated delay with chorus like default settings.
+    pub fn new() -> Self {
+        let mut this = Self {
+            delays: [
+                DelayBuffer::new_with_size(MOD_DELAY_BUFFER_SAMPLES),
+                DelayBuffer::new_with_size(MOD_DELAY_BUFFER_SAMPLES),
+            ],
+            lfos: [TriSawLFO::new(); 2],
+            lpfs: [OnePoleLPF::new(); 2],
+
+            delay_ms: 20.0,
+            depth_ms: 3.0,
+            rate_hz: 0.5,
+            feedback: 0.0,
+            mix: 0.5,
+        };
+        this.set_stereo_phase(0.25);
+        this.set_feedback(0.0, 8000.0);
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    /// Creates a chorus: 20ms delay, 3ms depth, 0.5Hz rate and 50% mix.
+    pub fn new_chorus() -> Self {
+        Self::new()
+    }
+
+    /// Creates a flanger: 2ms delay, 1.5ms depth, 0.2Hz rate, 70% feedback and 50% mix.
+    pub fn new_flanger() -> Self {
+        let mut this = Self::new();
+        this.set_params(2.0, 1.5, 0.2);
+        this.set_feedback(0.7, 12000.0);
+        this
+    }
+
+    /// Creates a vibrato: 5ms delay, 2ms depth, 5Hz rate and a fully wet mix.
+    pub fn new_vibrato() -> Self {
+        let mut this = Self::new();
+        this.set_params(5.0, 2.0, 5.0);
+        this.set_stereo_phase(0.0);
+        this.set_mix(1.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        for i in 0..2 {
+            self.delays[i].set_sample_rate(srate);
+            self.lfos[i].set_sample_rate(srate);
+            self.lfos[i].set(self.rate_hz, 0.5);
+            self.lpfs[i].set_sample_rate(srate);
+        }
+    }
+
+    /// Clears the delay lines and restarts the LFOs.
+    pub fn reset(&mut self) {
+        for i in 0..2 {
+            self.delays[i].reset();
+            self.lfos[i].reset();
+            self.lfos[i].set(self.rate_hz, 0.5);
+            self.lpfs[i].reset();
+        }
+    }
+
+    /// Sets the delay time and modulation.
+    ///
+    /// * `delay_ms` - The center delay time in milliseconds, up to 100ms.
+    /// * `depth_ms` - How far the delay time is modulated around `delay_ms` in milliseconds,
+    ///   up to 50ms.
+    /// * `rate_hz` - The rate of the LFO in Hz.
+    pub fn set_params(&mut self, delay_ms: f32, depth_ms: f32, rate_hz: f32) {
+        self.delay_ms = delay_ms.clamp(MOD_DELAY_MIN_MS, MOD_DELAY_MAX_MS);
+        self.depth_ms = depth_ms.clamp(0.0, MOD_DELAY_MAX_DEPTH_MS);
+        self.rate_hz = rate_hz;
+        self.lfos[0].set(rate_hz, 0.5);
+        self.lfos[1].set(rate_hz, 0.5);
+    }
+
+    /// Sets the phase offset of the right channel LFO relative to the left one.
+    /// This resets the LFOs.
+    ///
+    /// * `phase` - The phase offset, range 0.0 to 1.0. 0.5 is a half cycle.
+    pub fn set_stereo_phase(&mut self, phase: f32) {
+        self.lfos[0].set_phase_offs(0.0);
+        self.lfos[1].set_phase_offs(phase.rem_euclid(1.0));
+    }
+
+    /// Sets the feedback and the cutoff frequency of its damping filter.
+    ///
+    /// * `feedback` - The feedback, range -0.99 to 0.99. Negative values invert the
+    ///   feedback, which shifts the flanger notches.
+    /// * `damping_hz` - The cutoff frequency of the low pass in the feedback path.
+    pub fn set_feedback(&mut self, feedback: f32, damping_hz: f32) {
+        self.feedback = feedback.clamp(-MOD_DELAY_MAX_FEEDBACK, MOD_DELAY_MAX_FEEDBACK);
+        self.lpfs[0].set_freq(damping_hz);
+        self.lpfs[1].set_freq(damping_hz);
+    }
+
+    /// Sets the wet/dry mix, 0.0 is dry only and 1.0 is wet only.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Processes the next stereo sample, with the delay times modulated by the internal LFOs.
+    #[inline]
+    pub fn process(&mut self, in_l: f32, in_r: f32) -> (f32, f32) {
+        let mod_l = self.lfos[0].next_bipolar();
+        let mod_r = self.lfos[1].next_bipolar();
+        self.process_ext(in_l, in_r, mod_l, mod_r)
+    }
+
+    /// Processes the next stereo sample, with the delay times modulated by an external
+    /// signal instead of the internal LFOs.
+    ///
+    /// * `mod_l` / `mod_r` - The modulation of the left and right channel,
+    ///   range -1.0 to 1.0, which is scaled by the depth set with [ModulatedDelay::set_params].
+    #[inline]
+    pub fn process_ext(&mut self, in_l: f32, in_r: f32, mod_l: f32, mod_r: f32) -> (f32, f32) {
+        let max_ms = MOD_DELAY_MAX_MS + MOD_DELAY_MAX_DEPTH_MS;
+        let time_l = (self.delay_ms + self.depth_ms * mod_l).clamp(MOD_DELAY_MIN_MS, max_ms);
+        let time_r = (self.delay_ms + self.depth_ms * mod_r).clamp(MOD_DELAY_MIN_MS, max_ms);
+
+        let wet_l = self.delays[0].cubic_interpolate_at(time_l);
+        let wet_r = self.delays[1].cubic_interpolate_at(time_r);
+
+        let fb_l = self.lpfs[0].process(wet_l) * self.feedback;
+        let fb_r = self.lpfs[1].process(wet_r) * self.feedback;
+
+        self.delays[0].feed(in_l + fb_l);
+        self.delays[1].feed(in_r + fb_r);
+
+        (crossfade(in_l, wet_l, self.mix), crossfade(in_r, wet_r, self.mix))
+    }
+}
+
+impl Default for ModulatedDelay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The smallest number of all-pass stages of a [Phaser].
+const PHASER_MIN_STAGES: usize = 2;
+/// The largest number of all-pass stages of a [Phaser].
+const PHASER_MAX_STAGES: usize = 12;
+/// The maximum (absolute) feedback of a [Phaser].
+const PHASER_MAX_FEEDBACK: f32 = 0.95;
+/// The lowest frequency the all-pass stages of a [Phaser] can be swept to in Hz.
+const PHASER_MIN_HZ: f32 = 10.0;
+
+/// A stereo phaser, built from a chain of 2 to 12 first order all-pass filters.
+///
+/// The break frequency of all stages is swept exponentially between a lower and an upper
+/// frequency by a [TriSawLFO] (or an external modulation signal, see [Phaser::process_ext]).
+/// Mixing the phase shifted signal with the dry signal creates a notch
+/// for every 180 degrees of phase shift, so every two stages add one notch.
+/// The output of the last stage is fed back to the input of the chain, which
+/// sharpens the notches and peaks. The LFO of the right channel can be phase
+/// shifted for a wider stereo image.
+///
+///```
+/// use synfx_dsp::Phaser;
+///
+/// // Two stages with a fixed break frequency of 1kHz have a notch at 1kHz:
+/// let mut ph = Phaser::new();
+/// ph.set_sample_rate(44100.0);
+/// ph.set_stages(2);
+/// ph.set_freq_range(1000.0, 1000.0);
+/// ph.set_mix(0.5);
+///
+/// let mut peak = [0.0_f32; 2];
+/// for (i, freq) in [1000.0, 100.0].iter().enumerate() {
+///     ph.reset();
+///     for n in 0..44100 {
+///         let s = (n as f32 * freq * std::f32::consts::TAU / 44100.0).sin();
+///         let (l, _r) = ph.process(s, s);
+///         if n > 22050 {
+///             peak[i] = peak[i].max(l.abs());
+///         }
+///     }
+/// }
+/// assert!(peak[0] < 0.01);
+/// assert!(peak[1] > 0.9);
+///```
+#[derive(Debug, Clone)]
+pub struct Phaser {
+    /// The states of the all-pass stages per channel.
+    z: [[f32; PHASER_MAX_STAGES]; 2],
+    /// The last output of the all-pass chain per channel, for the feedback.
+    last: [f32; 2],
+    lfos: [TriSawLFO<f32>; 2],
+
+    israte: f32,
+    stages: usize,
+    min_hz: f32,
+    max_hz: f32,
+    rate_hz: f32,
+    feedback: f32,
+    mix: f32,
+}
+
+impl Phaser {
+    /// Creates a new phaser with 4 stages sweeping from 200Hz to 2kHz at 0.5Hz and 50% mix.
+    pub fn new() -> Self {
+        let mut this = Self {
+            z: [[0.0; PHASER_MAX_STAGES]; 2],
+            last: [0.0; 2],
+            lfos: [TriSawLFO::new(); 2],
+
+            israte: 1.0 / 44100.0,
+            stages: 4,
+            min_hz: 200.0,
+            max_hz: 2000.0,
+            rate_hz: 0.5,
+            feedback: 0.0,
+            mix: 0.5,
+        };
+        this.set_stereo_phase(0.25);
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.israte = 1.0 / srate;
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_sample_rate(srate);
+            lfo.set(self.rate_hz, 0.5);
+        }
+    }
+
+    /// Clears the all-pass stages and restarts the LFOs.
+    pub fn reset(&mut self) {
+        self.z = [[0.0; PHASER_MAX_STAGES]; 2];
+        self.last = [0.0; 2];
+        for lfo in self.lfos.iter_mut() {
+            lfo.reset();
+            lfo.set(self.rate_hz, 0.5);
+        }
+    }
+
+    /// Sets the number of all-pass stages, range 2 to 12.
+    pub fn set_stages(&mut self, stages: usize) {
+        self.stages = stages.clamp(PHASER_MIN_STAGES, PHASER_MAX_STAGES);
+    }
+
+    /// Sets the range the break frequency of the all-pass stages is swept over.
+    ///
+    /// * `min_hz` - The lower end of the sweep in Hz.
+    /// * `max_hz` - The upper end of the sweep in Hz.
+    pub fn set_freq_range(&mut self, min_hz: f32, max_hz: f32) {
+        self.min_hz = min_hz.max(PHASER_MIN_HZ);
+        self.max_hz = max_hz.max(self.min_hz);
+    }
+
+    /// Sets the rate of the LFO in Hz.
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.rate_hz = rate_hz;
+        self.lfos[0].set(rate_hz, 0.5);
+        self.lfos[1].set(rate_hz, 0.5);
+    }
+
+    /// Sets the phase offset of the right channel LFO relative to the left one.
+    /// This resets the LFOs.
+    ///
+    /// * `phase` - The phase offset, range 0.0 to 1.0. 0.5 is a half cycle.
+    pub fn set_stereo_phase(&mut self, phase: f32) {
+        self.lfos[0].set_phase_offs(0.0);
+        self.lfos[1].set_phase_offs(phase.rem_euclid(1.0));
+    }
+
+    /// Sets the feedback, range -0.95 to 0.95. Negative values shift the notches.
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(-PHASER_MAX_FEEDBACK, PHASER_MAX_FEEDBACK);
+    }
+
+    /// Sets the wet/dry mix, 0.0 is dry only and 1.0 is wet only.
+    /// The notches are deepest at 0.5.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Runs one sample of channel `ch` through the all-pass chain.
+    #[inline]
+    fn process_channel(&mut self, ch: usize, input: f32, modulation: f32) -> f32 {
+        let m = (modulation.clamp(-1.0, 1.0) + 1.0) * 0.5;
+        let freq = self.min_hz * (self.max_hz / self.min_hz).powf(m);
+        let freq = freq.min(0.49 / self.israte);
+        let g = (std::f32::consts::PI * freq * self.israte).tan();
+        let a = g / (1.0 + g);
+
+        let mut x = input + self.feedback * self.last[ch];
+        for z in self.z[ch][..self.stages].iter_mut() {
+            let v = a * (x - *z);
+            let lp = v + *z;
+            *z = lp + v;
+            x = 2.0 * lp - x;
+        }
+        self.last[ch] = x;
+
+        crossfade(input, x, self.mix)
+    }
+
+    /// Processes the next stereo sample, with the sweep modulated by the internal LFOs.
+    #[inline]
+    pub fn process(&mut self, in_l: f32, in_r: f32) -> (f32, f32) {
+        let mod_l = self.lfos[0].next_bipolar();
+        let mod_r = self.lfos[1].next_bipolar();
+        self.process_ext(in_l, in_r, mod_l, mod_r)
+    }
+
+    /// Processes the next stereo sample, with the sweep modulated by an external
+    /// signal instead of the internal LFOs.
+    ///
+    /// * `mod_l` / `mod_r` - The modulation of the left and right channel,
+    ///   range -1.0 (lower end of the sweep) to 1.0 (upper end of the sweep),
+    ///   see [Phaser::set_freq_range].
+    #[inline]
+    pub fn process_ext(&mut self, in_l: f32, in_r: f32, mod_l: f32, mod_r: f32) -> (f32, f32) {
+        (self.process_channel(0, in_l, mod_l), self.process_channel(1, in_r, mod_r))
+    }
+}
+
+impl Default for Phaser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Plays a sample buffer and loops a region of it forever, for instance to sustain a
+/// sampled instrument. The buffer is read with cubic interpolation (see [cubic_interpolate])
+/// at a fractional playback rate.
+///
+/// To avoid a click at the loop seam, the end of the loop is linearly crossfaded with the
+/// samples right before the loop start. When the read position wraps from the loop end
+/// back to the loop start, the crossfade has fully arrived at that (continuous) signal.
+/// The crossfade window is limited by the samples available before the loop start
+/// and by half the loop length.
+///
+///```
+/// use synfx_dsp::LoopPlayer;
+///
+/// let buf: Vec<f32> = (0..4410).map(|i| (i as f32 * 0.0627).sin()).collect();
+/// let mut player = LoopPlayer::new(buf);
+/// player.set_sample_rate(44100.0);
+/// player.set_loop(1000, 3000, 10.0);
+/// player.set_rate(1.5);
+///
+/// let mut max_step: f32 = 0.0;
+/// let mut prev = player.next();
+/// for _ in 0..44100 {
+///     let s = player.next();
+///     max_step = max_step.max((s - prev).abs());
+///     prev = s;
+/// }
+/// // No click at the seam:
+/// assert!(max_step < 0.2);
+///```
+#[derive(Debug, Clone)]
+pub struct LoopPlayer {
+    buf: Vec<f32>,
+    srate: f32,
+    pos: f64,
+    rate: f64,
+    loop_start: usize,
+    loop_end: usize,
+    xfade_ms: f32,
+    xfade_len: f64,
+}
+
+impl LoopPlayer {
+    /// Creates a loop player for the given sample buffer, looping the whole buffer
+    /// without crossfade.
+    pub fn new(buf: Vec<f32>) -> Self {
+        let buf = if buf.is_empty() { vec![0.0] } else { buf };
+        let len = buf.len();
+        let mut this = Self {
+            buf,
+            srate: 44100.0,
+            pos: 0.0,
+            rate: 1.0,
+            loop_start: 0,
+            loop_end: len,
+            xfade_ms: 0.0,
+            xfade_len: 0.0,
+        };
+        this.set_loop(0, len, 0.0);
+        this
+    }
+
+    /// Sets the sample rate, which is used for the crossfade time.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.set_loop(self.loop_start, self.loop_end, self.xfade_ms);
+    }
+
+    /// Restarts the playback at the start of the buffer.
+    pub fn reset(&mut self) {
+        self.pos = 0.0;
+    }
+
+    /// Sets the loop region.
+    ///
+    /// * `start` - The first sample of the loop.
+    /// * `end` - The sample after the last sample of the loop.
+    /// * `xfade_ms` - The length of the crossfade at the loop seam in milliseconds.
+    pub fn set_loop(&mut self, start: usize, end: usize, xfade_ms: f32) {
+        let len = self.buf.len();
+        self.loop_end = end.clamp(1, len);
+        self.loop_start = start.min(self.loop_end - 1);
+        self.xfade_ms = xfade_ms.max(0.0);
+
+        let loop_len = (self.loop_end - self.loop_start) as f64;
+        self.xfade_len = ((self.xfade_ms * self.srate / 1000.0) as f64)
+            .min(self.loop_start as f64)
+            .min(loop_len * 0.5);
+
+        if self.pos >= self.loop_end as f64 {
+            self.pos = self.loop_start as f64;
+        }
+    }
+
+    /// Sets the playback rate. 1.0 is the original speed, 2.0 is one octave up.
+    #[inline]
+    pub fn set_rate(&mut self, ratio: f32) {
+        self.rate = ratio.max(0.0) as f64;
+    }
+
+    #[inline]
+    fn read(&self, pos: f64) -> f32 {
+        let i = pos.floor();
+        cubic_interpolate(&self.buf[..], self.buf.len(), i as usize, (pos - i) as f32)
+    }
+
+    /// Returns the next sample.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let loop_end = self.loop_end as f64;
+        let loop_len = loop_end - (self.loop_start as f64);
+        let xfade_start = loop_end - self.xfade_len;
+
+        let s = if self.xfade_len > 0.0 && self.pos >= xfade_start {
+            let x = ((self.pos - xfade_start) / self.xfade_len) as f32;
+            let pre = self.read(self.pos - loop_len);
+            self.read(self.pos) * (1.0 - x) + pre * x
+        } else {
+            self.read(self.pos)
+        };
+
+        self.pos += self.rate;
+        while self.pos >= loop_end {
+            self.pos -= loop_len;
+        }
+
+        s
+    }
 }
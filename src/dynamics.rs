@@ -0,0 +1,774 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! Dynamics processing, such as envelope following, (sidechain) ducking, compression,
+//! limiting and metering.
+
+use crate::{coef2gain_db, gain_db2coef, AtomicFloatPair};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Calculates the coefficient of a one pole smoothing filter with the time constant `time_ms`.
+#[inline]
+fn time_coef(time_ms: f32, srate: f32) -> f32 {
+    let samples = time_ms * srate / 1000.0;
+    if samples < 1.0 {
+        0.0
+    } else {
+        (-1.0 / samples).exp()
+    }
+}
+
+/// The level detection mode of an [EnvelopeFollower].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvFollowerMode {
+    /// Follows the absolute value of the signal.
+    Peak,
+    /// Follows the RMS (root mean square) of the signal, averaged over an
+    /// exponential window, see [EnvelopeFollower::set_rms_window_ms].
+    /// This is closer to the perceived loudness.
+    Rms,
+}
+
+/// Follows the level of a signal with separate attack and release times.
+/// The level is detected either as peak or RMS level, see [EnvFollowerMode].
+///
+///```
+/// use synfx_dsp::EnvelopeFollower;
+///
+/// let mut env = EnvelopeFollower::new();
+/// env.set_sample_rate(44100.0);
+/// env.set_attack_release(1.0, 100.0);
+///
+/// for _ in 0..4410 {
+///     env.process(0.5);
+/// }
+/// assert!((env.value() - 0.5).abs() < 0.001);
+///
+/// // Slowly releases:
+/// for _ in 0..441 {
+///     env.process(0.0);
+/// }
+/// assert!(env.value() > 0.4 && env.value() < 0.5);
+///```
+///
+/// In RMS mode a sine wave settles at its RMS level of `amplitude / sqrt(2)`:
+///
+///```
+/// use synfx_dsp::{EnvelopeFollower, EnvFollowerMode};
+///
+/// let mut env = EnvelopeFollower::new();
+/// env.set_sample_rate(44100.0);
+/// env.set_mode(EnvFollowerMode::Rms);
+/// env.set_rms_window_ms(50.0);
+///
+/// let input: Vec<f32> =
+///     (0..44100).map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / 44100.0).sin()).collect();
+/// let mut output = vec![0.0; input.len()];
+/// env.process_block(&input[..], &mut output[..]);
+///
+/// assert!((output[44099] - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.02);
+///```
+#[derive(Debug, Clone)]
+pub struct EnvelopeFollower {
+    srate: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    attack_coef: f32,
+    release_coef: f32,
+    value: f32,
+    mode: EnvFollowerMode,
+    rms_window_ms: f32,
+    rms_coef: f32,
+    /// The averaged square of the signal in [EnvFollowerMode::Rms].
+    mean_sq: f32,
+}
+
+impl EnvelopeFollower {
+    /// Creates a new peak envelope follower with 10ms attack and 100ms release.
+    /// The RMS window defaults to 10ms.
+    pub fn new() -> Self {
+        let mut this = Self {
+            srate: 44100.0,
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            attack_coef: 0.0,
+            release_coef: 0.0,
+            value: 0.0,
+            mode: EnvFollowerMode::Peak,
+            rms_window_ms: 10.0,
+            rms_coef: 0.0,
+            mean_sq: 0.0,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.set_attack_release(self.attack_ms, self.release_ms);
+        self.set_rms_window_ms(self.rms_window_ms);
+    }
+
+    pub fn reset(&mut self) {
+        self.value = 0.0;
+        self.mean_sq = 0.0;
+    }
+
+    /// Sets the attack and release time constants in milliseconds.
+    pub fn set_attack_release(&mut self, attack_ms: f32, release_ms: f32) {
+        self.attack_ms = attack_ms;
+        self.release_ms = release_ms;
+        self.attack_coef = time_coef(attack_ms, self.srate);
+        self.release_coef = time_coef(release_ms, self.srate);
+    }
+
+    /// Sets the level detection mode, the default is [EnvFollowerMode::Peak].
+    pub fn set_mode(&mut self, mode: EnvFollowerMode) {
+        self.mode = mode;
+    }
+
+    /// Sets the time constant of the averaging window in [EnvFollowerMode::Rms] in
+    /// milliseconds. Longer windows give a smoother, but slower, level.
+    pub fn set_rms_window_ms(&mut self, window_ms: f32) {
+        self.rms_window_ms = window_ms;
+        self.rms_coef = time_coef(window_ms, self.srate);
+    }
+
+    /// Returns the current level.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Feeds the next sample into the follower and returns the current level.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let input = match self.mode {
+            EnvFollowerMode::Peak => input.abs(),
+            EnvFollowerMode::Rms => {
+                let sq = input * input;
+                self.mean_sq = sq + (self.mean_sq - sq) * self.rms_coef;
+                self.mean_sq.sqrt()
+            }
+        };
+        let coef = if input > self.value { self.attack_coef } else { self.release_coef };
+        self.value = input + (self.value - input) * coef;
+        self.value
+    }
+
+    /// Feeds a block of samples into the follower and writes the level of each
+    /// sample to `output`. Only `min(input.len(), output.len())` samples are processed.
+    #[inline]
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = self.process(*i);
+        }
+    }
+}
+
+impl Default for EnvelopeFollower {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A sidechain ducker, which attenuates a main signal depending on the level of a separate
+/// key signal. For instance to duck the music by a voiceover.
+///
+/// The level of the key signal is tracked with an [EnvelopeFollower]. Above the threshold
+/// the main signal is attenuated like by a compressor with the given ratio.
+///
+///```
+/// use synfx_dsp::Ducker;
+///
+/// let mut ducker = Ducker::new();
+/// ducker.set_sample_rate(44100.0);
+/// ducker.set_params(-20.0, 4.0, 1.0, 100.0);
+///
+/// // No key signal, no ducking:
+/// assert!((ducker.process(0.5, 0.0) - 0.5).abs() < 0.0001);
+///
+/// // A key signal 20dB above the threshold => 15dB ducking:
+/// let mut out = 0.0;
+/// for _ in 0..4410 {
+///     out = ducker.process(1.0, 1.0);
+/// }
+/// assert!((out - 0.1778).abs() < 0.001);
+///```
+#[derive(Debug, Clone)]
+pub struct Ducker {
+    follower: EnvelopeFollower,
+    threshold_db: f32,
+    ratio: f32,
+}
+
+impl Ducker {
+    /// Creates a new ducker with -20dB threshold, a ratio of 4:1, 10ms attack and 100ms release.
+    pub fn new() -> Self {
+        let mut this = Self { follower: EnvelopeFollower::new(), threshold_db: -20.0, ratio: 4.0 };
+        this.set_params(-20.0, 4.0, 10.0, 100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.follower.set_sample_rate(srate);
+    }
+
+    pub fn reset(&mut self) {
+        self.follower.reset();
+    }
+
+    /// Sets the parameters of the ducker.
+    ///
+    /// * `threshold_db` - The key level in decibels above which the main signal is ducked.
+    /// * `ratio` - The ratio of the ducking, eg. 4.0 attenuates the main signal by 3dB for each 4dB
+    ///   the key signal is above the threshold. Values below 1.0 are treated as 1.0.
+    /// * `attack_ms` - How fast the ducking kicks in.
+    /// * `release_ms` - How fast the main signal comes back.
+    pub fn set_params(&mut self, threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32) {
+        self.threshold_db = threshold_db;
+        self.ratio = ratio.max(1.0);
+        self.follower.set_attack_release(attack_ms, release_ms);
+    }
+
+    /// Returns the main signal, ducked by the level of the key signal.
+    #[inline]
+    pub fn process(&mut self, main: f32, key: f32) -> f32 {
+        let level_db = coef2gain_db(self.follower.process(key));
+        let over_db = level_db - self.threshold_db;
+        if over_db > 0.0 {
+            main * gain_db2coef(-over_db * (1.0 - 1.0 / self.ratio))
+        } else {
+            main
+        }
+    }
+}
+
+impl Default for Ducker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A feed forward compressor with a soft knee and makeup gain.
+///
+/// The level is detected with a peak detector and the gain computer works in the
+/// log (decibel) domain. The gain reduction is smoothed with separate attack and
+/// release times, which avoids the pumping of a level domain smoothing.
+///
+///```
+/// use synfx_dsp::Compressor;
+///
+/// let mut comp = Compressor::new();
+/// comp.set_sample_rate(44100.0);
+/// comp.set_params(-20.0, 4.0, 1.0, 100.0, 0.0, 5.0);
+///
+/// // 0dB input is 20dB over the threshold, which is reduced to 5dB,
+/// // plus 5dB of makeup gain:
+/// let mut out = 0.0;
+/// for _ in 0..4410 {
+///     out = comp.process(1.0);
+/// }
+/// assert!((comp.gain_reduction_db() - -15.0).abs() < 0.01);
+/// assert!((out - 0.3162).abs() < 0.001);
+///
+/// // Below the threshold only the makeup gain is applied:
+/// for _ in 0..44100 {
+///     out = comp.process(0.01);
+/// }
+/// assert!((out - 0.01778).abs() < 0.0001);
+///```
+#[derive(Debug, Clone)]
+pub struct Compressor {
+    srate: f32,
+    threshold_db: f32,
+    ratio: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    knee_db: f32,
+    makeup: f32,
+    attack_coef: f32,
+    release_coef: f32,
+    /// Current (smoothed) gain reduction in decibels, always <= 0.0.
+    gr_db: f32,
+}
+
+impl Compressor {
+    /// Creates a new compressor with -20dB threshold, 4:1 ratio, 10ms attack,
+    /// 100ms release, 6dB knee and no makeup gain.
+    pub fn new() -> Self {
+        let mut this = Self {
+            srate: 44100.0,
+            threshold_db: -20.0,
+            ratio: 4.0,
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            knee_db: 6.0,
+            makeup: 1.0,
+            attack_coef: 0.0,
+            release_coef: 0.0,
+            gr_db: 0.0,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.attack_coef = time_coef(self.attack_ms, srate);
+        self.release_coef = time_coef(self.release_ms, srate);
+    }
+
+    pub fn reset(&mut self) {
+        self.gr_db = 0.0;
+    }
+
+    /// Sets the parameters of the compressor.
+    ///
+    /// * `threshold_db` - The level in decibels above which the signal is compressed.
+    /// * `ratio` - The ratio, eg. 4.0 lets the output rise 1dB for each 4dB the input
+    ///   rises above the threshold. Values below 1.0 are treated as 1.0.
+    /// * `attack_ms` - How fast the gain reduction kicks in.
+    /// * `release_ms` - How fast the gain reduction recovers.
+    /// * `knee_db` - The width of the soft knee around the threshold in decibels,
+    ///   0.0 is a hard knee.
+    /// * `makeup_db` - Gain in decibels applied after the compression.
+    pub fn set_params(
+        &mut self,
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+        knee_db: f32,
+        makeup_db: f32,
+    ) {
+        self.threshold_db = threshold_db;
+        self.ratio = ratio.max(1.0);
+        self.attack_ms = attack_ms;
+        self.release_ms = release_ms;
+        self.knee_db = knee_db.max(0.0);
+        self.makeup = gain_db2coef(makeup_db);
+        self.set_sample_rate(self.srate);
+    }
+
+    /// Returns the current gain reduction in decibels, which is 0.0 or negative.
+    /// Useful for a gain reduction meter.
+    #[inline]
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.gr_db
+    }
+
+    /// The static gain computer, returns the gain reduction for the given
+    /// input level in decibels.
+    #[inline]
+    fn compute_gain_db(&self, level_db: f32) -> f32 {
+        let over_db = level_db - self.threshold_db;
+        let slope = 1.0 / self.ratio - 1.0;
+
+        if 2.0 * over_db <= -self.knee_db {
+            0.0
+        } else if 2.0 * over_db.abs() < self.knee_db {
+            let x = over_db + self.knee_db * 0.5;
+            slope * x * x / (2.0 * self.knee_db)
+        } else {
+            slope * over_db
+        }
+    }
+
+    /// Updates the smoothed gain reduction from the level of the detector signal
+    /// and returns the linear output gain.
+    #[inline]
+    fn next_gain(&mut self, detect: f32) -> f32 {
+        let target = self.compute_gain_db(coef2gain_db(detect.abs()));
+        let coef = if target < self.gr_db { self.attack_coef } else { self.release_coef };
+        self.gr_db = target + (self.gr_db - target) * coef;
+        gain_db2coef(self.gr_db) * self.makeup
+    }
+
+    /// Compresses the next sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        input * self.next_gain(input)
+    }
+
+    /// Compresses the next stereo sample. Both channels are compressed by the same gain,
+    /// which is derived from the louder channel, so the stereo image stays put.
+    #[inline]
+    pub fn process_stereo(&mut self, l: f32, r: f32) -> (f32, f32) {
+        let gain = self.next_gain(l.abs().max(r.abs()));
+        (l * gain, r * gain)
+    }
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The maximum look-ahead time of a [Limiter] in milliseconds.
+const LIMITER_MAX_LOOKAHEAD_MS: f32 = 20.0;
+/// The capacity of the look-ahead buffers of a [Limiter], enough for
+/// [LIMITER_MAX_LOOKAHEAD_MS] at 8*48000Hz.
+const LIMITER_BUFFER_SAMPLES: usize = (LIMITER_MAX_LOOKAHEAD_MS as usize) * 8 * 48;
+
+/// A brickwall limiter with look-ahead, whose output never exceeds the ceiling.
+///
+/// The signal is delayed by the look-ahead time, so the gain can be ramped down
+/// before a peak arrives. The required gain is held over the look-ahead window
+/// (a sliding minimum) and smoothed with a moving average of the same length,
+/// which guarantees the ceiling without distortion from sudden gain jumps.
+/// Afterwards the gain recovers with the release time.
+///
+/// The latency of the limiter is [Limiter::latency] samples.
+///
+///```
+/// use synfx_dsp::Limiter;
+///
+/// let mut lim = Limiter::new();
+/// lim.set_sample_rate(44100.0);
+/// lim.set_params(-1.0, 5.0, 50.0);
+///
+/// let ceiling = 10.0_f32.powf(-1.0 / 20.0);
+/// let mut peak: f32 = 0.0;
+/// for i in 0..44100 {
+///     // A sine with bursts up to +6dB:
+///     let amp = if (i / 4410) % 2 == 0 { 0.5 } else { 2.0 };
+///     let s = amp * (i as f32 * 0.05).sin();
+///     peak = peak.max(lim.process(s).abs());
+/// }
+/// assert!(peak <= ceiling + 0.0001);
+/// assert!(peak > ceiling * 0.95);
+/// assert_eq!(lim.latency(), 221);
+///```
+#[derive(Debug, Clone)]
+pub struct Limiter {
+    srate: f32,
+    ceiling: f32,
+    lookahead_ms: f32,
+    release_ms: f32,
+    release_coef: f32,
+    /// The look-ahead time in samples.
+    len: usize,
+
+    /// Delay line for the (stereo) signal.
+    delay: Vec<(f32, f32)>,
+    /// Ring buffer of the moving average of the gain.
+    avg: Vec<f32>,
+    avg_sum: f64,
+    pos: usize,
+    /// Monotonic queue of (sample index, gain) for the sliding minimum.
+    min_queue: VecDeque<(usize, f32)>,
+    counter: usize,
+    /// The held gain after the release smoothing.
+    hold: f32,
+    gain: f32,
+}
+
+impl Limiter {
+    /// Creates a new limiter with a ceiling of -0.3dB, 5ms look-ahead and 50ms release.
+    pub fn new() -> Self {
+        let mut this = Self {
+            srate: 44100.0,
+            ceiling: 1.0,
+            lookahead_ms: 5.0,
+            release_ms: 50.0,
+            release_coef: 0.0,
+            len: 1,
+
+            delay: vec![(0.0, 0.0); LIMITER_BUFFER_SAMPLES],
+            avg: vec![1.0; LIMITER_BUFFER_SAMPLES],
+            avg_sum: 0.0,
+            pos: 0,
+            min_queue: VecDeque::with_capacity(LIMITER_BUFFER_SAMPLES + 1),
+            counter: 0,
+            hold: 1.0,
+            gain: 1.0,
+        };
+        this.set_params(-0.3, 5.0, 50.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.set_params(coef2gain_db(self.ceiling), self.lookahead_ms, self.release_ms);
+    }
+
+    /// Clears the look-ahead buffers.
+    pub fn reset(&mut self) {
+        self.delay.fill((0.0, 0.0));
+        self.avg.fill(1.0);
+        self.avg_sum = self.len as f64;
+        self.pos = 0;
+        self.min_queue.clear();
+        self.counter = 0;
+        self.hold = 1.0;
+        self.gain = 1.0;
+    }
+
+    /// Sets the parameters of the limiter. Changing the look-ahead time resets the limiter.
+    ///
+    /// * `ceiling_db` - The maximum output level in decibels.
+    /// * `lookahead_ms` - The look-ahead time, which is also the attack time,
+    ///   range 0.0 to 20.0ms. At least one sample of look-ahead is always used.
+    /// * `release_ms` - How fast the gain recovers after a peak.
+    pub fn set_params(&mut self, ceiling_db: f32, lookahead_ms: f32, release_ms: f32) {
+        self.ceiling = gain_db2coef(ceiling_db);
+        self.lookahead_ms = lookahead_ms.clamp(0.0, LIMITER_MAX_LOOKAHEAD_MS);
+        self.release_ms = release_ms;
+        self.release_coef = time_coef(release_ms, self.srate);
+
+        let len = ((self.lookahead_ms * self.srate / 1000.0).round() as usize)
+            .clamp(1, LIMITER_BUFFER_SAMPLES);
+        if len != self.len {
+            self.len = len;
+            self.reset();
+        }
+    }
+
+    /// Returns the latency of the limiter in samples, which is the look-ahead time.
+    #[inline]
+    pub fn latency(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the current gain reduction in decibels, which is 0.0 or negative.
+    #[inline]
+    pub fn gain_reduction_db(&self) -> f32 {
+        coef2gain_db(self.gain)
+    }
+
+    /// Limits the next sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.process_stereo(input, input).0
+    }
+
+    /// Limits the next stereo sample. Both channels are limited by the same gain.
+    #[inline]
+    pub fn process_stereo(&mut self, l: f32, r: f32) -> (f32, f32) {
+        let peak = l.abs().max(r.abs());
+        let required = if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+
+        // Sliding minimum over the last len + 1 samples:
+        while let Some((_, g)) = self.min_queue.back() {
+            if *g >= required {
+                self.min_queue.pop_back();
+            } else {
+                break;
+            }
+        }
+        self.min_queue.push_back((self.counter, required));
+        while let Some((i, _)) = self.min_queue.front() {
+            if i + self.len < self.counter {
+                self.min_queue.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.counter += 1;
+        let min = self.min_queue.front().map(|(_, g)| *g).unwrap_or(1.0);
+
+        // Release, the held gain is never above the minimum:
+        self.hold = if min < self.hold { min } else { min + (self.hold - min) * self.release_coef };
+
+        // Moving average over len samples:
+        self.avg_sum += (self.hold - self.avg[self.pos]) as f64;
+        self.avg[self.pos] = self.hold;
+        self.gain = (self.avg_sum / (self.len as f64)) as f32;
+
+        let (dl, dr) = self.delay[self.pos];
+        self.delay[self.pos] = (l, r);
+        self.pos = (self.pos + 1) % self.len;
+
+        (dl * self.gain, dr * self.gain)
+    }
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How long [BlockMeter] holds the peak before it starts to fall.
+const BLOCK_METER_HOLD_MS: f32 = 1000.0;
+/// How fast the held peak of [BlockMeter] falls after the hold time.
+const BLOCK_METER_FALL_DB_PER_SEC: f32 = 20.0;
+/// Time constant of the integrated RMS of [BlockMeter], like a VU meter.
+const BLOCK_METER_RMS_MS: f32 = 300.0;
+
+/// The values measured by a [BlockMeter], which can be read lock free
+/// from another thread, eg. for displaying a meter in a GUI.
+/// Get it with [BlockMeter::values].
+#[derive(Debug, Default)]
+pub struct BlockMeterValues {
+    /// Peak and RMS of the last block.
+    block: AtomicFloatPair,
+    /// Peak hold and integrated RMS.
+    slow: AtomicFloatPair,
+}
+
+impl BlockMeterValues {
+    /// The peak (absolute) sample value of the last block.
+    #[inline]
+    pub fn peak(&self) -> f32 {
+        self.block.get().0
+    }
+
+    /// The RMS of the last block.
+    #[inline]
+    pub fn rms(&self) -> f32 {
+        self.block.get().1
+    }
+
+    /// The held peak, which starts to fall 1 second after the last higher peak.
+    #[inline]
+    pub fn peak_hold(&self) -> f32 {
+        self.slow.get().0
+    }
+
+    /// The RMS integrated over about 300ms.
+    #[inline]
+    pub fn integrated_rms(&self) -> f32 {
+        self.slow.get().1
+    }
+}
+
+/// A level meter that works on whole blocks of samples, which is cheaper than following
+/// the level per sample (like the [EnvelopeFollower]) when all you need is a GUI meter.
+///
+/// For every block the peak and RMS are calculated, a decaying peak hold and an integrated
+/// RMS are maintained. The values are stored in [AtomicFloatPair]s in [BlockMeterValues],
+/// which can be shared with a GUI thread.
+///
+///```
+/// use synfx_dsp::BlockMeter;
+///
+/// let mut meter = BlockMeter::new();
+/// meter.set_sample_rate(44100.0);
+/// let gui_values = meter.values();
+///
+/// let block: Vec<f32> =
+///     (0..128).map(|i| (i as f32 * std::f32::consts::TAU / 64.0).sin() * 0.5).collect();
+/// for _ in 0..1000 {
+///     meter.process_block(&block[..]);
+/// }
+/// assert!((gui_values.peak() - 0.5).abs() < 0.001);
+/// assert!((gui_values.rms() - 0.3536).abs() < 0.001);
+/// assert!((gui_values.integrated_rms() - 0.3536).abs() < 0.001);
+///
+/// // The peak hold stays after the signal is gone:
+/// let silence = [0.0; 128];
+/// meter.process_block(&silence[..]);
+/// assert_eq!(meter.peak(), 0.0);
+/// assert!((meter.peak_hold() - 0.5).abs() < 0.001);
+///
+/// // And falls after the hold time:
+/// for _ in 0..1000 {
+///     meter.process_block(&silence[..]);
+/// }
+/// assert!(meter.peak_hold() < 0.1);
+///```
+#[derive(Debug)]
+pub struct BlockMeter {
+    values: Arc<BlockMeterValues>,
+    srate: f32,
+    hold: f32,
+    hold_samples: usize,
+    mean_square: f32,
+}
+
+impl BlockMeter {
+    pub fn new() -> Self {
+        Self {
+            values: Arc::new(BlockMeterValues::default()),
+            srate: 44100.0,
+            hold: 0.0,
+            hold_samples: 0,
+            mean_square: 0.0,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+    }
+
+    pub fn reset(&mut self) {
+        self.hold = 0.0;
+        self.hold_samples = 0;
+        self.mean_square = 0.0;
+        self.values.block.set((0.0, 0.0));
+        self.values.slow.set((0.0, 0.0));
+    }
+
+    /// Returns the shared values, for reading them from another thread.
+    pub fn values(&self) -> Arc<BlockMeterValues> {
+        self.values.clone()
+    }
+
+    /// See [BlockMeterValues::peak].
+    #[inline]
+    pub fn peak(&self) -> f32 {
+        self.values.peak()
+    }
+
+    /// See [BlockMeterValues::rms].
+    #[inline]
+    pub fn rms(&self) -> f32 {
+        self.values.rms()
+    }
+
+    /// See [BlockMeterValues::peak_hold].
+    #[inline]
+    pub fn peak_hold(&self) -> f32 {
+        self.values.peak_hold()
+    }
+
+    /// See [BlockMeterValues::integrated_rms].
+    #[inline]
+    pub fn integrated_rms(&self) -> f32 {
+        self.values.integrated_rms()
+    }
+
+    /// Measures the next block of samples.
+    pub fn process_block(&mut self, block: &[f32]) {
+        if block.is_empty() {
+            return;
+        }
+
+        let mut peak: f32 = 0.0;
+        let mut sum_sq = 0.0;
+        for s in block {
+            peak = peak.max(s.abs());
+            sum_sq += s * s;
+        }
+        let block_ms = sum_sq / (block.len() as f32);
+
+        let len = block.len();
+        let hold_len = (BLOCK_METER_HOLD_MS * self.srate / 1000.0) as usize;
+        if peak >= self.hold {
+            self.hold = peak;
+            self.hold_samples = 0;
+        } else if self.hold_samples >= hold_len {
+            let fall_db = BLOCK_METER_FALL_DB_PER_SEC * (len as f32) / self.srate;
+            self.hold = (self.hold * gain_db2coef(-fall_db)).max(peak);
+        } else {
+            self.hold_samples += len;
+        }
+
+        let coef = time_coef(BLOCK_METER_RMS_MS, self.srate).powi(len as i32);
+        self.mean_square = block_ms + (self.mean_square - block_ms) * coef;
+
+        self.values.block.set((peak, block_ms.sqrt()));
+        self.values.slow.set((self.hold, self.mean_square.sqrt()));
+    }
+}
+
+impl Default for BlockMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -8,13 +8,15 @@ See also:
 
 - [EnvState] which holds the state of the envelope.
 - [EnvRetrigAD] is a complete implementation of an attack decay envelope.
+- [EnvADSR] is a gated ADSR envelope with linear or analog style exponential curves.
 - [crate::env_hold_stage] for a hold stage piece
 - [crate::env_target_stage] for an attack/decay/release stage piece
 - [crate::env_sustain_stage] for a sustain stage piece
+- [SilenceDetector] for detecting when a voice has decayed to silence
 */
 
 use crate::sqrt4_to_pow4;
-use crate::{TrigSignal, Trigger};
+use crate::{TrigSignal, Trigger, TRIG_LOW_THRES};
 
 /// Envelope state structure for the macros [crate::env_hold_stage],
 /// [crate::env_target_stage] and [crate::env_sustain_stage].
@@ -48,6 +50,8 @@ pub struct EnvState {
     pub phase: f32,
     pub start: f32,
     pub current: f32,
+    /// Optional `(min, max)` range for `current`, see [EnvState::set_clamp].
+    clamp: Option<(f32, f32)>,
 }
 
 impl EnvState {
@@ -59,6 +63,7 @@ impl EnvState {
             phase: 0.0,
             start: 0.0,
             current: 0.0,
+            clamp: None,
         }
     }
 
@@ -88,6 +93,44 @@ impl EnvState {
         self.start = 0.0;
         self.current = 0.0;
     }
+
+    /// Limits `current` to the range `min` to `max` after every stage computation.
+    /// Aggressive shape functions can overshoot the target value slightly, which
+    /// for a gain envelope means a transient above 1.0. By default there is no clamp.
+    ///
+    ///```
+    /// use synfx_dsp::{EnvState, env_target_stage};
+    ///
+    /// let mut state = EnvState::new();
+    /// state.set_sample_rate(1000.0);
+    /// state.set_clamp(0.0, 1.0);
+    ///
+    /// state.trigger();
+    /// let mut max: f32 = 0.0;
+    /// for _ in 0..20 {
+    ///     // A shape function that overshoots:
+    ///     env_target_stage!(state, 0, 10.0, 1.0, |x: f32| x * 1.2, {});
+    ///     max = max.max(state.current);
+    /// }
+    /// assert_eq!(max, 1.0);
+    ///```
+    pub fn set_clamp(&mut self, min: f32, max: f32) {
+        self.clamp = Some((min.min(max), max.max(min)));
+    }
+
+    /// Removes the clamp set with [EnvState::set_clamp].
+    pub fn clear_clamp(&mut self) {
+        self.clamp = None;
+    }
+
+    /// Applies the clamp set with [EnvState::set_clamp] to `current`.
+    /// This is called by the envelope stage macros.
+    #[inline]
+    pub fn apply_clamp(&mut self) {
+        if let Some((min, max)) = self.clamp {
+            self.current = self.current.clamp(min, max);
+        }
+    }
 }
 
 /// Holds the previous `state.current` value for `$time_ms`.
@@ -110,6 +153,7 @@ macro_rules! env_hold_stage {
                 $state.stage += 1;
             }
             $state.current = $state.start;
+            $state.apply_clamp();
         } else $else
     };
 }
@@ -141,6 +185,7 @@ macro_rules! env_target_stage {
                 let phase_shped = ($shape_fn)($state.phase);
                 $state.current = $state.start * (1.0 - phase_shped) + phase_shped * $value;
             }
+            $state.apply_clamp();
         } else $else
     };
 }
@@ -174,6 +219,7 @@ macro_rules! env_target_stage_lin_time_adj {
                 let phase_shped = ($shape_fn)($state.phase);
                 $state.current = $state.start * (1.0 - phase_shped) + phase_shped * $value;
             }
+            $state.apply_clamp();
         } else $else
     };
 }
@@ -191,6 +237,7 @@ macro_rules! env_sustain_stage {
             }
 
             $state.current = $sustain_value;
+            $state.apply_clamp();
         } else $else
     };
 }
@@ -222,12 +269,22 @@ pub struct EnvRetrigAD {
     state: EnvState,
     trig: Trigger,
     trig_sig: TrigSignal,
+    legato: bool,
+    elapsed_ms: f32,
+    attack_scale: f32,
 }
 
 impl EnvRetrigAD {
     /// Creates a new instance of the envelope.
     pub fn new() -> Self {
-        Self { state: EnvState::new(), trig: Trigger::new(), trig_sig: TrigSignal::new() }
+        Self {
+            state: EnvState::new(),
+            trig: Trigger::new(),
+            trig_sig: TrigSignal::new(),
+            legato: false,
+            elapsed_ms: 0.0,
+            attack_scale: 1.0,
+        }
     }
 
     /// Set the sample rate of the envelope. Unit in samples per second.
@@ -241,6 +298,43 @@ impl EnvRetrigAD {
         self.state.reset();
         self.trig_sig.reset();
         self.trig.reset();
+        self.elapsed_ms = 0.0;
+        self.attack_scale = 1.0;
+    }
+
+    /// Enables the legato retrigger mode. In this mode a retrigger, while the envelope
+    /// is still running, scales the attack time by how far the previous envelope had progressed:
+    ///
+    /// ```text
+    /// attack_time = attack_ms * (elapsed_ms / (attack_ms + decay_ms))
+    /// ```
+    ///
+    /// Where `elapsed_ms` is the time since the previous trigger. The scaling factor
+    /// is limited to the range 0.0 to 1.0. This comes on top of the shortening of the attack
+    /// by the current envelope value, which is always done (see [crate::env_target_stage_lin_time_adj]).
+    /// A trigger after the envelope has finished always uses the full attack time.
+    ///
+    /// That means fast played passages don't restart the full length envelope on every note.
+    ///
+    ///```
+    /// use synfx_dsp::EnvRetrigAD;
+    /// let mut env = EnvRetrigAD::new();
+    /// env.set_sample_rate(10.0); // 10 samples per second for testing here.
+    /// env.set_legato(true);
+    ///
+    /// // Let the attack stage run for 2 of the 10 samples of the envelope:
+    /// env.tick(1.0, 500.0, 0.5, 500.0, 0.5);
+    /// env.tick(1.0, 500.0, 0.5, 500.0, 0.5);
+    /// let (value, _) = env.tick(0.0, 500.0, 0.5, 500.0, 0.5);
+    /// assert!((value - 0.6).abs() < 0.0001);
+    ///
+    /// // Retrigger, the attack takes now 30% of 40% of the 500ms attack time,
+    /// // which is shorter than a single sample:
+    /// let (value, _) = env.tick(1.0, 500.0, 0.5, 500.0, 0.5);
+    /// assert!((value - 1.0).abs() < 0.0001);
+    ///```
+    pub fn set_legato(&mut self, legato: bool) {
+        self.legato = legato;
     }
 
     /// Computes the next tick for this envelope.
@@ -287,14 +381,22 @@ impl EnvRetrigAD {
         decay_shape: f32,
     ) -> (f32, f32) {
         if self.trig.check_trigger(trigger) {
+            self.attack_scale = if self.legato && self.state.is_running() {
+                (self.elapsed_ms / (attack_ms + decay_ms).max(0.0001)).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            self.elapsed_ms = 0.0;
             self.state.trigger();
         }
 
         if self.state.is_running() {
+            self.elapsed_ms += 1.0 / self.state.srate_ms;
+
             env_target_stage_lin_time_adj!(
                 self.state,
                 0,
-                attack_ms,
+                attack_ms * self.attack_scale,
                 0.0,
                 1.0,
                 |x: f32| sqrt4_to_pow4(x.clamp(0.0, 1.0), attack_shape),
@@ -428,6 +530,371 @@ impl EnvRetrigADSR {
     }
 }
 
+/// Overshoot ratio of the exponential attack of [EnvADSR]. The attack charges towards
+/// `1.0 + ratio` and stops at 1.0, like the capacitor in an analog envelope generator.
+const ENV_ADSR_ATTACK_RATIO: f32 = 0.3;
+/// Overshoot ratio of the exponential decay and release of [EnvADSR].
+const ENV_ADSR_DECAY_RATIO: f32 = 0.001;
+
+/// The segment curve of an [EnvADSR].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvCurve {
+    /// Straight line segments.
+    Linear,
+    /// Exponential (RC-style) segments, like classic analog envelope generators.
+    /// The attack is concave and the decay and release are convex.
+    Exponential,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EnvADSRStage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// Coefficients of one exponential envelope segment, cached by the segment time.
+#[derive(Debug, Clone, Copy)]
+struct EnvRCSegment {
+    time_ms: f32,
+    coef: f32,
+}
+
+impl EnvRCSegment {
+    fn new() -> Self {
+        Self { time_ms: -1.0, coef: 0.0 }
+    }
+
+    /// Returns the per sample coefficient, for reaching the target within
+    /// `time_ms` when charging towards `target + ratio`.
+    #[inline]
+    fn coef(&mut self, time_ms: f32, srate_ms: f32, ratio: f32) -> f32 {
+        if time_ms != self.time_ms {
+            self.time_ms = time_ms;
+            let samples = (time_ms * srate_ms).max(1.0);
+            self.coef = (-((1.0 + ratio) / ratio).ln() / samples).exp();
+        }
+        self.coef
+    }
+}
+
+/// A gated ADSR (Attack, Decay, Sustain & Release) envelope with selectable
+/// linear or exponential (RC-style) segments.
+///
+/// The attack starts when the gate goes high (see [crate::Trigger]), the release
+/// when it falls below [crate::TRIG_LOW_THRES]. A retrigger, while the envelope is still running,
+/// starts the attack from the current level, so there are no clicks.
+///
+/// The times are the durations of the complete segments: The attack from 0.0 to 1.0,
+/// the decay from 1.0 to the sustain level and the release from the level at which
+/// the gate was released to 0.0. A retriggered attack is shorter, because it starts
+/// from a higher level. The exponential release behaves like an RC circuit, its time
+/// is for a release from 1.0, so releasing from a lower level is faster.
+///
+///```
+/// use synfx_dsp::{EnvADSR, EnvCurve};
+///
+/// let mut env = EnvADSR::new();
+/// env.set_sample_rate(1000.0);
+/// env.set_curve(EnvCurve::Exponential);
+///
+/// // Attack 10ms, decay 20ms, sustain 0.5 and release 50ms:
+/// let mut values = vec![];
+/// for i in 0..200 {
+///     let gate = if i < 100 { 1.0 } else { 0.0 };
+///     let (value, _end) = env.tick(gate, 10.0, 20.0, 0.5, 50.0);
+///     values.push(value);
+/// }
+/// assert!(values[9] > 0.999);
+/// assert!((values[80] - 0.5).abs() < 0.001);
+/// assert!(values[120] < 0.5);
+/// assert_eq!(values[160], 0.0);
+///
+/// // A retrigger during the release starts from the current level:
+/// for _ in 0..10 {
+///     env.tick(1.0, 10.0, 20.0, 0.5, 50.0);
+/// }
+/// for _ in 0..10 {
+///     env.tick(0.0, 10.0, 20.0, 0.5, 50.0);
+/// }
+/// let (before, _) = env.tick(0.0, 10.0, 20.0, 0.5, 50.0);
+/// let (after, _) = env.tick(1.0, 10.0, 20.0, 0.5, 50.0);
+/// assert!(before > 0.2);
+/// assert!(after > before && after - before < 0.2);
+///```
+#[derive(Debug, Clone)]
+pub struct EnvADSR {
+    srate_ms: f32,
+    trig: Trigger,
+    trig_sig: TrigSignal,
+    curve: EnvCurve,
+    stage: EnvADSRStage,
+    value: f32,
+    /// Level at which the release started.
+    release_start: f32,
+    attack_rc: EnvRCSegment,
+    decay_rc: EnvRCSegment,
+    release_rc: EnvRCSegment,
+}
+
+impl EnvADSR {
+    /// Creates a new instance of the envelope with linear segments.
+    pub fn new() -> Self {
+        Self {
+            srate_ms: 44100.0 / 1000.0,
+            trig: Trigger::new(),
+            trig_sig: TrigSignal::new(),
+            curve: EnvCurve::Linear,
+            stage: EnvADSRStage::Idle,
+            value: 0.0,
+            release_start: 0.0,
+            attack_rc: EnvRCSegment::new(),
+            decay_rc: EnvRCSegment::new(),
+            release_rc: EnvRCSegment::new(),
+        }
+    }
+
+    /// Set the sample rate of the envelope. Unit in samples per second.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate_ms = srate / 1000.0;
+        self.trig_sig.set_sample_rate(srate);
+        self.attack_rc = EnvRCSegment::new();
+        self.decay_rc = EnvRCSegment::new();
+        self.release_rc = EnvRCSegment::new();
+    }
+
+    /// Reset the internal state of the envelope.
+    pub fn reset(&mut self) {
+        self.trig.reset();
+        self.trig_sig.reset();
+        self.stage = EnvADSRStage::Idle;
+        self.value = 0.0;
+        self.release_start = 0.0;
+    }
+
+    /// Selects the segment curve, the default is [EnvCurve::Linear].
+    pub fn set_curve(&mut self, curve: EnvCurve) {
+        self.curve = curve;
+    }
+
+    /// Returns true while the envelope is not idle.
+    pub fn is_running(&self) -> bool {
+        self.stage != EnvADSRStage::Idle
+    }
+
+    /// Computes the next tick for this envelope.
+    /// The inputs can be changed on each tick.
+    ///
+    /// * `gate` - Gate input signal, the rising edge is detected like [crate::Trigger].
+    /// * `attack_ms` - The milliseconds for the attack stage.
+    /// * `decay_ms` - The milliseconds for the decay stage.
+    /// * `sustain` - The sustain level, range 0.0 to 1.0.
+    /// * `release_ms` - The milliseconds for the release stage.
+    ///
+    /// Returned are two values:
+    /// * First the envelope value
+    /// * Second a trigger signal at the end of the envelope.
+    #[inline]
+    pub fn tick(
+        &mut self,
+        gate: f32,
+        attack_ms: f32,
+        decay_ms: f32,
+        sustain: f32,
+        release_ms: f32,
+    ) -> (f32, f32) {
+        let sustain = sustain.clamp(0.0, 1.0);
+
+        if self.trig.check_trigger(gate) {
+            self.stage = EnvADSRStage::Attack;
+        } else if gate < TRIG_LOW_THRES
+            && self.stage != EnvADSRStage::Idle
+            && self.stage != EnvADSRStage::Release
+        {
+            self.stage = EnvADSRStage::Release;
+            self.release_start = self.value;
+        }
+
+        let exp = self.curve == EnvCurve::Exponential;
+
+        match self.stage {
+            EnvADSRStage::Idle => (),
+            EnvADSRStage::Attack => {
+                if exp {
+                    let ratio = ENV_ADSR_ATTACK_RATIO;
+                    let coef = self.attack_rc.coef(attack_ms, self.srate_ms, ratio);
+                    self.value = (1.0 + ratio) + (self.value - (1.0 + ratio)) * coef;
+                } else {
+                    self.value += 1.0 / (attack_ms * self.srate_ms).max(1.0);
+                }
+
+                if self.value >= 1.0 {
+                    self.value = 1.0;
+                    self.stage = EnvADSRStage::Decay;
+                }
+            }
+            EnvADSRStage::Decay => {
+                if exp {
+                    let ratio = ENV_ADSR_DECAY_RATIO;
+                    let coef = self.decay_rc.coef(decay_ms, self.srate_ms, ratio);
+                    self.value = (sustain - ratio) + (self.value - (sustain - ratio)) * coef;
+                } else {
+                    self.value -= (1.0 - sustain) / (decay_ms * self.srate_ms).max(1.0);
+                }
+
+                if self.value <= sustain {
+                    self.value = sustain;
+                    self.stage = EnvADSRStage::Sustain;
+                }
+            }
+            EnvADSRStage::Sustain => {
+                self.value = sustain;
+            }
+            EnvADSRStage::Release => {
+                if exp {
+                    let ratio = ENV_ADSR_DECAY_RATIO;
+                    let coef = self.release_rc.coef(release_ms, self.srate_ms, ratio);
+                    self.value = -ratio + (self.value + ratio) * coef;
+                } else {
+                    self.value -= self.release_start / (release_ms * self.srate_ms).max(1.0);
+                }
+
+                if self.value <= 0.0 {
+                    self.value = 0.0;
+                    self.stage = EnvADSRStage::Idle;
+                    self.trig_sig.trigger();
+                }
+            }
+        }
+
+        (self.value, self.trig_sig.next())
+    }
+}
+
+impl Default for EnvADSR {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Release time of the level follower in [SilenceDetector].
+const SILENCE_DETECTOR_RELEASE_MS: f32 = 50.0;
+
+/// Detects when a signal has decayed to inaudibility, for instance to free
+/// the voice of a polyphonic synthesizer.
+///
+/// The absolute signal level is tracked with an instant attack and a slow release.
+/// The signal is considered silent once that level stayed below the threshold
+/// for the hold time.
+///
+///```
+/// use synfx_dsp::SilenceDetector;
+///
+/// let mut sd = SilenceDetector::new();
+/// sd.set_sample_rate(44100.0);
+/// sd.set_threshold_db(-60.0);
+/// sd.set_hold_ms(10.0);
+///
+/// for _ in 0..4410 {
+///     assert!(!sd.process(0.5));
+/// }
+///
+/// let mut silent = false;
+/// for _ in 0..44100 {
+///     silent = sd.process(0.0);
+/// }
+/// assert!(silent);
+/// assert!(sd.is_silent());
+///
+/// // Any audible sample resets the detector:
+/// assert!(!sd.process(0.1));
+///```
+#[derive(Debug, Clone)]
+pub struct SilenceDetector {
+    srate_ms: f32,
+    level: f32,
+    release_coef: f32,
+    threshold: f32,
+    hold_ms: f32,
+    hold_samples: usize,
+    below_count: usize,
+}
+
+impl SilenceDetector {
+    /// Creates a new silence detector with a threshold of -80dB and a hold time of 100ms.
+    pub fn new() -> Self {
+        let mut this = Self {
+            srate_ms: 44.1,
+            level: 0.0,
+            release_coef: 0.0,
+            threshold: 0.0,
+            hold_ms: 100.0,
+            hold_samples: 0,
+            below_count: 0,
+        };
+        this.set_threshold_db(-80.0);
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    /// Set the sample rate of the detector. Unit in samples per second.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate_ms = srate / 1000.0;
+        self.release_coef = (-1.0 / (SILENCE_DETECTOR_RELEASE_MS * self.srate_ms)).exp();
+        self.set_hold_ms(self.hold_ms);
+    }
+
+    /// Reset the internal state of the detector. The signal is not considered
+    /// silent until it stayed below the threshold for the hold time again.
+    pub fn reset(&mut self) {
+        self.level = 0.0;
+        self.below_count = 0;
+    }
+
+    /// Sets the level in decibels below which the signal is considered silent.
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold = 10.0_f32.powf(threshold_db * 0.05);
+    }
+
+    /// Sets the time the signal has to stay below the threshold in milliseconds.
+    pub fn set_hold_ms(&mut self, hold_ms: f32) {
+        self.hold_ms = hold_ms.max(0.0);
+        self.hold_samples = ((self.hold_ms * self.srate_ms) as usize).max(1);
+    }
+
+    /// Returns true if the signal stayed below the threshold for the hold time.
+    #[inline]
+    pub fn is_silent(&self) -> bool {
+        self.below_count >= self.hold_samples
+    }
+
+    /// Feeds the next sample into the detector and returns [SilenceDetector::is_silent].
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> bool {
+        let abs = sample.abs();
+        self.level =
+            if abs > self.level { abs } else { abs + (self.level - abs) * self.release_coef };
+
+        if self.level < self.threshold {
+            if self.below_count < self.hold_samples {
+                self.below_count += 1;
+            }
+        } else {
+            self.below_count = 0;
+        }
+
+        self.is_silent()
+    }
+}
+
+impl Default for SilenceDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -657,4 +1124,25 @@ mod test {
 
         assert_eq!(retrig_index, 15);
     }
+
+    #[test]
+    fn check_env_adsr_gate_shorter_than_attack() {
+        for curve in [EnvCurve::Linear, EnvCurve::Exponential] {
+            let mut env = EnvADSR::new();
+            env.set_sample_rate(1000.0);
+            env.set_curve(curve);
+
+            // A gate of 5ms with an attack of 100ms releases from within the attack:
+            let mut values = vec![];
+            for i in 0..200 {
+                let gate = if i < 5 { 1.0 } else { 0.0 };
+                values.push(env.tick(gate, 100.0, 20.0, 0.5, 50.0).0);
+            }
+            let peak = values.iter().fold(0.0_f32, |m, v| m.max(*v));
+            assert!(peak > 0.0 && peak < 0.5, "peak={}", peak);
+            assert!(values[5..].windows(2).all(|w| w[1] <= w[0]));
+            assert_eq!(values[199], 0.0);
+            assert!(!env.is_running());
+        }
+    }
 }
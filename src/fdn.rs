@@ -0,0 +1,362 @@
+// Copyright (c) 2021-2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! Contains a feedback delay network (FDN) reverb.
+
+use crate::{crossfade, DelayBuffer, OnePoleLPF, TriSawLFO};
+
+/// The maximum number of delay lines in a [FdnReverb].
+const FDN_MAX_LINES: usize = 16;
+
+/// The capacity of each delay line, enough for the longest line at 8*48000Hz.
+const FDN_DELAY_BUFFER_SAMPLES: usize = 65536;
+
+/// Base delay times of the lines in milliseconds. They are sorted, so that
+/// picking every 2nd or 4th line still covers the whole range.
+const FDN_LINE_TIMES_MS: [f64; FDN_MAX_LINES] = [
+    23.3, 26.9, 29.5, 31.9, 34.7, 37.1, 39.9, 42.7, 45.1, 48.3, 51.1, 53.9, 56.3, 59.3, 62.9, 66.7,
+];
+
+/// The minimum and maximum scaling of [FDN_LINE_TIMES_MS] by the room size.
+const FDN_ROOM_SCALE_MIN: f64 = 0.25;
+const FDN_ROOM_SCALE_MAX: f64 = 2.0;
+
+/// The maximum modulation depth of the delay times in milliseconds.
+const FDN_MOD_MAX_MS: f64 = 2.0;
+
+/// The range of the decay time (RT60) in seconds.
+const FDN_DECAY_MIN_S: f64 = 0.1;
+const FDN_DECAY_MAX_S: f64 = 30.0;
+
+/// The default crossfade time for freezing a [FdnReverb] in milliseconds.
+const FDN_FREEZE_FADE_MS: f64 = 50.0;
+
+/// The number of delay lines of a [FdnReverb].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdnSize {
+    Lines4,
+    Lines8,
+    Lines16,
+}
+
+impl FdnSize {
+    /// Returns the number of delay lines.
+    pub fn lines(&self) -> usize {
+        match self {
+            FdnSize::Lines4 => 4,
+            FdnSize::Lines8 => 8,
+            FdnSize::Lines16 => 16,
+        }
+    }
+}
+
+/// The feedback matrix that mixes the delay lines of a [FdnReverb].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdnMixing {
+    /// Householder reflection, each line feeds back mostly into itself.
+    /// Builds up echo density a bit slower.
+    Householder,
+    /// Hadamard matrix, each line feeds into all others with equal weight.
+    /// Gives the fastest build up of echo density.
+    Hadamard,
+}
+
+/// A feedback delay network reverb, as alternative to the [crate::DattorroReverb].
+///
+/// The network consists of 4, 8 or 16 modulated delay lines, each with
+/// its own damping low pass filter, which are mixed by a lossless Householder or
+/// Hadamard feedback matrix. The decay time is set as RT60 and the
+/// damping filters make the high frequencies decay faster.
+///
+/// The left input feeds the even lines and the right input the odd lines,
+/// the outputs are taken from the lines in the same way.
+///
+///```
+/// use synfx_dsp::*;
+///
+/// let mut rev = FdnReverb::new();
+/// rev.set_sample_rate(44100.0);
+/// rev.set_size(FdnSize::Lines8);
+/// rev.set_mixing(FdnMixing::Hadamard);
+/// rev.set_room_size(0.5);
+/// rev.set_decay_time(1.0);
+/// rev.set_damping_hz(8000.0);
+///
+/// let mut energy = [0.0; 4];
+/// for i in 0..(4 * 44100) {
+///     let inp = if i == 0 { 1.0 } else { 0.0 };
+///     let (l, r) = rev.process(inp, inp);
+///     energy[i / 44100] += l * l + r * r;
+/// }
+///
+/// // The tail is there and decays by 60dB per second:
+/// assert!(energy[0] > 0.01);
+/// assert!(energy[1] < energy[0] * 1e-4);
+/// assert!(energy[3] < energy[2]);
+///```
+#[derive(Debug, Clone)]
+pub struct FdnReverb {
+    delays: Vec<DelayBuffer<f64>>,
+    lpfs: [OnePoleLPF<f64>; FDN_MAX_LINES],
+    lfos: [TriSawLFO<f64>; FDN_MAX_LINES],
+    /// Delay time in milliseconds and the feedback gain of each line.
+    lines: [(f64, f64); FDN_MAX_LINES],
+
+    size: FdnSize,
+    mixing: FdnMixing,
+    room_size: f64,
+    decay_s: f64,
+    mod_depth_ms: f64,
+
+    srate: f64,
+    freeze: bool,
+    freeze_fade_ms: f64,
+    freeze_inc: f64,
+    /// Crossfade between normal operation (0.0) and frozen lines (1.0).
+    freeze_amt: f64,
+}
+
+impl FdnReverb {
+    /// Creates a new FDN reverb with 8 lines, Householder mixing, a room size of 0.5,
+    /// a decay time of 2 seconds and damping at 6kHz.
+    pub fn new() -> Self {
+        let mut this = Self {
+            delays: (0..FDN_MAX_LINES)
+                .map(|_| DelayBuffer::new_with_size(FDN_DELAY_BUFFER_SAMPLES))
+                .collect(),
+            lpfs: [OnePoleLPF::new(); FDN_MAX_LINES],
+            lfos: [TriSawLFO::new(); FDN_MAX_LINES],
+            lines: [(0.0, 0.0); FDN_MAX_LINES],
+
+            size: FdnSize::Lines8,
+            mixing: FdnMixing::Householder,
+            room_size: 0.5,
+            decay_s: 2.0,
+            mod_depth_ms: 0.0,
+
+            srate: 44100.0,
+            freeze: false,
+            freeze_fade_ms: FDN_FREEZE_FADE_MS,
+            freeze_inc: 0.0,
+            freeze_amt: 0.0,
+        };
+
+        this.set_sample_rate(44100.0);
+        this.set_damping_hz(6000.0);
+        this.set_modulation(0.5, 0.3);
+        this.recalc();
+        this
+    }
+
+    /// Reset the delay lines and filters.
+    pub fn reset(&mut self) {
+        for d in self.delays.iter_mut() {
+            d.reset();
+        }
+        for lpf in self.lpfs.iter_mut() {
+            lpf.reset();
+        }
+        for lfo in self.lfos.iter_mut() {
+            lfo.reset();
+        }
+        self.freeze = false;
+        self.freeze_amt = 0.0;
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f64) {
+        self.srate = srate;
+        self.set_freeze_fade_ms(self.freeze_fade_ms);
+        for d in self.delays.iter_mut() {
+            d.set_sample_rate(srate);
+        }
+        for lpf in self.lpfs.iter_mut() {
+            lpf.set_sample_rate(srate);
+        }
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_sample_rate(srate);
+        }
+    }
+
+    /// Sets the number of delay lines. This resets the reverb.
+    pub fn set_size(&mut self, size: FdnSize) {
+        if size != self.size {
+            self.size = size;
+            self.reset();
+            self.recalc();
+        }
+    }
+
+    /// Sets the feedback mixing matrix.
+    pub fn set_mixing(&mut self, mixing: FdnMixing) {
+        self.mixing = mixing;
+    }
+
+    /// Sets the size of the room, which scales the delay line lengths.
+    ///
+    /// * `size` - Range 0.0 to 1.0.
+    pub fn set_room_size(&mut self, size: f64) {
+        self.room_size = size.clamp(0.0, 1.0);
+        self.recalc();
+    }
+
+    /// Sets the decay time (RT60), the time it takes for the tail to decay by 60dB.
+    ///
+    /// * `decay_s` - Decay time in seconds, range 0.1 to 30.0.
+    pub fn set_decay_time(&mut self, decay_s: f64) {
+        self.decay_s = decay_s.clamp(FDN_DECAY_MIN_S, FDN_DECAY_MAX_S);
+        self.recalc();
+    }
+
+    /// Sets the cutoff frequency of the damping filters inside the lines.
+    ///
+    /// * `freq` - Cutoff frequency in Hz.
+    pub fn set_damping_hz(&mut self, freq: f64) {
+        for lpf in self.lpfs.iter_mut() {
+            lpf.set_freq(freq);
+        }
+    }
+
+    /// Sets the modulation of the delay times, which smears out metallic resonances.
+    ///
+    /// * `depth` - Modulation depth, range 0.0 to 1.0.
+    /// * `speed_hz` - The base frequency of the modulation LFOs, each line uses
+    ///   a slightly different frequency.
+    pub fn set_modulation(&mut self, depth: f64, speed_hz: f64) {
+        self.mod_depth_ms = depth.clamp(0.0, 1.0) * FDN_MOD_MAX_MS;
+        for (i, lfo) in self.lfos.iter_mut().enumerate() {
+            lfo.set(speed_hz * (1.0 + 0.13 * i as f64), 0.5);
+        }
+    }
+
+    /// Freezes the reverb: The feedback gain of all lines goes to unity, the damping
+    /// filters are bypassed and the input is muted, so that the current tail
+    /// sustains indefinitely without growing. The feedback matrix is lossless, so the
+    /// frozen tail keeps its level. Freezing and unfreezing is crossfaded,
+    /// see [FdnReverb::set_freeze_fade_ms].
+    ///
+    ///```
+    /// use synfx_dsp::FdnReverb;
+    ///
+    /// let mut rev = FdnReverb::new();
+    /// rev.set_sample_rate(44100.0);
+    /// rev.set_decay_time(1.0);
+    ///
+    /// for i in 0..4410 {
+    ///     let input = if i < 441 { 0.5 } else { 0.0 };
+    ///     rev.process(input, input);
+    /// }
+    ///
+    /// rev.set_freeze(true);
+    /// let mut energy = [0.0; 3];
+    /// for i in 0..(44100 * 3) {
+    ///     // The input is muted:
+    ///     let (l, r) = rev.process(1.0, 1.0);
+    ///     energy[i / 44100] += l * l + r * r;
+    /// }
+    /// // The tail neither decays nor grows:
+    /// assert!(energy[2] > 0.5 * energy[1]);
+    /// assert!(energy[2] < 2.0 * energy[1]);
+    ///```
+    pub fn set_freeze(&mut self, freeze: bool) {
+        self.freeze = freeze;
+    }
+
+    /// Returns whether the reverb is (fading into being) frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.freeze
+    }
+
+    /// Sets the crossfade time for freezing and unfreezing the reverb,
+    /// see [FdnReverb::set_freeze]. The default is 50ms.
+    pub fn set_freeze_fade_ms(&mut self, ms: f64) {
+        self.freeze_fade_ms = ms.max(0.0);
+        self.freeze_inc = 1.0 / (self.freeze_fade_ms * self.srate / 1000.0).max(1.0);
+    }
+
+    fn recalc(&mut self) {
+        let n = self.size.lines();
+        let stride = FDN_MAX_LINES / n;
+        let scale = FDN_ROOM_SCALE_MIN + (FDN_ROOM_SCALE_MAX - FDN_ROOM_SCALE_MIN) * self.room_size;
+
+        for (i, (time_ms, gain)) in self.lines.iter_mut().take(n).enumerate() {
+            *time_ms = FDN_LINE_TIMES_MS[i * stride] * scale;
+            // -60dB after decay_s seconds:
+            *gain = 10.0_f64.powf(-3.0 * (*time_ms / 1000.0) / self.decay_s);
+        }
+    }
+
+    #[inline]
+    fn mix(&self, v: &mut [f64]) {
+        let n = v.len();
+        match self.mixing {
+            FdnMixing::Householder => {
+                let s = v.iter().sum::<f64>() * 2.0 / (n as f64);
+                for x in v.iter_mut() {
+                    *x -= s;
+                }
+            }
+            FdnMixing::Hadamard => {
+                // Fast Walsh-Hadamard transform:
+                let mut h = 1;
+                while h < n {
+                    for i in (0..n).step_by(h * 2) {
+                        for j in i..(i + h) {
+                            let (a, b) = (v[j], v[j + h]);
+                            v[j] = a + b;
+                            v[j + h] = a - b;
+                        }
+                    }
+                    h *= 2;
+                }
+                let norm = 1.0 / (n as f64).sqrt();
+                for x in v.iter_mut() {
+                    *x *= norm;
+                }
+            }
+        }
+    }
+
+    /// Processes the next stereo sample.
+    pub fn process(&mut self, input_l: f64, input_r: f64) -> (f64, f64) {
+        let n = self.size.lines();
+        let mut out = [0.0; FDN_MAX_LINES];
+        let mut fb = [0.0; FDN_MAX_LINES];
+
+        if self.freeze {
+            self.freeze_amt = (self.freeze_amt + self.freeze_inc).min(1.0);
+        } else {
+            self.freeze_amt = (self.freeze_amt - self.freeze_inc).max(0.0);
+        }
+        let freeze_amt = self.freeze_amt;
+        let input_l = input_l * (1.0 - freeze_amt);
+        let input_r = input_r * (1.0 - freeze_amt);
+
+        for i in 0..n {
+            let time_ms =
+                self.lines[i].0 + self.mod_depth_ms * (0.5 + 0.5 * self.lfos[i].next_bipolar());
+            out[i] = self.delays[i].cubic_interpolate_at(time_ms);
+            let damped = self.lpfs[i].process(out[i]) * self.lines[i].1;
+            fb[i] = crossfade(damped, out[i], freeze_amt);
+        }
+
+        self.mix(&mut fb[0..n]);
+
+        let mut left = 0.0;
+        let mut right = 0.0;
+        for i in 0..n {
+            let (inp, sum) = if i % 2 == 0 { (input_l, &mut left) } else { (input_r, &mut right) };
+            self.delays[i].feed(fb[i] + inp);
+            *sum += out[i];
+        }
+
+        let out_gain = 1.0 / ((n / 2) as f64).sqrt();
+        (left * out_gain, right * out_gain)
+    }
+}
+
+impl Default for FdnReverb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
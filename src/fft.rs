@@ -0,0 +1,66 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! A radix-2 complex FFT, used by the [crate::Convolver] and the test helpers.
+
+use crate::{f, Flt};
+
+/// A radix-2 complex FFT with precomputed twiddle factors.
+#[derive(Debug, Clone)]
+pub(crate) struct Fft<F: Flt> {
+    cos: Vec<F>,
+    sin: Vec<F>,
+    rev: Vec<usize>,
+}
+
+impl<F: Flt> Fft<F> {
+    /// Creates an FFT of size `n`, which must be a power of two.
+    pub(crate) fn new(n: usize) -> Self {
+        let bits = n.trailing_zeros();
+        let rev = (0..n)
+            .map(|i| if bits == 0 { 0 } else { i.reverse_bits() >> (usize::BITS - bits) })
+            .collect();
+        let (sin, cos) = (0..n / 2)
+            .map(|k| {
+                let (s, c) = (-std::f64::consts::TAU * k as f64 / n as f64).sin_cos();
+                (f::<F>(s), f::<F>(c))
+            })
+            .unzip();
+
+        Self { cos, sin, rev }
+    }
+
+    /// Transforms `re` and `im` in place. The inverse transform is not normalized,
+    /// the result has to be divided by the size.
+    pub(crate) fn transform(&self, re: &mut [F], im: &mut [F], inverse: bool) {
+        let n = self.rev.len();
+        for i in 0..n {
+            let j = self.rev[i];
+            if i < j {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= n {
+            let step = n / len;
+            let half = len / 2;
+            for start in (0..n).step_by(len) {
+                for k in 0..half {
+                    let w_re = self.cos[k * step];
+                    let w_im = if inverse { -self.sin[k * step] } else { self.sin[k * step] };
+                    let (a, b) = (start + k, start + k + half);
+                    let t_re = re[b] * w_re - im[b] * w_im;
+                    let t_im = re[b] * w_im + im[b] * w_re;
+                    re[b] = re[a] - t_re;
+                    im[b] = im[a] - t_im;
+                    re[a] = re[a] + t_re;
+                    im[a] = im[a] + t_im;
+                }
+            }
+            len <<= 1;
+        }
+    }
+}
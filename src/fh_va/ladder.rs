@@ -7,14 +7,90 @@
 // Copied under GPL-3.0-or-later from https://github.com/Fredemus/va-filter
 
 use crate::fh_va::FilterParams;
-use std::simd::*;
-use std::simd::cmp::SimdPartialEq;
-use std::simd::cmp::SimdPartialOrd;
-use std::simd::prelude::SimdFloat;
-use std::sync::Arc;
+use crate::simd::*;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
 
 use super::{LadderMode, get_ladder_mix};
 
+/// The sample type a [LadderFilter] processes. Implemented for `f32x4`, which processes
+/// 4 channels at once, and for `f64`, which processes one channel in double precision.
+pub trait LadderSample:
+    Copy
+    + std::fmt::Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+{
+    /// Converts `v` to a sample, with `v` in all channels.
+    fn from_f64(v: f64) -> Self;
+    /// Returns the filter coefficients `g` and `k_ladder` of the `params`.
+    fn coefs(params: &FilterParams) -> (Self, Self);
+    /// The tanh approximation [crate::tanh_levien] of the sample.
+    fn tanh_levien(self) -> Self;
+    /// Calculates `tanh_levien(x) / x`, which is 1.0 for `x == 0.0`.
+    fn tanh_levien_div(self) -> Self;
+    /// Returns true if the absolute value of any channel is greater than `limit`.
+    fn any_abs_gt(self, limit: f64) -> bool;
+}
+
+impl LadderSample for f32x4 {
+    #[inline(always)]
+    fn from_f64(v: f64) -> Self {
+        f32x4::splat(v as f32)
+    }
+    #[inline(always)]
+    fn coefs(params: &FilterParams) -> (Self, Self) {
+        (f32x4::splat(params.g_ladder), f32x4::splat(params.k_ladder))
+    }
+    #[inline(always)]
+    fn tanh_levien(self) -> Self {
+        crate::tanh_levien(self)
+    }
+    #[inline(always)]
+    fn tanh_levien_div(self) -> Self {
+        // hopefully this should cook down to the original when not 0,
+        // and 1 when 0
+        let mask = self.simd_ne(f32x4::splat(0.));
+        // since the division can become NaN or other stuff when a value is 0,
+        // replace values where it is 0.
+        mask.select(crate::tanh_levien(self) / self, f32x4::splat(1.))
+    }
+    #[inline(always)]
+    fn any_abs_gt(self, limit: f64) -> bool {
+        self.abs().simd_gt(f32x4::splat(limit as f32)).any()
+    }
+}
+
+impl LadderSample for f64 {
+    #[inline(always)]
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+    #[inline(always)]
+    fn coefs(params: &FilterParams) -> (Self, Self) {
+        (params.g_ladder_f64, params.k_ladder as f64)
+    }
+    #[inline(always)]
+    fn tanh_levien(self) -> Self {
+        crate::tanh_levien_f64(self)
+    }
+    #[inline(always)]
+    fn tanh_levien_div(self) -> Self {
+        if self == 0. {
+            1.
+        } else {
+            crate::tanh_levien_f64(self) / self
+        }
+    }
+    #[inline(always)]
+    fn any_abs_gt(self, limit: f64) -> bool {
+        self.abs() > limit
+    }
+}
+
 #[allow(dead_code)]
 #[derive(PartialEq, Clone, Copy)]
 enum EstimateSource {
@@ -41,38 +117,148 @@ enum EstimateSource {
 /// 
 /// By mixing the output of the different stages, and the output of the
 /// feedback, we can create many other filter types. See `LadderMode`
+///
+/// The filter processes `f32x4` samples by default, see [LadderSample].
+/// Use [LadderFilterF64] for double precision processing of a single channel.
+///
+/// The filter owns its [FilterParams], change them with the setters like
+/// [LadderFilter::set_cutoff].
 #[derive(Debug, Clone)]
-pub struct LadderFilter {
-    pub params: Arc<FilterParams>,
+pub struct LadderFilter<T: LadderSample = f32x4> {
+    params: FilterParams,
+
+    vout: [T; 4],
+    pub s: [T; 4],
+    mix: [T; 5],
 
-    vout: [f32x4; 4],
-    pub s: [f32x4; 4],
-    mix: [f32x4; 5],
+    /// Whether the drive is smoothed, see [LadderFilter::set_drive_smoothed].
+    drive_smoothed: bool,
+    drive: f32,
+    drive_target: f32,
+    drive_smooth_ms: f32,
+    drive_coef: f32,
 }
+/// A [LadderFilter] that processes one channel in double precision.
+///
+///```
+/// use synfx_dsp::fh_va::{FilterParams, LadderFilterF64, LadderMode};
+///
+/// let mut ladder = LadderFilterF64::new(FilterParams::new());
+/// ladder.set_sample_rate(96000.0);
+/// ladder.set_cutoff(20.0);
+/// ladder.set_mix(LadderMode::LP24);
+/// let k = ladder.params().k_ladder as f64;
+///
+/// // A very low cutoff, where the tiny state updates suffer from single precision:
+/// let mut out = 0.0;
+/// for _ in 0..192000 {
+///     out = ladder.tick_linear(0.001);
+/// }
+/// // The DC gain of the (almost linear) ladder is `1 / (1 + k)`:
+/// assert!((out - 0.001 / (1.0 + k)).abs() < 1e-9);
+///```
+pub type LadderFilterF64 = LadderFilter<f64>;
+
 #[allow(dead_code)]
-impl LadderFilter {
-    pub fn new(params: Arc<FilterParams>) -> Self {
+impl<T: LadderSample> LadderFilter<T> {
+    pub fn new(params: FilterParams) -> Self {
         let mut a = Self {
             params,
-            vout: [f32x4::splat(0.); 4],
-            s: [f32x4::splat(0.); 4],
-            mix: [f32x4::splat(0.); 5],
+            vout: [T::from_f64(0.); 4],
+            s: [T::from_f64(0.); 4],
+            mix: [T::from_f64(0.); 5],
+            drive_smoothed: false,
+            drive: 1.0,
+            drive_target: 1.0,
+            drive_smooth_ms: 0.0,
+            drive_coef: 1.0,
         };
         a.set_mix(LadderMode::LP6);
         a
     }
     pub fn reset(&mut self) {
-        self.s = [f32x4::splat(0.); 4];
+        self.s = [T::from_f64(0.); 4];
+        self.drive = self.drive_target;
+    }
+    /// Returns the current filter parameters.
+    pub fn params(&self) -> &FilterParams {
+        &self.params
+    }
+    /// Copies all filter parameters from `params`, eg. from a shared set of parameters.
+    pub fn set_params(&mut self, params: &FilterParams) {
+        self.params = params.clone();
+        self.update_drive_coef();
+    }
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.params.set_sample_rate(srate);
+        self.update_drive_coef();
+    }
+    /// Sets the cutoff frequency, 5.0 Hz to 20 kHz.
+    pub fn set_cutoff(&mut self, freq: f32) {
+        self.params.set_frequency(freq);
+    }
+    /// Sets the resonance, 0.0 to 1.0.
+    pub fn set_resonance(&mut self, res: f32) {
+        self.params.set_resonance(res);
+    }
+    /// Sets the feedback directly, see [FilterParams::set_ladder_feedback].
+    pub fn set_feedback(&mut self, k_ladder: f32) {
+        self.params.set_ladder_feedback(k_ladder);
+    }
+    /// Enables the self-oscillation tuning, see [FilterParams::set_ladder_osc_tuning].
+    pub fn set_osc_tuning(&mut self, enabled: bool) {
+        self.params.set_ladder_osc_tuning(enabled);
+    }
+    /// Sets the drive, 1.0 to 15.8490 (gain to dB). The new drive is used immediately,
+    /// see also [LadderFilter::set_drive_smoothed].
+    pub fn set_drive(&mut self, drive: f32) {
+        self.params.drive = drive;
+    }
+    /// Sets the drive to `target` with a one-pole smoothing of `smooth_ms` milliseconds,
+    /// which is advanced with every `tick_*` call. This makes drive automation click free.
+    ///
+    /// Once called, the smoothed drive is used instead of the one set with
+    /// [LadderFilter::set_drive], until [LadderFilter::disable_drive_smoothing] is called.
+    /// Callers that smooth the drive upstream can just keep calling [LadderFilter::set_drive].
+    pub fn set_drive_smoothed(&mut self, target: f32, smooth_ms: f32) {
+        if !self.drive_smoothed {
+            self.drive_smoothed = true;
+            self.drive = self.params.drive;
+        }
+        self.drive_target = target;
+        self.drive_smooth_ms = smooth_ms;
+        self.update_drive_coef();
+    }
+    /// Recalculates the drive smoothing coefficient, after the smoothing time
+    /// or the sample rate changed.
+    fn update_drive_coef(&mut self) {
+        let samples = self.drive_smooth_ms * self.params.sample_rate / 1000.0;
+        self.drive_coef = if samples < 1.0 { 1.0 } else { 1.0 - (-1.0 / samples).exp() };
+    }
+    /// Goes back to using the drive of [LadderFilter::set_drive] directly,
+    /// see [LadderFilter::set_drive_smoothed].
+    pub fn disable_drive_smoothing(&mut self) {
+        self.drive_smoothed = false;
+    }
+    /// Advances the drive smoothing and returns the drive for the current sample.
+    #[inline(always)]
+    fn next_drive(&mut self) -> T {
+        if self.drive_smoothed {
+            self.drive += (self.drive_target - self.drive) * self.drive_coef;
+            T::from_f64(self.drive as f64)
+        } else {
+            T::from_f64(self.params.drive as f64)
+        }
     }
     pub fn set_mix(&mut self, mode: LadderMode) {
         let mix = get_ladder_mix(mode);
 
         for i in 0..self.mix.len() {
-            self.mix[i] = f32x4::splat(mix[i]);
+            self.mix[i] = T::from_f64(mix[i] as f64);
         }
     }
 
-    fn get_estimate(&mut self, n: usize, estimate: EstimateSource, input: f32x4) -> f32x4 {
+    fn get_estimate(&mut self, n: usize, estimate: EstimateSource, input: T) -> T {
         // if we ask for an estimate based on the linear filter, we have to run it
         if estimate == EstimateSource::LinearStateEstimate
             || estimate == EstimateSource::LinearVoutEstimate
@@ -82,37 +268,30 @@ impl LadderFilter {
         match estimate {
             EstimateSource::State => self.s[n],
             EstimateSource::PreviousVout => self.vout[n],
-            EstimateSource::LinearStateEstimate => f32x4::splat(2.) * self.vout[n] - self.s[n],
+            EstimateSource::LinearStateEstimate => T::from_f64(2.) * self.vout[n] - self.s[n],
             EstimateSource::LinearVoutEstimate => self.vout[n],
         }
     }
     #[inline(always)]
     fn update_state(&mut self) {
-        let two = f32x4::splat(2.);
+        let two = T::from_f64(2.);
         self.s[0] = two * self.vout[0] - self.s[0];
         self.s[1] = two * self.vout[1] - self.s[1];
         self.s[2] = two * self.vout[2] - self.s[2];
         self.s[3] = two * self.vout[3] - self.s[3];
     }
     // nonlinear ladder filter function with distortion, solved with Mystran's fixed-pivot method.
-    fn run_filter_pivotal(&mut self, input: f32x4) -> f32x4 {
-        let mut a: [f32x4; 5] = [f32x4::splat(1.); 5];
+    fn run_filter_pivotal(&mut self, input: T) -> T {
+        let mut a: [T; 5] = [T::from_f64(1.); 5];
         // let base = [input, self.s[0], self.s[1], self.s[2], self.s[3]];
-        let g = f32x4::splat(self.params.g);
-        let k = f32x4::splat(self.params.k_ladder);
+        let (g, k) = T::coefs(&self.params);
         let base = [input - k * self.s[3], self.s[0], self.s[1], self.s[2], self.s[3]];
         // a[n] is the fixed-pivot approximation for tanh()
         for n in 0..base.len() {
-            // hopefully this should cook down to the original when not 0,
-            // and 1 when 0
-            let mask = base[n].simd_ne(f32x4::splat(0.));
-            a[n] = crate::tanh_levien(base[n]) / base[n];
-            // since the line above can become NaN or other stuff when a value in base[n] is 0,
-            // replace values where a[n] is 0.
-            a[n] = mask.select(a[n], f32x4::splat(1.));
+            a[n] = base[n].tanh_levien_div();
         }
         // denominators of solutions of individual stages. Simplifies the math a bit
-        let one = f32x4::splat(1.);
+        let one = T::from_f64(1.);
         let g0 = one / (one + g * a[1]);
         let g1 = one / (one + g * a[2]);
         let g2 = one / (one + g * a[3]);
@@ -137,11 +316,10 @@ impl LadderFilter {
         self.pole_mix(input - k * self.vout[3])
     }
     // linear version without distortion
-    fn run_filter_linear(&mut self, input: f32x4) -> f32x4 {
+    fn run_filter_linear(&mut self, input: T) -> T {
         // denominators of solutions of individual stages. Simplifies the math a bit
-        let g = f32x4::splat(self.params.g);
-        let k = f32x4::splat(self.params.k_ladder);
-        let one = f32x4::splat(1.);
+        let (g, k) = T::coefs(&self.params);
+        let one = T::from_f64(1.);
         let g0 = one / (one + g);
         let g1 = g * g0 * g0;
         let g2 = g * g1 * g0;
@@ -156,45 +334,42 @@ impl LadderFilter {
         self.vout[2] = g0 * (g * self.vout[1] + self.s[2]);
         self.pole_mix(input - k * self.vout[3])
     }
-    fn run_filter_newton(&mut self, input: f32x4) -> f32x4 {
+    fn run_filter_newton(&mut self, input: T) -> T {
         //d// println!(
         //d//     "sr={} cutoff={}, res={}, drive={}",
         //d//     self.params.sample_rate, self.params.cutoff, self.params.res, self.params.drive
         //d// );
         // ---------- setup ----------
         // load in g and k from parameters
-        let g = f32x4::splat(self.params.g);
-        let k = f32x4::splat(self.params.k_ladder);
+        let (g, k) = T::coefs(&self.params);
         //d// println!("input={:?} G={:?}, K={:?}", input.as_array(), g.as_array(), k.as_array());
         // a[n] is the fixed-pivot approximation for whatever is being processed nonlinearly
-        let mut v_est: [f32x4; 4];
-        let mut temp: [f32x4; 4] = [f32x4::splat(0.); 4];
+        let mut v_est: [T; 4];
+        let mut temp: [T; 4] = [T::from_f64(0.); 4];
 
         // use state as estimate
         v_est = [self.s[0], self.s[1], self.s[2], self.s[3]];
 
-        let mut tanh_input = crate::tanh_levien(input - k * v_est[3]);
-        let mut tanh_y1_est = crate::tanh_levien(v_est[0]);
-        let mut tanh_y2_est = crate::tanh_levien(v_est[1]);
-        let mut tanh_y3_est = crate::tanh_levien(v_est[2]);
-        let mut tanh_y4_est = crate::tanh_levien(v_est[3]);
+        let mut tanh_input = T::tanh_levien(input - k * v_est[3]);
+        let mut tanh_y1_est = T::tanh_levien(v_est[0]);
+        let mut tanh_y2_est = T::tanh_levien(v_est[1]);
+        let mut tanh_y3_est = T::tanh_levien(v_est[2]);
+        let mut tanh_y4_est = T::tanh_levien(v_est[3]);
         let mut residue = [
             g * (tanh_input - tanh_y1_est) + self.s[0] - v_est[0],
             g * (tanh_y1_est - tanh_y2_est) + self.s[1] - v_est[1],
             g * (tanh_y2_est - tanh_y3_est) + self.s[2] - v_est[2],
             g * (tanh_y3_est - tanh_y4_est) + self.s[3] - v_est[3],
         ];
-        // let max_error = 0.00001;
-        let max_error = f32x4::splat(0.00001);
-
-        // f32x4.lt(max_error) returns a mask.
-        while residue[0].abs().simd_gt(max_error).any()
-            || residue[1].abs().simd_gt(max_error).any()
-            || residue[2].abs().simd_gt(max_error).any()
-            || residue[3].abs().simd_gt(max_error).any()
+        let max_error = 0.00001;
+
+        while residue[0].any_abs_gt(max_error)
+            || residue[1].any_abs_gt(max_error)
+            || residue[2].any_abs_gt(max_error)
+            || residue[3].any_abs_gt(max_error)
         // && n_iterations < 9
         {
-            let one = f32x4::splat(1.);
+            let one = T::from_f64(1.);
             // jacobian matrix
             let j10 = g * (one - tanh_y1_est * tanh_y1_est);
             let j00 = -j10 - one;
@@ -216,11 +391,11 @@ impl LadderFilter {
             temp[3] = (j32 * v_est[2] - j32 * temp[2] + j33 * v_est[3] - residue[3]) / (j33);
 
             v_est = temp;
-            tanh_input = crate::tanh_levien(input - k * v_est[3]);
-            tanh_y1_est = crate::tanh_levien(v_est[0]);
-            tanh_y2_est = crate::tanh_levien(v_est[1]);
-            tanh_y3_est = crate::tanh_levien(v_est[2]);
-            tanh_y4_est = crate::tanh_levien(v_est[3]);
+            tanh_input = T::tanh_levien(input - k * v_est[3]);
+            tanh_y1_est = T::tanh_levien(v_est[0]);
+            tanh_y2_est = T::tanh_levien(v_est[1]);
+            tanh_y3_est = T::tanh_levien(v_est[2]);
+            tanh_y4_est = T::tanh_levien(v_est[3]);
 
             residue = [
                 g * (tanh_input - tanh_y1_est) + self.s[0] - v_est[0],
@@ -234,23 +409,25 @@ impl LadderFilter {
         self.pole_mix(input - k * self.vout[3])
     }
     /// performs a complete filter process (newton-raphson method)
-    pub fn tick_newton(&mut self, input: f32x4) -> f32x4 {
+    pub fn tick_newton(&mut self, input: T) -> T {
         // perform filter process
-        let out = self.run_filter_newton(input * f32x4::splat(self.params.drive));
+        let drive = self.next_drive();
+        let out = self.run_filter_newton(input * drive);
         // update ic1eq and ic2eq for next sample
         self.update_state();
         out
     }
     /// performs a complete filter process (solved with Mystran's fixed-pivot method).
-    pub fn tick_pivotal(&mut self, input: f32x4) -> f32x4 {
+    pub fn tick_pivotal(&mut self, input: T) -> T {
         // perform filter process
-        let out = self.run_filter_pivotal(input * f32x4::splat(self.params.drive));
+        let drive = self.next_drive();
+        let out = self.run_filter_pivotal(input * drive);
         // update ic1eq and ic2eq for next sample
         self.update_state();
         out
     }
     /// performs a complete filter process (linear without distortion)
-    pub fn tick_linear(&mut self, input: f32x4) -> f32x4 {
+    pub fn tick_linear(&mut self, input: T) -> T {
         // perform filter process
         // let out = self.run_filter_linear(input * f32x4::splat(self.params.drive.value));
         let out = self.run_filter_linear(input);
@@ -258,8 +435,15 @@ impl LadderFilter {
         self.update_state();
         out
     }
+    /// Processes a block of samples with [LadderFilter::tick_pivotal].
+    /// Only `min(input.len(), output.len())` samples are processed.
+    pub fn process_block(&mut self, input: &[T], output: &mut [T]) {
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = self.tick_pivotal(*i);
+        }
+    }
     #[inline(always)]
-    fn pole_mix(&self, input: f32x4) -> f32x4 {
+    fn pole_mix(&self, input: T) -> T {
         let mut sum = self.mix[0] * input;
         for i in 0..4 {
             sum += self.mix[i + 1] * self.vout[i];
@@ -267,3 +451,115 @@ impl LadderFilter {
         sum
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_ladder_drive_step_is_smoothed() {
+        let input = f32x4::splat(0.2);
+        let mut params = FilterParams::new();
+        params.set_frequency(5000.0);
+        params.set_resonance(0.0);
+
+        let mut plain = LadderFilter::new(params.clone());
+        let mut smooth = LadderFilter::new(params);
+        smooth.set_drive_smoothed(1.0, 20.0);
+        for _ in 0..4410 {
+            plain.tick_newton(input);
+            smooth.tick_newton(input);
+        }
+
+        // The unsmoothed drive jumps, the output level does too:
+        let before = plain.tick_newton(input)[0].abs();
+        plain.set_drive(8.0);
+        let after = plain.tick_newton(input)[0].abs();
+        assert!(after - before > 0.05);
+
+        smooth.set_drive_smoothed(8.0, 20.0);
+        let out: Vec<f32> = (0..4410).map(|_| smooth.tick_newton(input)[0].abs()).collect();
+        let max_step = out.windows(2).map(|w| w[1] - w[0]).fold(0.0, f32::max);
+        assert!(max_step < 0.005, "max_step={}", max_step);
+        assert!(out[4409] - out[0] > 0.3);
+        crate::assert_monotonic_rising!(out);
+    }
+
+    #[test]
+    fn check_ladder_drive_smoothing_follows_sample_rate() {
+        let input = f32x4::splat(0.2);
+
+        let mut before = LadderFilter::new(FilterParams::new());
+        before.set_sample_rate(88200.0);
+        before.set_drive_smoothed(8.0, 20.0);
+
+        let mut after = LadderFilter::new(FilterParams::new());
+        after.set_drive_smoothed(8.0, 20.0);
+        after.set_sample_rate(88200.0);
+
+        for _ in 0..4410 {
+            assert_eq!(before.tick_newton(input)[0], after.tick_newton(input)[0]);
+        }
+    }
+
+    #[test]
+    fn check_ladder_feedback_survives_sample_rate_change() {
+        let mut ladder = LadderFilterF64::new(FilterParams::new());
+        ladder.set_cutoff(1000.0);
+        ladder.set_resonance(0.0);
+        ladder.set_feedback(5.0);
+        ladder.set_sample_rate(48000.0);
+        ladder.set_mix(LadderMode::LP24);
+        assert_eq!(ladder.params().k_ladder, 5.0);
+
+        // Still self-oscillates after an impulse:
+        let out: Vec<f64> =
+            (0..48000).map(|i| ladder.tick_newton(if i < 10 { 0.1 } else { 0.0 })).collect();
+        let peak = out[24000..].iter().fold(0.0_f64, |m, s| m.max(s.abs()));
+        assert!(peak > 0.1, "peak={}", peak);
+
+        let mut a = FilterParams::new();
+        a.set_ladder_feedback(5.0);
+        let mut b = FilterParams::new();
+        b.set_ladder_feedback(4.0);
+        assert_eq!(FilterParams::lerp(&a, &b, 0.0).k_ladder, 5.0);
+        assert!((FilterParams::lerp(&a, &b, 0.5).k_ladder - 4.5).abs() < 1e-6);
+    }
+
+    /// Returns the frequency the ladder self-oscillates at after a short impulse,
+    /// measured from the zero crossings of the second half of 2 seconds output.
+    fn self_osc_freq(cutoff: f32, res: f32, tuning: bool) -> f64 {
+        let mut ladder = LadderFilterF64::new(FilterParams::new());
+        ladder.set_osc_tuning(tuning);
+        ladder.set_cutoff(cutoff);
+        ladder.set_resonance(res);
+        ladder.set_mix(LadderMode::LP24);
+
+        let out: Vec<f64> =
+            (0..88200).map(|i| ladder.tick_newton(if i < 10 { 0.1 } else { 0.0 })).collect();
+
+        let crossings: Vec<f64> = out[44100..]
+            .windows(2)
+            .enumerate()
+            .filter(|(_, w)| w[0] < 0.0 && w[1] >= 0.0)
+            .map(|(i, w)| i as f64 - w[0] / (w[1] - w[0]))
+            .collect();
+        let periods = (crossings.len() - 1) as f64;
+        periods * 44100.0 / (crossings[crossings.len() - 1] - crossings[0])
+    }
+
+    #[test]
+    fn check_ladder_self_osc_tuning() {
+        let cents = |f: f64, cutoff: f32| 1200.0 * (f / cutoff as f64).log2();
+
+        // Without the tuning, the full resonance oscillates flat:
+        assert!(cents(self_osc_freq(1000.0, 1.0, false), 1000.0) < -20.0);
+
+        for cutoff in [110.0, 1000.0, 5000.0, 12000.0] {
+            for res in [0.98, 1.0, 1.05] {
+                let c = cents(self_osc_freq(cutoff, res, true), cutoff);
+                assert!(c.abs() < 2.0, "cutoff={} res={} cents={}", cutoff, res, c);
+            }
+        }
+    }
+}
@@ -15,10 +15,112 @@ use solver::DKSolver;
 mod sallen_key;
 mod svf;
 
-pub use ladder::LadderFilter;
+pub use ladder::{LadderFilter, LadderFilterF64, LadderSample};
 pub use sallen_key::SallenKey;
 pub use svf::Svf;
 
+use std::ops::{Add, Div, Mul, Neg, Sub, SubAssign};
+
+/// The sample type of the DK-method filter cores of [Svf] and [SallenKey], `f32` or `f64`.
+/// The `f32` processing keeps the filter state and coefficients in single precision,
+/// only the nonlinear solver always runs in double precision.
+trait DKSample:
+    Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + SubAssign
+{
+    fn from_f32(v: f32) -> Self;
+    fn from_f64(v: f64) -> Self;
+    fn into_f64(self) -> f64;
+    /// Returns the `g` of the `params` in the precision of the sample type.
+    fn g(params: &FilterParams) -> f64;
+    fn clamp(self, min: Self, max: Self) -> Self;
+    fn is_finite(self) -> bool;
+}
+
+impl DKSample for f32 {
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v
+    }
+    #[inline(always)]
+    fn from_f64(v: f64) -> Self {
+        v as f32
+    }
+    #[inline(always)]
+    fn into_f64(self) -> f64 {
+        self as f64
+    }
+    #[inline(always)]
+    fn g(params: &FilterParams) -> f64 {
+        params.g as f64
+    }
+    #[inline(always)]
+    fn clamp(self, min: Self, max: Self) -> Self {
+        f32::clamp(self, min, max)
+    }
+    #[inline(always)]
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+}
+
+impl DKSample for f64 {
+    #[inline(always)]
+    fn from_f32(v: f32) -> Self {
+        v as f64
+    }
+    #[inline(always)]
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+    #[inline(always)]
+    fn into_f64(self) -> f64 {
+        self
+    }
+    #[inline(always)]
+    fn g(params: &FilterParams) -> f64 {
+        params.g_f64
+    }
+    #[inline(always)]
+    fn clamp(self, min: Self, max: Self) -> Self {
+        f64::clamp(self, min, max)
+    }
+    #[inline(always)]
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
+
+/// The self-oscillation frequency of the [crate::fh_va::LadderFilter] (solved with
+/// [crate::fh_va::LadderFilter::tick_newton]) relative to the cutoff, for `k_ladder`
+/// from 4.0 to 6.0 in steps of 0.1. The non-linear stages lower the pitch
+/// the more, the louder the oscillation gets.
+const LADDER_OSC_PITCH: [f32; 21] = [
+    1.0000, 0.9941, 0.9887, 0.9839, 0.9795, 0.9755, 0.9719, 0.9686, 0.9655, 0.9627, 0.9602, 0.9578,
+    0.9556, 0.9536, 0.9517, 0.9500, 0.9484, 0.9469, 0.9455, 0.9442, 0.9430,
+];
+
+/// How much the detuning of [LADDER_OSC_PITCH] shrinks towards the Nyquist frequency,
+/// relative to the squared cutoff to sample rate ratio.
+const LADDER_OSC_PITCH_WARP: f32 = 5.0;
+
+/// Returns the factor the cutoff of the ladder has to be raised by, so that it
+/// self-oscillates at the cutoff with the feedback `k_ladder`.
+/// `rel_freq` is the cutoff divided by the sample rate.
+fn ladder_osc_compensation(k_ladder: f32, rel_freq: f32) -> f32 {
+    let pos = ((k_ladder - 4.0) * 10.0).clamp(0.0, (LADDER_OSC_PITCH.len() - 1) as f32);
+    let i = (pos as usize).min(LADDER_OSC_PITCH.len() - 2);
+    let f = pos - i as f32;
+    let detune = 1.0 - (LADDER_OSC_PITCH[i] * (1.0 - f) + LADDER_OSC_PITCH[i + 1] * f);
+    let warp = (1.0 - LADDER_OSC_PITCH_WARP * rel_freq * rel_freq).max(0.0);
+    1.0 / (1.0 - detune * warp)
+}
+
 /// The SVF filter mode (LP, HP, BP1, Notch, BP2)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SvfMode {
@@ -44,8 +146,18 @@ pub struct FilterParams {
     /// The Ladder filter mode.
     pub ladder_mode: LadderMode,
 
-    /// Calculated by the [FilterParams::set_frequency] function.
+    /// Calculated by the [FilterParams::set_frequency] function. Don't set it by hand,
+    /// the filters use the coefficients derived along with it by [FilterParams::set_frequency].
     pub g: f32,
+    /// The same as `g` in double precision, for the f64 processing functions.
+    g_f64: f64,
+    /// The `g` of the [crate::fh_va::LadderFilter], see [FilterParams::g_ladder].
+    g_ladder: f32,
+    /// The same as `g_ladder` in double precision, for the f64 processing functions.
+    g_ladder_f64: f64,
+    /// Whether the ladder is tuned to self-oscillate at the cutoff,
+    /// see [FilterParams::set_ladder_osc_tuning].
+    pub ladder_osc_tuning: bool,
     /// Use the [FilterParams::set_sample_rate] function to update this.
     pub sample_rate: f32,
     /// Resistance based internal parameter, set by [FilterParams::set_resonance].
@@ -63,8 +175,12 @@ impl FilterParams {
 
             mode: SvfMode::LP,
             ladder_mode: LadderMode::LP6,
+            ladder_osc_tuning: false,
 
             g: 0.0,
+            g_f64: 0.0,
+            g_ladder: 0.0,
+            g_ladder_f64: 0.0,
             sample_rate: 0.0,
             zeta: 0.0,
             k_ladder: 0.0,
@@ -81,20 +197,134 @@ impl FilterParams {
         self.zeta = 5. - 5.0 * res;
         //        self.k_ladder = res.powi(2) * 3.8 - 0.2;
         self.k_ladder = res.powi(2) * 4.5 - 0.2;
+        self.update_ladder_g();
+    }
+
+    /// Sets the feedback of the ladder directly, overriding the one derived from
+    /// the resonance until the next [FilterParams::set_resonance]. The ladder self-oscillates for `k_ladder >= 4.0`, louder and with more
+    /// distortion for higher values. Use this with [FilterParams::set_ladder_osc_tuning]
+    /// to play the ladder as a sine oscillator.
+    #[inline]
+    pub fn set_ladder_feedback(&mut self, k_ladder: f32) {
+        self.k_ladder = k_ladder;
+        self.update_ladder_g();
+    }
+
+    /// Enables the calibrated self-oscillation tuning of the ladder filter.
+    ///
+    /// With `k_ladder >= 4.0` the ladder self-oscillates. The tangent prewarping of `g`
+    /// already compensates the frequency warping of the bilinear transform, but the
+    /// saturation of the stages lowers the pitch of the oscillation by up to 6%
+    /// (about a semitone), depending on `k_ladder`. When enabled, the ladder cutoff is raised
+    /// by the measured detuning, so that the oscillation of
+    /// [crate::fh_va::LadderFilter::tick_newton] tracks the cutoff within a few cents.
+    /// [crate::fh_va::LadderFilter::tick_pivotal] is not calibrated, it drifts more.
+    ///
+    /// The filtering below self-oscillation (`k_ladder < 4.0`) is not changed.
+    /// The oscillation needs some input to start, at very low cutoffs
+    /// it might not build up at all.
+    ///
+    ///```
+    /// use synfx_dsp::fh_va::FilterParams;
+    ///
+    /// let mut params = FilterParams::new();
+    /// params.set_frequency(1000.0);
+    /// params.set_ladder_feedback(5.0);
+    /// assert_eq!(params.g_ladder(), params.g);
+    ///
+    /// params.set_ladder_osc_tuning(true);
+    /// assert!(params.g_ladder() > params.g);
+    ///```
+    #[inline]
+    pub fn set_ladder_osc_tuning(&mut self, enabled: bool) {
+        self.ladder_osc_tuning = enabled;
+        self.update_ladder_g();
     }
 
     #[inline]
     pub fn set_frequency(&mut self, freq: f32) {
         self.cutoff = freq;
         self.g = (std::f32::consts::PI * freq / self.sample_rate).tan();
+        self.g_f64 = (std::f64::consts::PI * freq as f64 / self.sample_rate as f64).tan();
+        self.update_ladder_g();
+    }
+
+    /// Returns the `g` of the [crate::fh_va::LadderFilter], which differs from `g` when
+    /// [FilterParams::set_ladder_osc_tuning] is enabled.
+    #[inline]
+    pub fn g_ladder(&self) -> f32 {
+        self.g_ladder
+    }
+
+    /// Recalculates `g_ladder`, after the cutoff or `k_ladder` changed.
+    #[inline]
+    fn update_ladder_g(&mut self) {
+        if self.ladder_osc_tuning && self.k_ladder > 4.0 {
+            let freq = (self.cutoff
+                * ladder_osc_compensation(self.k_ladder, self.cutoff / self.sample_rate))
+            .min(self.sample_rate * 0.49);
+            self.g_ladder = (std::f32::consts::PI * freq / self.sample_rate).tan();
+            self.g_ladder_f64 =
+                (std::f64::consts::PI * freq as f64 / self.sample_rate as f64).tan();
+        } else {
+            self.g_ladder = self.g;
+            self.g_ladder_f64 = self.g_f64;
+        }
     }
 
     #[inline]
     pub fn set_sample_rate(&mut self, sr: f32) {
         self.sample_rate = sr;
-        self.set_resonance(self.res);
         self.set_frequency(self.cutoff);
     }
+
+    /// Interpolates between the two filter configurations `a` and `b`, at `t` in the
+    /// range 0.0 (`a`) to 1.0 (`b`). Useful for morphing a filter between two settings.
+    ///
+    /// The cutoff is interpolated in the log domain, resonance, drive and the ladder
+    /// feedback `k_ladder` linearly. The other derived fields are recalculated. The modes snap from `a` to `b` at `t = 0.5`.
+    /// The sample rate is taken from `a`.
+    ///
+    ///```
+    /// use synfx_dsp::fh_va::FilterParams;
+    ///
+    /// let mut a = FilterParams::new();
+    /// a.set_frequency(100.0);
+    /// let mut b = FilterParams::new();
+    /// b.set_frequency(10000.0);
+    ///
+    /// let mid = FilterParams::lerp(&a, &b, 0.5);
+    /// assert!((mid.cutoff - 1000.0).abs() < 0.1);
+    ///```
+    pub fn lerp(a: &FilterParams, b: &FilterParams, t: f32) -> FilterParams {
+        let t = t.clamp(0.0, 1.0);
+        let modes = if t < 0.5 { a } else { b };
+
+        let cutoff_a = a.cutoff.max(1.0).ln();
+        let cutoff_b = b.cutoff.max(1.0).ln();
+
+        let mut params = Self {
+            cutoff: (cutoff_a + (cutoff_b - cutoff_a) * t).exp(),
+            res: a.res + (b.res - a.res) * t,
+            drive: a.drive + (b.drive - a.drive) * t,
+
+            mode: modes.mode,
+            ladder_mode: modes.ladder_mode,
+            ladder_osc_tuning: modes.ladder_osc_tuning,
+
+            g: 0.0,
+            g_f64: 0.0,
+            g_ladder: 0.0,
+            g_ladder_f64: 0.0,
+            sample_rate: a.sample_rate,
+            zeta: 0.0,
+            k_ladder: 0.0,
+        };
+        params.set_sample_rate(a.sample_rate);
+        params.set_resonance(params.res);
+        params.set_ladder_feedback(a.k_ladder + (b.k_ladder - a.k_ladder) * t);
+        params
+    }
 }
 
 /// The Ladder mode, You can choose between low pass, high pass, band pass and notch.
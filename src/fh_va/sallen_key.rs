@@ -6,12 +6,11 @@
 // VA filter implementation.
 // Copied under GPL-3.0-or-later from https://github.com/Fredemus/va-filter
 
-use crate::{fh_va::DKSolver, fh_va::FilterParams};
+use crate::{fh_va::DKSample, fh_va::DKSolver, fh_va::FilterParams};
 // use packed_simd::f32x4;
 // use core_simd::*;
 // use std_float::*;
-use std::sync::Arc;
-use std::simd::f32x4;
+use crate::simd::f32x4;
 
 //const N_P: usize = 2;
 //const N_N: usize = 4;
@@ -45,33 +44,99 @@ const TOL: f64 = 1e-5;
 /// compare, since most of the operations are dot products anyway, but the
 /// current fast version is definitely fast enough for real-time use in DAW
 /// projects.  pub struct SallenKey { filters: [SallenKeyCoreFast; 2], }
+///
+/// The filter owns its [FilterParams], change them with the setters like
+/// [SallenKey::set_cutoff], which also update the internal coefficients.
 #[derive(Debug, Clone)]
 pub struct SallenKey {
-    filters: [SallenKeyCoreFast; 2],
+    params: FilterParams,
+    filters: [SallenKeyCoreFast<f32>; 2],
+    filters_f64: [SallenKeyCoreFast<f64>; 2],
 }
 
 impl SallenKey {
-    pub fn new(params: Arc<FilterParams>) -> Self {
-        Self { filters: [SallenKeyCoreFast::new(params.clone()), SallenKeyCoreFast::new(params)] }
+    pub fn new(params: FilterParams) -> Self {
+        let mut this = Self {
+            filters: [SallenKeyCoreFast::new(&params), SallenKeyCoreFast::new(&params)],
+            filters_f64: [SallenKeyCoreFast::new(&params), SallenKeyCoreFast::new(&params)],
+            params,
+        };
+        this.update();
+        this
+    }
+    /// Returns the current filter parameters.
+    pub fn params(&self) -> &FilterParams {
+        &self.params
+    }
+    /// Copies all filter parameters from `params`, eg. from a shared set of parameters.
+    pub fn set_params(&mut self, params: &FilterParams) {
+        self.params = params.clone();
+        self.update();
+    }
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.params.set_sample_rate(srate);
+        self.update();
+    }
+    /// Sets the cutoff frequency, 5.0 Hz to 20 kHz.
+    pub fn set_cutoff(&mut self, freq: f32) {
+        self.params.set_frequency(freq);
+        self.update();
+    }
+    /// Sets the resonance, 0.0 to 1.0.
+    pub fn set_resonance(&mut self, res: f32) {
+        self.params.set_resonance(res);
+        self.update();
+    }
+    /// Sets the drive, 1.0 to 15.8490 (gain to dB).
+    pub fn set_drive(&mut self, drive: f32) {
+        self.params.drive = drive;
     }
     /// Process a stereo sample.
     pub fn process(&mut self, input: f32x4) -> f32x4 {
+        let p = &self.params;
         f32x4::from_array([
-            self.filters[0].tick_dk(input[0]),
-            self.filters[1].tick_dk(input[1]),
+            self.filters[0].tick_dk(p, input[0]),
+            self.filters[1].tick_dk(p, input[1]),
             0.,
             0.,
         ])
     }
-    /// Call this whenver the resonance or cutoff frequency of the [FilterParams] change.
-    pub fn update(&mut self) {
-        self.filters[0].update_matrices();
-        self.filters[1].update_matrices();
+    /// Process a block of stereo samples. Only `min(input.len(), output.len())`
+    /// samples are processed.
+    pub fn process_block(&mut self, input: &[f32x4], output: &mut [f32x4]) {
+        let p = &self.params;
+        let [left, right] = &mut self.filters;
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = f32x4::from_array([left.tick_dk(p, i[0]), right.tick_dk(p, i[1]), 0., 0.]);
+        }
+    }
+    /// Process a stereo sample in double precision.
+    pub fn process_f64(&mut self, input: [f64; 2]) -> [f64; 2] {
+        let p = &self.params;
+        [self.filters_f64[0].tick_dk(p, input[0]), self.filters_f64[1].tick_dk(p, input[1])]
+    }
+    /// Process a block of stereo samples in double precision.
+    /// Only `min(input.len(), output.len())` samples are processed.
+    pub fn process_block_f64(&mut self, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+        let p = &self.params;
+        let [left, right] = &mut self.filters_f64;
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = [left.tick_dk(p, i[0]), right.tick_dk(p, i[1])];
+        }
+    }
+    /// Updates the coefficients after the resonance or cutoff frequency changed.
+    fn update(&mut self) {
+        self.filters[0].update_matrices(&self.params);
+        self.filters[1].update_matrices(&self.params);
+        self.filters_f64[0].update_matrices(&self.params);
+        self.filters_f64[1].update_matrices(&self.params);
     }
     /// Reset the filter.
     pub fn reset(&mut self) {
         self.filters[0].reset();
         self.filters[1].reset();
+        self.filters_f64[0].reset();
+        self.filters_f64[1].reset();
     }
 }
 //pub struct SallenKeyCore {
@@ -367,13 +432,12 @@ const N_N2: usize = 3;
 const P_LEN2: usize = 6;
 /// this does the same as `SallenKeyCore`, but with most equations simplified to make it faster
 #[derive(Debug, Clone)]
-struct SallenKeyCoreFast {
-    pub params: Arc<FilterParams>,
-    pub vout: [f32; N_OUTS],
-    pub s: [f32; N_STATES],
+struct SallenKeyCoreFast<T: DKSample> {
+    pub vout: [T; N_OUTS],
+    pub s: [T; N_STATES],
 
     // used to find the nonlinear contributions
-    eq: [f32; N_P2],
+    eq: [T; N_P2],
     // pub fq: [[f64; N_N2]; P_LEN2],
     fq20: f64,
     fq22: f64,
@@ -384,43 +448,45 @@ struct SallenKeyCoreFast {
     fq52: f64,
 
     // used to update the capacitor states
-    b: [f32; N_STATES],
-    c: [[f32; N_N2]; N_STATES],
+    b: [T; N_STATES],
+    c: [[T; N_N2]; N_STATES],
 
     // used to find the output values
-    fy: [[f32; N_N2]; N_OUTS],
+    fy: [[T; N_N2]; N_OUTS],
 
     jq: [f64; 6],
 
     solver: DKSolver<N_N2, N_P2, P_LEN2>,
 }
 // here we flatten a bunch of stuff to hopefully make it faster
-impl SallenKeyCoreFast {
-    pub fn new(params: Arc<FilterParams>) -> Self {
+impl<T: DKSample> SallenKeyCoreFast<T> {
+    pub fn new(params: &FilterParams) -> Self {
         let fs = params.sample_rate;
         let g = (std::f32::consts::PI * 1000. / (fs as f32)).tan();
         let res = 0.1;
         let g_f64 = g as f64;
-        let res_f64 = res as f64;
+        let zero = T::from_f64(0.);
 
         let mut a = Self {
-            params,
-            vout: [0.; 1],
-            s: [0.; 2],
+            vout: [zero; 1],
+            s: [zero; 2],
 
-            eq: [0., 2. * g],
-            fq20: (0.25 + 0.5 * g_f64) / res_f64,
+            eq: [zero, T::from_f64(2. * g_f64)],
+            fq20: (0.25 + 0.5 * g_f64) / res,
             fq22: 2. * g_f64,
-            fq30: -0.25 / res_f64,
+            fq30: -0.25 / res,
             fq40: 0.25,
             fq42: (2. * g_f64 + 1.),
             fq50: -1.25,
             fq52: -(2. * g_f64 + 1.),
 
-            b: [4. * g, 0.],
-            c: [[0., 0., -4. * g], [g / res, 0., 4. * g]],
+            b: [T::from_f64(4. * g_f64), zero],
+            c: [
+                [zero, zero, T::from_f64(-4. * g_f64)],
+                [T::from_f64(g_f64 / res), zero, T::from_f64(4. * g_f64)],
+            ],
 
-            fy: [[-0.25 / res, 0., 0.]],
+            fy: [[T::from_f64(-0.25 / res), zero, zero]],
 
             jq: [0., -1., 0., -1., 0., 1.],
 
@@ -430,47 +496,47 @@ impl SallenKeyCoreFast {
 
         a
     }
-    pub fn update_matrices(&mut self) {
-        let g = self.params.g;
-        let res = (self.params.res * 0.79).clamp(0.01, 0.99);
-        let g_f64 = g as f64;
-        let res_f64 = res as f64;
+    pub fn update_matrices(&mut self, params: &FilterParams) {
+        let g = T::g(params);
+        let res = (T::from_f32(params.res) * T::from_f64(0.79))
+            .clamp(T::from_f64(0.01), T::from_f64(0.99));
+        let res_f64 = res.into_f64();
 
         self.fq30 = -0.25 / res_f64;
-        self.fq22 = 2. * g_f64;
-        self.fq20 = (0.25 + 0.5 * g_f64) / res_f64;
-        self.fq42 = 2. * g_f64 + 1.;
-        self.fq52 = -(2. * g_f64 + 1.);
+        self.fq22 = 2. * g;
+        self.fq20 = (0.25 + 0.5 * g) / res_f64;
+        self.fq42 = 2. * g + 1.;
+        self.fq52 = -(2. * g + 1.);
 
-        self.b[0] = 4. * g;
+        self.b[0] = T::from_f64(4. * g);
 
-        self.c[0][2] = -4. * g;
-        self.c[1][0] = g / res;
-        self.c[1][2] = 4. * g;
+        self.c[0][2] = T::from_f64(-4. * g);
+        self.c[1][0] = T::from_f64(g) / res;
+        self.c[1][2] = T::from_f64(4. * g);
 
-        self.eq[1] = 2. * g;
+        self.eq[1] = T::from_f64(2. * g);
 
-        self.fy[0][0] = -0.25 / res;
+        self.fy[0][0] = T::from_f64(-0.25) / res;
     }
 
-    pub fn tick_dk(&mut self, input: f32) -> f32 {
-        let input = input * self.params.drive;
+    pub fn tick_dk(&mut self, params: &FilterParams, input: T) -> T {
+        let input = input * T::from_f32(params.drive);
 
         // let p = dot(dq, s) + dot(eq, input);
         let mut p = [0f64; 2];
-        p[0] = self.s[1] as f64;
-        p[1] = (self.s[0] + self.eq[1] * input) as f64;
+        p[0] = self.s[1].into_f64();
+        p[1] = (self.s[0] + self.eq[1] * input).into_f64();
 
         // self.nonlinear_contribs(p);
         // find nonlinear contributions (values for solver.z that falls in the null-space described by fq), applying homotopy if it fails to converge
         self.homotopy_solver(p);
         // find output voltage(s)
-        self.vout[0] = self.fy[0][0] * self.solver.z[0] as f32;
+        let z0 = T::from_f64(self.solver.z[0]);
+        let z2 = T::from_f64(self.solver.z[2]);
+        self.vout[0] = self.fy[0][0] * z0;
         // update states
-        self.s[0] = self.s[0] + self.b[0] * input + self.solver.z[2] as f32 * self.c[0][2];
-        self.s[1] = self.s[1]
-            + self.solver.z[0] as f32 * self.c[1][0]
-            + self.solver.z[2] as f32 * self.c[1][2];
+        self.s[0] = self.s[0] + self.b[0] * input + z2 * self.c[0][2];
+        self.s[1] = self.s[1] + z0 * self.c[1][0] + z2 * self.c[1][2];
         self.vout[0]
     }
 
@@ -600,7 +666,7 @@ impl SallenKeyCoreFast {
     }
 
     pub fn reset(&mut self) {
-        self.s = [0.; 2];
+        self.s = [T::from_f64(0.); 2];
         self.solver.p_full = [0.; P_LEN2];
         self.evaluate_nonlinearities([0.; N_N2]);
         self.solver.set_extrapolation_origin([0.; N_P2], [0.; N_N2]);
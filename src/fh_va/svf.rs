@@ -6,9 +6,8 @@
 // VA filter implementation.
 // Copied under GPL-3.0-or-later from https://github.com/Fredemus/va-filter
 
-use crate::fh_va::{DKSolver, FilterParams, SvfMode};
-use std::sync::Arc;
-use std::simd::f32x4;
+use crate::fh_va::{DKSample, DKSolver, FilterParams, SvfMode};
+use crate::simd::f32x4;
 
 /// This is a 2-pole multimode filter.
 ///
@@ -47,9 +46,31 @@ use std::simd::f32x4;
 /// projects.  Sadly convergence varies too much for using simd-lanes for
 /// processing left and right at the same time to bring a big performance
 /// benefit.
+///
+/// The filter owns its [FilterParams], change them with the setters like
+/// [Svf::set_cutoff], which also update the internal coefficients.
+///
+///```
+/// use synfx_dsp::fh_va::{FilterParams, Svf, SvfMode};
+///
+/// let mut svf = Svf::new(FilterParams::new());
+/// svf.set_mode(SvfMode::LP);
+/// svf.set_cutoff(100.0);
+/// svf.set_resonance(0.1);
+///
+/// let mut out = [0.0; 2];
+/// for _ in 0..44100 {
+///     out = svf.process_f64([0.5, -0.5]);
+/// }
+/// // The low pass passes DC, with the gain of 0.5 of the circuit:
+/// assert!((out[0] - 0.25).abs() < 0.001);
+/// assert!((out[1] + 0.25).abs() < 0.001);
+///```
 #[derive(Debug, Clone)]
 pub struct Svf {
-    filters: [SvfCoreFast; 2],
+    params: FilterParams,
+    filters: [SvfCoreFast<f32>; 2],
+    filters_f64: [SvfCoreFast<f64>; 2],
 }
 
 const N_P: usize = 3;
@@ -60,35 +81,106 @@ const N_STATES: usize = 2;
 const TOL: f64 = 1e-5;
 
 impl Svf {
-    pub fn new(params: Arc<FilterParams>) -> Self {
-        Self { filters: [SvfCoreFast::new(params.clone()), SvfCoreFast::new(params)] }
+    pub fn new(params: FilterParams) -> Self {
+        let mut this = Self {
+            filters: [SvfCoreFast::new(&params), SvfCoreFast::new(&params)],
+            filters_f64: [SvfCoreFast::new(&params), SvfCoreFast::new(&params)],
+            params,
+        };
+        this.update();
+        this
+    }
+    /// Returns the current filter parameters.
+    pub fn params(&self) -> &FilterParams {
+        &self.params
+    }
+    /// Copies all filter parameters from `params`, eg. from a shared set of parameters.
+    pub fn set_params(&mut self, params: &FilterParams) {
+        self.params = params.clone();
+        self.update();
+    }
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.params.set_sample_rate(srate);
+        self.update();
+    }
+    /// Sets the cutoff frequency, 5.0 Hz to 20 kHz.
+    pub fn set_cutoff(&mut self, freq: f32) {
+        self.params.set_frequency(freq);
+        self.update();
+    }
+    /// Sets the resonance, 0.0 to 1.0.
+    pub fn set_resonance(&mut self, res: f32) {
+        self.params.set_resonance(res);
+        self.update();
+    }
+    /// Sets the drive, 1.0 to 15.8490 (gain to dB).
+    pub fn set_drive(&mut self, drive: f32) {
+        self.params.drive = drive;
+    }
+    pub fn set_mode(&mut self, mode: SvfMode) {
+        self.params.mode = mode;
     }
     /// Process a stereo sample.
     pub fn process(&mut self, input: f32x4) -> f32x4 {
+        let p = &self.params;
         f32x4::from_array([
-            self.filters[0].tick_dk(input[0]),
-            self.filters[1].tick_dk(input[1]),
+            self.filters[0].tick_dk(p, input[0]),
+            self.filters[1].tick_dk(p, input[1]),
             0.,
             0.,
         ])
     }
-    /// Call this whenver the resonance or cutoff frequency of the [FilterParams] change.
-    pub fn update(&mut self) {
-        self.filters[0].update_matrices();
-        self.filters[1].update_matrices();
+    /// Process a block of stereo samples. Only `min(input.len(), output.len())`
+    /// samples are processed.
+    pub fn process_block(&mut self, input: &[f32x4], output: &mut [f32x4]) {
+        let p = &self.params;
+        let [left, right] = &mut self.filters;
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = f32x4::from_array([left.tick_dk(p, i[0]), right.tick_dk(p, i[1]), 0., 0.]);
+        }
+    }
+    /// Process a stereo sample in double precision.
+    pub fn process_f64(&mut self, input: [f64; 2]) -> [f64; 2] {
+        let p = &self.params;
+        [self.filters_f64[0].tick_dk(p, input[0]), self.filters_f64[1].tick_dk(p, input[1])]
+    }
+    /// Process a block of stereo samples in double precision.
+    /// Only `min(input.len(), output.len())` samples are processed.
+    pub fn process_block_f64(&mut self, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+        let p = &self.params;
+        let [left, right] = &mut self.filters_f64;
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = [left.tick_dk(p, i[0]), right.tick_dk(p, i[1])];
+        }
+    }
+    /// Updates the coefficients after the resonance or cutoff frequency changed.
+    fn update(&mut self) {
+        self.filters[0].update_matrices(&self.params);
+        self.filters[1].update_matrices(&self.params);
+        self.filters_f64[0].update_matrices(&self.params);
+        self.filters_f64[1].update_matrices(&self.params);
     }
     /// Reset the filter.
     pub fn reset(&mut self) {
         self.filters[0].reset();
         self.filters[1].reset();
+        self.filters_f64[0].reset();
+        self.filters_f64[1].reset();
+    }
+    /// Returns true if the nonlinear solver failed to converge or produced a non-finite
+    /// output since the last [Svf::reset]. In that case the linear output of the filter
+    /// was used instead, so that no NaN ends up in the audio stream.
+    pub fn had_instability(&self) -> bool {
+        self.filters.iter().any(|f| f.had_instability())
+            || self.filters_f64.iter().any(|f| f.had_instability())
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct SvfCoreFast {
-    pub params: Arc<FilterParams>,
-    pub vout: [f32; N_OUTS],
-    pub s: [f32; N_STATES],
+pub struct SvfCoreFast<T: DKSample> {
+    pub vout: [T; N_OUTS],
+    pub s: [T; N_STATES],
+    instability: bool,
 
     // the not-trivial coefficients in the model
     c1: f64,
@@ -98,8 +190,8 @@ pub struct SvfCoreFast {
     solver: DKSolver<N_N, N_P, P_LEN>,
 }
 
-impl SvfCoreFast {
-    pub fn new(params: Arc<FilterParams>) -> Self {
+impl<T: DKSample> SvfCoreFast<T> {
+    pub fn new(params: &FilterParams) -> Self {
         let fs = params.sample_rate;
         let g = (std::f32::consts::PI * 1000. / (fs as f32)).tan();
         let res = 0.1;
@@ -107,9 +199,9 @@ impl SvfCoreFast {
         let res_f64 = res as f64;
 
         let mut a = Self {
-            params,
-            vout: [0.; N_OUTS],
-            s: [0.; 2],
+            vout: [T::from_f64(0.); N_OUTS],
+            s: [T::from_f64(0.); 2],
+            instability: false,
 
             c1: 2. * g_f64,
             c2: res_f64,
@@ -121,37 +213,68 @@ impl SvfCoreFast {
         a
     }
 
-    pub fn update_matrices(&mut self) {
-        let g = self.params.g * 2.;
-        let res = self.params.zeta;
-        let g_f64 = g as f64;
-        let res_f64 = res as f64;
+    pub fn update_matrices(&mut self, params: &FilterParams) {
+        let g = T::g(params) * 2.;
+        let res = params.zeta as f64;
 
-        self.c1 = 2. * g_f64;
-        self.c2 = res_f64;
+        self.c1 = 2. * g;
+        self.c2 = res;
     }
-    pub fn tick_dk(&mut self, input: f32) -> f32 {
+    pub fn tick_dk(&mut self, params: &FilterParams, input: T) -> T {
         // -input since the svf inverts it
-        let input = -input * (self.params.drive);
+        let input = -input * T::from_f32(params.drive);
 
         let mut p = [0.; N_P];
 
-        p[0] = -self.s[0] as f64;
-        p[1] = -self.s[1] as f64;
-        p[2] = input as f64;
+        p[0] = -self.s[0].into_f64();
+        p[1] = -self.s[1].into_f64();
+        p[2] = input.into_f64();
 
         // find nonlinear contributions (solver.z), applying homotopy if it fails to converge
         self.homotopy_solver(p);
         // self.nonlinear_contribs(p);
 
-        self.vout[0] = self.solver.z[3] as f32;
-        self.vout[1] = self.solver.z[2] as f32;
-        self.vout[2] = self.solver.z[1] as f32;
+        // if even the homotopy failed, fall back to the linearized circuit
+        if self.solver.resmaxabs >= TOL || self.solver.z.iter().any(|z| !z.is_finite()) {
+            self.instability = true;
+            self.linear_contribs(p);
+        }
+
+        self.vout[0] = T::from_f64(self.solver.z[3]);
+        self.vout[1] = T::from_f64(self.solver.z[2]);
+        self.vout[2] = T::from_f64(self.solver.z[1]);
+
+        let two = T::from_f64(2.);
+        self.s[0] -= two * T::from_f64(self.c1 * self.solver.z[1]);
+        self.s[1] -= two * T::from_f64(self.c1 * self.solver.z[2]);
+
+        let out = self.get_output(params.mode, input, T::from_f32(params.zeta));
+        if out.is_finite() && self.s.iter().all(|s| s.is_finite()) {
+            out
+        } else {
+            // nothing sensible left to recover, eg. because the input was not finite
+            self.instability = true;
+            self.reset_state();
+            T::from_f64(0.)
+        }
+    }
 
-        self.s[0] = self.s[0] - 2. * (self.c1 * self.solver.z[1]) as f32;
-        self.s[1] = self.s[1] - 2. * (self.c1 * self.solver.z[2]) as f32;
+    pub fn had_instability(&self) -> bool {
+        self.instability
+    }
 
-        self.get_output(input, self.params.zeta)
+    // solves the circuit with all nonlinearities linearized around 0, this always succeeds
+    fn linear_contribs(&mut self, p: [f64; N_P]) {
+        let (_, jq_diode) = self.solver.eval_diodepair(0., 0., 1e-12, 1.28);
+        self.jq[0] = 1.;
+        self.jq[2] = 1.;
+        self.jq[4] = 1.;
+        self.jq[6] = jq_diode[0];
+
+        // the residue of the linearized circuit at z = 0
+        let residue = [0., p[0], p[1], -p[2]];
+        self.solver.z = self.solve_lin_equations(residue).map(|x| -x);
+        self.solver.set_extrapolation_origin(p, self.solver.z);
     }
 
     pub fn homotopy_solver(&mut self, p: [f64; N_P]) {
@@ -303,15 +426,19 @@ impl SvfCoreFast {
         x
     }
     pub fn reset(&mut self) {
-        self.s = [0.; 2];
+        self.instability = false;
+        self.reset_state();
+    }
+    fn reset_state(&mut self) {
+        self.s = [T::from_f64(0.); 2];
         self.solver.p_full = [0.; P_LEN];
         self.evaluate_nonlinearities([0.; N_N]);
         self.solver.set_extrapolation_origin([0.; N_P], [0.; N_N]);
     }
     // highpass and notch doesn't work right, likely because `input` isn't quite defined right. Prolly doesn't need to be subtracted?
     // ^ seems to be fixed now?
-    fn get_output(&self, input: f32, k: f32) -> f32 {
-        match self.params.mode {
+    fn get_output(&self, mode: SvfMode, input: T, k: T) -> T {
+        match mode {
             SvfMode::LP => self.vout[0],  // lowpass
             SvfMode::HP => self.vout[2],  // highpass
             SvfMode::BP1 => self.vout[1], // bandpass
@@ -323,3 +450,50 @@ impl SvfCoreFast {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_svf_never_outputs_nan() {
+        let mut svf = Svf::new(FilterParams::new());
+        svf.set_cutoff(1000.0);
+        svf.set_resonance(0.9);
+
+        for _ in 0..100 {
+            let out = svf.process(f32x4::splat(0.5));
+            assert!(out[0].is_finite());
+        }
+        assert!(!svf.had_instability());
+
+        let out = svf.process(f32x4::splat(f32::NAN));
+        assert_eq!(out[0], 0.0);
+        assert_eq!(out[1], 0.0);
+        assert!(svf.had_instability());
+
+        let out = svf.process(f32x4::splat(0.5));
+        assert!(out[0].is_finite());
+
+        svf.reset();
+        assert!(!svf.had_instability());
+    }
+
+    #[test]
+    fn check_svf_f64_matches_f32() {
+        let mut params = FilterParams::new();
+        params.set_frequency(1000.0);
+        params.set_resonance(0.5);
+        let mut svf = Svf::new(params.clone());
+        let mut svf64 = Svf::new(FilterParams::new());
+        svf64.set_params(&params);
+
+        for i in 0..1000 {
+            let x = if (i / 50) % 2 == 0 { 0.5 } else { -0.5 };
+            let out = svf.process(f32x4::splat(x));
+            let out64 = svf64.process_f64([x as f64, x as f64]);
+            assert!((out[0] as f64 - out64[0]).abs() < 1e-4);
+            assert_eq!(out64[0], out64[1]);
+        }
+    }
+}
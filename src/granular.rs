@@ -0,0 +1,247 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! Granular synthesis, the playback of many short overlapping grains from a sample buffer.
+
+use crate::{cubic_interpolate, Rng};
+
+/// The fraction of the grain length the fade in and fade out of
+/// [GrainEnvelope::Trapezoid] take.
+const GRAIN_TRAPEZOID_FADE: f32 = 0.25;
+
+/// The shortest grain size in milliseconds of the [GrainPlayer].
+const GRAIN_MIN_SIZE_MS: f32 = 1.0;
+
+/// The amplitude envelope of the grains of a [GrainPlayer].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrainEnvelope {
+    /// A Hann window, the smoothest grains with the least side bands.
+    Hann,
+    /// A linear fade in and out over a quarter of the grain each, with a flat top.
+    /// Keeps more of the source loudness and transients.
+    Trapezoid,
+}
+
+impl GrainEnvelope {
+    /// Returns the envelope gain at `phase` in the range 0.0 to 1.0 of the grain.
+    #[inline]
+    pub fn gain(&self, phase: f32) -> f32 {
+        match self {
+            GrainEnvelope::Hann => 0.5 - 0.5 * (std::f32::consts::TAU * phase).cos(),
+            GrainEnvelope::Trapezoid => {
+                (phase / GRAIN_TRAPEZOID_FADE).min((1.0 - phase) / GRAIN_TRAPEZOID_FADE).min(1.0)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Grain {
+    active: bool,
+    /// The read position in the source, in samples.
+    pos: f64,
+    /// The position in the grain envelope, from 0.0 to 1.0.
+    phase: f32,
+    phase_inc: f32,
+    gain_l: f32,
+    gain_r: f32,
+}
+
+impl Grain {
+    fn new() -> Self {
+        Self { active: false, pos: 0.0, phase: 0.0, phase_inc: 0.0, gain_l: 1.0, gain_r: 1.0 }
+    }
+}
+
+/// A granular playback engine, that plays overlapping grains from a source sample buffer.
+///
+/// New grains are started at a constant rate given by the density. Every grain starts
+/// at the playback position, randomly offset by up to the position jitter, and plays
+/// the source with the pitch of the player (read with [cubic_interpolate]). The grains
+/// are faded in and out with the [GrainEnvelope], and panned randomly within
+/// the stereo spread.
+///
+/// The source is treated as a loop, grains that run over the end continue at the start.
+/// The output is not normalized, about `density * grain size` grains overlap at any time.
+/// If all grains are busy, no new grain is started until one has finished.
+///
+///```
+/// use synfx_dsp::{GrainPlayer, GrainEnvelope};
+///
+/// let source: Vec<f32> =
+///     (0..44100).map(|i| (i as f32 * std::f32::consts::TAU * 220.0 / 44100.0).sin()).collect();
+///
+/// let mut gp = GrainPlayer::new(32, 42);
+/// gp.set_sample_rate(44100.0);
+/// gp.set_grain_size_ms(50.0);
+/// gp.set_density(40.0);
+/// gp.set_position(0.5);
+/// gp.set_jitter_ms(10.0);
+/// gp.set_pitch_semitones(12.0);
+/// gp.set_envelope(GrainEnvelope::Hann);
+/// gp.set_stereo_spread(0.5);
+///
+/// let out: Vec<(f32, f32)> = (0..44100).map(|_| gp.process(&source[..])).collect();
+///
+/// // About 2 grains overlap:
+/// assert!(gp.active_grains() >= 1 && gp.active_grains() <= 3);
+/// let peak = out.iter().fold(0.0_f32, |p, (l, r)| p.max(l.abs()).max(r.abs()));
+/// assert!(peak > 0.5 && peak < 3.0);
+///```
+#[derive(Debug, Clone)]
+pub struct GrainPlayer {
+    grains: Vec<Grain>,
+    rng: Rng,
+    seed: u64,
+    srate: f32,
+    /// Samples until the next grain starts.
+    next_grain: f32,
+
+    size_ms: f32,
+    density: f32,
+    position: f32,
+    jitter_ms: f32,
+    pitch: f64,
+    envelope: GrainEnvelope,
+    spread: f32,
+}
+
+impl GrainPlayer {
+    /// Creates a new grain player.
+    ///
+    /// * `max_grains` - The maximum number of grains, that can play at the same time.
+    /// * `seed` - The seed of the random position jitter and panning.
+    pub fn new(max_grains: usize, seed: u64) -> Self {
+        let mut rng = Rng::new();
+        rng.seed(seed);
+
+        Self {
+            grains: vec![Grain::new(); max_grains.max(1)],
+            rng,
+            seed,
+            srate: 44100.0,
+            next_grain: 0.0,
+
+            size_ms: 50.0,
+            density: 20.0,
+            position: 0.0,
+            jitter_ms: 0.0,
+            pitch: 1.0,
+            envelope: GrainEnvelope::Hann,
+            spread: 0.0,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+    }
+
+    /// Stops all grains and restarts the random generator.
+    pub fn reset(&mut self) {
+        for grain in self.grains.iter_mut() {
+            grain.active = false;
+        }
+        self.rng.seed(self.seed);
+        self.next_grain = 0.0;
+    }
+
+    /// Sets the length of new grains in milliseconds.
+    pub fn set_grain_size_ms(&mut self, size_ms: f32) {
+        self.size_ms = size_ms.max(GRAIN_MIN_SIZE_MS);
+    }
+
+    /// Sets the number of grains started per second.
+    pub fn set_density(&mut self, grains_per_sec: f32) {
+        self.density = grains_per_sec.max(0.0);
+    }
+
+    /// Sets the playback position, where the grains start in the source,
+    /// in the range 0.0 (start) to 1.0 (end).
+    pub fn set_position(&mut self, position: f32) {
+        self.position = position.clamp(0.0, 1.0);
+    }
+
+    /// Sets the maximum random offset in milliseconds of the grain start
+    /// from the playback position, in both directions.
+    pub fn set_jitter_ms(&mut self, jitter_ms: f32) {
+        self.jitter_ms = jitter_ms.max(0.0);
+    }
+
+    /// Sets the pitch of the grains in semitones, relative to the source.
+    pub fn set_pitch_semitones(&mut self, semitones: f32) {
+        self.pitch = 2.0_f64.powf(semitones as f64 / 12.0);
+    }
+
+    pub fn set_envelope(&mut self, envelope: GrainEnvelope) {
+        self.envelope = envelope;
+    }
+
+    /// Sets how far new grains are randomly panned, 0.0 plays all grains in the center
+    /// and 1.0 pans them anywhere between hard left and hard right.
+    pub fn set_stereo_spread(&mut self, spread: f32) {
+        self.spread = spread.clamp(0.0, 1.0);
+    }
+
+    /// Returns the number of currently playing grains.
+    pub fn active_grains(&self) -> usize {
+        self.grains.iter().filter(|g| g.active).count()
+    }
+
+    fn start_grain(&mut self, len: usize) {
+        let idx = match self.grains.iter().position(|g| !g.active) {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        let jitter = (self.rng.next() * 2.0 - 1.0) * self.jitter_ms * 0.001 * self.srate;
+        let pos = (self.position * len as f32 + jitter) as f64;
+
+        let pan = self.spread * (self.rng.next() * 2.0 - 1.0);
+        let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+
+        self.grains[idx] = Grain {
+            active: true,
+            pos: pos.rem_euclid(len as f64),
+            phase: 0.0,
+            phase_inc: 1000.0 / (self.size_ms * self.srate),
+            gain_l: std::f32::consts::SQRT_2 * angle.cos(),
+            gain_r: std::f32::consts::SQRT_2 * angle.sin(),
+        };
+    }
+
+    /// Returns the next stereo sample of the grains played from the `source`.
+    /// Pass the same source every sample, an empty source produces silence.
+    #[inline]
+    pub fn process(&mut self, source: &[f32]) -> (f32, f32) {
+        let len = source.len();
+        if len == 0 {
+            return (0.0, 0.0);
+        }
+
+        if self.density > 0.0 {
+            self.next_grain -= 1.0;
+            if self.next_grain <= 0.0 {
+                self.next_grain += self.srate / self.density;
+                self.start_grain(len);
+            }
+        }
+
+        let (mut l, mut r) = (0.0, 0.0);
+        for grain in self.grains.iter_mut().filter(|g| g.active) {
+            let i = grain.pos.floor();
+            let s = cubic_interpolate(source, len, i as usize % len, (grain.pos - i) as f32);
+            let s = s * self.envelope.gain(grain.phase);
+            l += s * grain.gain_l;
+            r += s * grain.gain_r;
+
+            grain.pos = (grain.pos + self.pitch) % len as f64;
+            grain.phase += grain.phase_inc;
+            if grain.phase >= 1.0 {
+                grain.active = false;
+            }
+        }
+
+        (l, r)
+    }
+}
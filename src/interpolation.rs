@@ -77,6 +77,44 @@ pub fn crossfade_exp(v1: f32, v2: f32, mix: f32) -> f32 {
     crossfade(v1, v2, mix * mix)
 }
 
+/// Selects the curve of a crossfade, see also [crossfade], [crossfade_cpow],
+/// [crossfade_log] and [crossfade_exp].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossfadeCurve {
+    Linear,
+    EqualPower,
+    Log,
+    Exp,
+}
+
+impl CrossfadeCurve {
+    /// Returns the gains for signal 1 and signal 2 at the mix position `mix`,
+    /// in the range 0.0 to 1.0.
+    ///
+    ///```
+    /// use synfx_dsp::CrossfadeCurve;
+    ///
+    /// let (g1, g2) = CrossfadeCurve::EqualPower.gains(0.5);
+    /// assert!((g1 - g2).abs() < 0.0001);
+    /// assert!((g1 * g1 + g2 * g2 - 1.0).abs() < 0.0001);
+    ///```
+    #[inline]
+    pub fn gains(&self, mix: f32) -> (f32, f32) {
+        match self {
+            CrossfadeCurve::Linear => (1.0 - mix, mix),
+            CrossfadeCurve::EqualPower => (
+                ((1.0 - mix) * std::f32::consts::FRAC_PI_2).sin(),
+                (mix * std::f32::consts::FRAC_PI_2).sin(),
+            ),
+            CrossfadeCurve::Log => {
+                let x = (mix * (CROSS_LOG_MAX - CROSS_LOG_MIN) + CROSS_LOG_MIN).exp();
+                (1.0 - x, x)
+            }
+            CrossfadeCurve::Exp => (1.0 - mix * mix, mix * mix),
+        }
+    }
+}
+
 /// Apply linear interpolation between the value a and b.
 ///
 /// * `a` - value at x=0.0
@@ -97,6 +135,104 @@ pub fn lerp64(x: f64, a: f64, b: f64) -> f64 {
     (a * (1.0 - x)) + (b * x)
 }
 
+/// The smoothstep easing curve `3x^2 - 2x^3`, with a slope of 0 at both ends.
+/// Useful for click free parameter transitions and shaping LFOs.
+/// `x` is clamped to the range 0.0 to 1.0.
+///
+///```
+/// use synfx_dsp::smoothstep;
+///
+/// assert_eq!(smoothstep(0.0_f32), 0.0);
+/// assert_eq!(smoothstep(0.5_f32), 0.5);
+/// assert_eq!(smoothstep(1.0_f32), 1.0);
+/// assert!(smoothstep(0.25_f32) < 0.25);
+///```
+#[inline]
+pub fn smoothstep<F: Flt>(x: F) -> F {
+    let x = x.max(f(0.0)).min(f(1.0));
+    x * x * (f::<F>(3.0) - f::<F>(2.0) * x)
+}
+
+/// The smootherstep easing curve `6x^5 - 15x^4 + 10x^3`, which also has a second
+/// derivative of 0 at both ends, see also [smoothstep].
+/// `x` is clamped to the range 0.0 to 1.0.
+///
+///```
+/// use synfx_dsp::{smootherstep, smoothstep};
+///
+/// assert_eq!(smootherstep(0.0_f64), 0.0);
+/// assert_eq!(smootherstep(0.5_f64), 0.5);
+/// assert_eq!(smootherstep(1.0_f64), 1.0);
+/// assert!(smootherstep(0.1_f64) < smoothstep(0.1_f64));
+///```
+#[inline]
+pub fn smootherstep<F: Flt>(x: F) -> F {
+    let x = x.max(f(0.0)).min(f(1.0));
+    x * x * x * (x * (x * f::<F>(6.0) - f::<F>(15.0)) + f::<F>(10.0))
+}
+
+/// Power based ease in/out curve, it accelerates like `x^p` in the first half and
+/// decelerates symmetrically in the second half.
+/// `x` is clamped to the range 0.0 to 1.0.
+///
+/// * `x` - The input, range 0.0 to 1.0.
+/// * `p` - The exponent, 1.0 is linear, higher values make the curve steeper in the middle.
+///
+///```
+/// use synfx_dsp::ease_in_out_pow;
+///
+/// assert_eq!(ease_in_out_pow(0.0_f32, 3.0), 0.0);
+/// assert_eq!(ease_in_out_pow(0.5_f32, 3.0), 0.5);
+/// assert_eq!(ease_in_out_pow(1.0_f32, 3.0), 1.0);
+/// assert_eq!(ease_in_out_pow(0.25_f32, 3.0), 0.0625);
+/// assert_eq!(ease_in_out_pow(0.75_f32, 3.0), 0.9375);
+///```
+#[inline]
+pub fn ease_in_out_pow<F: Flt>(x: F, p: F) -> F {
+    let x = x.max(f(0.0)).min(f(1.0));
+    let half = f::<F>(0.5);
+    let two = f::<F>(2.0);
+    if x < half {
+        half * (two * x).powf(p)
+    } else {
+        F::one() - half * (two - two * x).powf(p)
+    }
+}
+
+/// S-curve with an adjustable steepness, based on a normalized tunable sigmoid.
+/// `x` is clamped to the range 0.0 to 1.0.
+///
+/// * `x` - The input, range 0.0 to 1.0.
+/// * `steepness` - Range -1.0 to 1.0. 0.0 is linear, positive values give an S-curve
+///   that is steeper in the middle and negative values an inverted S-curve, that is flatter
+///   in the middle.
+///
+///```
+/// use synfx_dsp::s_curve;
+///
+/// for steepness in [-0.9_f32, -0.5, 0.0, 0.5, 0.9] {
+///     assert_eq!(s_curve(0.0, steepness), 0.0);
+///     assert!((s_curve(0.5, steepness) - 0.5).abs() < 1e-6);
+///     assert_eq!(s_curve(1.0, steepness), 1.0);
+/// }
+/// assert!((s_curve(0.25_f32, 0.0) - 0.25).abs() < 1e-6);
+/// assert!(s_curve(0.25_f32, 0.5) < 0.25);
+/// assert!(s_curve(0.25_f32, -0.5) > 0.25);
+///```
+#[inline]
+pub fn s_curve<F: Flt>(x: F, steepness: F) -> F {
+    let x = x.max(f(0.0)).min(f(1.0));
+    // The tunable sigmoid is steep in the middle for a negative `k`:
+    let k = -steepness.max(f(-0.999)).min(f(0.999));
+    let half = f::<F>(0.5);
+    let two = f::<F>(2.0);
+
+    // Tunable sigmoid in the range -1.0 to 1.0:
+    let t = two * x - F::one();
+    let y = (t - t * k) / (k - two * t.abs() * k + F::one());
+    (y + F::one()) * half
+}
+
 /// Hermite / Cubic interpolation of a buffer full of samples at the given _index_.
 /// _len_ is the buffer length to consider and wrap the index into. And _fract_ is the
 /// fractional part of the index.
@@ -164,3 +300,94 @@ pub fn cubic_interpolate<F: Flt>(data: &[F], len: usize, index: usize, fract: F)
 
     res
 }
+
+/// A precomputed polyphase table of windowed sinc (Blackman window) coefficients,
+/// for the highest quality fractional delay. See [crate::DelayBuffer::sinc_interpolate_at_s].
+///
+/// The table is read only after creation, so create it once and share it (eg. with
+/// a reference or a `std::sync::Arc`) between all the delay lines that use it,
+/// instead of keeping one table per instance.
+///
+///```
+/// use synfx_dsp::SincTable;
+///
+/// let table = SincTable::new(16, 256);
+/// assert_eq!(table.taps(), 16);
+/// assert_eq!(table.phases(), 256);
+///
+/// // Every phase has unity gain at DC:
+/// let sum: f64 = table.phase(100).iter().sum();
+/// assert!((sum - 1.0).abs() < 0.000001);
+///```
+#[derive(Debug, Clone)]
+pub struct SincTable {
+    taps: usize,
+    phases: usize,
+    coefs: Vec<f64>,
+}
+
+impl SincTable {
+    /// Creates a new table.
+    ///
+    /// * `taps` - The number of taps (filter length) per phase, is rounded up to an even
+    ///   number of at least 2. Higher values give a steeper low pass for the interpolation,
+    ///   but cost more CPU. 16 to 32 are good values.
+    /// * `phases` - The number of fractional positions between two samples, at least 1.
+    ///   The coefficients between those are linearly interpolated.
+    pub fn new(taps: usize, phases: usize) -> Self {
+        let taps = (taps.max(2) + 1) & !1;
+        let phases = phases.max(1);
+        let half = (taps / 2) as f64;
+
+        let mut coefs = Vec::with_capacity((phases + 1) * taps);
+        for p in 0..=phases {
+            let fract = (p as f64) / (phases as f64);
+            let start = coefs.len();
+
+            for j in 0..taps {
+                // Distance of the tap from the interpolated position:
+                let t = fract + half - 1.0 - (j as f64);
+                let sinc = if t.abs() < 1e-12 {
+                    1.0
+                } else {
+                    (std::f64::consts::PI * t).sin() / (std::f64::consts::PI * t)
+                };
+                let wx = std::f64::consts::PI * t / half;
+                let window = 0.42 + 0.5 * wx.cos() + 0.08 * (2.0 * wx).cos();
+                coefs.push(sinc * window);
+            }
+
+            let sum: f64 = coefs[start..].iter().sum();
+            for c in coefs[start..].iter_mut() {
+                *c /= sum;
+            }
+        }
+
+        Self { taps, phases, coefs }
+    }
+
+    /// The number of taps per phase.
+    pub fn taps(&self) -> usize {
+        self.taps
+    }
+
+    /// The number of phases between two samples.
+    pub fn phases(&self) -> usize {
+        self.phases
+    }
+
+    /// Returns the coefficients of the phase at `index`, which ranges from 0 to
+    /// (including) [SincTable::phases]. The first coefficient belongs to the newest sample.
+    #[inline]
+    pub fn phase(&self, index: usize) -> &[f64] {
+        let start = index.min(self.phases) * self.taps;
+        &self.coefs[start..(start + self.taps)]
+    }
+}
+
+impl Default for SincTable {
+    /// A table with 16 taps and 512 phases.
+    fn default() -> Self {
+        Self::new(16, 512)
+    }
+}
@@ -22,7 +22,13 @@ if they comply with the GPL-3.0-or-later license and don't break the test suite
 that also has to pass if these algorithms are touched. The flip side is, that these implementations
 are actually covered by a test suite.
 
-**Requires Nightly as of 2022-10-02 due to std::simd!**
+**Requires Nightly as of 2022-10-02 due to std::simd!** Unless the default `simd` feature
+is disabled, in that case a scalar fallback for `f32x4` is used and the crate
+builds on stable Rust:
+
+```toml
+synfx-dsp = { version = "0.5", default-features = false }
+```
 
 Copyright, Licenses, Attribution, Contributions
 ===============================================
@@ -89,6 +95,11 @@ Here is a list of sources parts of this library copied or translated code from:
     http://www.willpirkle.com/Downloads/AN-4VirtualAnalogFilters.pdf
     (page 5)
     ```
+- [crate::HilbertIIR]
+    ```text
+    Hilbert transformer coefficients by Olli Niemitalo, released into the public domain:
+    https://yehar.com/blog/?p=368
+    ```
 - [crate::FixedOnePole]
     ```text
     Fixed one pole with setable pole and gain.
@@ -193,6 +204,11 @@ Here is a list of sources parts of this library copied or translated code from:
     except according to those terms.
     - splitmix64 (http://xoroshiro.di.unimi.it/splitmix64.c)
     ```
+- [crate::PinkNoise]
+    ```text
+    Pink noise filter (refined method) by Paul Kellet, from musicdsp.org:
+    https://www.musicdsp.org/en/latest/Filters/76-pink-noise-filter.html
+    ```
 - [crate::f_distort] / [crate::f_fold_distort]
     ```text
     Ported from LMMS under GPLv2
@@ -227,38 +243,66 @@ Here is a list of sources parts of this library copied or translated code from:
     ```
 */
 
-#![feature(portable_simd)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
+mod analysis;
 mod approx;
 mod atomic;
 mod biquad;
+mod convolution;
 mod dattorro;
 mod delay;
+mod dynamics;
 mod env;
+mod fdn;
+mod fft;
 pub mod fh_va;
 mod filters;
+mod granular;
 mod interpolation;
 mod low_freq;
+mod mixer;
 mod oscillators;
 mod oversampling;
+mod physical;
+mod pitch;
 mod rand;
+mod simd;
 mod test;
 mod trig_clock;
 mod waveshapers;
 
+pub use analysis::*;
 pub use approx::*;
 pub use atomic::*;
-pub use biquad::{Biquad, BiquadCoefs};
-pub use dattorro::{DattorroReverb, DattorroReverbParams};
+pub use biquad::{
+    Biquad, BiquadCoefs, Crossover3Band, CrossoverLR2, CrossoverLR4, EqBand,
+    FractionalDelayAllpass, LinkwitzRileyCrossover, MultibandSplitter, ParametricEq,
+    FRAC_DELAY_ALLPASS_MAX, FRAC_DELAY_ALLPASS_MIN,
+};
+pub use convolution::*;
+pub use dattorro::{DattorroReverb, DattorroReverbParams, DattorroReverbParamsDefault};
 pub use delay::*;
+pub use dynamics::*;
 pub use env::*;
+pub use fdn::*;
 pub use filters::*;
+pub use granular::*;
 pub use interpolation::*;
 pub use low_freq::*;
+pub use mixer::*;
 pub use oscillators::*;
+pub use oversampling::Decimator;
+pub use oversampling::Interpolator;
+pub use oversampling::OversampledDistortion;
 pub use oversampling::Oversampling;
+pub use oversampling::OversamplingPrefill;
 pub use oversampling::PolyIIRHalfbandFilter;
+pub use oversampling::Resampler;
+pub use physical::*;
+pub use pitch::*;
 pub use rand::*;
+pub use simd::f32x4;
 #[allow(unused)]
 pub use test::*;
 pub use trig_clock::*;
@@ -312,6 +356,60 @@ pub fn note_to_freq(note: f32) -> f32 {
     440.0 * (2.0_f32).powf((note - 69.0) / 12.0)
 }
 
+/// Converts a frequency to a (fractional) midi note, the inverse of [note_to_freq].
+///
+///```
+/// use synfx_dsp::*;
+///
+/// assert_eq!(freq_to_note(440.0), 69.0);
+/// assert_eq!(freq_to_note(880.0), 81.0);
+/// assert!((freq_to_note(note_to_freq(60.5)) - 60.5).abs() < 0.0001);
+///```
+pub fn freq_to_note(freq: f32) -> f32 {
+    69.0 + 12.0 * (freq / 440.0).log2()
+}
+
+/// Converts a midi note to a pitch CV in the 0.1 per octave convention used
+/// by [Quantizer] and [CtrlPitchQuantizer], where 0.0 is the A4 (midi note 69).
+///
+///```
+/// use synfx_dsp::*;
+///
+/// assert_eq!(note_to_pitch_cv(69.0), 0.0);
+/// assert_eq!(note_to_pitch_cv(81.0), 0.1);
+/// assert!((note_to_pitch_cv(57.0) - -0.1).abs() < 0.00001);
+///```
+pub fn note_to_pitch_cv(note: f32) -> f32 {
+    (note - 69.0) / 120.0
+}
+
+/// Converts a pitch CV (0.1 per octave, 0.0 is A4 at 440Hz) to a frequency.
+///
+///```
+/// use synfx_dsp::*;
+///
+/// assert_eq!(pitch_cv_to_freq(0.0), 440.0);
+/// assert_eq!(pitch_cv_to_freq(0.1), 880.0);
+/// assert_eq!(pitch_cv_to_freq(-0.2), 110.0);
+///```
+pub fn pitch_cv_to_freq(cv: f32) -> f32 {
+    440.0 * (2.0_f32).powf(cv * 10.0)
+}
+
+/// Converts a frequency to a pitch CV (0.1 per octave, 0.0 is A4 at 440Hz),
+/// the inverse of [pitch_cv_to_freq].
+///
+///```
+/// use synfx_dsp::*;
+///
+/// assert_eq!(freq_to_pitch_cv(440.0), 0.0);
+/// assert_eq!(freq_to_pitch_cv(220.0), -0.1);
+/// assert!((freq_to_pitch_cv(pitch_cv_to_freq(0.123)) - 0.123).abs() < 0.00001);
+///```
+pub fn freq_to_pitch_cv(freq: f32) -> f32 {
+    (freq / 440.0).log2() * 0.1
+}
+
 /// Converts gain in decibels to a factor/coeffient
 ///
 /// ```
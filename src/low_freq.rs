@@ -4,7 +4,7 @@
 
 //! Low frequency utilities for handling control signals (partially also at audio rate).
 
-use crate::{f, fclampc, Flt};
+use crate::{cubic_interpolate, f, fclampc, Flt, SplitMix64, Trigger};
 
 // Adapted from https://github.com/ValleyAudio/ValleyRackFree/blob/v1.0/src/Common/DSP/LFO.hpp
 //
@@ -96,6 +96,536 @@ impl<F: Flt> TriSawLFO<F> {
     }
 }
 
+/// An LFO that plays back an arbitrary single cycle shape, for instance a user drawn
+/// curve or a stepped sequence. The shape is read with linear interpolation between
+/// the stored points, wrapping around from the last point back to the first.
+///
+///```
+/// use synfx_dsp::WavetableLFO;
+///
+/// let mut lfo = WavetableLFO::from_samples(&[0.0, 1.0, 0.0, -1.0]);
+/// lfo.set_sample_rate(8.0);
+/// lfo.set_freq(1.0);
+///
+/// let out: Vec<f32> = (0..8).map(|_| lfo.next()).collect();
+/// assert_eq!(out, vec![0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -0.5]);
+///```
+#[derive(Debug, Clone)]
+pub struct WavetableLFO {
+    /// The single cycle shape.
+    table: Vec<f32>,
+    /// The (inverse) sample rate. Eg. 1.0 / 44100.0.
+    israte: f32,
+    /// The current phase in the range 0.0 to 1.0.
+    phase: f32,
+    /// The frequency.
+    freq: f32,
+    /// The phase that is restored by [WavetableLFO::reset].
+    init_phase: f32,
+}
+
+impl WavetableLFO {
+    /// Creates an LFO that plays back the given single cycle shape.
+    /// An empty `samples` slice results in a silent LFO.
+    pub fn from_samples(samples: &[f32]) -> Self {
+        let table = if samples.is_empty() { vec![0.0] } else { samples.to_vec() };
+        Self { table, israte: 1.0 / 44100.0, phase: 0.0, freq: 1.0, init_phase: 0.0 }
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.israte = 1.0 / srate;
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = self.init_phase;
+    }
+
+    /// Sets the frequency of the LFO in Hz.
+    #[inline]
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq;
+    }
+
+    /// Sets the current phase (0.0 to 1.0), which is also restored on [WavetableLFO::reset].
+    pub fn set_phase(&mut self, phase: f32) {
+        let phase = phase.fract();
+        let phase = if phase < 0.0 { phase + 1.0 } else { phase };
+        self.init_phase = phase;
+        self.phase = phase;
+    }
+
+    /// Returns the next value of the shape.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let len = self.table.len();
+        let pos = self.phase * (len as f32);
+        let i = (pos as usize).min(len - 1);
+        let fract = pos - (i as f32);
+
+        let a = self.table[i];
+        let b = self.table[(i + 1) % len];
+        let s = a + (b - a) * fract;
+
+        self.phase += self.freq * self.israte;
+        self.phase -= self.phase.floor();
+
+        s
+    }
+}
+
+/// A random sample & hold LFO. It picks a new random value at the given rate
+/// and holds it until the next one is picked, resulting in a stepped random signal.
+///
+/// See [SmoothRandomLFO] for a variant that glides between the random values.
+///
+///```
+/// use synfx_dsp::SampleHoldNoiseLFO;
+///
+/// let mut lfo = SampleHoldNoiseLFO::new(42);
+/// lfo.set_sample_rate(100.0);
+/// lfo.set_freq(10.0);
+///
+/// let out: Vec<f32> = (0..100).map(|_| lfo.next_bipolar()).collect();
+/// assert!(out.iter().all(|s| s.abs() <= 1.0));
+///
+/// // The value is held for 10 samples:
+/// assert!(out[0..10].iter().all(|s| *s == out[0]));
+/// assert!(out[10..20].iter().all(|s| *s == out[10]));
+/// assert_ne!(out[0], out[10]);
+///
+/// // The same seed gives the same sequence:
+/// let mut lfo2 = SampleHoldNoiseLFO::new(42);
+/// assert_eq!(out[0], lfo2.next_bipolar());
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct SampleHoldNoiseLFO {
+    rng: SplitMix64,
+    seed: u64,
+    /// The (inverse) sample rate. Eg. 1.0 / 44100.0.
+    israte: f32,
+    /// The current phase in the range 0.0 to 1.0.
+    phase: f32,
+    /// The rate of new random values in Hz.
+    freq: f32,
+    /// The currently held value in the range 0.0 to 1.0.
+    value: f32,
+}
+
+impl SampleHoldNoiseLFO {
+    /// Creates a new sample & hold LFO with the given `seed`.
+    pub fn new(seed: u64) -> Self {
+        let mut this = Self {
+            rng: SplitMix64::new(seed),
+            seed,
+            israte: 1.0 / 44100.0,
+            phase: 0.0,
+            freq: 1.0,
+            value: 0.0,
+        };
+        this.reset();
+        this
+    }
+
+    /// Restarts the LFO with a new `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    /// Resets the phase and restarts the random sequence of the current seed.
+    pub fn reset(&mut self) {
+        self.rng = SplitMix64::new(self.seed);
+        self.phase = 0.0;
+        self.value = self.rng.next_open01() as f32;
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.israte = 1.0 / srate;
+    }
+
+    /// Sets the rate in Hz at which new random values are picked.
+    #[inline]
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq.max(0.0);
+    }
+
+    /// Returns the next value in the range 0.0 to 1.0.
+    #[inline]
+    pub fn next_unipolar(&mut self) -> f32 {
+        let s = self.value;
+
+        self.phase += self.freq * self.israte;
+        if self.phase >= 1.0 {
+            self.phase -= self.phase.floor();
+            self.value = self.rng.next_open01() as f32;
+        }
+
+        s
+    }
+
+    /// Returns the next value in the range -1.0 to 1.0.
+    #[inline]
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_unipolar() * 2.0 - 1.0
+    }
+}
+
+impl Default for SampleHoldNoiseLFO {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// A smooth random LFO, that picks new random values at the given rate and
+/// glides between them with cubic interpolation. Useful for analog style drift
+/// and wobble of parameters like pitch or cutoff.
+///
+/// See [SampleHoldNoiseLFO] for the stepped variant.
+///
+///```
+/// use synfx_dsp::SmoothRandomLFO;
+///
+/// let mut lfo = SmoothRandomLFO::new(42);
+/// lfo.set_sample_rate(1000.0);
+/// lfo.set_freq(10.0);
+///
+/// let out: Vec<f32> = (0..1000).map(|_| lfo.next_bipolar()).collect();
+/// assert!(out.iter().all(|s| s.abs() <= 1.0));
+///
+/// // There are no steps, neighbouring samples stay close together:
+/// assert!(out.windows(2).all(|w| (w[0] - w[1]).abs() < 0.05));
+///
+/// // But the signal still wanders around:
+/// let min = out.iter().cloned().fold(1.0, f32::min);
+/// let max = out.iter().cloned().fold(-1.0, f32::max);
+/// assert!(max - min > 0.5);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct SmoothRandomLFO {
+    rng: SplitMix64,
+    seed: u64,
+    /// The (inverse) sample rate. Eg. 1.0 / 44100.0.
+    israte: f32,
+    /// The current phase between `points[1]` and `points[2]`, range 0.0 to 1.0.
+    phase: f32,
+    /// The rate of new random values in Hz.
+    freq: f32,
+    /// The last four random points in the range 0.0 to 1.0, the output
+    /// is interpolated between the middle two.
+    points: [f32; 4],
+}
+
+impl SmoothRandomLFO {
+    /// Creates a new smooth random LFO with the given `seed`.
+    pub fn new(seed: u64) -> Self {
+        let mut this = Self {
+            rng: SplitMix64::new(seed),
+            seed,
+            israte: 1.0 / 44100.0,
+            phase: 0.0,
+            freq: 1.0,
+            points: [0.0; 4],
+        };
+        this.reset();
+        this
+    }
+
+    /// Restarts the LFO with a new `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    /// Resets the phase and restarts the random sequence of the current seed.
+    pub fn reset(&mut self) {
+        self.rng = SplitMix64::new(self.seed);
+        self.phase = 0.0;
+        for p in self.points.iter_mut() {
+            *p = self.rng.next_open01() as f32;
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.israte = 1.0 / srate;
+    }
+
+    /// Sets the rate in Hz at which new random points are picked.
+    #[inline]
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq.max(0.0);
+    }
+
+    /// Returns the next value in the range 0.0 to 1.0.
+    #[inline]
+    pub fn next_unipolar(&mut self) -> f32 {
+        // The interpolation may overshoot a bit at the extremes:
+        let s = cubic_interpolate(&self.points[..], 4, 1, self.phase).clamp(0.0, 1.0);
+
+        self.phase += self.freq * self.israte;
+        if self.phase >= 1.0 {
+            self.phase -= self.phase.floor();
+            self.points.copy_within(1.., 0);
+            self.points[3] = self.rng.next_open01() as f32;
+        }
+
+        s
+    }
+
+    /// Returns the next value in the range -1.0 to 1.0.
+    #[inline]
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_unipolar() * 2.0 - 1.0
+    }
+}
+
+impl Default for SmoothRandomLFO {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// The waveform of an [Lfo].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LfoWaveform {
+    Sine,
+    /// A triangle, that starts at 0.0 and rises first, like the sine.
+    Triangle,
+    SawUp,
+    SawDown,
+    /// A square wave, that is high for the pulse width, see [Lfo::set_pulse_width].
+    Square,
+    /// A new random value for every cycle, held until the next one.
+    SampleHold,
+}
+
+/// One sample of all outputs of an [Lfo].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LfoOutputs<F: Flt> {
+    /// The waveform in the range -1.0 to 1.0.
+    pub bipolar: F,
+    /// The waveform in the range 0.0 to 1.0.
+    pub unipolar: F,
+    /// The phase of this sample in the range 0.0 to 1.0, for chaining other
+    /// modulators or oscillators to the LFO.
+    pub phase: F,
+}
+
+/// A multi waveform LFO, with tempo sync and retrigger.
+///
+/// The LFO either runs at the frequency set by [Lfo::set_freq], or it is synced to the
+/// period of a clock, for instance measured by a [crate::TriggerPhaseClock]
+/// (see [Lfo::set_sync_period]). A trigger at the retrigger input of [Lfo::next]
+/// restarts the cycle at the phase offset.
+///
+///```
+/// use synfx_dsp::{Lfo, LfoWaveform, TriggerPhaseClock};
+///
+/// let mut lfo = Lfo::<f64>::new();
+/// lfo.set_waveform(LfoWaveform::SawUp);
+/// // One LFO cycle takes 2 clock periods:
+/// lfo.set_sync_ratio(0.5);
+///
+/// // A clock trigger every 100 samples:
+/// let mut clock = TriggerPhaseClock::new();
+/// for i in 0..1000 {
+///     clock.next_phase(1.0, if i % 100 < 2 { 1.0 } else { 0.0 });
+/// }
+/// lfo.set_sync_period(clock.period_samples());
+///
+/// let out: Vec<_> = (0..400).map(|_| lfo.next(0.0)).collect();
+/// assert!((out[50].phase - 0.25).abs() < 1e-9);
+/// assert!((out[50].bipolar + 0.5).abs() < 1e-9);
+/// assert!((out[50].unipolar - 0.25).abs() < 1e-9);
+///
+/// // A retrigger restarts the cycle:
+/// let out = lfo.next(1.0);
+/// assert_eq!(out.phase, 0.0);
+/// assert_eq!(out.bipolar, -1.0);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct Lfo<F: Flt> {
+    /// The (inverse) sample rate. Eg. 1.0 / 44100.0.
+    israte: F,
+    /// The current phase in the range 0.0 to 1.0.
+    phase: F,
+    /// The phase that is restored by [Lfo::reset] and by a retrigger.
+    init_phase: F,
+    /// The frequency.
+    freq: F,
+    /// The period of the synced clock in samples.
+    sync_period: Option<F>,
+    /// The LFO cycles per period of the synced clock.
+    sync_ratio: F,
+    waveform: LfoWaveform,
+    pulse_width: F,
+    retrig: Trigger,
+    rng: SplitMix64,
+    seed: u64,
+    /// The currently held value of [LfoWaveform::SampleHold] in the range 0.0 to 1.0.
+    sh_value: F,
+}
+
+impl<F: Flt> Lfo<F> {
+    /// Creates a new sine LFO at 1 Hz.
+    pub fn new() -> Self {
+        let mut this = Self {
+            israte: f(1.0 / 44100.0),
+            phase: f(0.0),
+            init_phase: f(0.0),
+            freq: f(1.0),
+            sync_period: None,
+            sync_ratio: f(1.0),
+            waveform: LfoWaveform::Sine,
+            pulse_width: f(0.5),
+            retrig: Trigger::new(),
+            rng: SplitMix64::new(0),
+            seed: 0,
+            sh_value: f(0.0),
+        };
+        this.reset();
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: F) {
+        self.israte = f::<F>(1.0) / srate;
+    }
+
+    /// Resets the phase to the phase offset and restarts the random sequence
+    /// of [LfoWaveform::SampleHold].
+    pub fn reset(&mut self) {
+        self.phase = self.init_phase;
+        self.retrig.reset();
+        self.rng = SplitMix64::new(self.seed);
+        self.sh_value = f(self.rng.next_open01());
+    }
+
+    /// Restarts the LFO with a new `seed` for [LfoWaveform::SampleHold].
+    pub fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    pub fn set_waveform(&mut self, waveform: LfoWaveform) {
+        self.waveform = waveform;
+    }
+
+    /// Sets the frequency of the LFO in Hz, which is used while the LFO is not synced.
+    #[inline]
+    pub fn set_freq(&mut self, freq: F) {
+        self.freq = freq;
+    }
+
+    /// Sets the pulse width of [LfoWaveform::Square] in the range 0.0 to 1.0.
+    #[inline]
+    pub fn set_pulse_width(&mut self, pulse_width: F) {
+        self.pulse_width = fclampc(pulse_width, 0.0, 1.0);
+    }
+
+    /// Sets the phase offset (0.0 to 1.0), where the LFO starts on reset and retrigger.
+    pub fn set_phase_offs(&mut self, phase: F) {
+        let phase = phase - phase.floor();
+        self.init_phase = phase;
+        self.phase = phase;
+    }
+
+    /// Syncs the LFO to a clock with a period of `period_samples`, like returned by
+    /// [crate::TriggerPhaseClock::period_samples]. With `None` the LFO runs free again,
+    /// at the frequency of [Lfo::set_freq].
+    #[inline]
+    pub fn set_sync_period(&mut self, period_samples: Option<F>) {
+        self.sync_period = period_samples.filter(|p| *p > f(0.0));
+    }
+
+    /// Sets the number of LFO cycles per clock period, while synced.
+    /// Eg. 0.25 for one cycle every 4 clock periods, or 2.0 for two cycles per clock period.
+    #[inline]
+    pub fn set_sync_ratio(&mut self, cycles_per_period: F) {
+        self.sync_ratio = cycles_per_period.max(f(0.0));
+    }
+
+    /// Restarts the cycle at the phase offset with the next sample.
+    #[inline]
+    pub fn retrigger(&mut self) {
+        self.phase = self.init_phase;
+        self.sh_value = f(self.rng.next_open01());
+    }
+
+    /// Returns the next sample of all outputs.
+    ///
+    /// * `retrig_in` - Retrigger input signal, a trigger restarts the cycle,
+    ///   see [Lfo::retrigger].
+    #[inline]
+    pub fn next(&mut self, retrig_in: F) -> LfoOutputs<F> {
+        if self.retrig.check_trigger(retrig_in.to_f32().unwrap_or(0.0)) {
+            self.retrigger();
+        }
+
+        let p = self.phase;
+        let one = f::<F>(1.0);
+        let two = f::<F>(2.0);
+        let bipolar = match self.waveform {
+            LfoWaveform::Sine => (p * F::TAU()).sin(),
+            LfoWaveform::Triangle => {
+                let t = p + f(0.75);
+                f::<F>(4.0) * (t - t.floor() - f(0.5)).abs() - one
+            }
+            LfoWaveform::SawUp => p * two - one,
+            LfoWaveform::SawDown => one - p * two,
+            LfoWaveform::Square => {
+                if p < self.pulse_width {
+                    one
+                } else {
+                    -one
+                }
+            }
+            LfoWaveform::SampleHold => self.sh_value * two - one,
+        };
+
+        let inc = match self.sync_period {
+            Some(period) => self.sync_ratio / period,
+            None => self.freq * self.israte,
+        };
+        self.phase = self.phase + inc;
+        if self.phase >= one {
+            self.phase = self.phase - self.phase.floor();
+            self.sh_value = f(self.rng.next_open01());
+        }
+
+        LfoOutputs { bipolar, unipolar: (bipolar + one) * f(0.5), phase: p }
+    }
+}
+
+impl<F: Flt> Default for Lfo<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts a note division to a duration in milliseconds at the given tempo.
+///
+/// * `bpm` - The tempo in beats (quarter notes) per minute.
+/// * `beats` - The length of the division in beats. Eg. 1.0 is a quarter note,
+///   0.5 an eighth note and 0.75 a dotted eighth note.
+///
+///```
+/// use synfx_dsp::{tempo_division_ms, tempo_division_hz};
+///
+/// assert_eq!(tempo_division_ms(120.0, 1.0), 500.0);
+/// assert_eq!(tempo_division_ms(120.0, 0.75), 375.0);
+/// assert_eq!(tempo_division_hz(120.0, 0.25), 8.0);
+///```
+#[inline]
+pub fn tempo_division_ms(bpm: f32, beats: f32) -> f32 {
+    (60000.0 / bpm.max(1.0)) * beats.max(0.0)
+}
+
+/// Converts a note division to a frequency in Hz at the given tempo,
+/// for instance to sync an LFO. See also [tempo_division_ms].
+#[inline]
+pub fn tempo_division_hz(bpm: f32, beats: f32) -> f32 {
+    1000.0 / tempo_division_ms(bpm, beats).max(0.001)
+}
+
 /// A slew rate limiter, with a configurable time per 1.0 increase.
 #[derive(Debug, Clone, Copy)]
 pub struct SlewValue<F: Flt> {
@@ -189,6 +719,12 @@ impl<F: Flt> RampValue<F> {
         self.current
     }
 
+    /// Returns the value the ramp is heading to.
+    #[inline]
+    pub fn target(&self) -> F {
+        self.target
+    }
+
     #[inline]
     pub fn next(&mut self) -> F {
         if self.slew_count > 0 {
@@ -202,6 +738,285 @@ impl<F: Flt> RampValue<F> {
     }
 }
 
+/// The timing behaviour of a [Portamento].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlideMode {
+    /// Every glide takes the same time, regardless of the interval.
+    ConstTime,
+    /// Every glide moves with the same speed in semitones per second,
+    /// so larger intervals take longer.
+    ConstRate,
+}
+
+/// A portamento (glide) for frequencies. The glide is done in the log frequency domain,
+/// so that it moves evenly through the octaves (linearly in semitones).
+///
+/// The first target after [Portamento::new] or [Portamento::reset] is jumped to directly.
+///
+///```
+/// use synfx_dsp::{Portamento, GlideMode};
+///
+/// let mut porta = Portamento::new();
+/// porta.set_sample_rate(1000.0);
+/// porta.set_mode(GlideMode::ConstTime);
+/// porta.set_time_or_rate(10.0); // 10ms
+///
+/// porta.set_target(110.0);
+/// assert!((porta.next() - 110.0).abs() < 0.001);
+///
+/// // Glide one octave up in 10 samples:
+/// porta.set_target(220.0);
+/// let out: Vec<f32> = (0..10).map(|_| porta.next()).collect();
+/// // Half way through the glide we are half way through the octave:
+/// assert!((out[4] - 110.0 * 2.0_f32.sqrt()).abs() < 0.01);
+/// assert!((out[9] - 220.0).abs() < 0.01);
+///
+/// // With 12 semitones per second one octave takes one second:
+/// porta.set_mode(GlideMode::ConstRate);
+/// porta.set_time_or_rate(12.0);
+/// porta.set_target(110.0);
+/// let out: Vec<f32> = (0..1000).map(|_| porta.next()).collect();
+/// assert!((out[499] - 110.0 * 2.0_f32.sqrt()).abs() < 0.01);
+/// assert!((out[999] - 110.0).abs() < 0.01);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct Portamento {
+    mode: GlideMode,
+    srate: f32,
+    time_or_rate: f32,
+    /// Current position in octaves (log2 of the frequency).
+    current: f32,
+    /// Target position in octaves.
+    target: f32,
+    /// Increment per sample in octaves.
+    inc: f32,
+    /// Remaining samples of a constant time glide.
+    count: u32,
+    initialized: bool,
+}
+
+impl Portamento {
+    pub fn new() -> Self {
+        Self {
+            mode: GlideMode::ConstTime,
+            srate: 44100.0,
+            time_or_rate: 0.0,
+            current: 0.0,
+            target: 0.0,
+            inc: 0.0,
+            count: 0,
+            initialized: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.current = 0.0;
+        self.target = 0.0;
+        self.inc = 0.0;
+        self.count = 0;
+        self.initialized = false;
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+    }
+
+    /// Sets the timing mode, takes effect with the next [Portamento::set_target].
+    pub fn set_mode(&mut self, mode: GlideMode) {
+        self.mode = mode;
+    }
+
+    /// Sets the glide time in milliseconds for [GlideMode::ConstTime] or
+    /// the glide rate in semitones per second for [GlideMode::ConstRate].
+    /// Takes effect with the next [Portamento::set_target].
+    /// A value of 0.0 disables the glide.
+    pub fn set_time_or_rate(&mut self, value: f32) {
+        self.time_or_rate = value.max(0.0);
+    }
+
+    /// Starts a glide from the current frequency to `freq_hz`.
+    pub fn set_target(&mut self, freq_hz: f32) {
+        self.target = freq_hz.max(1.0).log2();
+
+        if !self.initialized {
+            self.initialized = true;
+            self.current = self.target;
+            self.count = 0;
+            return;
+        }
+
+        let dist = self.target - self.current;
+        let samples = match self.mode {
+            GlideMode::ConstTime => self.time_or_rate * self.srate / 1000.0,
+            GlideMode::ConstRate => {
+                if self.time_or_rate <= 0.0 {
+                    0.0
+                } else {
+                    dist.abs() * 12.0 * self.srate / self.time_or_rate
+                }
+            }
+        };
+
+        if samples < 1.0 {
+            self.current = self.target;
+            self.count = 0;
+        } else {
+            self.count = samples.round() as u32;
+            self.inc = dist / (self.count as f32);
+        }
+    }
+
+    /// Returns the current frequency in Hz.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.current.exp2()
+    }
+
+    /// Returns true while a glide is running.
+    #[inline]
+    pub fn is_gliding(&self) -> bool {
+        self.count > 0
+    }
+
+    /// Advances the glide by one sample and returns the current frequency in Hz.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        if self.count > 0 {
+            self.count -= 1;
+            self.current = if self.count == 0 { self.target } else { self.current + self.inc };
+        }
+
+        self.current.exp2()
+    }
+}
+
+impl Default for Portamento {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interpolation between the points of an [AutomationLane].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutomationInterp {
+    /// Linear interpolation between the points.
+    Linear,
+    /// Holds the value of a point until the next point is reached.
+    Hold,
+}
+
+/// Plays back a timeline of parameter automation, independent of any triggers.
+/// Useful for rendering automated parameter sweeps deterministically, for instance offline.
+///
+/// The timeline is a list of `(time_sec, value)` points. Before the first point its
+/// value is returned, after the last point the value of the last point is held.
+///
+///```
+/// use synfx_dsp::{AutomationLane, AutomationInterp};
+///
+/// let mut lane = AutomationLane::new();
+/// lane.set_sample_rate(10.0);
+/// lane.set_points(&[(0.0, 0.0), (1.0, 1.0), (2.0, 0.5)]);
+///
+/// let out: Vec<f32> = (0..5).map(|_| lane.next()).collect();
+/// assert_eq!(out, vec![0.0, 0.1, 0.2, 0.3, 0.4]);
+///
+/// lane.seek(1.5);
+/// assert!((lane.next() - 0.75).abs() < 0.0001);
+///
+/// lane.set_interpolation(AutomationInterp::Hold);
+/// lane.seek(1.5);
+/// assert_eq!(lane.next(), 1.0);
+/// lane.seek(10.0);
+/// assert_eq!(lane.next(), 0.5);
+///```
+#[derive(Debug, Clone)]
+pub struct AutomationLane {
+    points: Vec<(f32, f32)>,
+    interp: AutomationInterp,
+    israte: f64,
+    time: f64,
+    /// Index of the last point at or before `time`.
+    idx: usize,
+}
+
+impl AutomationLane {
+    pub fn new() -> Self {
+        Self {
+            points: vec![],
+            interp: AutomationInterp::Linear,
+            israte: 1.0 / 44100.0,
+            time: 0.0,
+            idx: 0,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.israte = 1.0 / (srate as f64);
+    }
+
+    /// Restarts the playback at the beginning of the timeline.
+    pub fn reset(&mut self) {
+        self.seek(0.0);
+    }
+
+    /// Sets the points of the timeline, they are sorted by their time.
+    pub fn set_points(&mut self, points: &[(f32, f32)]) {
+        self.points = points.to_vec();
+        self.points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self.seek(self.time as f32);
+    }
+
+    pub fn set_interpolation(&mut self, interp: AutomationInterp) {
+        self.interp = interp;
+    }
+
+    /// Returns the current playback position in seconds.
+    pub fn time(&self) -> f32 {
+        self.time as f32
+    }
+
+    /// Moves the playback position to `time_sec`.
+    pub fn seek(&mut self, time_sec: f32) {
+        self.time = time_sec.max(0.0) as f64;
+        self.idx = 0;
+    }
+
+    /// Returns the value at the current playback position and advances it by one sample.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        if self.points.is_empty() {
+            return 0.0;
+        }
+
+        let t = self.time as f32;
+        self.time += self.israte;
+
+        while self.idx + 1 < self.points.len() && self.points[self.idx + 1].0 <= t {
+            self.idx += 1;
+        }
+
+        let (t0, v0) = self.points[self.idx];
+        if t <= t0 || self.idx + 1 >= self.points.len() {
+            return v0;
+        }
+
+        match self.interp {
+            AutomationInterp::Hold => v0,
+            AutomationInterp::Linear => {
+                let (t1, v1) = self.points[self.idx + 1];
+                v0 + (v1 - v0) * ((t - t0) / (t1 - t0))
+            }
+        }
+    }
+}
+
+impl Default for AutomationLane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Quantizer {
     old_mask: i64,
@@ -0,0 +1,371 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! Utilities for mixing signals, such as dry/wet mixing of effect returns,
+//! panning, the amplitude modulation effects [Tremolo] and [AutoPan] and
+//! summing of synthesizer voices with the [VoiceMixer].
+
+use crate::{tempo_division_hz, CrossfadeCurve, TriSawLFO};
+
+/// Applies a mid/side based stereo width to a stereo signal.
+///
+/// * `l` / `r` - The stereo input.
+/// * `width` - 0.0 is mono, 1.0 leaves the signal untouched and 2.0 doubles the side signal.
+#[inline]
+pub fn stereo_width(l: f32, r: f32, width: f32) -> (f32, f32) {
+    let mid = (l + r) * 0.5;
+    let side = (l - r) * 0.5 * width;
+    (mid + side, mid - side)
+}
+
+/// Calculates the gains for a constant power pan, the summed power of both
+/// channels stays the same for all pan positions.
+///
+/// * `pan` - The pan position, -1.0 is left, 0.0 center and 1.0 right.
+///
+///```
+/// use synfx_dsp::pan_constant_power;
+///
+/// let (l, r) = pan_constant_power(0.0);
+/// assert!((l - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.0001);
+/// assert!((l - r).abs() < 0.0001);
+///
+/// let (l, r) = pan_constant_power(0.3);
+/// assert!((l * l + r * r - 1.0).abs() < 0.0001);
+///```
+#[inline]
+pub fn pan_constant_power(pan: f32) -> (f32, f32) {
+    let x = (pan.clamp(-1.0, 1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+    (x.cos(), x.sin())
+}
+
+/// Dry/wet mixer for the send/return of stereo effects such as the [crate::DattorroReverb].
+///
+/// The stereo width of the wet signal is adjusted with [stereo_width] before it is crossfaded
+/// with the dry signal. The crossfade curve is equal power by default.
+///
+///```
+/// use synfx_dsp::{WetDryMixer, CrossfadeCurve};
+///
+/// let mut mixer = WetDryMixer::new();
+///
+/// let (l, r) = mixer.process((0.5, 0.5), (1.0, -1.0), 1.0, 0.0);
+/// assert!(l.abs() < 0.0001 && r.abs() < 0.0001);
+///
+/// mixer.set_curve(CrossfadeCurve::Linear);
+/// let (l, r) = mixer.process((0.5, 0.5), (1.0, -1.0), 0.5, 1.0);
+/// assert!((l - 0.75).abs() < 0.0001);
+/// assert!((r - -0.25).abs() < 0.0001);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct WetDryMixer {
+    curve: CrossfadeCurve,
+}
+
+impl WetDryMixer {
+    /// Creates a new mixer with an equal power crossfade.
+    pub fn new() -> Self {
+        Self { curve: CrossfadeCurve::EqualPower }
+    }
+
+    /// Selects the crossfade curve between dry and wet.
+    pub fn set_curve(&mut self, curve: CrossfadeCurve) {
+        self.curve = curve;
+    }
+
+    /// Mixes the dry and wet signals.
+    ///
+    /// * `dry` - The dry stereo signal.
+    /// * `wet` - The wet stereo signal, eg. the return of a reverb.
+    /// * `mix` - The mix between dry and wet, range 0.0 (dry) to 1.0 (wet).
+    /// * `width` - The stereo width of the wet signal, see [stereo_width].
+    #[inline]
+    pub fn process(&self, dry: (f32, f32), wet: (f32, f32), mix: f32, width: f32) -> (f32, f32) {
+        let (wet_l, wet_r) = stereo_width(wet.0, wet.1, width);
+        let (dry_gain, wet_gain) = self.curve.gains(mix.clamp(0.0, 1.0));
+
+        (dry.0 * dry_gain + wet_l * wet_gain, dry.1 * dry_gain + wet_r * wet_gain)
+    }
+}
+
+impl Default for WetDryMixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A tremolo, which modulates the amplitude of a signal with a [TriSawLFO].
+///
+/// The gain is `1.0 - depth * lfo`, with the LFO running from 0.0 to 1.0. A `depth` of 0.0
+/// bypasses the tremolo, 1.0 modulates the gain fully down to 0.0. The gain
+/// never becomes negative, so the phase of the signal is never inverted.
+///
+///```
+/// use synfx_dsp::Tremolo;
+///
+/// let mut trem = Tremolo::new();
+/// trem.set_sample_rate(44100.0);
+/// // 8th notes at 120 BPM, triangle shape:
+/// trem.set_params(1.0, 0.5, 0.5);
+/// trem.set_sync(120.0, 0.5);
+///
+/// let out: Vec<f32> = (0..44100).map(|_| trem.process(1.0)).collect();
+/// let min = out.iter().fold(1.0_f32, |m, s| m.min(*s));
+/// assert!((min - 0.5).abs() < 0.001);
+/// assert!(out.iter().all(|s| *s <= 1.0));
+///```
+#[derive(Debug, Clone)]
+pub struct Tremolo {
+    lfo: TriSawLFO<f32>,
+    rate_hz: f32,
+    depth: f32,
+    shape: f32,
+}
+
+impl Tremolo {
+    pub fn new() -> Self {
+        let mut this = Self { lfo: TriSawLFO::new(), rate_hz: 5.0, depth: 0.0, shape: 0.5 };
+        this.set_params(5.0, 0.0, 0.5);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.lfo.set_sample_rate(srate);
+        self.lfo.set(self.rate_hz, self.shape);
+    }
+
+    pub fn reset(&mut self) {
+        self.lfo.reset();
+        self.lfo.set(self.rate_hz, self.shape);
+    }
+
+    /// Sets the parameters of the tremolo.
+    ///
+    /// * `rate_hz` - The LFO rate in Hz.
+    /// * `depth` - The modulation depth, 0.0 is bypass, 1.0 is full modulation.
+    /// * `shape` - The LFO shape, 0.0 is a falling saw, 0.5 a triangle and 1.0 a rising saw.
+    ///   See also [TriSawLFO].
+    pub fn set_params(&mut self, rate_hz: f32, depth: f32, shape: f32) {
+        self.rate_hz = rate_hz;
+        self.depth = depth.clamp(0.0, 1.0);
+        self.shape = shape;
+        self.lfo.set(rate_hz, shape);
+    }
+
+    /// Syncs the LFO rate to a tempo, see also [crate::tempo_division_hz].
+    ///
+    /// * `bpm` - The tempo in beats (quarter notes) per minute.
+    /// * `beats` - The length of one LFO cycle in beats.
+    pub fn set_sync(&mut self, bpm: f32, beats: f32) {
+        self.set_params(tempo_division_hz(bpm, beats), self.depth, self.shape);
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let lfo = self.lfo.next_unipolar().clamp(0.0, 1.0);
+        input * (1.0 - self.depth * lfo)
+    }
+}
+
+impl Default for Tremolo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An auto pan, which moves a stereo signal between the channels with a [TriSawLFO]
+/// and a constant power pan (see [pan_constant_power]).
+///
+/// The pan gains are normalized to 1.0 in the center, so a `depth` of 0.0 bypasses the
+/// auto pan. At a `depth` of 1.0 the signal moves fully from one side to the other,
+/// and the channel the signal moved to is boosted by up to 3dB.
+///
+///```
+/// use synfx_dsp::AutoPan;
+///
+/// let mut pan = AutoPan::new();
+/// pan.set_sample_rate(44100.0);
+/// pan.set_params(2.0, 1.0, 0.5);
+///
+/// let mut max_l: f32 = 0.0;
+/// let mut min_l: f32 = 2.0;
+/// for _ in 0..44100 {
+///     let (l, r) = pan.process(1.0, 1.0);
+///     // The power stays constant:
+///     assert!((l * l + r * r - 2.0).abs() < 0.001);
+///     max_l = max_l.max(l);
+///     min_l = min_l.min(l);
+/// }
+/// assert!(min_l < 0.01);
+/// assert!(max_l > 1.4);
+///```
+#[derive(Debug, Clone)]
+pub struct AutoPan {
+    lfo: TriSawLFO<f32>,
+    rate_hz: f32,
+    depth: f32,
+    shape: f32,
+}
+
+impl AutoPan {
+    pub fn new() -> Self {
+        let mut this = Self { lfo: TriSawLFO::new(), rate_hz: 1.0, depth: 0.0, shape: 0.5 };
+        this.set_params(1.0, 0.0, 0.5);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.lfo.set_sample_rate(srate);
+        self.lfo.set(self.rate_hz, self.shape);
+    }
+
+    pub fn reset(&mut self) {
+        self.lfo.reset();
+        self.lfo.set(self.rate_hz, self.shape);
+    }
+
+    /// Sets the parameters of the auto pan.
+    ///
+    /// * `rate_hz` - The LFO rate in Hz.
+    /// * `depth` - The modulation depth, 0.0 is bypass, 1.0 pans fully from left to right.
+    /// * `shape` - The LFO shape, 0.0 is a falling saw, 0.5 a triangle and 1.0 a rising saw.
+    ///   See also [TriSawLFO].
+    pub fn set_params(&mut self, rate_hz: f32, depth: f32, shape: f32) {
+        self.rate_hz = rate_hz;
+        self.depth = depth.clamp(0.0, 1.0);
+        self.shape = shape;
+        self.lfo.set(rate_hz, shape);
+    }
+
+    /// Syncs the LFO rate to a tempo, see also [crate::tempo_division_hz].
+    ///
+    /// * `bpm` - The tempo in beats (quarter notes) per minute.
+    /// * `beats` - The length of one LFO cycle in beats.
+    pub fn set_sync(&mut self, bpm: f32, beats: f32) {
+        self.set_params(tempo_division_hz(bpm, beats), self.depth, self.shape);
+    }
+
+    #[inline]
+    pub fn process(&mut self, l: f32, r: f32) -> (f32, f32) {
+        let lfo = self.lfo.next_bipolar().clamp(-1.0, 1.0);
+        let (gain_l, gain_r) = pan_constant_power(self.depth * lfo);
+        (l * gain_l * std::f32::consts::SQRT_2, r * gain_r * std::f32::consts::SQRT_2)
+    }
+}
+
+impl Default for AutoPan {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Time constant of the gain smoothing of [VoiceMixer], when the voice count changes.
+const VOICE_MIXER_GAIN_SLEW_MS: f32 = 5.0;
+
+/// Sums the outputs of a variable number of synthesizer voices onto a bus.
+///
+/// The sum is normalized by `1 / sqrt(voice_count)`, which keeps the loudness of
+/// uncorrelated voices about the same, regardless of how many are playing. The gain change
+/// is smoothed to prevent clicks. After that any DC offset is removed with a [crate::DCBlockFilter]
+/// and the bus is gently saturated with [crate::tanh_levien_f64], so that correlated voices
+/// (unison, chords with common phases) do not clip hard.
+///
+///```
+/// use synfx_dsp::VoiceMixer;
+///
+/// let mut mix = VoiceMixer::new();
+/// mix.set_sample_rate(44100.0);
+/// mix.set_voice_count(8);
+///
+/// let mut max: f32 = 0.0;
+/// let mut sum = 0.0;
+/// for i in 0..44100 {
+///     mix.begin();
+///     for v in 0..8 {
+///         // 8 voices with a DC offset, all in phase:
+///         let phase = i as f32 * std::f32::consts::TAU * 110.0 * (1.0 + v as f32 * 0.01) / 44100.0;
+///         mix.add(phase.sin() + 0.5);
+///     }
+///     let out = mix.finish();
+///     max = max.max(out.abs());
+///     if i >= 22050 {
+///         sum += out;
+///     }
+/// }
+///
+/// // Never exceeds full scale:
+/// assert!(max < 1.0);
+/// // The DC offset is gone:
+/// assert!((sum / 22050.0).abs() < 0.01);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct VoiceMixer {
+    sum: f32,
+    voice_count: usize,
+    gain: f32,
+    gain_coef: f32,
+    dc_block: crate::DCBlockFilter<f32>,
+}
+
+impl VoiceMixer {
+    pub fn new() -> Self {
+        let mut this = Self {
+            sum: 0.0,
+            voice_count: 1,
+            gain: 1.0,
+            gain_coef: 0.0,
+            dc_block: crate::DCBlockFilter::new(),
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.dc_block.set_sample_rate(srate);
+        self.gain_coef = 1.0 - (-1000.0 / (VOICE_MIXER_GAIN_SLEW_MS * srate)).exp();
+    }
+
+    pub fn reset(&mut self) {
+        self.sum = 0.0;
+        self.gain = self.target_gain();
+        self.dc_block.reset();
+    }
+
+    /// Sets the number of currently active voices, which determines the normalization gain.
+    /// A count of 0 is treated like 1.
+    pub fn set_voice_count(&mut self, n: usize) {
+        self.voice_count = n.max(1);
+    }
+
+    #[inline]
+    fn target_gain(&self) -> f32 {
+        1.0 / (self.voice_count as f32).sqrt()
+    }
+
+    /// Starts summing the voices for the next sample.
+    #[inline]
+    pub fn begin(&mut self) {
+        self.sum = 0.0;
+    }
+
+    /// Adds the output sample of one voice.
+    #[inline]
+    pub fn add(&mut self, sample: f32) {
+        self.sum += sample;
+    }
+
+    /// Returns the normalized, DC free and saturated bus sample.
+    #[inline]
+    pub fn finish(&mut self) -> f32 {
+        self.gain += (self.target_gain() - self.gain) * self.gain_coef;
+        let s = self.dc_block.next(self.sum * self.gain);
+        crate::tanh_levien_f64(s as f64) as f32
+    }
+}
+
+impl Default for VoiceMixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -5,6 +5,7 @@
 //! Various "voltage" controlled (usually band limited) oscillator implementations.
 
 use crate::fast_sin;
+use crate::{lerp, Rng};
 
 // PolyBLEP by Tale
 // (slightly modified)
@@ -26,7 +27,15 @@ fn poly_blep_64(t: f64, dt: f64) -> f64 {
     }
 }
 
-fn poly_blep(t: f32, dt: f32) -> f32 {
+/// The PolyBLEP residual, that band limits a step (a discontinuity in the value) of
+/// height 2.0 in a naive waveform. Add it scaled by the half height of the step.
+///
+/// * `t` - The phase of the waveform relative to the step, in the range 0.0 to 1.0.
+/// * `dt` - The phase increment per sample.
+///
+/// See also [poly_blamp] for discontinuities in the slope.
+#[inline]
+pub fn poly_blep(t: f32, dt: f32) -> f32 {
     if t < dt {
         let t = t / dt;
         2. * t - (t * t) - 1.
@@ -38,9 +47,42 @@ fn poly_blep(t: f32, dt: f32) -> f32 {
     }
 }
 
+/// The PolyBLAMP residual, that band limits a corner (a discontinuity in the slope)
+/// in a naive waveform. It is the integral of [poly_blep] and belongs to a slope
+/// increase of 2.0 per sample. For a slope change of `d` per phase, add it scaled
+/// by `0.5 * d * dt`.
+///
+/// * `t` - The phase of the waveform relative to the corner, in the range 0.0 to 1.0.
+/// * `dt` - The phase increment per sample.
+///
+/// See also: Esqueda, Välimäki, Bilbao: "Rounding Corners with BLAMP" (DAFx-16).
+///
+///```
+/// use synfx_dsp::poly_blamp;
+///
+/// // The residual is largest at the corner and fades out within one sample:
+/// assert!((poly_blamp(0.0, 0.01) - 1.0 / 3.0).abs() < 0.0001);
+/// assert!(poly_blamp(0.005, 0.01) < poly_blamp(0.0, 0.01));
+/// assert_eq!(poly_blamp(0.5, 0.01), 0.0);
+///```
+#[inline]
+pub fn poly_blamp(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let t = 1.0 - t / dt;
+        t * t * t / 3.0
+    } else if t > (1.0 - dt) {
+        let t = (t - 1.0) / dt + 1.0;
+        t * t * t / 3.0
+    } else {
+        0.
+    }
+}
+
 /// This is a band-limited oscillator based on the PolyBlep technique.
 ///
-/// **NOTE:** You need to call [crate::init_cos_tab].
+/// **NOTE:** [PolyBlepOscillator::next_sin] uses [crate::fast_sin], call
+/// [crate::init_cos_tab] on initialization to keep the table generation out of
+/// your audio thread.
 ///
 /// Here is a quick example on how to use it:
 ///
@@ -49,7 +91,8 @@ fn poly_blep(t: f32, dt: f32) -> f32 {
 /// init_cos_tab();
 ///
 /// // Randomize the initial phase to make cancellation on summing less
-/// // likely:
+/// // likely. For reproducible phases of multiple oscillators
+/// // see also [crate::spread_init_phases].
 /// let mut osc =
 ///     PolyBlepOscillator::new(rand_01() * 0.25);
 ///
@@ -75,6 +118,20 @@ pub struct PolyBlepOscillator {
     phase: f32,
     init_phase: f32,
     last_output: f32,
+    tri_dc: f32,
+}
+
+/// The cutoff frequency of the DC servo in [PolyBlepOscillator::next_tri].
+const TRI_DC_SERVO_HZ: f32 = 5.0;
+
+/// Flushes values that are about to become denormal to zero.
+#[inline]
+fn flush_denormal(x: f32) -> f32 {
+    if x.abs() < 1e-20 {
+        0.0
+    } else {
+        x
+    }
 }
 
 impl PolyBlepOscillator {
@@ -91,7 +148,7 @@ impl PolyBlepOscillator {
     /// let mut osc = PolyBlepOscillator::new(rand_01() * 0.25);
     ///```
     pub fn new(init_phase: f32) -> Self {
-        Self { phase: 0.0, last_output: 0.0, init_phase }
+        Self { phase: 0.0, last_output: 0.0, tri_dc: 0.0, init_phase }
     }
 
     /// Reset the internal state of the oscillator as if you just called
@@ -100,9 +157,11 @@ impl PolyBlepOscillator {
     pub fn reset(&mut self) {
         self.phase = self.init_phase;
         self.last_output = 0.0;
+        self.tri_dc = 0.0;
     }
 
     /// Creates the next sample of a sine wave.
+    /// If you don't want to depend on [crate::init_cos_tab], see [SineOsc].
     ///
     /// * `freq` - The frequency in Hz.
     /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
@@ -133,6 +192,11 @@ impl PolyBlepOscillator {
     /// Creates the next sample of a triangle wave. Please note that the
     /// bandlimited waveform needs a few initial samples to swing in.
     ///
+    /// The internal integrator is clamped, flushed of denormals and has a DC servo
+    /// (a high pass at about 5 Hz) applied, so that long held low notes don't drift.
+    /// See [PolyBlepOscillator::next_tri_blamp] for a triangle without integrator,
+    /// that keeps its amplitude and phase at all frequencies.
+    ///
     /// * `freq` - The frequency in Hz.
     /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
     ///```
@@ -158,8 +222,14 @@ impl PolyBlepOscillator {
 
         // leaky integrator: y[n] = A * x[n] + (1 - A) * y[n-1]
         s = phase_inc * s + (1.0 - phase_inc) * self.last_output;
+        s = flush_denormal(s.clamp(-1.0, 1.0));
         self.last_output = s;
 
+        // DC servo: subtract the slowly tracked DC offset of the integrator.
+        let dc_coef = (std::f32::consts::TAU * TRI_DC_SERVO_HZ * israte).min(1.0);
+        self.tri_dc = flush_denormal(self.tri_dc + dc_coef * (s - self.tri_dc));
+        s -= self.tri_dc;
+
         self.phase += phase_inc;
         self.phase = self.phase.fract();
 
@@ -169,6 +239,42 @@ impl PolyBlepOscillator {
         s * 4.0
     }
 
+    /// Creates the next sample of a triangle wave, which is band limited with
+    /// [poly_blamp] at its corners. Unlike [PolyBlepOscillator::next_tri] this needs
+    /// no integrator, so there is no swing in, no amplitude or phase drift at low
+    /// frequencies and no DC offset. The output range is -1.0 to 1.0.
+    ///
+    /// * `freq` - The frequency in Hz.
+    /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
+    ///```
+    /// use synfx_dsp::*;
+    ///
+    /// let mut osc = PolyBlepOscillator::new(0.0);
+    ///
+    /// // A very low note keeps its full amplitude:
+    /// let israte = 1.0 / 44100.0;
+    /// let out: Vec<f32> = (0..44100 * 4).map(|_| osc.next_tri_blamp(2.0, israte)).collect();
+    /// let max = out.iter().fold(-2.0_f32, |m, s| m.max(*s));
+    /// let min = out.iter().fold(2.0_f32, |m, s| m.min(*s));
+    /// assert!((max - 1.0).abs() < 0.001);
+    /// assert!((min + 1.0).abs() < 0.001);
+    ///```
+    #[inline]
+    pub fn next_tri_blamp(&mut self, freq: f32, israte: f32) -> f32 {
+        let phase_inc = freq * israte;
+
+        let mut s = if self.phase < 0.5 { 4.0 * self.phase - 1.0 } else { 3.0 - 4.0 * self.phase };
+
+        // The slope changes by 8.0 per phase at the corners:
+        s += 4.0 * phase_inc * poly_blamp(self.phase, phase_inc);
+        s -= 4.0 * phase_inc * poly_blamp((self.phase + 0.5).fract(), phase_inc);
+
+        self.phase += phase_inc;
+        self.phase = self.phase.fract();
+
+        s
+    }
+
     /// Creates the next sample of a sawtooth wave.
     ///
     /// * `freq` - The frequency in Hz.
@@ -278,6 +384,1112 @@ impl PolyBlepOscillator {
     }
 }
 
+/// The differentiated parabolic wave (DPW) of the sawtooth at `phase`, see [DPWOscillator].
+#[inline]
+fn dpw_saw(phase: f64, phase_inc: f64) -> f64 {
+    let parabola = |phase: f64| {
+        let s = 2.0 * phase - 1.0;
+        s * s
+    };
+    let prev_phase = (phase - phase_inc).rem_euclid(1.0);
+    (parabola(phase) - parabola(prev_phase)) / (4.0 * phase_inc)
+}
+
+/// A band-limited oscillator based on the differentiated parabolic wave (DPW) technique,
+/// as an alternative to the [PolyBlepOscillator].
+///
+/// The naive sawtooth is squared into a parabola, which has far less aliasing,
+/// and is then differentiated back into a sawtooth. This is cheaper than PolyBlep,
+/// but the suppression of aliasing is less effective at high pitches. The square wave is
+/// the difference of two sawtooth waves, that are half a period apart.
+///
+/// The phase and the differentiation are calculated in `f64`,
+/// as the differentiation needs a lot of precision at low pitches.
+///
+///```
+/// use synfx_dsp::DPWOscillator;
+///
+/// let mut osc = DPWOscillator::new(0.0);
+///
+/// let freq = 441.0; // Hz
+/// let israte = 1.0 / 44100.0; // Seconds per Sample
+///
+/// let saw: Vec<f32> = (0..100).map(|_| osc.next_saw(freq, israte)).collect();
+/// // The sawtooth rises from -1.0 to 1.0 over one period:
+/// assert!((saw[50] - 0.0).abs() < 0.02);
+/// assert!(saw[10] < saw[20] && saw[20] < saw[30]);
+/// assert!(saw.iter().all(|s| s.abs() <= 1.0));
+///
+/// osc.reset();
+/// let square: Vec<f32> = (0..100).map(|_| osc.next_square(freq, israte)).collect();
+/// assert!((square[25] - 1.0).abs() < 0.001);
+/// assert!((square[75] + 1.0).abs() < 0.001);
+/// // No DC offset:
+/// assert!(square.iter().sum::<f32>().abs() < 0.001);
+///```
+#[derive(Debug, Clone)]
+pub struct DPWOscillator {
+    phase: f64,
+    init_phase: f64,
+}
+
+impl DPWOscillator {
+    /// Create a new instance of [DPWOscillator].
+    ///
+    /// * `init_phase` - Initial phase of the oscillator, range 0.0 to 1.0.
+    pub fn new(init_phase: f32) -> Self {
+        let init_phase = (init_phase as f64).rem_euclid(1.0);
+        Self { phase: init_phase, init_phase }
+    }
+
+    /// Reset the internal state of the oscillator as if you just called
+    /// [DPWOscillator::new].
+    #[inline]
+    pub fn reset(&mut self) {
+        self.phase = self.init_phase;
+    }
+
+    #[inline]
+    fn advance(&mut self, freq: f32, israte: f32) -> f64 {
+        // Above half the sample rate the differentiation breaks down:
+        let phase_inc = ((freq * israte) as f64).clamp(1e-7, 0.5);
+        self.phase = (self.phase + phase_inc).fract();
+        phase_inc
+    }
+
+    /// Creates the next sample of a sawtooth wave.
+    ///
+    /// * `freq` - The frequency in Hz.
+    /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
+    #[inline]
+    pub fn next_saw(&mut self, freq: f32, israte: f32) -> f32 {
+        let phase_inc = self.advance(freq, israte);
+        dpw_saw(self.phase, phase_inc).clamp(-1.0, 1.0) as f32
+    }
+
+    /// Creates the next sample of a square wave.
+    ///
+    /// * `freq` - The frequency in Hz.
+    /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
+    #[inline]
+    pub fn next_square(&mut self, freq: f32, israte: f32) -> f32 {
+        let phase_inc = self.advance(freq, israte);
+        let s = dpw_saw((self.phase + 0.5).fract(), phase_inc) - dpw_saw(self.phase, phase_inc);
+        s.clamp(-1.0, 1.0) as f32
+    }
+}
+
+/// The number of taps of the windowed sinc impulses of a [BlitOscillator].
+const BLIT_TAPS: usize = 16;
+/// The number of fractional positions of the windowed sinc table of a [BlitOscillator].
+const BLIT_PHASES: usize = 64;
+/// The corner frequency of the leaky integrators of a [BlitOscillator] in Hz.
+const BLIT_LEAK_HZ: f64 = 1.0;
+
+/// One sample of all outputs of a [BlitOscillator].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlitOutputs {
+    /// The band limited impulse train. Each impulse has an area of 1.0,
+    /// so the signal has a DC offset of the frequency divided by the sample rate.
+    pub impulse: f32,
+    /// The integrated impulse train, a rising sawtooth in the range -1.0 to 1.0.
+    pub saw: f32,
+    /// The integrated bipolar impulse train, a square wave in the range -1.0 to 1.0.
+    pub square: f32,
+}
+
+/// An oscillator based on band limited impulse trains (BLIT), as another classic technique
+/// next to [PolyBlepOscillator] and [DPWOscillator].
+///
+/// Every discontinuity of the waveform is rendered as a windowed sinc impulse
+/// (see [crate::SincTable]) at its exact fractional position. The impulses are integrated
+/// by leaky integrators into a sawtooth and a square wave. As the impulses are band limited
+/// to the Nyquist frequency independently of the pitch, the amplitude stays the same
+/// across the whole frequency range. The phase is calculated in `f64`.
+///
+/// The impulses are centered on their position, which delays the output by 7 samples.
+///
+/// Hard sync is supported by resetting the phase at a fractional position within
+/// a sample, see [BlitOscillator::next_sync] and [BlitOscillator::last_wrap].
+///
+///```
+/// use synfx_dsp::BlitOscillator;
+///
+/// let israte = 1.0 / 44100.0;
+///
+/// for freq in [50.0, 500.0, 5000.0] {
+///     let mut osc = BlitOscillator::new(0.0);
+///     let out: Vec<_> = (0..44100).map(|_| osc.next(freq, israte)).collect();
+///
+///     // The amplitude is stable across the frequency range, the RMS of
+///     // a sawtooth is 1.0 / sqrt(3.0):
+///     let rms = |f: fn(&_) -> f32| {
+///         (out[22050..].iter().map(|o| f(o) * f(o)).sum::<f32>() / 22050.0).sqrt()
+///     };
+///     assert!((rms(|o| o.saw) - 0.577).abs() < 0.01, "{}", freq);
+///     assert!((rms(|o| o.square) - 1.0).abs() < 0.02, "{}", freq);
+///
+///     // One impulse of area 1.0 per period:
+///     let area: f32 = out[22050..].iter().map(|o| o.impulse).sum();
+///     assert!((area - freq * 0.5).abs() < 1.0);
+/// }
+///```
+#[derive(Debug, Clone)]
+pub struct BlitOscillator {
+    phase: f64,
+    init_phase: f64,
+    table: crate::SincTable,
+    /// The accumulated impulses of the sawtooth (0) and the square wave (1).
+    bufs: [[f64; BLIT_TAPS]; 2],
+    buf_pos: usize,
+    saw: f64,
+    square: f64,
+    /// Whether the integrators were set up for the output latency since the last reset.
+    primed: bool,
+    last_wrap: Option<f32>,
+}
+
+impl BlitOscillator {
+    /// Create a new instance of [BlitOscillator].
+    ///
+    /// * `init_phase` - Initial phase of the oscillator, range 0.0 to 1.0.
+    pub fn new(init_phase: f32) -> Self {
+        let init_phase = (init_phase as f64).rem_euclid(1.0);
+        Self {
+            phase: init_phase,
+            init_phase,
+            table: crate::SincTable::new(BLIT_TAPS, BLIT_PHASES),
+            bufs: [[0.0; BLIT_TAPS]; 2],
+            buf_pos: 0,
+            saw: 0.0,
+            square: 0.0,
+            primed: false,
+            last_wrap: None,
+        }
+    }
+
+    /// Reset the internal state of the oscillator as if you just called
+    /// [BlitOscillator::new].
+    pub fn reset(&mut self) {
+        self.phase = self.init_phase;
+        self.bufs = [[0.0; BLIT_TAPS]; 2];
+        self.buf_pos = 0;
+        self.primed = false;
+        self.last_wrap = None;
+    }
+
+    /// Returns the position within the last sample (range 0.0 to 1.0), where the phase
+    /// wrapped around, or `None` if it did not. Pass it to [BlitOscillator::next_sync]
+    /// of another oscillator to hard sync it to this one.
+    pub fn last_wrap(&self) -> Option<f32> {
+        self.last_wrap
+    }
+
+    /// Adds an impulse with the weight `w` to the impulse buffer `buf` (0 is the sawtooth,
+    /// 1 the square wave) at the time `time` in samples, relative to the start
+    /// of the current sample.
+    #[inline]
+    fn add_impulse(&mut self, buf: usize, time: f64, w: f64) {
+        let n = time.floor();
+        // The impulse is centered on the position, the first tap is the latest sample:
+        let fract = (1.0 - (time - n)) * BLIT_PHASES as f64;
+        let idx = (fract as usize).min(BLIT_PHASES - 1);
+        let f = fract - idx as f64;
+        let (c0, c1) = (self.table.phase(idx), self.table.phase(idx + 1));
+
+        let half = (BLIT_TAPS / 2) as i64;
+        for j in 0..BLIT_TAPS {
+            let t = n as i64 + half - j as i64;
+            let c = c0[j] + (c1[j] - c0[j]) * f;
+            // Taps before the oldest sample, that is still waiting for output,
+            // go directly into the integrators:
+            if t < 1 - half {
+                if buf == 0 {
+                    self.saw -= 2.0 * c * w;
+                } else {
+                    self.square += 2.0 * c * w;
+                }
+                continue;
+            }
+            self.bufs[buf][(self.buf_pos as i64 + t).rem_euclid(BLIT_TAPS as i64) as usize] +=
+                c * w;
+        }
+    }
+
+    /// Advances the phase from the time `from` to `to` in samples relative to the start
+    /// of the current sample, and adds the impulses of all discontinuities in between.
+    fn run(&mut self, dt: f64, from: f64, to: f64, mut sync: Option<f64>) {
+        let mut t = from;
+        loop {
+            // The next discontinuity is the falling edge of the square or the wrap around:
+            let target = if self.phase < 0.5 { 0.5 } else { 1.0 };
+            let t_event = t + (target - self.phase) / dt;
+
+            if let Some(s) = sync {
+                if s <= t_event && s < to {
+                    self.phase += (s - t) * dt;
+                    // The sawtooth drops from its current value to -1.0, and the square
+                    // rises if it was low:
+                    self.add_impulse(0, s, self.phase);
+                    if self.phase >= 0.5 {
+                        self.add_impulse(1, s, 1.0);
+                    }
+                    self.phase = 0.0;
+                    t = s;
+                    sync = None;
+                    continue;
+                }
+            }
+
+            if t_event >= to {
+                self.phase += (to - t) * dt;
+                break;
+            }
+
+            if target < 1.0 {
+                self.add_impulse(1, t_event, -1.0);
+                self.phase = 0.5;
+            } else {
+                self.add_impulse(0, t_event, 1.0);
+                self.add_impulse(1, t_event, 1.0);
+                self.last_wrap = Some(t_event.clamp(0.0, 1.0) as f32);
+                self.phase = 0.0;
+            }
+            t = t_event;
+        }
+    }
+
+    /// Creates the next sample of all outputs.
+    ///
+    /// * `freq` - The frequency in Hz.
+    /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
+    #[inline]
+    pub fn next(&mut self, freq: f32, israte: f32) -> BlitOutputs {
+        self.next_sync(freq, israte, None)
+    }
+
+    /// Creates the next sample of all outputs, with hard sync.
+    ///
+    /// * `freq` - The frequency in Hz.
+    /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
+    /// * `sync` - The position within this sample (range 0.0 to 1.0), where the phase
+    ///   is reset to 0.0, or `None` for no sync. See also [BlitOscillator::last_wrap].
+    ///
+    ///```
+    /// use synfx_dsp::BlitOscillator;
+    ///
+    /// let israte = 1.0 / 44100.0;
+    /// let mut master = BlitOscillator::new(0.0);
+    /// let mut slave = BlitOscillator::new(0.0);
+    ///
+    /// let mut saw = vec![];
+    /// for _ in 0..44100 {
+    ///     master.next(100.0, israte);
+    ///     saw.push(slave.next_sync(250.0, israte, master.last_wrap()).saw);
+    /// }
+    ///
+    /// // The slave repeats with the period of the master (441 samples):
+    /// for i in 22050..22500 {
+    ///     assert!((saw[i] - saw[i + 441]).abs() < 0.01);
+    /// }
+    ///```
+    pub fn next_sync(&mut self, freq: f32, israte: f32, sync: Option<f32>) -> BlitOutputs {
+        let dt = ((freq * israte) as f64).clamp(1e-7, 0.5);
+
+        if !self.primed {
+            // The output lags behind the phase. Start the integrators with the naive
+            // waveform far enough in the past, that the impulses from before are complete,
+            // and integrate everything before the first output sample. The summed impulses
+            // step half a sample earlier than the ramp, hence the extra half sample:
+            self.primed = true;
+            let latency = (BLIT_TAPS / 2 - 1) as f64;
+            let start = latency + (BLIT_TAPS / 2 + 1) as f64;
+            self.phase = (self.phase - start * dt).rem_euclid(1.0);
+            self.saw = 2.0 * self.phase - 1.0 + 2.0 * dt * (start - latency - 0.5);
+            self.square = if self.phase < 0.5 { 1.0 } else { -1.0 };
+            self.run(dt, -start, 0.0, None);
+        }
+
+        self.last_wrap = None;
+        self.run(dt, 0.0, 1.0, sync.map(|s| (s as f64).clamp(0.0, 1.0)));
+
+        let pos = self.buf_pos;
+        let out_idx = (pos + 1 + BLIT_TAPS / 2) % BLIT_TAPS;
+        let impulse = self.bufs[0][out_idx];
+        let square_impulse = self.bufs[1][out_idx];
+        self.bufs[0][out_idx] = 0.0;
+        self.bufs[1][out_idx] = 0.0;
+        self.buf_pos = (pos + 1) % BLIT_TAPS;
+
+        let leak = 1.0 - std::f64::consts::TAU * BLIT_LEAK_HZ * israte as f64;
+        self.saw = self.saw * leak + 2.0 * (dt - impulse);
+        self.square = self.square * leak + 2.0 * square_impulse;
+
+        BlitOutputs { impulse: impulse as f32, saw: self.saw as f32, square: self.square as f32 }
+    }
+}
+
+/// A plain sine oscillator based on a phase accumulator.
+///
+/// In contrast to [PolyBlepOscillator::next_sin] this oscillator does not need the
+/// [crate::init_cos_tab] wave table.
+///
+///```
+/// use synfx_dsp::SineOsc;
+///
+/// let mut osc = SineOsc::new();
+/// osc.set_sample_rate(44100.0);
+/// osc.set_freq(440.0);
+///
+/// let mut block_of_samples = [0.0; 128];
+/// for output_sample in block_of_samples.iter_mut() {
+///     *output_sample = osc.next();
+/// }
+///
+/// assert!(block_of_samples[0].abs() < 0.0001);
+/// assert!((block_of_samples[25] - 1.0).abs() < 0.01);
+///```
+#[derive(Debug, Clone)]
+pub struct SineOsc {
+    phase: f32,
+    init_phase: f32,
+    israte: f32,
+    phase_inc: f32,
+    freq: f32,
+}
+
+impl SineOsc {
+    /// Create a new sine oscillator at 440 Hz and 44.1 kHz sample rate.
+    pub fn new() -> Self {
+        let mut this = Self {
+            phase: 0.0,
+            init_phase: 0.0,
+            israte: 1.0 / 44100.0,
+            phase_inc: 0.0,
+            freq: 440.0,
+        };
+        this.set_freq(440.0);
+        this
+    }
+
+    /// Set the sample rate of the oscillator.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.israte = 1.0 / srate;
+        self.set_freq(self.freq);
+    }
+
+    /// Reset the phase of the oscillator to the phase set by [SineOsc::set_phase].
+    #[inline]
+    pub fn reset(&mut self) {
+        self.phase = self.init_phase;
+    }
+
+    /// Set the frequency in Hz.
+    #[inline]
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq;
+        self.phase_inc = freq * self.israte;
+    }
+
+    /// The current phase of the oscillator, in the range 0.0 to 1.0.
+    #[inline]
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Sets the phase of the oscillator, in the range 0.0 to 1.0.
+    /// [SineOsc::reset] will return to this phase.
+    #[inline]
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+        self.init_phase = self.phase;
+    }
+
+    /// Creates the next sample of the sine wave.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let s = (self.phase * std::f32::consts::TAU).sin();
+
+        self.phase += self.phase_inc;
+        self.phase = self.phase.rem_euclid(1.0);
+
+        s
+    }
+}
+
+impl Default for SineOsc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An additive oscillator made from a bank of harmonic [SineOsc] partials.
+///
+/// To make the tone less static, the partials can be stretched with an inharmonicity
+/// coefficient (like the strings of a piano) with [SineBank::set_inharmonicity], and their
+/// amplitudes can slowly drift randomly with [SineBank::set_partial_drift].
+///
+///```
+/// use synfx_dsp::SineBank;
+///
+/// let mut bank = SineBank::new(8);
+/// bank.set_sample_rate(44100.0);
+/// bank.set_freq(110.0);
+/// for i in 0..8 {
+///     bank.set_partial_amp(i, 1.0 / (i + 1) as f32);
+/// }
+/// bank.set_inharmonicity(0.0004);
+/// bank.set_partial_drift(0.2, 0.5);
+///
+/// let mut block_of_samples = [0.0; 128];
+/// for output_sample in block_of_samples.iter_mut() {
+///     *output_sample = bank.next();
+/// }
+///```
+#[derive(Debug, Clone)]
+pub struct SineBank {
+    oscs: Vec<SineOsc>,
+    amps: Vec<f32>,
+    drift_prev: Vec<f32>,
+    drift_next: Vec<f32>,
+    drift_depth: f32,
+    drift_rate: f32,
+    drift_phase: f32,
+    rng: Rng,
+    srate: f32,
+    freq: f32,
+    inharmonicity: f32,
+}
+
+impl SineBank {
+    /// Creates a bank of `partials` sine oscillators. The first partial is the
+    /// fundamental and has an amplitude of 1.0, all others start silent.
+    pub fn new(partials: usize) -> Self {
+        let mut amps = vec![0.0; partials];
+        if let Some(a) = amps.first_mut() {
+            *a = 1.0;
+        }
+
+        let mut this = Self {
+            oscs: vec![SineOsc::new(); partials],
+            amps,
+            drift_prev: vec![0.0; partials],
+            drift_next: vec![0.0; partials],
+            drift_depth: 0.0,
+            drift_rate: 0.0,
+            drift_phase: 0.0,
+            rng: Rng::new(),
+            srate: 44100.0,
+            freq: 110.0,
+            inharmonicity: 0.0,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    /// Set the sample rate of all partials.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        for osc in self.oscs.iter_mut() {
+            osc.set_sample_rate(srate);
+        }
+        self.update_freqs();
+    }
+
+    /// Resets the phases of all partials and the drift.
+    pub fn reset(&mut self) {
+        for osc in self.oscs.iter_mut() {
+            osc.reset();
+        }
+        self.drift_prev.fill(0.0);
+        self.drift_next.fill(0.0);
+        self.drift_phase = 0.0;
+    }
+
+    /// Seeds the random generator of the partial drift.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.seed(seed);
+    }
+
+    /// Set the fundamental frequency in Hz.
+    #[inline]
+    pub fn set_freq(&mut self, freq: f32) {
+        if freq != self.freq {
+            self.freq = freq;
+            self.update_freqs();
+        }
+    }
+
+    /// Set the amplitude of the partial with index `idx`, the fundamental is at index 0.
+    #[inline]
+    pub fn set_partial_amp(&mut self, idx: usize, amp: f32) {
+        if let Some(a) = self.amps.get_mut(idx) {
+            *a = amp;
+        }
+    }
+
+    /// Sets the inharmonicity coefficient `B`. The partial `n` (starting with 1 for the
+    /// fundamental) is stretched to `n * freq * sqrt(1 + B * n^2)`.
+    /// Piano strings have values of about 0.0001 to 0.001. 0.0 is purely harmonic.
+    #[inline]
+    pub fn set_inharmonicity(&mut self, coef: f32) {
+        if coef != self.inharmonicity {
+            self.inharmonicity = coef.max(0.0);
+            self.update_freqs();
+        }
+    }
+
+    /// Sets the random slow amplitude drift of the partials.
+    ///
+    /// * `depth` - The drift depth, range 0.0 (off) to 1.0 (amplitudes drift between 0.0 and 2.0).
+    /// * `rate` - The rate in Hz at which new random amplitudes are approached.
+    #[inline]
+    pub fn set_partial_drift(&mut self, depth: f32, rate: f32) {
+        self.drift_depth = depth.clamp(0.0, 1.0);
+        self.drift_rate = rate.max(0.0);
+    }
+
+    fn update_freqs(&mut self) {
+        let nyquist = self.srate * 0.5;
+        for (i, osc) in self.oscs.iter_mut().enumerate() {
+            let n = (i + 1) as f32;
+            let freq = n * self.freq * (1.0 + self.inharmonicity * n * n).sqrt();
+            // Partials above nyquist would alias, they are silenced in next().
+            osc.set_freq(if freq < nyquist { freq } else { 0.0 });
+        }
+    }
+
+    /// Creates the next sample of the sum of all partials.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        if self.drift_depth > 0.0 {
+            self.drift_phase += self.drift_rate / self.srate;
+            if self.drift_phase >= 1.0 {
+                self.drift_phase = self.drift_phase.fract();
+                for (prev, next) in self.drift_prev.iter_mut().zip(self.drift_next.iter_mut()) {
+                    *prev = *next;
+                    *next = self.rng.next() * 2.0 - 1.0;
+                }
+            }
+        }
+
+        let mut sum = 0.0;
+        for (i, osc) in self.oscs.iter_mut().enumerate() {
+            let s = osc.next();
+            let mut amp = self.amps[i];
+            if self.drift_depth > 0.0 {
+                let drift = lerp(self.drift_phase, self.drift_prev[i], self.drift_next[i]);
+                amp *= 1.0 + self.drift_depth * drift;
+            }
+            if osc.phase_inc > 0.0 {
+                sum += s * amp;
+            }
+        }
+
+        sum
+    }
+}
+
+/// Noise generator that confines white noise to a frequency band, for wind,
+/// surf or percussion sounds. The white noise from [Rng] is filtered through a Simper SVF
+/// band pass (see [crate::process_simper_svf]), normalized to unity gain at the center frequency.
+///
+/// The center frequency can be swept at audio rate for filtered noise sweeps.
+///
+///```
+/// use synfx_dsp::BandNoise;
+///
+/// let mut noise = BandNoise::new();
+/// noise.set_sample_rate(44100.0);
+/// noise.set_band(2000.0, 4.0);
+///
+/// let mut peak: f32 = 0.0;
+/// for i in 0..4410 {
+///     // A wind gust:
+///     noise.set_band(1000.0 + (i as f32) * 0.5, 4.0);
+///     peak = peak.max(noise.next().abs());
+/// }
+/// assert!(peak > 0.1);
+/// assert!(peak < 1.5);
+///```
+#[derive(Debug, Clone)]
+pub struct BandNoise {
+    rng: Rng,
+    israte: f32,
+    center: f32,
+    res: f32,
+    k: f32,
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl BandNoise {
+    pub fn new() -> Self {
+        let mut this = Self {
+            rng: Rng::new(),
+            israte: 1.0 / 44100.0,
+            center: 1000.0,
+            res: 0.0,
+            k: 2.0,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        };
+        this.set_band(1000.0, 1.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.israte = 1.0 / srate;
+    }
+
+    pub fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+
+    /// Seeds the underlying white noise generator.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.seed(seed);
+    }
+
+    /// Sets the frequency band of the noise.
+    ///
+    /// * `center_hz` - The center frequency of the band in Hz.
+    /// * `q` - The quality factor of the band pass, the band gets narrower with higher values.
+    ///   Ranges from 0.5 to roughly 90.
+    #[inline]
+    pub fn set_band(&mut self, center_hz: f32, q: f32) {
+        self.center = center_hz.clamp(1.0, 0.49 / self.israte);
+        // Inverse of the resonance mapping in process_simper_svf: k = 2.0 - 1.989 * res
+        self.res = ((2.0 - 1.0 / q.max(0.5)) / 1.989).clamp(0.0, 1.0);
+        self.k = 2.0 - 1.989 * self.res;
+    }
+
+    /// Returns the next sample of band limited noise.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let white = self.rng.next() * 2.0 - 1.0;
+        let (_, band, _) = crate::process_simper_svf(
+            white,
+            self.center,
+            self.res,
+            self.israte,
+            &mut self.ic1eq,
+            &mut self.ic2eq,
+        );
+        band * self.k
+    }
+}
+
+impl Default for BandNoise {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lowest frequency [PitchSyncOscillator] tracks, longer periods count as unvoiced.
+const PITCH_SYNC_MIN_HZ: f32 = 30.0;
+/// Highest frequency [PitchSyncOscillator] tracks.
+const PITCH_SYNC_MAX_HZ: f32 = 2000.0;
+/// Cutoff of the low pass that removes the harmonics before the zero crossing detection.
+const PITCH_SYNC_LPF_HZ: f32 = 1000.0;
+/// Level below which the input of [PitchSyncOscillator] is considered unvoiced.
+const PITCH_SYNC_VOICED_DB: f32 = -50.0;
+/// Smoothing time of the detected pitch (in the log frequency domain).
+const PITCH_SYNC_GLIDE_MS: f32 = 20.0;
+/// Fade time of the output when the input becomes (un)voiced.
+const PITCH_SYNC_FADE_MS: f32 = 10.0;
+
+/// An oscillator that tracks the pitch of an input signal and plays a sine wave at that
+/// (or a harmonically related) frequency. Useful for sub synth and octave doubler effects.
+///
+/// The pitch is tracked by measuring the time between the rising zero crossings of the low pass
+/// filtered input, with a hysteresis relative to the input level. The detected pitch is smoothed
+/// in the log frequency domain, so that jitter in the detection does not cause audible
+/// pitch wobble. The output follows the level of the input. If the input is too quiet or
+/// has no detectable period between 30Hz and 2kHz (unvoiced), the output fades out
+/// and the last detected pitch is held, so that it resumes without a glide from somewhere else.
+///
+///```
+/// use synfx_dsp::PitchSyncOscillator;
+///
+/// let mut sub = PitchSyncOscillator::new();
+/// sub.set_sample_rate(44100.0);
+/// // One octave down:
+/// sub.set_interval(-12.0);
+///
+/// let mut crossings = 0;
+/// let mut last = 0.0;
+/// for i in 0..88200 {
+///     let input = (i as f32 * std::f32::consts::TAU * 220.0 / 44100.0).sin() * 0.5;
+///     let s = sub.process(input);
+///     if i >= 44100 && last < 0.0 && s >= 0.0 {
+///         crossings += 1;
+///     }
+///     last = s;
+/// }
+///
+/// assert!(sub.is_voiced());
+/// assert!((sub.detected_freq() - 220.0).abs() < 1.0);
+/// assert!((crossings - 110_i32).abs() <= 1);
+///```
+#[derive(Debug, Clone)]
+pub struct PitchSyncOscillator {
+    osc: SineOsc,
+    lpf: crate::TptOnePole,
+    level: crate::EnvelopeFollower,
+    srate: f32,
+    interval: f32,
+    armed: bool,
+    last_input: f32,
+    since_crossing: f32,
+    period_valid: bool,
+    voiced: bool,
+    log_freq: f32,
+    target_log_freq: f32,
+    glide_coef: f32,
+    amp: f32,
+    fade_coef: f32,
+}
+
+impl PitchSyncOscillator {
+    pub fn new() -> Self {
+        let mut this = Self {
+            osc: SineOsc::new(),
+            lpf: crate::TptOnePole::new(),
+            level: crate::EnvelopeFollower::new(),
+            srate: 44100.0,
+            interval: 0.0,
+            armed: false,
+            last_input: 0.0,
+            since_crossing: 0.0,
+            period_valid: false,
+            voiced: false,
+            log_freq: 220.0_f32.log2(),
+            target_log_freq: 220.0_f32.log2(),
+            glide_coef: 0.0,
+            amp: 0.0,
+            fade_coef: 0.0,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.osc.set_sample_rate(srate);
+        self.lpf.set_sample_rate(srate);
+        self.lpf.set_freq(PITCH_SYNC_LPF_HZ);
+        self.level.set_sample_rate(srate);
+        self.level.set_attack_release(1.0, 1000.0 / PITCH_SYNC_MIN_HZ);
+        self.glide_coef = 1.0 - (-1000.0 / (PITCH_SYNC_GLIDE_MS * srate)).exp();
+        self.fade_coef = 1.0 - (-1000.0 / (PITCH_SYNC_FADE_MS * srate)).exp();
+    }
+
+    pub fn reset(&mut self) {
+        self.osc.reset();
+        self.lpf.reset();
+        self.level.reset();
+        self.armed = false;
+        self.last_input = 0.0;
+        self.since_crossing = 0.0;
+        self.period_valid = false;
+        self.voiced = false;
+        self.amp = 0.0;
+    }
+
+    /// Sets the interval of the output relative to the detected pitch in semitones.
+    /// Eg. -12.0 is one octave down.
+    pub fn set_interval(&mut self, semitones: f32) {
+        self.interval = semitones;
+    }
+
+    /// Returns the (smoothed) detected pitch of the input in Hz.
+    pub fn detected_freq(&self) -> f32 {
+        self.log_freq.exp2()
+    }
+
+    /// Returns true if a pitch is currently detected in the input.
+    pub fn is_voiced(&self) -> bool {
+        self.voiced
+    }
+
+    /// Feeds the next input sample and returns the next output sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let x = self.lpf.process(input);
+        let level = self.level.process(x);
+        let hysteresis = level * 0.1;
+
+        self.since_crossing += 1.0;
+
+        if x < -hysteresis {
+            self.armed = true;
+        } else if self.armed && x >= 0.0 && self.last_input < 0.0 {
+            self.armed = false;
+
+            // Position of the crossing between the last and the current sample:
+            let frac = -self.last_input / (x - self.last_input);
+            let period = self.since_crossing - (1.0 - frac);
+            self.since_crossing = 1.0 - frac;
+
+            let freq = self.srate / period;
+            if self.period_valid && (PITCH_SYNC_MIN_HZ..=PITCH_SYNC_MAX_HZ).contains(&freq) {
+                if !self.voiced {
+                    // Jump to the new pitch instead of gliding from the last one:
+                    self.log_freq = freq.log2();
+                }
+                self.voiced = true;
+                self.target_log_freq = freq.log2();
+            }
+            self.period_valid = true;
+        }
+        self.last_input = x;
+
+        let voiced_level = 10.0_f32.powf(PITCH_SYNC_VOICED_DB * 0.05);
+        if self.since_crossing > self.srate / PITCH_SYNC_MIN_HZ {
+            // The next crossing does not end a valid period anymore:
+            self.period_valid = false;
+            self.voiced = false;
+        }
+        if level < voiced_level {
+            self.voiced = false;
+        }
+
+        self.log_freq += (self.target_log_freq - self.log_freq) * self.glide_coef;
+
+        let target_amp = if self.voiced { level } else { 0.0 };
+        self.amp += (target_amp - self.amp) * self.fade_coef;
+
+        self.osc.set_freq((self.log_freq + self.interval / 12.0).exp2());
+        self.osc.next() * self.amp
+    }
+}
+
+impl Default for PitchSyncOscillator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Length of one single cycle table inside a [WavetableOscillator].
+const WAVETABLE_LEN: usize = 1024;
+
+/// Number of band-limited mipmap levels (one per octave) in a [WavetableOscillator].
+/// The first level holds up to `WAVETABLE_LEN / 2` harmonics, the last one only the
+/// fundamental.
+const WAVETABLE_MIP_LEVELS: usize = 10;
+
+/// The interpolation mode between the frames of a [WavetableOscillator].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavetableInterp {
+    /// Linear crossfade between the two neighbouring frames.
+    Linear,
+    /// Cubic (hermite) interpolation across the four surrounding frames.
+    Cubic,
+}
+
+/// A wavetable oscillator with mipmapped band-limiting.
+///
+/// It takes a set of user provided single cycle waveforms (frames) of any length,
+/// resamples them to an internal table size and builds one band-limited
+/// version per octave. When playing, the mipmap level is selected by the
+/// frequency, so that no harmonics above the Nyquist frequency are generated.
+/// The `position` parameter of [WavetableOscillator::next] morphs through the frames.
+///
+/// Building the mipmaps is done with a plain DFT, so [WavetableOscillator::new]
+/// should not be called from the audio thread.
+///
+///```
+/// use synfx_dsp::*;
+///
+/// // A naive sawtooth, which would alias heavily if played back directly:
+/// let saw: Vec<f32> = (0..2048).map(|i| (i as f32 / 1024.0) - 1.0).collect();
+/// let mut osc = WavetableOscillator::new(&[saw]);
+///
+/// let israte = 1.0 / 44100.0;
+///
+/// // At 15kHz only the fundamental fits below Nyquist, which has
+/// // an amplitude of 2/PI:
+/// let mut max: f32 = 0.0;
+/// for _ in 0..1000 {
+///     max = max.max(osc.next(15000.0, israte, 0.0));
+/// }
+/// assert!((max - 2.0 / std::f32::consts::PI).abs() < 0.01);
+///
+/// // At 100Hz many harmonics are kept, so the saw is close to the original:
+/// osc.reset();
+/// let mut max: f32 = 0.0;
+/// for _ in 0..1000 {
+///     max = max.max(osc.next(100.0, israte, 0.0));
+/// }
+/// assert!(max > 0.9 && max < 1.2);
+///```
+#[derive(Debug, Clone)]
+pub struct WavetableOscillator {
+    tables: Vec<f32>,
+    frames: usize,
+    phase: f32,
+    interp: WavetableInterp,
+}
+
+impl WavetableOscillator {
+    /// Create a new wavetable oscillator from the given frames.
+    /// Each frame is a single cycle waveform, they may differ in length.
+    /// If no frames are given, the oscillator outputs silence.
+    ///
+    /// * `frames` - The single cycle waveforms to morph between.
+    pub fn new(frames: &[Vec<f32>]) -> Self {
+        let silence = [vec![0.0]];
+        let frames = if frames.is_empty() { &silence[..] } else { frames };
+        let n_frames = frames.len();
+
+        let mut tables = vec![0.0; WAVETABLE_MIP_LEVELS * n_frames * WAVETABLE_LEN];
+
+        let mut cos_tab = [0.0_f64; WAVETABLE_LEN];
+        let mut sin_tab = [0.0_f64; WAVETABLE_LEN];
+        for (i, (c, s)) in cos_tab.iter_mut().zip(sin_tab.iter_mut()).enumerate() {
+            let x = std::f64::consts::TAU * (i as f64) / (WAVETABLE_LEN as f64);
+            *c = x.cos();
+            *s = x.sin();
+        }
+
+        for (fi, frame) in frames.iter().enumerate() {
+            let len = frame.len().max(1);
+            let max_harm = ((len - 1) / 2).min(WAVETABLE_LEN / 2 - 1);
+
+            let dc = frame.iter().map(|s| *s as f64).sum::<f64>() / (len as f64);
+
+            // DFT of the input frame:
+            let mut harmonics = vec![(0.0, 0.0); max_harm + 1];
+            for (h, (re, im)) in harmonics.iter_mut().enumerate().skip(1) {
+                for (i, s) in frame.iter().enumerate() {
+                    let x = std::f64::consts::TAU * ((h * i) % len) as f64 / (len as f64);
+                    *re += (*s as f64) * x.cos();
+                    *im += (*s as f64) * x.sin();
+                }
+                *re *= 2.0 / (len as f64);
+                *im *= 2.0 / (len as f64);
+            }
+
+            // Resynthesis of one band limited table per octave:
+            for level in 0..WAVETABLE_MIP_LEVELS {
+                let level_harm = ((WAVETABLE_LEN / 2) >> level).min(max_harm);
+                let offs = (level * n_frames + fi) * WAVETABLE_LEN;
+                let table = &mut tables[offs..(offs + WAVETABLE_LEN)];
+
+                for (j, out) in table.iter_mut().enumerate() {
+                    let mut sum = dc;
+                    for (h, (re, im)) in harmonics.iter().enumerate().take(level_harm + 1).skip(1) {
+                        let idx = (h * j) % WAVETABLE_LEN;
+                        sum += re * cos_tab[idx] + im * sin_tab[idx];
+                    }
+                    *out = sum as f32;
+                }
+            }
+        }
+
+        Self { tables, frames: n_frames, phase: 0.0, interp: WavetableInterp::Linear }
+    }
+
+    /// Reset the phase of the oscillator to 0.0.
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    /// Set the current phase of the oscillator.
+    ///
+    /// * `phase` - The phase, range 0.0 to 1.0.
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    /// Set the interpolation mode between the frames, the default is
+    /// [WavetableInterp::Linear].
+    ///
+    ///```
+    /// use synfx_dsp::*;
+    ///
+    /// let sine: Vec<f32> =
+    ///     (0..256).map(|i| (i as f32 * std::f32::consts::TAU / 256.0).sin()).collect();
+    /// let inv_sine: Vec<f32> = sine.iter().map(|s| -s).collect();
+    ///
+    /// let mut osc = WavetableOscillator::new(&[sine, inv_sine]);
+    /// osc.set_frame_interpolation(WavetableInterp::Cubic);
+    ///
+    /// // Halfway between a sine and its inverse everything cancels out:
+    /// for _ in 0..100 {
+    ///     assert!(osc.next(440.0, 1.0 / 44100.0, 0.5).abs() < 0.001);
+    /// }
+    ///```
+    pub fn set_frame_interpolation(&mut self, interp: WavetableInterp) {
+        self.interp = interp;
+    }
+
+    /// Returns the number of frames in this wavetable.
+    pub fn frame_count(&self) -> usize {
+        self.frames
+    }
+
+    #[inline]
+    fn read_frame(&self, level: usize, frame: usize, i: usize, fract: f32) -> f32 {
+        let offs = (level * self.frames + frame) * WAVETABLE_LEN;
+        let table = &self.tables[offs..(offs + WAVETABLE_LEN)];
+        crate::cubic_interpolate(table, WAVETABLE_LEN, i, fract)
+    }
+
+    /// Creates the next sample of the oscillator.
+    ///
+    /// * `freq` - The frequency in Hz.
+    /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
+    /// * `position` - The wavetable position, range 0.0 (first frame) to 1.0 (last frame).
+    #[inline]
+    pub fn next(&mut self, freq: f32, israte: f32, position: f32) -> f32 {
+        let inc = (freq * israte).abs();
+
+        let max_harm = if inc > 0.0 { 0.5 / inc } else { f32::INFINITY };
+        let mut level = 0;
+        while level < (WAVETABLE_MIP_LEVELS - 1) && ((WAVETABLE_LEN / 2) >> level) as f32 > max_harm
+        {
+            level += 1;
+        }
+
+        let pos = self.phase * (WAVETABLE_LEN as f32);
+        let i = (pos.floor() as usize) % WAVETABLE_LEN;
+        let fract = pos.fract();
+
+        let frame_pos = position.clamp(0.0, 1.0) * ((self.frames - 1) as f32);
+        let fi = (frame_pos.floor() as usize).min(self.frames - 1);
+        let ffract = frame_pos - (fi as f32);
+        let last = self.frames - 1;
+
+        let out = match self.interp {
+            WavetableInterp::Linear => {
+                let a = self.read_frame(level, fi, i, fract);
+                if ffract > 0.0 {
+                    let b = self.read_frame(level, (fi + 1).min(last), i, fract);
+                    lerp(ffract, a, b)
+                } else {
+                    a
+                }
+            }
+            WavetableInterp::Cubic => {
+                let v = [
+                    self.read_frame(level, fi.saturating_sub(1), i, fract),
+                    self.read_frame(level, fi, i, fract),
+                    self.read_frame(level, (fi + 1).min(last), i, fract),
+                    self.read_frame(level, (fi + 2).min(last), i, fract),
+                ];
+                crate::cubic_interpolate(&v[..], 4, 1, ffract)
+            }
+        };
+
+        self.phase = (self.phase + freq * israte).rem_euclid(1.0);
+
+        out
+    }
+}
+
 // This oscillator is based on the work "VECTOR PHASESHAPING SYNTHESIS"
 // by: Jari Kleimola*, Victor Lazzarini†, Joseph Timoney†, Vesa Välimäki*
 // *Aalto University School of Electrical Engineering Espoo, Finland;
@@ -293,7 +1505,8 @@ impl PolyBlepOscillator {
 /// use synfx_dsp::*;
 ///
 /// // Randomize the initial phase to make cancellation on summing less
-/// // likely:
+/// // likely. For reproducible phases of multiple oscillators
+/// // see also [crate::spread_init_phases].
 /// let mut osc =
 ///     VPSOscillator::new(rand_01() * 0.25);
 ///
@@ -429,65 +1642,205 @@ impl VPSOscillator {
     }
 }
 
-//pub struct UnisonBlep {
-//    oscs: Vec<PolyBlepOscillator>,
-////    dc_block: crate::filter::DCBlockFilter,
-//}
-//
-//impl UnisonBlep {
-//    pub fn new(max_unison: usize) -> Self {
-//        let mut oscs = vec![];
-//        let mut rng = RandGen::new();
-//
-//        let dis_init_phase = 0.05;
-//        for i in 0..(max_unison + 1) {
-//            // randomize phases so we fatten the unison, get
-//            // less DC and not an amplified signal until the
-//            // detune desyncs the waves.
-//            // But no random phase for first, so we reduce the click
-//            let init_phase =
-//                if i == 0 { 0.0 } else { rng.next_open01() };
-//            oscs.push(PolyBlepOscillator::new(init_phase));
-//        }
-//
-//        Self {
-//            oscs,
-////            dc_block: crate::filter::DCBlockFilter::new(),
-//        }
-//    }
-//
-//    pub fn set_sample_rate(&mut self, srate: f32) {
-////        self.dc_block.set_sample_rate(srate);
-//        for o in self.oscs.iter_mut() {
-//            o.set_sample_rate(srate);
-//        }
-//    }
-//
-//    pub fn reset(&mut self) {
-////        self.dc_block.reset();
-//        for o in self.oscs.iter_mut() {
-//            o.reset();
-//        }
-//    }
-//
-//    pub fn next<P: OscillatorInputParams>(&mut self, params: &P) -> f32 {
-//        let unison =
-//            (params.unison().floor() as usize)
-//            .min(self.oscs.len() - 1);
-//        let detune = params.detune() as f64;
-//
-//        let mix = (1.0 / ((unison + 1) as f32)).sqrt();
-//
-//        let mut s = mix * self.oscs[0].next(params, 0.0);
-//
-//        for u in 0..unison {
-//            let detune_factor =
-//                detune * (((u / 2) + 1) as f64
-//                          * if (u % 2) == 0 { 1.0 } else { -1.0 });
-//            s += mix * self.oscs[u + 1].next(params, detune_factor * 0.01);
-//        }
-//
-////        self.dc_block.next(s)
-//        s
-//    }
-//}
+/// The maximum detune in cents for the outermost voices of an [UnisonOscillator].
+const UNISON_MAX_DETUNE_CENTS: f32 = 100.0;
+
+/// A unison oscillator, that stacks multiple [PolyBlepOscillator] voices, like a supersaw.
+///
+/// The voices are detuned symmetrically around the base frequency, the outermost voices
+/// get the full detune. The stereo spread pans neighbouring voices to opposite sides.
+/// The output is gain compensated by `1 / sqrt(voices)`, so that the loudness stays
+/// about the same, regardless of the number of voices. DC offsets are removed from the
+/// output by a [crate::DCBlockFilter] per channel.
+///
+/// The phases of the voices are randomized on [UnisonOscillator::reset],
+/// to prevent the voices from adding up to a loud click and to fatten the sound.
+///
+///```
+/// use synfx_dsp::UnisonOscillator;
+///
+/// let mut osc = UnisonOscillator::new(16, 42);
+/// osc.set_sample_rate(44100.0);
+/// osc.set_voices(7);
+/// osc.set_detune(25.0);
+/// osc.set_stereo_spread(1.0);
+/// osc.reset();
+///
+/// let israte = 1.0 / 44100.0;
+/// let out: Vec<(f32, f32)> = (0..44100).map(|_| osc.next_saw(110.0, israte)).collect();
+///
+/// let rms = |s: &[f32]| (s.iter().map(|s| s * s).sum::<f32>() / s.len() as f32).sqrt();
+/// let left: Vec<f32> = out[4410..].iter().map(|s| s.0).collect();
+/// let right: Vec<f32> = out[4410..].iter().map(|s| s.1).collect();
+///
+/// // About the loudness of a single saw (0.577):
+/// assert!(rms(&left) > 0.4 && rms(&left) < 0.8);
+/// assert!(rms(&right) > 0.4 && rms(&right) < 0.8);
+/// // The channels differ:
+/// assert!(left.iter().zip(right.iter()).any(|(l, r)| (l - r).abs() > 0.1));
+/// // No DC:
+/// assert!((left.iter().sum::<f32>() / left.len() as f32).abs() < 0.01);
+///```
+#[derive(Debug, Clone)]
+pub struct UnisonOscillator {
+    oscs: Vec<PolyBlepOscillator>,
+    /// Frequency factor of each voice.
+    detune_factors: Vec<f32>,
+    /// Left and right gain of each voice.
+    pan_gains: Vec<(f32, f32)>,
+    dc_block: (crate::DCBlockFilter<f32>, crate::DCBlockFilter<f32>),
+    rng: crate::SplitMix64,
+
+    voices: usize,
+    detune_cents: f32,
+    stereo_spread: f32,
+    phase_random: f32,
+    gain: f32,
+}
+
+impl UnisonOscillator {
+    /// Creates a new unison oscillator with a single voice.
+    ///
+    /// * `max_voices` - The maximum number of voices, at least 1.
+    /// * `seed` - Seed for the phase randomization.
+    pub fn new(max_voices: usize, seed: u64) -> Self {
+        let max_voices = max_voices.max(1);
+        let mut this = Self {
+            oscs: vec![PolyBlepOscillator::new(0.0); max_voices],
+            detune_factors: vec![1.0; max_voices],
+            pan_gains: vec![(1.0, 1.0); max_voices],
+            dc_block: (crate::DCBlockFilter::new(), crate::DCBlockFilter::new()),
+            rng: crate::SplitMix64::new(seed),
+
+            voices: 1,
+            detune_cents: 0.0,
+            stereo_spread: 0.0,
+            phase_random: 1.0,
+            gain: 1.0,
+        };
+        this.recalc();
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.dc_block.0.set_sample_rate(srate);
+        self.dc_block.1.set_sample_rate(srate);
+    }
+
+    /// Resets the oscillator and randomizes the phases of the voices. The first voice
+    /// always starts at phase 0.0, to reduce the click at the start.
+    pub fn reset(&mut self) {
+        self.dc_block.0.reset();
+        self.dc_block.1.reset();
+
+        for (i, osc) in self.oscs.iter_mut().enumerate() {
+            let phase = if i == 0 { 0.0 } else { self.rng.next_open01() as f32 };
+            *osc = PolyBlepOscillator::new(phase * self.phase_random);
+            osc.reset();
+        }
+    }
+
+    /// Sets the number of voices, range 1 to the `max_voices` of [UnisonOscillator::new].
+    pub fn set_voices(&mut self, voices: usize) {
+        self.voices = voices.clamp(1, self.oscs.len());
+        self.recalc();
+    }
+
+    /// Sets the detune of the outermost voices in cents, range 0.0 to 100.0.
+    pub fn set_detune(&mut self, cents: f32) {
+        self.detune_cents = cents.clamp(0.0, UNISON_MAX_DETUNE_CENTS);
+        self.recalc();
+    }
+
+    /// Sets the stereo spread.
+    ///
+    /// * `spread` - Range 0.0 to 1.0. At 0.0 all voices are in the center,
+    ///   at 1.0 the outermost voices are panned hard left and right.
+    pub fn set_stereo_spread(&mut self, spread: f32) {
+        self.stereo_spread = spread.clamp(0.0, 1.0);
+        self.recalc();
+    }
+
+    /// Sets the amount of phase randomization for the next [UnisonOscillator::reset].
+    ///
+    /// * `amount` - Range 0.0 to 1.0. At 0.0 all voices start in phase.
+    pub fn set_phase_random(&mut self, amount: f32) {
+        self.phase_random = amount.clamp(0.0, 1.0);
+    }
+
+    fn recalc(&mut self) {
+        let n = self.voices;
+        self.gain = 1.0 / (n as f32).sqrt();
+
+        for i in 0..n {
+            // Position of the voice, from -1.0 to 1.0:
+            let pos = if n > 1 { (2.0 * i as f32) / ((n - 1) as f32) - 1.0 } else { 0.0 };
+            self.detune_factors[i] = 2.0_f32.powf(pos * self.detune_cents / 1200.0);
+
+            // Equal power panning, the center is at 1.0 on both sides:
+            let pan = self.stereo_spread * if i % 2 == 0 { pos } else { -pos };
+            let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+            self.pan_gains[i] =
+                (std::f32::consts::SQRT_2 * angle.cos(), std::f32::consts::SQRT_2 * angle.sin());
+        }
+    }
+
+    #[inline]
+    fn mix(&mut self, mut voice: impl FnMut(&mut PolyBlepOscillator, f32) -> f32) -> (f32, f32) {
+        let mut l = 0.0;
+        let mut r = 0.0;
+
+        for i in 0..self.voices {
+            let s = voice(&mut self.oscs[i], self.detune_factors[i]);
+            l += s * self.pan_gains[i].0;
+            r += s * self.pan_gains[i].1;
+        }
+
+        (self.dc_block.0.next(l * self.gain), self.dc_block.1.next(r * self.gain))
+    }
+
+    /// Creates the next stereo sample of the stacked sawtooth waves.
+    ///
+    /// * `freq` - The frequency in Hz.
+    /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
+    #[inline]
+    pub fn next_saw(&mut self, freq: f32, israte: f32) -> (f32, f32) {
+        self.mix(|osc, detune| osc.next_saw(freq * detune, israte))
+    }
+
+    /// Creates the next stereo sample of the stacked pulse waves.
+    /// See also [PolyBlepOscillator::next_pulse].
+    ///
+    /// * `freq` - The frequency in Hz.
+    /// * `israte` - The inverse sampling rate, or seconds per sample as in eg. `1.0 / 44100.0`.
+    /// * `pw` - The pulse width. Use the value 0.0 for a square wave.
+    #[inline]
+    pub fn next_pulse(&mut self, freq: f32, israte: f32, pw: f32) -> (f32, f32) {
+        self.mix(|osc, detune| osc.next_pulse(freq * detune, israte, pw))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_tri_no_dc_drift_at_low_freq() {
+        let mut osc = PolyBlepOscillator::new(0.0);
+        let israte = 1.0 / 44100.0;
+
+        let mut samples = vec![];
+        for _ in 0..(44100 * 4) {
+            samples.push(osc.next_tri(20.0, israte));
+        }
+
+        // Skip the first second, the servo needs some time to settle:
+        let held = &samples[44100..];
+        let mean = held.iter().sum::<f32>() / (held.len() as f32);
+        assert!(mean.abs() < 0.01, "mean={}", mean);
+
+        let max = held.iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+        assert!(max > 0.5);
+        assert!(max < 1.5);
+    }
+}
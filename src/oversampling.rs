@@ -4,8 +4,8 @@
 
 //! Oversampling related utilities, such as an up/downsampling filter.
 
-use crate::{Biquad, BiquadCoefs};
-use std::simd::f32x4;
+use crate::simd::f32x4;
+use crate::{apply_distortion, Biquad, BiquadCoefs, OnePoleHPF};
 
 // Loosely adapted from https://github.com/VCVRack/Befaco/blob/v1/src/ChowDSP.hpp
 // Copyright (c) 2019-2020 Andrew Belt and Befaco contributors
@@ -19,11 +19,32 @@ use std::simd::f32x4;
 pub struct Oversampling<const N: usize> {
     filters: [Biquad; 4],
     buffer: [f32; N],
+    prefill: OversamplingPrefill,
+    last: f32,
+}
+
+/// How [Oversampling::upsample] fills the oversampled buffer before
+/// the anti-imaging filter is applied.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OversamplingPrefill {
+    /// The input sample (times N) is placed in the first slot, all other slots are zero.
+    /// The filter alone does the interpolation.
+    ZeroStuff,
+    /// The slots are linearly interpolated between the previous and the current
+    /// input sample. This already attenuates the images before the filter, at the cost
+    /// of a high frequency roll off (about -3.2dB at 15kHz for 44.1kHz) and a latency
+    /// of one (not oversampled) sample.
+    Linear,
 }
 
 impl<const N: usize> Oversampling<N> {
     pub fn new() -> Self {
-        let mut this = Self { filters: [Biquad::new(); 4], buffer: [0.0; N] };
+        let mut this = Self {
+            filters: [Biquad::new(); 4],
+            buffer: [0.0; N],
+            prefill: OversamplingPrefill::ZeroStuff,
+            last: 0.0,
+        };
 
         this.set_sample_rate(44100.0);
 
@@ -32,11 +53,18 @@ impl<const N: usize> Oversampling<N> {
 
     pub fn reset(&mut self) {
         self.buffer = [0.0; N];
+        self.last = 0.0;
         for filt in &mut self.filters {
             filt.reset();
         }
     }
 
+    /// Selects how the oversampled buffer is filled in [Oversampling::upsample].
+    /// The default is [OversamplingPrefill::ZeroStuff].
+    pub fn set_prefill_mode(&mut self, prefill: OversamplingPrefill) {
+        self.prefill = prefill;
+    }
+
     pub fn set_sample_rate(&mut self, srate: f32) {
         let cutoff = 0.98 * (0.5 * srate);
 
@@ -52,8 +80,19 @@ impl<const N: usize> Oversampling<N> {
 
     #[inline]
     pub fn upsample(&mut self, v: f32) {
-        self.buffer.fill(0.0);
-        self.buffer[0] = (N as f32) * v;
+        match self.prefill {
+            OversamplingPrefill::ZeroStuff => {
+                self.buffer.fill(0.0);
+                self.buffer[0] = (N as f32) * v;
+            }
+            OversamplingPrefill::Linear => {
+                for (i, s) in self.buffer.iter_mut().enumerate() {
+                    let x = ((i + 1) as f32) / (N as f32);
+                    *s = self.last + (v - self.last) * x;
+                }
+            }
+        }
+        self.last = v;
 
         for s in &mut self.buffer {
             for filt in &mut self.filters {
@@ -81,6 +120,268 @@ impl<const N: usize> Oversampling<N> {
     }
 }
 
+/// Order of the Butterworth filters of [Decimator] and [Interpolator], realized as
+/// a cascade of `RATE_CONV_ORDER / 2` biquads.
+const RATE_CONV_ORDER: usize = 8;
+
+/// Sets up a cascade of biquads as one Butterworth low pass, for the lower of the two
+/// rates of a [Decimator] or [Interpolator]. The cutoff is slightly below its Nyquist frequency.
+fn rate_conv_filters(filters: &mut [Biquad; RATE_CONV_ORDER / 2], srate: f32, factor: usize) {
+    let cutoff = 0.9 * (0.5 * srate / (factor as f32));
+    let filters_len = filters.len();
+
+    for (i, filt) in filters.iter_mut().enumerate() {
+        let q = BiquadCoefs::calc_cascaded_butter_q(RATE_CONV_ORDER, filters_len - i);
+        filt.set_coefs(BiquadCoefs::lowpass(srate, q, cutoff));
+    }
+}
+
+/// Reduces the sample rate by `FACTOR`, for intentionally running parts of a signal graph
+/// at a lower internal rate. Unlike a lo-fi sample rate reducer, the signal is low pass filtered
+/// with an 8th order Butterworth filter before decimating, to prevent aliasing.
+/// See [Interpolator] for going back to the original rate.
+///
+///```
+/// use synfx_dsp::Decimator;
+///
+/// let mut dec = Decimator::<2>::new();
+/// dec.set_sample_rate(44100.0);
+///
+/// let mut measure = |freq: f32| {
+///     dec.reset();
+///     let mut max: f32 = 0.0;
+///     let mut count = 0;
+///     for i in 0..44100 {
+///         let input = (i as f32 * std::f32::consts::TAU * freq / 44100.0).sin();
+///         if let Some(out) = dec.process(input) {
+///             count += 1;
+///             if i > 22050 {
+///                 max = max.max(out.abs());
+///             }
+///         }
+///     }
+///     assert_eq!(count, 22050);
+///     max
+/// };
+///
+/// // A 1kHz tone passes:
+/// assert!((measure(1000.0) - 1.0).abs() < 0.01);
+/// // 18kHz would alias down to 4050Hz, but is filtered out:
+/// assert!(measure(18000.0) < 0.02);
+///```
+#[derive(Debug, Copy, Clone)]
+pub struct Decimator<const FACTOR: usize> {
+    filters: [Biquad; RATE_CONV_ORDER / 2],
+    count: usize,
+}
+
+impl<const FACTOR: usize> Decimator<FACTOR> {
+    pub fn new() -> Self {
+        let mut this = Self { filters: [Biquad::new(); RATE_CONV_ORDER / 2], count: 0 };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+        for filt in &mut self.filters {
+            filt.reset();
+        }
+    }
+
+    /// Sets the (higher) input sample rate. The output rate is `srate / FACTOR`.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        rate_conv_filters(&mut self.filters, srate, FACTOR);
+    }
+
+    /// Processes one input sample and returns an output sample every `FACTOR` calls.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> Option<f32> {
+        let mut s = input;
+        for filt in &mut self.filters {
+            s = filt.tick(s);
+        }
+
+        self.count += 1;
+        if self.count >= FACTOR {
+            self.count = 0;
+            Some(s)
+        } else {
+            None
+        }
+    }
+}
+
+impl<const FACTOR: usize> Default for Decimator<FACTOR> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Increases the sample rate by `FACTOR`, the counterpart of [Decimator].
+/// The input is zero stuffed and the images are removed with an 8th order
+/// Butterworth low pass filter.
+///
+///```
+/// use synfx_dsp::{Decimator, Interpolator};
+///
+/// let mut dec = Decimator::<4>::new();
+/// let mut int = Interpolator::<4>::new();
+/// dec.set_sample_rate(48000.0);
+/// int.set_sample_rate(48000.0);
+///
+/// let mut max: f32 = 0.0;
+/// for i in 0..48000 {
+///     let input = (i as f32 * std::f32::consts::TAU * 500.0 / 48000.0).sin();
+///     if let Some(low_rate) = dec.process(input) {
+///         for s in int.process(low_rate) {
+///             if i > 24000 {
+///                 max = max.max(s.abs());
+///             }
+///         }
+///     }
+/// }
+/// assert!((max - 1.0).abs() < 0.01);
+///```
+#[derive(Debug, Copy, Clone)]
+pub struct Interpolator<const FACTOR: usize> {
+    filters: [Biquad; RATE_CONV_ORDER / 2],
+}
+
+impl<const FACTOR: usize> Interpolator<FACTOR> {
+    pub fn new() -> Self {
+        let mut this = Self { filters: [Biquad::new(); RATE_CONV_ORDER / 2] };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn reset(&mut self) {
+        for filt in &mut self.filters {
+            filt.reset();
+        }
+    }
+
+    /// Sets the (higher) output sample rate. The input rate is `srate / FACTOR`.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        rate_conv_filters(&mut self.filters, srate, FACTOR);
+    }
+
+    /// Processes one input sample and returns `FACTOR` output samples.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> [f32; FACTOR] {
+        let mut out = [0.0; FACTOR];
+        if FACTOR > 0 {
+            out[0] = input * (FACTOR as f32);
+        }
+
+        for s in &mut out {
+            for filt in &mut self.filters {
+                *s = filt.tick(*s);
+            }
+        }
+
+        out
+    }
+}
+
+impl<const FACTOR: usize> Default for Interpolator<FACTOR> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cutoff frequency of the DC blocker in [OversampledDistortion].
+const OVERSAMPLED_DIST_DC_HZ: f32 = 5.0;
+
+/// Applies [apply_distortion] at an oversampled rate, for alias free distortion out of the box.
+/// It packages the recommended chain: Oversample ([Oversampling] with a ratio of N)
+/// => distort => DC block => downsample.
+///
+/// The upsampling and the downsampling use separate [Oversampling] instances,
+/// so the anti-imaging and the anti-aliasing filters don't share their state.
+///
+///```
+/// use synfx_dsp::OversampledDistortion;
+///
+/// let mut dist: OversampledDistortion<4> = OversampledDistortion::new();
+/// dist.set_sample_rate(44100.0);
+/// dist.set_type(1); // TanH
+/// dist.set_amount(0.5);
+///
+/// let mut max: f32 = 0.0;
+/// for i in 0..4410 {
+///     let s = dist.process((i as f32 * 0.0627).sin());
+///     max = max.max(s.abs());
+/// }
+/// // The band limiting rings a bit on the hard edges:
+/// assert!(max > 0.8);
+/// assert!(max < 1.4);
+///```
+#[derive(Debug, Clone)]
+pub struct OversampledDistortion<const N: usize> {
+    up: Oversampling<N>,
+    down: Oversampling<N>,
+    dc_block: OnePoleHPF<f32>,
+    dist_type: u8,
+    amount: f32,
+}
+
+impl<const N: usize> OversampledDistortion<N> {
+    pub fn new() -> Self {
+        let mut this = Self {
+            up: Oversampling::new(),
+            down: Oversampling::new(),
+            dc_block: OnePoleHPF::new(),
+            dist_type: 0,
+            amount: 0.0,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn reset(&mut self) {
+        self.up.reset();
+        self.down.reset();
+        self.dc_block.reset();
+    }
+
+    /// Sets the (not oversampled) sample rate.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.up.set_sample_rate(srate);
+        self.down.set_sample_rate(srate);
+        self.dc_block.set_sample_rate((N as f32) * srate);
+        self.dc_block.set_freq(OVERSAMPLED_DIST_DC_HZ);
+    }
+
+    /// Selects the distortion type, see the `dist_type` of [apply_distortion]
+    /// and [crate::fa_distort]. 0 is off.
+    pub fn set_type(&mut self, dist_type: u8) {
+        self.dist_type = dist_type;
+    }
+
+    /// Sets the distortion amount, see the `damt` of [apply_distortion].
+    /// Range 0.0 to 1.0.
+    pub fn set_amount(&mut self, amount: f32) {
+        self.amount = amount;
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.up.upsample(input);
+        let buf = self.down.resample_buffer();
+        for (out, s) in buf.iter_mut().zip(self.up.resample_buffer().iter()) {
+            *out = self.dc_block.process(apply_distortion(*s, self.amount, self.dist_type));
+        }
+        self.down.downsample()
+    }
+}
+
+impl<const N: usize> Default for OversampledDistortion<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Taken from va-filter by Fredemus aka Frederik Halkjær aka RocketPhysician
 // https://github.com/Fredemus/va-filter
 // Under License GPL-3.0-or-later
@@ -155,8 +456,8 @@ impl AllpassCascade {
 ///
 /// Usage:
 ///```
-/// #![feature(portable_simd)]
-/// use std::simd::f32x4;
+#[cfg_attr(feature = "simd", doc = "#![feature(portable_simd)]")]
+/// use synfx_dsp::f32x4;
 ///
 /// use synfx_dsp::PolyIIRHalfbandFilter;
 ///
@@ -408,3 +709,313 @@ impl Default for PolyIIRHalfbandFilter {
         PolyIIRHalfbandFilter { filter_a, filter_b, old_out: f32x4::splat(0.0) }
     }
 }
+
+/// The number of taps of the [Resampler] filter, when the input rate is the lower one.
+/// When downsampling, the filter is stretched by the ratio.
+const RESAMPLER_TAPS: usize = 64;
+/// The number of fractional positions of the [Resampler] filter table.
+const RESAMPLER_PHASES: usize = 256;
+/// The Kaiser window parameter of the [Resampler] filter, gives about 90dB stopband attenuation.
+const RESAMPLER_KAISER_BETA: f64 = 9.0;
+/// The cutoff of the [Resampler] filter relative to the lower of the two sample rates.
+/// The transition band ends at the Nyquist frequency.
+const RESAMPLER_CUTOFF: f64 = 0.455;
+
+/// The modified Bessel function of the first kind of order 0, for the Kaiser window.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let q = x * x * 0.25;
+    for k in 1..50 {
+        term *= q / ((k * k) as f64);
+        sum += term;
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+    sum
+}
+
+/// A sample rate converter for arbitrary ratios, such as 44.1kHz to 48kHz, based on a polyphase
+/// windowed sinc (Kaiser window) FIR filter.
+///
+/// The low pass filter is placed below the Nyquist frequency of the lower of the two rates,
+/// so that both aliasing (downsampling) and images (upsampling) are suppressed by about 90dB.
+/// The pass band reaches up to about 82% of that Nyquist frequency.
+///
+/// Use [Resampler::process_block] for streaming, the output is delayed by
+/// [Resampler::latency] input samples. [Resampler::convert] converts a whole buffer in one go,
+/// with the latency removed.
+///
+///```
+/// use synfx_dsp::Resampler;
+///
+/// let sine = |i: usize, srate: f64| (i as f64 * std::f64::consts::TAU * 1000.0 / srate).sin() as f32;
+///
+/// let input: Vec<f32> = (0..44100).map(|i| sine(i, 44100.0)).collect();
+/// let output = Resampler::convert(&input[..], 44100.0, 48000.0);
+/// assert_eq!(output.len(), 48000);
+///
+/// // Apart from the edges, the output is the same sine at the new sample rate:
+/// for i in 1000..47000 {
+///     assert!((output[i] - sine(i, 48000.0)).abs() < 0.001);
+/// }
+///```
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    /// Input samples per output sample.
+    step: f64,
+    taps: usize,
+    /// The filter coefficients of all phases, the first tap of each phase belongs to
+    /// the newest sample.
+    coefs: Vec<f32>,
+    /// The input history, stored twice for contiguous access.
+    hist: Vec<f32>,
+    hist_pos: usize,
+    /// The position of the next output sample, relative to the center of the filter.
+    /// Below 1.0 the output is ready, otherwise the next input sample is needed.
+    t: f64,
+}
+
+impl Resampler {
+    /// Creates a resampler from `in_rate` to `out_rate`, both in Hz.
+    pub fn new(in_rate: f32, out_rate: f32) -> Self {
+        let step = in_rate.max(1.0) as f64 / out_rate.max(1.0) as f64;
+        // The cutoff as fraction of the input sample rate, the filter gets
+        // proportionally longer when it is lower than the input Nyquist frequency:
+        let scale = step.max(1.0);
+        let cutoff = RESAMPLER_CUTOFF / scale;
+        let taps = ((RESAMPLER_TAPS as f64 * scale).ceil() as usize + 1) & !1;
+        let half = (taps / 2) as f64;
+
+        let norm = bessel_i0(RESAMPLER_KAISER_BETA);
+        let mut coefs = Vec::with_capacity((RESAMPLER_PHASES + 1) * taps);
+        for p in 0..=RESAMPLER_PHASES {
+            let fract = p as f64 / RESAMPLER_PHASES as f64;
+            let start = coefs.len();
+            let mut sum = 0.0;
+
+            for j in 0..taps {
+                // Distance of the tap from the interpolated position:
+                let x = fract - half + j as f64;
+                let sinc = if x.abs() < 1e-12 {
+                    1.0
+                } else {
+                    let a = std::f64::consts::TAU * cutoff * x;
+                    a.sin() / a
+                };
+                let u = (x / half).clamp(-1.0, 1.0);
+                let window = bessel_i0(RESAMPLER_KAISER_BETA * (1.0 - u * u).sqrt()) / norm;
+                sum += sinc * window;
+                coefs.push((sinc * window) as f32);
+            }
+
+            for c in coefs[start..].iter_mut() {
+                *c /= sum as f32;
+            }
+        }
+
+        Self { step, taps, coefs, hist: vec![0.0; 2 * taps], hist_pos: 0, t: 1.0 }
+    }
+
+    /// Returns the ratio of the input rate to the output rate.
+    pub fn ratio(&self) -> f64 {
+        self.step
+    }
+
+    /// Returns the delay of the output in input samples.
+    pub fn latency(&self) -> usize {
+        self.taps / 2
+    }
+
+    /// Clears the input history.
+    pub fn reset(&mut self) {
+        self.hist.fill(0.0);
+        self.hist_pos = 0;
+        self.t = 1.0;
+    }
+
+    /// Calculates the output at the fractional position `t` (range 0.0 to 1.0)
+    /// behind the center of the filter.
+    #[inline]
+    fn interpolate(&self, t: f64) -> f32 {
+        let taps = self.taps;
+        let p = t * RESAMPLER_PHASES as f64;
+        let idx = (p as usize).min(RESAMPLER_PHASES - 1);
+        let f = (p - idx as f64) as f32;
+        let c0 = &self.coefs[idx * taps..(idx + 1) * taps];
+        let c1 = &self.coefs[(idx + 1) * taps..(idx + 2) * taps];
+        // The newest sample is at the end of the window:
+        let newest = self.hist_pos + taps - 1;
+
+        let mut out = 0.0;
+        for j in 0..taps {
+            out += (c0[j] + (c1[j] - c0[j]) * f) * self.hist[newest - j];
+        }
+        out
+    }
+
+    /// Converts the `input` samples into the `output` buffer, and returns how many
+    /// input samples were consumed and how many output samples were written.
+    ///
+    /// Processing stops when either all input is consumed or the output buffer
+    /// is full. Pass the remaining input again with the next call.
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) -> (usize, usize) {
+        let taps = self.taps;
+        let (mut consumed, mut written) = (0, 0);
+
+        loop {
+            if self.t < 1.0 {
+                if written == output.len() {
+                    break;
+                }
+                output[written] = self.interpolate(self.t);
+                written += 1;
+                self.t += self.step;
+            } else {
+                if consumed == input.len() {
+                    break;
+                }
+                self.hist[self.hist_pos] = input[consumed];
+                self.hist[self.hist_pos + taps] = input[consumed];
+                self.hist_pos = (self.hist_pos + 1) % taps;
+                consumed += 1;
+                self.t -= 1.0;
+            }
+        }
+
+        (consumed, written)
+    }
+
+    /// Converts a whole buffer from `in_rate` to `out_rate`. The output is aligned with the
+    /// input (without latency) and has `input.len() * out_rate / in_rate` samples,
+    /// rounded up.
+    ///
+    /// **Attention:** This allocates, don't call it from the audio thread.
+    pub fn convert(input: &[f32], in_rate: f32, out_rate: f32) -> Vec<f32> {
+        let mut rs = Self::new(in_rate, out_rate);
+        let out_len = (input.len() as f64 / rs.step).ceil() as usize;
+        let mut output = vec![0.0; out_len];
+
+        // Skip the output before the first input sample reaches the center of the filter:
+        rs.t += rs.latency() as f64;
+
+        let (_, mut written) = rs.process_block(input, &mut output[..]);
+        let silence = [0.0; 64];
+        while written < out_len {
+            written += rs.process_block(&silence[..], &mut output[written..]).1;
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Upsamples a tone and measures the level (in dB relative to the input amplitude)
+    /// of the oversampled signal at the frequency `measure_hz`.
+    fn upsampled_level_db(prefill: OversamplingPrefill, tone_hz: f32, measure_hz: f32) -> f32 {
+        let srate = 44100.0;
+        let mut ovr: Oversampling<4> = Oversampling::new();
+        ovr.set_sample_rate(srate);
+        ovr.set_prefill_mode(prefill);
+
+        let ovr_srate = srate as f64 * 4.0;
+        let (mut re, mut im) = (0.0_f64, 0.0_f64);
+        let mut count = 0;
+        for i in 0..44100 {
+            let phase = (i as f64) * std::f64::consts::TAU * (tone_hz as f64) / (srate as f64);
+            ovr.upsample(phase.sin() as f32);
+
+            // Skip the settling of the filters:
+            if i < 4410 {
+                continue;
+            }
+
+            for (j, s) in ovr.resample_buffer().iter().enumerate() {
+                let t = (i * 4 + j) as f64 / ovr_srate;
+                let w = std::f64::consts::TAU * (measure_hz as f64) * t;
+                re += (*s as f64) * w.cos();
+                im += (*s as f64) * w.sin();
+                count += 1;
+            }
+        }
+
+        let amp = 2.0 * (re * re + im * im).sqrt() / (count as f64);
+        (20.0 * amp.log10()) as f32
+    }
+
+    #[test]
+    fn check_resampler_block_sizes() {
+        let mut rng = crate::Rng::new();
+        let input: Vec<f32> = (0..5000).map(|_| rng.next() - 0.5).collect();
+
+        for (in_rate, out_rate) in [(44100.0, 48000.0), (48000.0, 44100.0), (96000.0, 44100.0)] {
+            let mut rs = Resampler::new(in_rate, out_rate);
+            let mut whole = vec![0.0; 20000];
+            let (consumed, written) = rs.process_block(&input[..], &mut whole[..]);
+            assert_eq!(consumed, input.len());
+            whole.truncate(written);
+
+            // Feed the input in blocks, that don't fit the ratio, into a small output buffer:
+            rs.reset();
+            let mut streamed = vec![];
+            let mut buf = [0.0; 37];
+            for chunk in input.chunks(45) {
+                let mut chunk = chunk;
+                while !chunk.is_empty() {
+                    let (consumed, written) = rs.process_block(chunk, &mut buf[..]);
+                    streamed.extend_from_slice(&buf[..written]);
+                    chunk = &chunk[consumed..];
+                }
+            }
+
+            assert_eq!(whole, streamed);
+            let expected = input.len() as f64 / rs.ratio();
+            assert!((written as f64 - expected).abs() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn check_resampler_alias_rejection() {
+        let level = |freq: f64, in_rate: f32, out_rate: f32| {
+            let input: Vec<f32> = (0..48000)
+                .map(|i| (i as f64 * std::f64::consts::TAU * freq / in_rate as f64).sin() as f32)
+                .collect();
+            let output = Resampler::convert(&input[..], in_rate, out_rate);
+            let mid = &output[output.len() / 4..output.len() * 3 / 4];
+            (mid.iter().map(|s| s * s).sum::<f32>() / mid.len() as f32).sqrt()
+        };
+
+        // Passes below 80% of the Nyquist frequency of the lower rate:
+        assert!((level(17000.0, 48000.0, 44100.0) - 0.7071).abs() < 0.01);
+        assert!((level(17000.0, 44100.0, 48000.0) - 0.7071).abs() < 0.01);
+        // 23kHz would alias to 21.1kHz at 44.1kHz:
+        assert!(level(23000.0, 48000.0, 44100.0) < 0.0001);
+        assert!(level(30000.0, 96000.0, 44100.0) < 0.0001);
+    }
+
+    #[test]
+    fn check_prefill_image_rejection() {
+        let tone = 15000.0;
+        let image = 44100.0 - tone;
+
+        let zs_tone = upsampled_level_db(OversamplingPrefill::ZeroStuff, tone, tone);
+        let zs_image = upsampled_level_db(OversamplingPrefill::ZeroStuff, tone, image);
+        let lin_tone = upsampled_level_db(OversamplingPrefill::Linear, tone, tone);
+        let lin_image = upsampled_level_db(OversamplingPrefill::Linear, tone, image);
+        let levels = format!(
+            "zero stuff: tone={:.2}dB image={:.2}dB, linear: tone={:.2}dB image={:.2}dB",
+            zs_tone, zs_image, lin_tone, lin_image
+        );
+
+        // Linear prefill rejects the image better...
+        assert!(lin_image < zs_image - 6.0, "{}", levels);
+        // ...but rolls off the high frequencies a bit:
+        assert!(zs_tone.abs() < 0.5, "{}", levels);
+        assert!(lin_tone < zs_tone - 1.0, "{}", levels);
+        assert!(lin_tone > zs_tone - 4.0, "{}", levels);
+    }
+}
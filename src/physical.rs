@@ -0,0 +1,219 @@
+// Copyright (c) 2021-2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! Physical modeling voices, built on the delay line and all-pass primitives.
+//! See also [crate::Waveguide].
+
+use crate::{DCBlockFilter, DelayBuffer, FractionalDelayAllpass, SplitMix64};
+
+/// Capacity of the delay lines of a [KarplusString], enough for 10Hz at 96kHz.
+const KARPLUS_BUFFER_SAMPLES: usize = 9600;
+
+/// The lowest frequency a [KarplusString] can be tuned to.
+const KARPLUS_MIN_FREQ: f32 = 10.0;
+
+/// A Karplus-Strong plucked string voice.
+///
+/// The string is a delay line loop with a damping low pass and an all-pass
+/// filter for the fractional part of the period, so that the string is tuned exactly.
+/// The loop gain is derived from the decay time (RT60) and the tuning frequency.
+///
+/// The string is excited either by the input of [KarplusString::process] or by
+/// a noise burst with [KarplusString::pluck]. The excitation is shaped by a comb filter,
+/// that simulates the position where the string is plucked.
+/// Any DC offset that builds up in the loop is removed from the output.
+///
+///```
+/// use synfx_dsp::KarplusString;
+///
+/// let mut string = KarplusString::new(42);
+/// string.set_sample_rate(44100.0);
+/// string.set_freq(220.0);
+/// string.set_decay_time(2.0);
+/// string.set_damping(0.5);
+/// string.set_pick_position(0.2);
+///
+/// string.pluck(1.0);
+/// let out: Vec<f32> = (0..44100).map(|_| string.process(0.0)).collect();
+///
+/// // The period of the string is 44100 / 220 = 200.45 samples,
+/// // find it by autocorrelation:
+/// let corr = |lag: usize| (0..4410).map(|i| out[i + 4410] * out[i + 4410 + lag]).sum::<f32>();
+/// let period = (150..250).max_by(|a, b| corr(*a).total_cmp(&corr(*b))).unwrap();
+/// assert!(period == 200 || period == 201);
+///
+/// // And it decays:
+/// let rms = |s: &[f32]| (s.iter().map(|s| s * s).sum::<f32>() / s.len() as f32).sqrt();
+/// assert!(rms(&out[0..4410]) > 0.05);
+/// assert!(rms(&out[39690..44100]) < 0.5 * rms(&out[0..4410]));
+///```
+#[derive(Debug, Clone)]
+pub struct KarplusString {
+    delay: DelayBuffer<f32>,
+    tuning: FractionalDelayAllpass,
+    pick: DelayBuffer<f32>,
+    dc_block: DCBlockFilter<f32>,
+    rng: SplitMix64,
+    seed: u64,
+
+    srate: f32,
+    freq: f32,
+    decay_s: f32,
+    damping: f32,
+    pick_pos: f32,
+
+    /// Integer part of the loop delay in samples.
+    loop_samples: usize,
+    /// Weight of the previous sample in the damping filter, which is also
+    /// its delay in samples.
+    damp_w: f32,
+    loop_gain: f32,
+    /// Delay of the pick position comb filter in samples, 0 disables it.
+    pick_samples: usize,
+
+    /// The previous sample for the damping filter.
+    last: f32,
+    /// Remaining samples and amplitude of the noise burst from [KarplusString::pluck].
+    burst: usize,
+    burst_amp: f32,
+}
+
+impl KarplusString {
+    /// Creates a new string, the `seed` is used for the noise bursts of [KarplusString::pluck].
+    pub fn new(seed: u64) -> Self {
+        let mut this = Self {
+            delay: DelayBuffer::new_with_size(KARPLUS_BUFFER_SAMPLES),
+            tuning: FractionalDelayAllpass::new(),
+            pick: DelayBuffer::new_with_size(KARPLUS_BUFFER_SAMPLES),
+            dc_block: DCBlockFilter::new(),
+            rng: SplitMix64::new(seed),
+            seed,
+
+            srate: 44100.0,
+            freq: 440.0,
+            decay_s: 2.0,
+            damping: 0.5,
+            pick_pos: 0.0,
+
+            loop_samples: 1,
+            damp_w: 0.0,
+            loop_gain: 0.0,
+            pick_samples: 0,
+
+            last: 0.0,
+            burst: 0,
+            burst_amp: 0.0,
+        };
+        this.recalc();
+        this
+    }
+
+    /// Silences the string and restarts the noise sequence of the seed.
+    pub fn reset(&mut self) {
+        self.delay.reset();
+        self.tuning.reset();
+        self.pick.reset();
+        self.dc_block.reset();
+        self.rng = SplitMix64::new(self.seed);
+        self.last = 0.0;
+        self.burst = 0;
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.dc_block.set_sample_rate(srate);
+        self.recalc();
+    }
+
+    /// Sets the tuning frequency of the string in Hz.
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq;
+        self.recalc();
+    }
+
+    /// Sets the time in seconds it takes the fundamental to decay by 60dB.
+    pub fn set_decay_time(&mut self, decay_s: f32) {
+        self.decay_s = decay_s.max(0.001);
+        self.recalc();
+    }
+
+    /// Sets the damping of the high frequencies.
+    ///
+    /// * `damping` - Range 0.0 to 1.0. At 0.0 all partials decay at the same rate,
+    ///   at 1.0 the string sounds dark and the high partials die quickly.
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping.clamp(0.0, 1.0);
+        self.recalc();
+    }
+
+    /// Sets the position along the string where it is plucked.
+    ///
+    /// * `pos` - Range 0.0 to 1.0. 0.0 disables the pick position filter. At 0.5 the
+    ///   string is plucked in the middle, which cancels all even harmonics.
+    ///   Values close to the ends sound bright and thin.
+    pub fn set_pick_position(&mut self, pos: f32) {
+        self.pick_pos = pos.clamp(0.0, 1.0);
+        self.recalc();
+    }
+
+    fn recalc(&mut self) {
+        let period = (self.srate / self.freq.max(KARPLUS_MIN_FREQ))
+            .clamp(3.0, (KARPLUS_BUFFER_SAMPLES - 1) as f32);
+
+        self.damp_w = 0.5 * self.damping;
+
+        // The loop delay is split into the delay line, the damping filter and
+        // the tuning all-pass, which covers 0.5 to 1.5 samples:
+        let rest = period - self.damp_w;
+        self.loop_samples = ((rest - 0.5).floor() as usize).max(1);
+        self.tuning.set_delay_samples(rest - self.loop_samples as f32);
+
+        // -60dB after decay_s seconds:
+        self.loop_gain = 10.0_f32.powf(-3.0 * period / (self.srate * self.decay_s)).min(1.0);
+
+        self.pick_samples = (self.pick_pos * period).round() as usize;
+    }
+
+    /// Plucks the string with a noise burst that is one period long.
+    ///
+    /// * `velocity` - The amplitude of the noise burst.
+    pub fn pluck(&mut self, velocity: f32) {
+        self.burst = (self.srate / self.freq.max(KARPLUS_MIN_FREQ)).round() as usize;
+        self.burst_amp = velocity;
+    }
+
+    /// Computes the next sample of the string.
+    ///
+    /// * `excitation` - Signal that is fed into the string, use 0.0 if the string
+    ///   is only excited by [KarplusString::pluck].
+    #[inline]
+    pub fn process(&mut self, excitation: f32) -> f32 {
+        let mut exc = excitation;
+        if self.burst > 0 {
+            self.burst -= 1;
+            exc += self.burst_amp * (self.rng.next_open01() as f32 * 2.0 - 1.0);
+        }
+
+        if self.pick_samples > 0 {
+            let reflected = self.pick.at(self.pick_samples - 1);
+            self.pick.feed(exc);
+            exc -= reflected;
+        }
+
+        // Reading before feeding adds one sample of delay:
+        let out = self.delay.at(self.loop_samples - 1);
+        let damped = (1.0 - self.damp_w) * out + self.damp_w * self.last;
+        self.last = out;
+
+        let s = self.tuning.tick(damped) * self.loop_gain + exc;
+        self.delay.feed(s);
+        self.dc_block.next(s)
+    }
+}
+
+impl Default for KarplusString {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
@@ -0,0 +1,455 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! Pitch detection for tuners and audio to pitch CV tracking, and tuning tables.
+//! See also [crate::PitchSyncOscillator] for a simpler zero crossing based tracker.
+
+/// The default threshold of the normalized difference function of [PitchDetector].
+const PITCH_DETECT_DEFAULT_THRESHOLD: f32 = 0.15;
+
+/// A monophonic pitch detector, based on the YIN algorithm.
+///
+/// The input is collected in a sliding window, and every hop size samples the
+/// period is searched with the cumulative mean normalized difference function
+/// of YIN, refined by parabolic interpolation. The analysis needs a window size
+/// of at least one period of the lowest detectable frequency, longer windows are
+/// more robust, but react slower and cost more CPU.
+///
+/// Along with the frequency a confidence from 0.0 to 1.0 is estimated, a clean periodic
+/// signal has a confidence close to 1.0, noise or silence have a low confidence.
+/// The threshold set with [PitchDetector::set_threshold] determines when the
+/// detector considers the signal voiced, see [PitchDetector::is_voiced].
+/// While unvoiced the last detected frequency is held.
+///
+/// The YIN algorithm is described in: A. de Cheveigné and H. Kawahara,
+/// "YIN, a fundamental frequency estimator for speech and music", 2002.
+///
+///```
+/// use synfx_dsp::PitchDetector;
+///
+/// let mut pd = PitchDetector::new(1024);
+/// pd.set_sample_rate(44100.0);
+/// pd.set_freq_range(50.0, 2000.0);
+///
+/// let mut result = (0.0, 0.0);
+/// for i in 0..8192 {
+///     let phase = i as f32 * std::f32::consts::TAU * 220.0 / 44100.0;
+///     // With a strong 2nd harmonic, that confuses simpler pitch trackers:
+///     result = pd.process(0.3 * phase.sin() + 0.5 * (2.0 * phase).sin());
+/// }
+///
+/// let (freq, confidence) = result;
+/// assert!((freq - 220.0).abs() < 0.5);
+/// assert!(confidence > 0.9);
+/// assert!(pd.is_voiced());
+///
+/// // Silence has no confidence:
+/// for _ in 0..8192 {
+///     result = pd.process(0.0);
+/// }
+/// assert_eq!(result.1, 0.0);
+/// assert!(!pd.is_voiced());
+///```
+#[derive(Debug, Clone)]
+pub struct PitchDetector {
+    /// Ring buffer of the input, two window sizes long.
+    input: Vec<f32>,
+    wr: usize,
+    /// The linearized input for the analysis.
+    frame: Vec<f32>,
+    /// The cumulative mean normalized difference function.
+    cmnd: Vec<f32>,
+    window: usize,
+    hop: usize,
+    since_analysis: usize,
+
+    srate: f32,
+    min_hz: f32,
+    max_hz: f32,
+    threshold: f32,
+
+    freq: f32,
+    confidence: f32,
+    voiced: bool,
+}
+
+impl PitchDetector {
+    /// Creates a new pitch detector.
+    ///
+    /// * `window_size` - The number of samples the difference function is integrated over,
+    ///   and also the longest period that can be detected. 1024 samples detect
+    ///   down to about 43Hz at 44.1kHz. At least 64 samples.
+    pub fn new(window_size: usize) -> Self {
+        let window = window_size.max(64);
+
+        Self {
+            input: vec![0.0; 2 * window],
+            wr: 0,
+            frame: vec![0.0; 2 * window],
+            cmnd: vec![1.0; window + 2],
+            window,
+            hop: window / 4,
+            since_analysis: 0,
+
+            srate: 44100.0,
+            min_hz: 50.0,
+            max_hz: 2000.0,
+            threshold: PITCH_DETECT_DEFAULT_THRESHOLD,
+
+            freq: 0.0,
+            confidence: 0.0,
+            voiced: false,
+        }
+    }
+
+    /// Resets the input buffer and the detected pitch.
+    pub fn reset(&mut self) {
+        self.input.fill(0.0);
+        self.wr = 0;
+        self.since_analysis = 0;
+        self.freq = 0.0;
+        self.confidence = 0.0;
+        self.voiced = false;
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+    }
+
+    /// Sets the number of samples between two analyses, range 1 to the window size.
+    /// The default is a quarter of the window size.
+    pub fn set_hop_size(&mut self, hop: usize) {
+        self.hop = hop.clamp(1, self.window);
+    }
+
+    /// Sets the frequency range, that is searched for the pitch. The lowest frequency
+    /// is also limited by the window size given to [PitchDetector::new].
+    pub fn set_freq_range(&mut self, min_hz: f32, max_hz: f32) {
+        self.min_hz = min_hz.max(1.0);
+        self.max_hz = max_hz.max(self.min_hz);
+    }
+
+    /// Sets the threshold of the normalized difference function, below which
+    /// a period counts as detected.
+    ///
+    /// * `threshold` - Range 0.01 to 1.0, the default is 0.15. Lower values only
+    ///   accept very clean signals, higher values also track noisy signals, but may
+    ///   jump to wrong octaves.
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold.clamp(0.01, 1.0);
+    }
+
+    /// Returns the last detected frequency in Hz, 0.0 if no pitch was detected yet.
+    #[inline]
+    pub fn freq(&self) -> f32 {
+        self.freq
+    }
+
+    /// Returns the confidence of the last analysis, from 0.0 to 1.0.
+    #[inline]
+    pub fn confidence(&self) -> f32 {
+        self.confidence
+    }
+
+    /// Returns true if the last analysis detected a pitch below the threshold.
+    #[inline]
+    pub fn is_voiced(&self) -> bool {
+        self.voiced
+    }
+
+    /// Feeds the next input sample and returns the detected frequency in Hz
+    /// and the confidence. Both only change every hop size samples.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> (f32, f32) {
+        self.input[self.wr] = input;
+        self.wr = (self.wr + 1) % self.input.len();
+
+        self.since_analysis += 1;
+        if self.since_analysis >= self.hop {
+            self.since_analysis = 0;
+            self.analyze();
+        }
+
+        (self.freq, self.confidence)
+    }
+
+    fn analyze(&mut self) {
+        let len = self.input.len();
+        for (i, s) in self.frame.iter_mut().enumerate() {
+            *s = self.input[(self.wr + i) % len];
+        }
+
+        let w = self.window;
+        let tau_min = ((self.srate / self.max_hz).floor() as usize).clamp(2, w - 3);
+        let tau_max = ((self.srate / self.min_hz).ceil() as usize).clamp(tau_min + 1, w - 1);
+
+        // Cumulative mean normalized difference function:
+        let frame = &self.frame[..];
+        let mut running_sum = 0.0;
+        self.cmnd[0] = 1.0;
+        for tau in 1..=(tau_max + 1) {
+            let mut diff = 0.0;
+            for j in 0..w {
+                let d = frame[j] - frame[j + tau];
+                diff += d * d;
+            }
+            running_sum += diff;
+
+            self.cmnd[tau] =
+                if running_sum > 1e-12 { diff * tau as f32 / running_sum } else { 1.0 };
+        }
+
+        // The first dip below the threshold, or the global minimum otherwise:
+        let mut best = tau_min;
+        let mut found = false;
+        for tau in tau_min..=tau_max {
+            if self.cmnd[tau] < self.threshold {
+                best = tau;
+                while best < tau_max && self.cmnd[best + 1] < self.cmnd[best] {
+                    best += 1;
+                }
+                found = true;
+                break;
+            }
+            if self.cmnd[tau] < self.cmnd[best] {
+                best = tau;
+            }
+        }
+
+        self.confidence = (1.0 - self.cmnd[best]).clamp(0.0, 1.0);
+        self.voiced = found;
+        if !found {
+            return;
+        }
+
+        let (a, b, c) = (self.cmnd[best - 1], self.cmnd[best], self.cmnd[best + 1]);
+        let denom = a - 2.0 * b + c;
+        let offs = if denom.abs() > 1e-9 { (0.5 * (a - c) / denom).clamp(-0.5, 0.5) } else { 0.0 };
+
+        self.freq = self.srate / (best as f32 + offs);
+    }
+}
+
+/// The number of scale degrees of a [Tuning].
+const TUNING_DEGREES: usize = 12;
+
+/// A 12 note tuning table, which maps midi notes to frequencies.
+///
+/// The tuning consists of the reference frequency of the A4 (midi note 69), a deviation
+/// in cents from equal temperament for each of the 12 scale degrees (starting at C)
+/// and the period, the interval in cents after which the scale repeats (1200 cents,
+/// an octave, for most scales). The default is 12 tone equal temperament with A4 at 440Hz.
+///
+/// For pitch CV (0.1 per octave) conversions see also [crate::pitch_cv_to_freq] and
+/// [crate::freq_to_pitch_cv].
+///
+///```
+/// use synfx_dsp::Tuning;
+///
+/// let mut tuning = Tuning::new();
+/// assert_eq!(tuning.note_to_freq(69.0), 440.0);
+/// assert!((tuning.note_to_freq(60.0) - 261.6256).abs() < 0.001);
+///
+/// // Baroque pitch, with the E 14 cents flat:
+/// tuning.set_a4_hz(415.0);
+/// tuning.set_cent_offset(4, -14.0);
+/// assert_eq!(tuning.note_to_freq(69.0), 415.0);
+/// assert!((tuning.freq_to_note(tuning.note_to_freq(64.0)) - 64.0).abs() < 0.0001);
+///
+/// // One octave up in pitch CV:
+/// assert!((tuning.note_to_pitch_cv(81.0) - tuning.note_to_pitch_cv(69.0) - 0.1).abs() < 0.00001);
+///```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    a4_hz: f32,
+    /// The deviation of each scale degree from equal temperament in cents.
+    offsets: [f32; TUNING_DEGREES],
+    period_cents: f32,
+}
+
+impl Tuning {
+    /// Creates a 12 tone equal temperament tuning with A4 at 440Hz.
+    pub fn new() -> Self {
+        Self { a4_hz: 440.0, offsets: [0.0; TUNING_DEGREES], period_cents: 1200.0 }
+    }
+
+    /// Creates a tuning from a 12 note scala (`.scl`) scale file, with A4 at 440Hz.
+    ///
+    /// Lines starting with `!` are comments. The first line is the description,
+    /// followed by the number of notes, which must be 12, and one pitch per line. Pitches
+    /// with a `.` are in cents, the others are ratios like `3/2` or `2`. The first degree is
+    /// implicitly 0 cents and the last pitch is the period, usually `2/1`.
+    ///
+    /// Note that A4 keeps the reference frequency, the scale is only relative to it.
+    /// Returns `None` if the text could not be parsed, or if the period is not positive.
+    ///
+    ///```
+    /// use synfx_dsp::Tuning;
+    ///
+    /// let scl = "! pythagorean.scl\n!\nPythagorean 12 note\n 12\n!\n\
+    ///     256/243\n9/8\n32/27\n81/64\n4/3\n729/512\n3/2\n128/81\n27/16\n16/9\n243/128\n2/1\n";
+    ///
+    /// let tuning = Tuning::from_scala(scl).unwrap();
+    /// // The fifth is pure:
+    /// let ratio = tuning.note_to_freq(67.0) / tuning.note_to_freq(60.0);
+    /// assert!((ratio - 1.5).abs() < 0.0001);
+    /// assert!((tuning.cent_offset(7) - 1.955).abs() < 0.001);
+    ///```
+    pub fn from_scala(text: &str) -> Option<Self> {
+        let mut lines = text.lines().map(|l| l.trim()).filter(|l| !l.starts_with('!')).skip(1);
+
+        let count: usize = lines.next()?.split_whitespace().next()?.parse().ok()?;
+        if count != TUNING_DEGREES {
+            return None;
+        }
+
+        let mut pitches = [0.0; TUNING_DEGREES];
+        for pitch in pitches.iter_mut() {
+            let token = lines.next()?.split_whitespace().next()?;
+            *pitch = if token.contains('.') {
+                token.parse::<f32>().ok()?
+            } else {
+                let ratio = match token.split_once('/') {
+                    Some((num, den)) => num.parse::<f32>().ok()? / den.parse::<f32>().ok()?,
+                    None => token.parse::<f32>().ok()?,
+                };
+                if ratio <= 0.0 {
+                    return None;
+                }
+                1200.0 * ratio.log2()
+            };
+        }
+
+        let period_cents = pitches[TUNING_DEGREES - 1];
+        if !period_cents.is_finite() || period_cents <= 0.0 {
+            return None;
+        }
+
+        let mut tuning = Self::new();
+        tuning.period_cents = period_cents;
+        for degree in 1..TUNING_DEGREES {
+            tuning.offsets[degree] = pitches[degree - 1] - 100.0 * degree as f32;
+        }
+        Some(tuning)
+    }
+
+    /// Sets the reference frequency of the A4 (midi note 69) in Hz.
+    pub fn set_a4_hz(&mut self, a4_hz: f32) {
+        self.a4_hz = a4_hz;
+    }
+
+    pub fn a4_hz(&self) -> f32 {
+        self.a4_hz
+    }
+
+    /// Sets the deviation of a scale degree from equal temperament.
+    ///
+    /// * `degree` - The scale degree, 0 is C and 11 is B.
+    /// * `cents` - The deviation in cents.
+    pub fn set_cent_offset(&mut self, degree: usize, cents: f32) {
+        self.offsets[degree % TUNING_DEGREES] = cents;
+    }
+
+    /// Returns the deviation of a scale degree (0 is C) from equal temperament in cents.
+    pub fn cent_offset(&self, degree: usize) -> f32 {
+        self.offsets[degree % TUNING_DEGREES]
+    }
+
+    /// Sets the deviations of all scale degrees from equal temperament in cents,
+    /// starting at C.
+    pub fn set_cent_offsets(&mut self, cents: &[f32; TUNING_DEGREES]) {
+        self.offsets = *cents;
+    }
+
+    /// Returns the pitch of the (integer) midi note in cents, relative to the C0.
+    #[inline]
+    fn note_cents(&self, note: i64) -> f32 {
+        let octave = note.div_euclid(TUNING_DEGREES as i64);
+        let degree = note.rem_euclid(TUNING_DEGREES as i64) as usize;
+        octave as f32 * self.period_cents + 100.0 * degree as f32 + self.offsets[degree]
+    }
+
+    /// Converts a (fractional) midi note to a frequency. Fractional notes are
+    /// interpolated linearly in cents between the neighbouring notes.
+    pub fn note_to_freq(&self, note: f32) -> f32 {
+        let n = note.floor();
+        let c0 = self.note_cents(n as i64);
+        let c1 = self.note_cents(n as i64 + 1);
+        let cents = c0 + (c1 - c0) * (note - n) - self.note_cents(69);
+        self.a4_hz * (2.0_f32).powf(cents / 1200.0)
+    }
+
+    /// Converts a frequency to a (fractional) midi note, the inverse of
+    /// [Tuning::note_to_freq]. The scale degrees have to be ascending.
+    ///
+    /// Returns NaN for a frequency of 0.0 or less, or one that is not finite,
+    /// like the 0.0 of an unvoiced [crate::PitchDetector].
+    pub fn freq_to_note(&self, freq: f32) -> f32 {
+        if !freq.is_finite() || freq <= 0.0 {
+            return f32::NAN;
+        }
+
+        let cents = 1200.0 * (freq / self.a4_hz).log2() + self.note_cents(69);
+        let octave = (cents / self.period_cents).floor() as i64;
+        let mut note = octave * TUNING_DEGREES as i64;
+
+        // The offsets can push the pitch over the neighbouring octave boundaries:
+        while self.note_cents(note) > cents {
+            note -= 1;
+        }
+        while self.note_cents(note + 1) <= cents {
+            note += 1;
+        }
+
+        let (c0, c1) = (self.note_cents(note), self.note_cents(note + 1));
+        note as f32 + (cents - c0) / (c1 - c0)
+    }
+
+    /// Converts a (fractional) midi note to a pitch CV (0.1 per octave, 0.0 is 440Hz),
+    /// see also [crate::freq_to_pitch_cv].
+    pub fn note_to_pitch_cv(&self, note: f32) -> f32 {
+        crate::freq_to_pitch_cv(self.note_to_freq(note))
+    }
+
+    /// Converts a pitch CV (0.1 per octave, 0.0 is 440Hz) to a (fractional) midi note
+    /// of this tuning, see also [crate::pitch_cv_to_freq].
+    pub fn pitch_cv_to_note(&self, cv: f32) -> f32 {
+        self.freq_to_note(crate::pitch_cv_to_freq(cv))
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_tuning_freq_to_note_invalid_freq() {
+        let tuning = Tuning::new();
+        assert!(tuning.freq_to_note(0.0).is_nan());
+        assert!(tuning.freq_to_note(-440.0).is_nan());
+        assert!(tuning.freq_to_note(f32::INFINITY).is_nan());
+        assert!(tuning.freq_to_note(f32::NAN).is_nan());
+        assert!((tuning.freq_to_note(440.0) - 69.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn check_tuning_from_scala_rejects_invalid_period() {
+        let scl = |period: &str| {
+            format!(
+                "zero period\n12\n100.0\n200.0\n300.0\n400.0\n500.0\n600.0\n700.0\n\
+                 800.0\n900.0\n1000.0\n1100.0\n{}\n",
+                period
+            )
+        };
+        assert!(Tuning::from_scala(&scl("1200.0")).is_some());
+        assert!(Tuning::from_scala(&scl("0.0")).is_none());
+        assert!(Tuning::from_scala(&scl("-1200.0")).is_none());
+    }
+}
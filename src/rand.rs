@@ -5,20 +5,42 @@
 //! Random number generators and utilities.
 /// Be aware that some might need some initialization function!
 use std::cell::RefCell;
+use std::sync::OnceLock;
 
-/// A wavetable filled entirely with white noise.
-/// Don't forget to call [init_white_noise_tab] before using it.
-static mut WHITE_NOISE_TAB: [f64; 1024] = [0.0; 1024];
+/// A wavetable filled entirely with white noise, it is generated on first use.
+static WHITE_NOISE_TAB: OnceLock<[f64; 1024]> = OnceLock::new();
 
 #[allow(rustdoc::private_intra_doc_links)]
-/// Initializes [WHITE_NOISE_TAB]
+/// Initializes [WHITE_NOISE_TAB].
+///
+/// Calling this is optional, the table is also generated on the first call of
+/// [white_noise_tab]. Calling it once on process initialization keeps the table
+/// generation out of your audio thread. It is safe to call this multiple times
+/// and from multiple threads.
 pub fn init_white_noise_tab() {
-    let mut rng = RandGen::new();
-    unsafe {
-        for i in 0..WHITE_NOISE_TAB.len() {
-            WHITE_NOISE_TAB[i as usize] = rng.next_open01();
+    white_noise_tab();
+}
+
+/// Returns a wavetable filled entirely with white noise in the range 0.0 to 1.0.
+///
+///```
+/// use synfx_dsp::white_noise_tab;
+///
+/// let tab = white_noise_tab();
+/// assert_eq!(tab.len(), 1024);
+/// assert!(tab.iter().all(|s| *s >= 0.0 && *s < 1.0));
+/// // The table is generated only once:
+/// assert_eq!(tab.as_ptr(), white_noise_tab().as_ptr());
+///```
+pub fn white_noise_tab() -> &'static [f64; 1024] {
+    WHITE_NOISE_TAB.get_or_init(|| {
+        let mut rng = RandGen::new();
+        let mut tab = [0.0; 1024];
+        for v in tab.iter_mut() {
+            *v = rng.next_open01();
         }
-    }
+        tab
+    })
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -89,11 +111,28 @@ impl Rng {
         self.sm = SplitMix64::new(seed);
     }
 
+    /// Seeds the generator of one voice, see [SplitMix64::new_voice].
+    pub fn seed_voice(&mut self, base_seed: u64, voice: usize) {
+        self.sm = SplitMix64::new_voice(base_seed, voice);
+    }
+
     #[inline]
     pub fn next(&mut self) -> f32 {
         self.sm.next_open01() as f32
     }
 
+    /// Next normal distributed random number, see [SplitMix64::next_gauss].
+    #[inline]
+    pub fn next_gauss(&mut self) -> f32 {
+        self.sm.next_gauss() as f32
+    }
+
+    /// Next triangular distributed random number, see [SplitMix64::next_tri].
+    #[inline]
+    pub fn next_tri(&mut self) -> f32 {
+        self.sm.next_tri() as f32
+    }
+
     #[inline]
     pub fn next_u64(&mut self) -> u64 {
         self.sm.next_u64()
@@ -114,6 +153,38 @@ pub fn rand_u64() -> u64 {
     GLOBAL_RNG.with(|r| r.borrow_mut().next_u64())
 }
 
+/// Generates `count` reproducible initial phases in the range `[0.0, 1.0)` for a pool of
+/// oscillators, for instance for unison voices or additive partials.
+///
+/// Instead of independent random values, which can cluster and cause phase cancellation,
+/// the phases form a golden ratio (low discrepancy) sequence. Only its starting
+/// point is derived from `seed`. The same `count` and `seed` always return the same phases.
+///
+///```
+/// use synfx_dsp::spread_init_phases;
+///
+/// let phases = spread_init_phases(8, 42);
+/// assert_eq!(phases, spread_init_phases(8, 42));
+/// assert_ne!(phases, spread_init_phases(8, 43));
+///
+/// // The phases are evenly spread, there are no big gaps:
+/// let mut sorted = phases.clone();
+/// sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+/// let mut max_gap = 1.0 - sorted[7] + sorted[0];
+/// for w in sorted.windows(2) {
+///     max_gap = max_gap.max(w[1] - w[0]);
+/// }
+/// assert!(max_gap < 2.0 / 8.0);
+/// assert!(phases.iter().all(|p| (0.0..1.0).contains(p)));
+///```
+pub fn spread_init_phases(count: usize, seed: u64) -> Vec<f32> {
+    // The inverse of the golden ratio:
+    const INV_PHI: f64 = 0.6180339887498949;
+
+    let start = SplitMix64::new(seed).next_open01();
+    (0..count).map(|i| ((start + (i as f64) * INV_PHI).fract()) as f32).collect()
+}
+
 // Copyright 2018 Developers of the Rand project.
 //
 // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
@@ -146,6 +217,31 @@ impl SplitMix64 {
         Self::new(u64::from_be_bytes(seed.to_be_bytes()))
     }
 
+    /// Creates the generator of one voice of a polyphonic instrument. The same `base_seed`
+    /// and `voice` index always give the same sequence, and the sequences of
+    /// different voices are uncorrelated.
+    ///
+    ///```
+    /// use synfx_dsp::SplitMix64;
+    ///
+    /// let mut v0 = SplitMix64::new_voice(42, 0);
+    /// let mut v1 = SplitMix64::new_voice(42, 1);
+    /// let a = v0.next_u64();
+    /// assert_ne!(a, v1.next_u64());
+    /// assert_eq!(a, SplitMix64::new_voice(42, 0).next_u64());
+    /// assert_ne!(a, SplitMix64::new_voice(43, 0).next_u64());
+    ///
+    /// // Voice 1 is not just voice 0 shifted by some steps:
+    /// let seq0: Vec<u64> = (0..100).map(|_| v0.next_u64()).collect();
+    /// assert!(!seq0.contains(&v1.next_u64()));
+    ///```
+    pub fn new_voice(base_seed: u64, voice: usize) -> Self {
+        // Neighbouring states only shift the sequence, so hash the seed and the
+        // voice index into far apart states:
+        let key = Self::new(base_seed).next_u64();
+        Self::new(Self::new(key ^ voice as u64).next_u64())
+    }
+
     pub fn new_time_seed() -> Self {
         use std::time::SystemTime;
 
@@ -173,4 +269,373 @@ impl SplitMix64 {
     pub fn next_open01(&mut self) -> f64 {
         u64_to_open01(self.next_u64())
     }
+
+    /// Next normal distributed random number with a mean of 0.0 and a standard
+    /// deviation of 1.0, calculated with the Box-Muller transform.
+    ///
+    ///```
+    /// use synfx_dsp::SplitMix64;
+    ///
+    /// let mut sm = SplitMix64::new(42);
+    /// let v: Vec<f64> = (0..10000).map(|_| sm.next_gauss()).collect();
+    ///
+    /// let mean = v.iter().sum::<f64>() / 10000.0;
+    /// let var = v.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / 10000.0;
+    /// assert!(mean.abs() < 0.05);
+    /// assert!((var - 1.0).abs() < 0.05);
+    /// // About 68% are within one standard deviation:
+    /// let inside = v.iter().filter(|x| x.abs() < 1.0).count();
+    /// assert!(inside > 6600 && inside < 7000);
+    ///```
+    #[inline]
+    pub fn next_gauss(&mut self) -> f64 {
+        // Mapped into (0.0, 1.0], so that the logarithm stays finite:
+        let u1 = 1.0 - self.next_open01();
+        let u2 = self.next_open01();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+
+    /// Next triangular distributed random number in the range `(-1.0, 1.0)`, values
+    /// near 0.0 are the most likely. It is the sum of two uniform random numbers.
+    ///
+    ///```
+    /// use synfx_dsp::SplitMix64;
+    ///
+    /// let mut sm = SplitMix64::new(42);
+    /// let v: Vec<f64> = (0..10000).map(|_| sm.next_tri()).collect();
+    ///
+    /// assert!(v.iter().all(|x| x.abs() < 1.0));
+    /// assert!((v.iter().sum::<f64>() / 10000.0).abs() < 0.05);
+    /// // Half of the values are closer than 1.0 - 1.0 / sqrt(2.0) to the center:
+    /// let inside = v.iter().filter(|x| x.abs() < 0.293).count();
+    /// assert!(inside > 4800 && inside < 5200);
+    ///```
+    #[inline]
+    pub fn next_tri(&mut self) -> f64 {
+        self.next_open01() + self.next_open01() - 1.0
+    }
+}
+
+// Pink noise filter (refined method) by Paul Kellet, from musicdsp.org:
+// https://www.musicdsp.org/en/latest/Filters/76-pink-noise-filter.html
+/// Pink noise generator, with a spectrum that falls by 3dB per octave.
+///
+/// Implemented with the refined filter method by Paul Kellet, which filters white noise
+/// from a [SplitMix64] with a bank of one pole low pass filters. The filter coefficients
+/// are designed for 44.1kHz, the slope is still close enough at the other common
+/// sample rates. See also [BrownNoise] and [BlueNoise].
+///
+///```
+/// use synfx_dsp::PinkNoise;
+///
+/// let mut noise = PinkNoise::new(42);
+/// let samples: Vec<f32> = (0..44100).map(|_| noise.next()).collect();
+/// assert!(samples.iter().all(|s| s.abs() < 1.0));
+///
+/// // Pink noise is more correlated than white noise:
+/// let corr = samples.windows(2).map(|w| w[0] * w[1]).sum::<f32>()
+///     / samples.iter().map(|s| s * s).sum::<f32>();
+/// assert!(corr > 0.5 && corr < 0.95);
+///
+/// // The same seed gives the same noise:
+/// let mut noise2 = PinkNoise::new(42);
+/// assert_eq!(samples[0], noise2.next());
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct PinkNoise {
+    rng: SplitMix64,
+    seed: u64,
+    b: [f32; 7],
+}
+
+impl PinkNoise {
+    /// Creates a new pink noise generator with the given `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { rng: SplitMix64::new(seed), seed, b: [0.0; 7] }
+    }
+
+    /// Restarts the noise with a new `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    /// Resets the filter state and restarts the random sequence of the current seed.
+    pub fn reset(&mut self) {
+        self.rng = SplitMix64::new(self.seed);
+        self.b = [0.0; 7];
+    }
+
+    /// Returns the next sample, roughly in the range -1.0 to 1.0.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let white = self.rng.next_open01() as f32 * 2.0 - 1.0;
+        let b = &mut self.b;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.153852;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.016898;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        pink * PINK_NOISE_GAIN
+    }
+}
+
+/// Output gain of [PinkNoise], to bring it roughly into the range -1.0 to 1.0.
+const PINK_NOISE_GAIN: f32 = 0.11;
+
+/// Brown (also called red) noise generator, with a spectrum that falls by 6dB per octave.
+///
+/// It integrates white noise from a [SplitMix64] with a leaky integrator, so that it
+/// does not drift away. See also [PinkNoise] and [BlueNoise].
+///
+///```
+/// use synfx_dsp::BrownNoise;
+///
+/// let mut noise = BrownNoise::new(42);
+/// let samples: Vec<f32> = (0..44100).map(|_| noise.next()).collect();
+/// assert!(samples.iter().all(|s| s.abs() < 1.0));
+///
+/// // Brown noise is strongly correlated:
+/// let corr = samples.windows(2).map(|w| w[0] * w[1]).sum::<f32>()
+///     / samples.iter().map(|s| s * s).sum::<f32>();
+/// assert!(corr > 0.95);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct BrownNoise {
+    rng: SplitMix64,
+    seed: u64,
+    y: f32,
+}
+
+impl BrownNoise {
+    /// Creates a new brown noise generator with the given `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { rng: SplitMix64::new(seed), seed, y: 0.0 }
+    }
+
+    /// Restarts the noise with a new `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    /// Resets the integrator and restarts the random sequence of the current seed.
+    pub fn reset(&mut self) {
+        self.rng = SplitMix64::new(self.seed);
+        self.y = 0.0;
+    }
+
+    /// Returns the next sample, roughly in the range -1.0 to 1.0.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let white = self.rng.next_open01() as f32 * 2.0 - 1.0;
+        self.y = (self.y + 0.02 * white) / 1.02;
+        self.y * BROWN_NOISE_GAIN
+    }
+}
+
+/// Output gain of [BrownNoise], to bring it roughly into the range -1.0 to 1.0.
+const BROWN_NOISE_GAIN: f32 = 3.5;
+
+/// Blue noise generator, with a spectrum that rises by 3dB per octave.
+///
+/// It differentiates the output of a [PinkNoise] generator, which turns the
+/// falling slope into a rising one. See also [BrownNoise].
+///
+///```
+/// use synfx_dsp::BlueNoise;
+///
+/// let mut noise = BlueNoise::new(42);
+/// let samples: Vec<f32> = (0..44100).map(|_| noise.next()).collect();
+/// assert!(samples.iter().all(|s| s.abs() < 1.0));
+///
+/// // Most of the energy of blue noise is at high frequencies,
+/// // so neighbouring samples tend to have opposite signs:
+/// let corr = samples.windows(2).map(|w| w[0] * w[1]).sum::<f32>()
+///     / samples.iter().map(|s| s * s).sum::<f32>();
+/// assert!(corr < -0.2);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct BlueNoise {
+    pink: PinkNoise,
+    last: f32,
+}
+
+impl BlueNoise {
+    /// Creates a new blue noise generator with the given `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { pink: PinkNoise::new(seed), last: 0.0 }
+    }
+
+    /// Restarts the noise with a new `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.pink.seed(seed);
+        self.last = 0.0;
+    }
+
+    /// Resets the filter state and restarts the random sequence of the current seed.
+    pub fn reset(&mut self) {
+        self.pink.reset();
+        self.last = 0.0;
+    }
+
+    /// Returns the next sample, roughly in the range -1.0 to 1.0.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let pink = self.pink.next();
+        let out = pink - self.last;
+        self.last = pink;
+        out * BLUE_NOISE_GAIN
+    }
+}
+
+/// Output gain of [BlueNoise], to bring it roughly into the range -1.0 to 1.0.
+const BLUE_NOISE_GAIN: f32 = 2.5;
+
+/// Velvet noise generator, sparse impulses of random sign (-1.0 or 1.0) at a controllable
+/// density, with all other samples being 0.0.
+///
+/// The time is divided into frames of `sample rate / density` samples (rounded to a whole
+/// number of samples), and every frame contains exactly one impulse at a random position.
+/// Above about 2000 impulses per second velvet noise sounds as smooth as white noise, but
+/// filtering with it only needs an addition per impulse. This makes it the building block
+/// for cheap decorrelators and the diffusion of artificial reverbs.
+/// See also: Välimäki, Lehtonen, Takanen: "A Perceptual Study on Velvet Noise and Its
+/// Variants at Different Pulse Densities" (2013).
+///
+/// [VelvetNoise::sequence] generates a fixed sequence, for instance as taps
+/// of a [crate::DelayBuffer]:
+///
+///```
+/// use synfx_dsp::{DelayBuffer, VelvetNoise};
+///
+/// let mut noise = VelvetNoise::new(42);
+/// noise.set_sample_rate(44100.0);
+/// noise.set_density(2000.0);
+///
+/// let samples: Vec<f32> = (0..44100).map(|_| noise.next()).collect();
+/// assert!(samples.iter().all(|s| *s == 0.0 || s.abs() == 1.0));
+/// let impulses = samples.iter().filter(|s| **s != 0.0).count();
+/// assert!((impulses as i64 - 2005).abs() <= 1);
+///
+/// // A sparse FIR filter, that decorrelates a signal with 30ms of velvet noise:
+/// noise.set_density(1000.0);
+/// noise.reset();
+/// let taps = noise.sequence(1323);
+/// assert_eq!(taps.len(), 30);
+///
+/// let mut delay = DelayBuffer::<f32>::new();
+/// delay.feed(1.0);
+/// let mut response = vec![];
+/// for _ in 0..1323 {
+///     response.push(taps.iter().map(|(pos, sign)| delay.at(*pos) * sign).sum::<f32>());
+///     delay.feed(0.0);
+/// }
+/// for (pos, sign) in taps.iter() {
+///     assert_eq!(response[*pos], *sign);
+/// }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct VelvetNoise {
+    rng: SplitMix64,
+    seed: u64,
+    srate: f32,
+    density: f32,
+    /// The length of a frame in samples.
+    frame_len: usize,
+    /// The position within the current frame.
+    pos: usize,
+    /// The position of the impulse within the current frame.
+    impulse_pos: usize,
+    impulse_sign: f32,
+}
+
+impl VelvetNoise {
+    /// Creates a new velvet noise generator with 2000 impulses per second
+    /// and the given `seed`.
+    pub fn new(seed: u64) -> Self {
+        let mut this = Self {
+            rng: SplitMix64::new(seed),
+            seed,
+            srate: 44100.0,
+            density: 2000.0,
+            frame_len: 1,
+            pos: 0,
+            impulse_pos: 0,
+            impulse_sign: 1.0,
+        };
+        this.update_frame_len();
+        this
+    }
+
+    /// Restarts the noise with a new `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    /// Restarts the random sequence of the current seed at the start of a frame.
+    pub fn reset(&mut self) {
+        self.rng = SplitMix64::new(self.seed);
+        self.pos = 0;
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.update_frame_len();
+    }
+
+    /// Sets the density in impulses per second. It is limited to the sample rate,
+    /// where every sample is an impulse.
+    pub fn set_density(&mut self, impulses_per_sec: f32) {
+        self.density = impulses_per_sec.max(0.001);
+        self.update_frame_len();
+    }
+
+    fn update_frame_len(&mut self) {
+        self.frame_len = ((self.srate / self.density).round() as usize).max(1);
+        if self.pos >= self.frame_len {
+            self.pos = 0;
+        }
+    }
+
+    /// Returns the next sample, either 0.0, -1.0 or 1.0.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        if self.pos == 0 {
+            self.impulse_pos = (self.rng.next_open01() * self.frame_len as f64) as usize;
+            self.impulse_sign = if self.rng.next_open01() < 0.5 { -1.0 } else { 1.0 };
+        }
+
+        let out = if self.pos == self.impulse_pos { self.impulse_sign } else { 0.0 };
+
+        self.pos += 1;
+        if self.pos >= self.frame_len {
+            self.pos = 0;
+        }
+
+        out
+    }
+
+    /// Generates the next `length` samples as a sparse sequence of the impulse
+    /// positions (relative to the start of the sequence) and their signs.
+    /// The positions are sorted, use them for instance as taps of a [crate::DelayBuffer]
+    /// or as a sparse FIR filter.
+    ///
+    /// **Attention:** This allocates the sequence, don't call it from the audio thread.
+    pub fn sequence(&mut self, length: usize) -> Vec<(usize, f32)> {
+        (0..length)
+            .filter_map(|i| {
+                let s = self.next();
+                if s != 0.0 {
+                    Some((i, s))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
 }
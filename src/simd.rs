@@ -0,0 +1,221 @@
+// Copyright (c) 2021-2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! The 4 lane `f32x4` vector type used by the oversampling, [crate::tanh_levien]
+//! and the [crate::fh_va] filters.
+//!
+//! With the `simd` feature (enabled by default, requires nightly) this is
+//! `std::simd::f32x4`. Without it a scalar fallback with the same API is used,
+//! so that the crate compiles on stable Rust.
+
+#[cfg(feature = "simd")]
+pub use std::simd::f32x4;
+#[cfg(feature = "simd")]
+pub(crate) use std::simd::{
+    cmp::{SimdPartialEq, SimdPartialOrd},
+    prelude::SimdFloat,
+    Select, StdFloat,
+};
+
+#[cfg(not(feature = "simd"))]
+pub use scalar::f32x4;
+
+#[cfg(not(feature = "simd"))]
+mod scalar {
+    use std::ops::{
+        Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+    };
+
+    /// Scalar fallback for `std::simd::f32x4`, the lanes are processed one after another.
+    #[allow(non_camel_case_types)]
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub struct f32x4([f32; 4]);
+
+    /// Scalar fallback for `std::simd::mask32x4`, as returned by the lane wise comparisons.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Mask32x4([bool; 4]);
+
+    impl f32x4 {
+        /// The number of lanes.
+        pub const LEN: usize = 4;
+
+        #[inline]
+        pub const fn from_array(array: [f32; 4]) -> Self {
+            Self(array)
+        }
+
+        #[inline]
+        pub const fn splat(value: f32) -> Self {
+            Self([value; 4])
+        }
+
+        #[inline]
+        pub const fn to_array(self) -> [f32; 4] {
+            self.0
+        }
+
+        #[inline]
+        pub const fn as_array(&self) -> &[f32; 4] {
+            &self.0
+        }
+
+        #[inline]
+        pub fn as_mut_array(&mut self) -> &mut [f32; 4] {
+            &mut self.0
+        }
+
+        #[inline]
+        fn map(self, f: impl Fn(f32) -> f32) -> Self {
+            Self(self.0.map(f))
+        }
+
+        #[inline]
+        fn cmp(self, other: Self, f: impl Fn(f32, f32) -> bool) -> Mask32x4 {
+            let (a, b) = (self.0, other.0);
+            Mask32x4([f(a[0], b[0]), f(a[1], b[1]), f(a[2], b[2]), f(a[3], b[3])])
+        }
+
+        #[inline]
+        pub fn abs(self) -> Self {
+            self.map(f32::abs)
+        }
+
+        #[inline]
+        pub fn sqrt(self) -> Self {
+            self.map(f32::sqrt)
+        }
+
+        #[inline]
+        pub fn simd_min(self, other: Self) -> Self {
+            let (a, b) = (self.0, other.0);
+            Self([a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2]), a[3].min(b[3])])
+        }
+
+        #[inline]
+        pub fn simd_max(self, other: Self) -> Self {
+            let (a, b) = (self.0, other.0);
+            Self([a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2]), a[3].max(b[3])])
+        }
+
+        #[inline]
+        pub fn simd_eq(self, other: Self) -> Mask32x4 {
+            self.cmp(other, |a, b| a == b)
+        }
+
+        #[inline]
+        pub fn simd_ne(self, other: Self) -> Mask32x4 {
+            self.cmp(other, |a, b| a != b)
+        }
+
+        #[inline]
+        pub fn simd_lt(self, other: Self) -> Mask32x4 {
+            self.cmp(other, |a, b| a < b)
+        }
+
+        #[inline]
+        pub fn simd_le(self, other: Self) -> Mask32x4 {
+            self.cmp(other, |a, b| a <= b)
+        }
+
+        #[inline]
+        pub fn simd_gt(self, other: Self) -> Mask32x4 {
+            self.cmp(other, |a, b| a > b)
+        }
+
+        #[inline]
+        pub fn simd_ge(self, other: Self) -> Mask32x4 {
+            self.cmp(other, |a, b| a >= b)
+        }
+    }
+
+    impl Mask32x4 {
+        /// Takes the lanes of `true_values` where the mask is set,
+        /// and the lanes of `false_values` otherwise.
+        #[inline]
+        pub fn select(self, true_values: f32x4, false_values: f32x4) -> f32x4 {
+            let (m, t, f) = (self.0, true_values.0, false_values.0);
+            f32x4([
+                if m[0] { t[0] } else { f[0] },
+                if m[1] { t[1] } else { f[1] },
+                if m[2] { t[2] } else { f[2] },
+                if m[3] { t[3] } else { f[3] },
+            ])
+        }
+
+        #[inline]
+        pub fn any(self) -> bool {
+            self.0.iter().any(|m| *m)
+        }
+
+        #[inline]
+        pub fn all(self) -> bool {
+            self.0.iter().all(|m| *m)
+        }
+    }
+
+    impl From<[f32; 4]> for f32x4 {
+        #[inline]
+        fn from(array: [f32; 4]) -> Self {
+            Self(array)
+        }
+    }
+
+    impl From<f32x4> for [f32; 4] {
+        #[inline]
+        fn from(v: f32x4) -> Self {
+            v.0
+        }
+    }
+
+    impl Index<usize> for f32x4 {
+        type Output = f32;
+
+        #[inline]
+        fn index(&self, index: usize) -> &f32 {
+            &self.0[index]
+        }
+    }
+
+    impl IndexMut<usize> for f32x4 {
+        #[inline]
+        fn index_mut(&mut self, index: usize) -> &mut f32 {
+            &mut self.0[index]
+        }
+    }
+
+    impl Neg for f32x4 {
+        type Output = Self;
+
+        #[inline]
+        fn neg(self) -> Self {
+            self.map(|x| -x)
+        }
+    }
+
+    macro_rules! impl_binop {
+        ($trait: ident, $fn: ident, $assign_trait: ident, $assign_fn: ident, $op: tt) => {
+            impl $trait for f32x4 {
+                type Output = Self;
+
+                #[inline]
+                fn $fn(self, rhs: Self) -> Self {
+                    let (a, b) = (self.0, rhs.0);
+                    Self([a[0] $op b[0], a[1] $op b[1], a[2] $op b[2], a[3] $op b[3]])
+                }
+            }
+
+            impl $assign_trait for f32x4 {
+                #[inline]
+                fn $assign_fn(&mut self, rhs: Self) {
+                    *self = *self $op rhs;
+                }
+            }
+        };
+    }
+
+    impl_binop!(Add, add, AddAssign, add_assign, +);
+    impl_binop!(Sub, sub, SubAssign, sub_assign, -);
+    impl_binop!(Mul, mul, MulAssign, mul_assign, *);
+    impl_binop!(Div, div, DivAssign, div_assign, /);
+}
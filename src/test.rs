@@ -6,6 +6,8 @@
 
 */
 
+use crate::fft::Fft;
+
 /// This macro allows you to float compare two vectors to a precision of `0.0001`.
 #[macro_export]
 macro_rules! assert_vec_feq {
@@ -231,3 +233,320 @@ assertion failed: `(left[{}] == right[{}])`
         }
     };
 }
+
+/// Calculates the root mean square (RMS) of the samples in `buf`.
+/// Returns `0.0` for an empty slice.
+///
+///```
+/// use synfx_dsp::rms;
+///
+/// assert_eq!(rms(&[0.5, -0.5, 0.5, -0.5]), 0.5);
+/// assert_eq!(rms(&[]), 0.0);
+///```
+pub fn rms(buf: &[f32]) -> f32 {
+    if buf.is_empty() {
+        return 0.0;
+    }
+
+    let sum: f64 = buf.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+    (sum / (buf.len() as f64)).sqrt() as f32
+}
+
+/// Calculates the RMS (see [rms]) of `$vec` and compares it with `$expected_rms`
+/// with a precision of `$tol`. Useful for asserting on the energy of stochastic or
+/// phase sensitive output, like noise or reverb tails, where comparing the
+/// exact samples is meaningless.
+///
+///```
+/// use synfx_dsp::assert_rms_feq;
+///
+/// let sine: Vec<f32> =
+///     (0..1000).map(|i| (i as f32 * std::f32::consts::TAU / 100.0).sin()).collect();
+/// assert_rms_feq!(sine, std::f32::consts::FRAC_1_SQRT_2, 0.001);
+///```
+#[macro_export]
+macro_rules! assert_rms_feq {
+    ($vec:expr, $expected_rms:expr, $tol:expr) => {
+        let res: Vec<f32> = $vec.iter().copied().collect();
+        let rms = $crate::rms(&res[..]);
+        let expected: f32 = $expected_rms;
+        let tol: f32 = $tol;
+
+        if (rms - expected).abs() > tol {
+            panic!(
+                r#"assertion failed: `(rms(left) == right)` with tolerance {:?}
+  rms(left): `{:?}`,
+      right: `{:?}`"#,
+                tol, rms, expected
+            )
+        }
+    };
+}
+
+/// Asserts that the values in `$vec` are monotonically rising. Decreases of up to `0.000001`
+/// are tolerated, to allow for floating point noise.
+///
+///```
+/// use synfx_dsp::assert_monotonic_rising;
+///
+/// assert_monotonic_rising!(vec![0.0, 0.1, 0.1, 0.5, 1.0]);
+///```
+#[macro_export]
+macro_rules! assert_monotonic_rising {
+    ($vec:expr) => {
+        let res: Vec<f32> = $vec.iter().copied().collect();
+
+        for (i, w) in res.windows(2).enumerate() {
+            if w[1] < w[0] - 0.000001 {
+                panic!(
+                    r#"
+table: {:?}
+
+assertion failed: monotonic rising `(left[{}] <= left[{}])`
+      left[{}]: `{:?}`,
+      left[{}]: `{:?}`"#,
+                    &res[i..],
+                    i,
+                    i + 1,
+                    i,
+                    w[0],
+                    i + 1,
+                    w[1]
+                )
+            }
+        }
+    };
+}
+
+/// Asserts that the values in `$vec` are monotonically falling. Increases of up to `0.000001`
+/// are tolerated, to allow for floating point noise.
+///
+///```
+/// use synfx_dsp::assert_monotonic_falling;
+///
+/// assert_monotonic_falling!(vec![1.0, 0.5, 0.5, 0.1, 0.0]);
+///```
+#[macro_export]
+macro_rules! assert_monotonic_falling {
+    ($vec:expr) => {
+        let res: Vec<f32> = $vec.iter().copied().collect();
+
+        for (i, w) in res.windows(2).enumerate() {
+            if w[1] > w[0] + 0.000001 {
+                panic!(
+                    r#"
+table: {:?}
+
+assertion failed: monotonic falling `(left[{}] >= left[{}])`
+      left[{}]: `{:?}`,
+      left[{}]: `{:?}`"#,
+                    &res[i..],
+                    i,
+                    i + 1,
+                    i,
+                    w[0],
+                    i + 1,
+                    w[1]
+                )
+            }
+        }
+    };
+}
+
+/// Asserts that all values in `$vec` are within `$min` and `$max` (inclusive).
+///
+///```
+/// use synfx_dsp::assert_in_range;
+///
+/// assert_in_range!(vec![0.0, 0.5, -0.5, 1.0], -1.0, 1.0);
+///```
+#[macro_export]
+macro_rules! assert_in_range {
+    ($vec:expr, $min:expr, $max:expr) => {
+        let res: Vec<f32> = $vec.iter().copied().collect();
+        let min: f32 = $min;
+        let max: f32 = $max;
+
+        for (i, s) in res.iter().enumerate() {
+            if *s < min || *s > max || s.is_nan() {
+                panic!(
+                    r#"
+table: {:?}
+
+assertion failed: `({:?} <= left[{}] <= {:?})`
+      left[{}]: `{:?}`"#,
+                    &res[i..],
+                    min,
+                    i,
+                    max,
+                    i,
+                    s
+                )
+            }
+        }
+    };
+}
+
+/// Calculates the magnitude spectrum of `buf` with a Hann window, for use in tests.
+///
+/// Only the first `2^k` samples, that fit into `buf`, are analyzed. The returned vector
+/// contains the `2^(k-1) + 1` bins from DC up to the Nyquist frequency, scaled so that
+/// a sine with an amplitude of 1.0 has a peak magnitude of about 1.0.
+/// Bin `i` is at the frequency `i * sample_rate / 2^k`.
+///
+///```
+/// use synfx_dsp::fft_magnitudes;
+///
+/// // A sine at bin 16 of a 1024 point FFT:
+/// let sine: Vec<f32> =
+///     (0..1024).map(|i| 0.5 * (i as f32 * std::f32::consts::TAU * 16.0 / 1024.0).sin()).collect();
+/// let mags = fft_magnitudes(&sine);
+///
+/// assert_eq!(mags.len(), 513);
+/// assert!((mags[16] - 0.5).abs() < 0.001);
+/// assert!(mags[40] < 0.0001);
+///```
+pub fn fft_magnitudes(buf: &[f32]) -> Vec<f32> {
+    if buf.len() < 2 {
+        return vec![];
+    }
+
+    let n = 1 << (usize::BITS - 1 - buf.len().leading_zeros());
+
+    let mut re: Vec<f64> = buf[0..n]
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let w = 0.5 - 0.5 * (std::f64::consts::TAU * (i as f64) / (n as f64)).cos();
+            (*s as f64) * w
+        })
+        .collect();
+    let mut im = vec![0.0; n];
+
+    Fft::new(n).transform(&mut re[..], &mut im[..], false);
+
+    // The Hann window has a coherent gain of 0.5:
+    let scale = 4.0 / (n as f64);
+    (0..=(n / 2)).map(|i| ((re[i] * re[i] + im[i] * im[i]).sqrt() * scale) as f32).collect()
+}
+
+/// Returns the level in dB (relative to a sine with an amplitude of 1.0) of the
+/// frequency `freq` in the magnitudes `mags` returned by [fft_magnitudes].
+///
+/// The strongest bin next to `freq` is used, and if it is a local maximum, the level
+/// is refined by parabolic interpolation, to compensate for frequencies between two bins.
+pub fn spectrum_level_db(mags: &[f32], sample_rate: f32, freq: f32) -> f32 {
+    if mags.len() < 2 {
+        return -200.0;
+    }
+
+    let to_db = |m: f32| 20.0 * m.max(1e-10).log10();
+    let bin_hz = sample_rate / (2.0 * (mags.len() - 1) as f32);
+    let center = ((freq / bin_hz).round() as usize).min(mags.len() - 1);
+
+    let lo = center.saturating_sub(1);
+    let hi = (center + 1).min(mags.len() - 1);
+    let peak = (lo..=hi).max_by(|a, b| mags[*a].total_cmp(&mags[*b])).unwrap_or(center);
+
+    if peak == 0 || peak == mags.len() - 1 {
+        return to_db(mags[peak]);
+    }
+
+    let (a, b, c) = (to_db(mags[peak - 1]), to_db(mags[peak]), to_db(mags[peak + 1]));
+    if b < a || b < c {
+        return b;
+    }
+
+    let denom = a - 2.0 * b + c;
+    if denom.abs() < 1e-9 {
+        return b;
+    }
+    let p = 0.5 * (a - c) / denom;
+    b - 0.25 * (a - c) * p
+}
+
+/// Calculates the spectrum (see [fft_magnitudes]) of `$vec`, sampled with the sample rate
+/// `$srate`, and asserts that its strongest frequency (ignoring DC) is within `$tol_hz`
+/// of `$freq`.
+///
+///```
+/// use synfx_dsp::assert_fft_peak_at;
+///
+/// let sine: Vec<f32> =
+///     (0..4096).map(|i| (i as f32 * std::f32::consts::TAU * 440.0 / 44100.0).sin()).collect();
+/// assert_fft_peak_at!(sine, 44100.0, 440.0, 11.0);
+///```
+#[macro_export]
+macro_rules! assert_fft_peak_at {
+    ($vec:expr, $srate:expr, $freq:expr, $tol_hz:expr) => {
+        let res: Vec<f32> = $vec.iter().copied().collect();
+        let srate: f32 = $srate;
+        let freq: f32 = $freq;
+        let tol_hz: f32 = $tol_hz;
+
+        let mags = $crate::fft_magnitudes(&res[..]);
+        let bin_hz = srate / (2.0 * (mags.len().max(2) - 1) as f32);
+        let peak = (1..mags.len()).max_by(|a, b| mags[*a].total_cmp(&mags[*b])).unwrap_or(0);
+        let peak_hz = peak as f32 * bin_hz;
+
+        if (peak_hz - freq).abs() > tol_hz {
+            panic!(
+                r#"assertion failed: `(fft_peak(left) == right)` with tolerance {:?}Hz
+  fft_peak(left): `{:?}Hz` ({:?}dB),
+           right: `{:?}Hz`"#,
+                tol_hz,
+                peak_hz,
+                20.0 * mags[peak].max(1e-10).log10(),
+                freq
+            )
+        }
+    };
+}
+
+/// Calculates the spectrum (see [fft_magnitudes]) of `$vec`, sampled with the sample rate
+/// `$srate`, and compares the levels at the frequencies in `$expected`, a list of
+/// `(frequency, level in dB)` pairs, with a precision of `$tol_db`.
+/// The levels are measured with [spectrum_level_db].
+///
+/// Useful for asserting on the harmonic content of oscillators or the attenuation of filters.
+/// To assert that there is no content at a frequency, like aliasing, expect a very low level
+/// with a large tolerance, eg. `(freq, -200.0)` with a tolerance of `140.0` allows up to -60dB.
+///
+///```
+/// use synfx_dsp::assert_spectrum_feq;
+///
+/// // 1kHz at -6dB plus its 3rd harmonic at -20dB:
+/// let sig: Vec<f32> = (0..8192)
+///     .map(|i| {
+///         let phase = i as f32 * std::f32::consts::TAU * 1000.0 / 44100.0;
+///         0.5 * phase.sin() + 0.1 * (3.0 * phase).sin()
+///     })
+///     .collect();
+///
+/// assert_spectrum_feq!(sig, 44100.0, [(1000.0, -6.02), (3000.0, -20.0)], 0.2);
+/// // Nothing at the 2nd harmonic:
+/// assert_spectrum_feq!(sig, 44100.0, [(2000.0, -200.0)], 140.0);
+///```
+#[macro_export]
+macro_rules! assert_spectrum_feq {
+    ($vec:expr, $srate:expr, $expected:expr, $tol_db:expr) => {
+        let res: Vec<f32> = $vec.iter().copied().collect();
+        let srate: f32 = $srate;
+        let tol_db: f32 = $tol_db;
+
+        let mags = $crate::fft_magnitudes(&res[..]);
+        for (freq, expected_db) in $expected.iter().copied() {
+            let (freq, expected_db): (f32, f32) = (freq, expected_db);
+            let level_db = $crate::spectrum_level_db(&mags[..], srate, freq);
+
+            if (level_db - expected_db).abs() > tol_db {
+                panic!(
+                    r#"assertion failed: `(level_at(left, {:?}Hz) == right)` with tolerance {:?}dB
+  level_at(left): `{:?}dB`,
+           right: `{:?}dB`"#,
+                    freq, tol_db, level_db, expected_db
+                )
+            }
+        }
+    };
+}
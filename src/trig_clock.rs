@@ -16,42 +16,156 @@ pub const TRIG_LOW_THRES: f32 = 0.25;
 /// a logical '1'. Anything below this is a logical '0'.
 pub const TRIG_HIGH_THRES: f32 = 0.5;
 
+/// How a [GateSignal] reacts to a trigger while its gate is still open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateRetrigger {
+    /// The gate closes for one sample and opens again with the full length,
+    /// so that envelopes further down the signal chain are retriggered.
+    Restart,
+    /// The gate stays open and ends the full length after the new trigger, like legato.
+    Extend,
+    /// The trigger is ignored, the gate ends as if it never happened.
+    Ignore,
+}
+
 /// Gate signal generator for HexoDSP nodes.
 ///
 /// This generator generates a gate signal when [GateSignal::trigger] is called.
-/// The length is given as parameter to [GateSignal::next].
+/// The length is either given as parameter to [GateSignal::next], or set with
+/// [GateSignal::set_length_ms] or [GateSignal::set_length_fraction] for
+/// [GateSignal::process], which also outputs a trigger at the end of the gate.
+///
+/// What happens on a trigger while the gate is still open is set by [GateRetrigger],
+/// the default is [GateRetrigger::Extend].
+///
+///```
+/// use synfx_dsp::{GateSignal, GateRetrigger};
+///
+/// let mut gs = GateSignal::new();
+/// gs.set_sample_rate(1000.0);
+/// gs.set_length_ms(5.0);
+///
+/// gs.trigger();
+/// let out: Vec<(f32, f32)> = (0..10).map(|_| gs.process()).collect();
+/// let gate: Vec<f32> = out.iter().map(|o| o.0).collect();
+/// let end: Vec<f32> = out.iter().map(|o| o.1).collect();
+/// assert_eq!(gate, vec![1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+/// // The end of gate trigger is 2 milliseconds long:
+/// assert_eq!(end, vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+///
+/// // Restart closes the gate for one sample:
+/// gs.set_retrigger(GateRetrigger::Restart);
+/// gs.trigger();
+/// gs.process();
+/// gs.process();
+/// gs.trigger();
+/// let gate: Vec<f32> = (0..8).map(|_| gs.process().0).collect();
+/// assert_eq!(gate, vec![0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0]);
+///
+/// // Ignore lets the first gate end in time:
+/// gs.set_retrigger(GateRetrigger::Ignore);
+/// gs.trigger();
+/// gs.process();
+/// gs.process();
+/// gs.trigger();
+/// let gate: Vec<f32> = (0..6).map(|_| gs.process().0).collect();
+/// assert_eq!(gate, vec![1.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+///
+/// // Half of a clock period of 20 samples:
+/// gs.set_length_fraction(0.5, 20);
+/// gs.trigger();
+/// let len = (0..30).filter(|_| gs.process().0 > 0.5).count();
+/// assert_eq!(len, 11);
+///```
 #[derive(Debug, Clone, Copy)]
 pub struct GateSignal {
     ms_per_sample: f32,
     ms_count: f32,
+    length_ms: f32,
+    retrigger: GateRetrigger,
+    /// Whether the gate closes for one sample, before it opens again.
+    restart: bool,
+    last_gate: f32,
+    end_trig: TrigSignal,
 }
 
 impl GateSignal {
     /// Create a new gate generator
     pub fn new() -> Self {
-        Self { ms_per_sample: 1000.0 / 44100.0, ms_count: 0.0 }
+        Self {
+            ms_per_sample: 1000.0 / 44100.0,
+            ms_count: 0.0,
+            length_ms: TRIG_SIGNAL_LENGTH_MS,
+            retrigger: GateRetrigger::Extend,
+            restart: false,
+            last_gate: 0.0,
+            end_trig: TrigSignal::new(),
+        }
     }
 
     /// Reset the gate generator.
     pub fn reset(&mut self) {
         self.ms_count = 0.0;
+        self.restart = false;
+        self.last_gate = 0.0;
+        self.end_trig.reset();
     }
 
     /// Set the sample rate
     pub fn set_sample_rate(&mut self, srate: f32) {
         self.ms_per_sample = 1000.0 / srate;
+        self.end_trig.set_sample_rate(srate);
+    }
+
+    /// Sets the gate length in milliseconds for [GateSignal::process].
+    pub fn set_length_ms(&mut self, length_ms: f32) {
+        self.length_ms = length_ms.max(0.0);
+    }
+
+    /// Sets the gate length for [GateSignal::process] as `fraction` of a clock period
+    /// of `period_samples`, as measured by [TriggerSampleClock] for instance.
+    /// Set the sample rate first, the length is converted to milliseconds.
+    pub fn set_length_fraction(&mut self, fraction: f32, period_samples: u32) {
+        self.set_length_ms(fraction * period_samples as f32 * self.ms_per_sample);
+    }
+
+    /// Sets how a trigger while the gate is open is handled.
+    pub fn set_retrigger(&mut self, retrigger: GateRetrigger) {
+        self.retrigger = retrigger;
+    }
+
+    /// Returns true while the gate is open.
+    pub fn is_open(&self) -> bool {
+        self.ms_count > 0.0
     }
 
-    /// Start a new gate the next time [TrigSignal::next] is called.
+    /// Start a new gate the next time [GateSignal::next] or [GateSignal::process]
+    /// is called.
     #[inline]
     pub fn trigger(&mut self) {
-        self.ms_count = 0.0001;
+        match self.retrigger {
+            GateRetrigger::Restart => {
+                self.restart = self.ms_count > 0.0;
+                self.ms_count = 0.0001;
+            }
+            GateRetrigger::Extend => {
+                self.ms_count = 0.0001;
+            }
+            GateRetrigger::Ignore => {
+                if self.ms_count <= 0.0 {
+                    self.ms_count = 0.0001;
+                }
+            }
+        }
     }
 
     /// Gate signal output, the length is given via 'length_ms'.
     #[inline]
     pub fn next(&mut self, length_ms: f32) -> f32 {
-        if self.ms_count > 0.0 {
+        if self.restart {
+            self.restart = false;
+            0.0
+        } else if self.ms_count > 0.0 {
             self.ms_count += self.ms_per_sample;
             if (self.ms_count - 0.0001) > length_ms {
                 self.ms_count = 0.0;
@@ -61,6 +175,21 @@ impl GateSignal {
             0.0
         }
     }
+
+    /// Gate signal output with the length set by [GateSignal::set_length_ms]
+    /// or [GateSignal::set_length_fraction].
+    ///
+    /// Returns the gate and the trigger signal, which starts when the gate closes.
+    #[inline]
+    pub fn process(&mut self) -> (f32, f32) {
+        let gate = self.next(self.length_ms);
+        if self.last_gate > 0.5 && gate < 0.5 {
+            self.end_trig.trigger();
+        }
+        self.last_gate = gate;
+
+        (gate, self.end_trig.next())
+    }
 }
 
 impl Default for GateSignal {
@@ -71,18 +200,40 @@ impl Default for GateSignal {
 
 /// Trigger signal generator for HexoDSP nodes.
 ///
-/// A trigger in HexoSynth and HexoDSP is commonly 2.0 milliseconds.
+/// A trigger in HexoSynth and HexoDSP is commonly 2.0 milliseconds ([TRIG_SIGNAL_LENGTH_MS]).
+/// Hardware that needs longer pulses can use a different length,
+/// see [TrigSignal::new_with_length_ms].
 /// This generator generates a trigger signal when [TrigSignal::trigger] is called.
+///
+///```
+/// use synfx_dsp::TrigSignal;
+///
+/// let mut ts = TrigSignal::new_with_length_ms(10.0);
+/// ts.set_sample_rate(1000.0);
+///
+/// ts.trigger();
+/// let pulse: Vec<f32> = (0..12).map(|_| ts.next()).collect();
+/// assert_eq!(pulse.iter().sum::<f32>(), 10.0);
+///```
 #[derive(Debug, Clone, Copy)]
 pub struct TrigSignal {
     length: u32,
     scount: u32,
+    length_ms: f32,
+    srate: f32,
 }
 
 impl TrigSignal {
-    /// Create a new trigger generator
+    /// Create a new trigger generator, with triggers of [TRIG_SIGNAL_LENGTH_MS].
     pub fn new() -> Self {
-        Self { length: ((44100.0 * TRIG_SIGNAL_LENGTH_MS) / 1000.0).ceil() as u32, scount: 0 }
+        Self::new_with_length_ms(TRIG_SIGNAL_LENGTH_MS)
+    }
+
+    /// Create a new trigger generator, with triggers of `length_ms` milliseconds.
+    pub fn new_with_length_ms(length_ms: f32) -> Self {
+        let mut this = Self { length: 0, scount: 0, length_ms, srate: 44100.0 };
+        this.set_length_ms(length_ms);
+        this
     }
 
     /// Reset the trigger generator.
@@ -92,10 +243,24 @@ impl TrigSignal {
 
     /// Set the sample rate to calculate the amount of samples for the trigger signal.
     pub fn set_sample_rate(&mut self, srate: f32) {
-        self.length = ((srate * TRIG_SIGNAL_LENGTH_MS) / 1000.0).ceil() as u32;
+        self.srate = srate;
+        self.length = ((srate * self.length_ms) / 1000.0).ceil() as u32;
         self.scount = 0;
     }
 
+    /// Sets the length of the triggers in milliseconds. A trigger that is currently
+    /// being sent is shortened, if it would be longer than the new length.
+    pub fn set_length_ms(&mut self, length_ms: f32) {
+        self.length_ms = length_ms.max(0.0);
+        self.length = ((self.srate * self.length_ms) / 1000.0).ceil() as u32;
+        self.scount = self.scount.min(self.length);
+    }
+
+    /// Returns the length of the triggers in milliseconds.
+    pub fn length_ms(&self) -> f32 {
+        self.length_ms
+    }
+
     /// Enable sending a trigger impulse the next time [TrigSignal::next] is called.
     #[inline]
     pub fn trigger(&mut self) {
@@ -132,12 +297,22 @@ pub struct ChangeTrig {
 impl ChangeTrig {
     /// Create a new change detector
     pub fn new() -> Self {
+        Self::new_with_length_ms(TRIG_SIGNAL_LENGTH_MS)
+    }
+
+    /// Create a new change detector, that emits triggers of `length_ms` milliseconds.
+    pub fn new_with_length_ms(length_ms: f32) -> Self {
         Self {
-            ts: TrigSignal::new(),
+            ts: TrigSignal::new_with_length_ms(length_ms),
             last: -100.0, // some random value :-)
         }
     }
 
+    /// Sets the length of the emitted triggers in milliseconds.
+    pub fn set_length_ms(&mut self, length_ms: f32) {
+        self.ts.set_length_ms(length_ms);
+    }
+
     /// Reset internal state.
     pub fn reset(&mut self) {
         self.ts.reset();
@@ -169,6 +344,325 @@ impl Default for ChangeTrig {
     }
 }
 
+/// Pitch change detector that emits a trigger when a pitch CV moves to a new note.
+///
+/// The input is interpreted as 0.1 V/oct pitch CV (0.1 per octave, like in HexoDSP).
+/// A trigger is only emitted, when the input moved away by more than the interval
+/// from the pitch of the previous trigger. That way new notes retrigger envelopes,
+/// but vibrato or slow drift does not.
+///
+///```
+/// use synfx_dsp::PitchChangeTrig;
+///
+/// let semitone = 0.1 / 12.0;
+///
+/// let mut pct = PitchChangeTrig::new();
+/// pct.set_sample_rate(1000.0);
+/// pct.set_interval_semitones(0.5);
+///
+/// // The first input is a new note:
+/// assert_eq!(pct.next(0.0), 1.0);
+/// for _ in 0..10 {
+///     pct.next(0.0);
+/// }
+///
+/// // Vibrato does not trigger:
+/// for i in 0..100 {
+///     assert_eq!(pct.next((i as f32 * 0.3).sin() * 0.3 * semitone), 0.0);
+/// }
+///
+/// // But the next note does:
+/// assert_eq!(pct.next(2.0 * semitone), 1.0);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct PitchChangeTrig {
+    ts: TrigSignal,
+    last: f32,
+    interval: f32,
+}
+
+impl PitchChangeTrig {
+    /// Create a new pitch change detector with an interval of 0.5 semitones.
+    pub fn new() -> Self {
+        Self { ts: TrigSignal::new(), last: -100.0, interval: 0.5 * 0.1 / 12.0 }
+    }
+
+    /// Reset internal state.
+    pub fn reset(&mut self) {
+        self.ts.reset();
+        self.last = -100.0;
+    }
+
+    /// Set the sample rate for the trigger signal generator
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.ts.set_sample_rate(srate);
+    }
+
+    /// Sets the interval in semitones, the pitch has to move by more than this to emit a trigger.
+    pub fn set_interval_semitones(&mut self, semitones: f32) {
+        self.interval = semitones.abs() * 0.1 / 12.0;
+    }
+
+    /// Feed a new pitch CV sample.
+    ///
+    /// The return value is the trigger signal.
+    #[inline]
+    pub fn next(&mut self, pitch_cv: f32) -> f32 {
+        if (pitch_cv - self.last).abs() > self.interval {
+            self.ts.trigger();
+            self.last = pitch_cv;
+        }
+
+        self.ts.next()
+    }
+}
+
+impl Default for PitchChangeTrig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The crossing direction that [LevelCrossTrig] reacts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossDirection {
+    /// Trigger when the signal rises above the level.
+    Rising,
+    /// Trigger when the signal falls below the level.
+    Falling,
+    /// Trigger on both crossings.
+    Both,
+}
+
+/// Emits a trigger when a signal crosses a level, for instance to fire a second envelope
+/// when the first one passes 50%.
+///
+/// To prevent chatter from noisy or slowly moving signals, the signal has to cross
+/// the level by half the hysteresis, before the crossing counts.
+/// The first input sample only initializes the state and never triggers.
+///
+///```
+/// use synfx_dsp::{LevelCrossTrig, CrossDirection};
+///
+/// let mut lct = LevelCrossTrig::new();
+/// lct.set_sample_rate(1000.0);
+/// lct.set_level(0.5);
+/// lct.set_direction(CrossDirection::Rising);
+///
+/// let ramp: Vec<f32> = (0..=10).map(|i| lct.next(i as f32 * 0.1)).collect();
+/// // Triggers when the ramp passes 0.5 plus half the hysteresis:
+/// assert_eq!(ramp[5], 0.0);
+/// assert_eq!(ramp[6], 1.0);
+///
+/// // Chatter around the level does not trigger again:
+/// lct.reset();
+/// lct.next(0.0);
+/// assert_eq!(lct.next(0.6), 1.0);
+/// for _ in 0..10 {
+///     lct.next(0.0);
+/// }
+/// let chatter: Vec<f32> = (0..20).map(|i| lct.next(if i % 2 == 0 { 0.498 } else { 0.502 })).collect();
+/// assert!(chatter.iter().all(|t| *t == 0.0));
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct LevelCrossTrig {
+    ts: TrigSignal,
+    level: f32,
+    hysteresis: f32,
+    direction: CrossDirection,
+    /// Whether the signal is above the level, `None` before the first sample.
+    above: Option<bool>,
+}
+
+impl LevelCrossTrig {
+    /// Create a new level crossing detector, triggering on rising crossings
+    /// of 0.5 with a hysteresis of 0.01.
+    pub fn new() -> Self {
+        Self {
+            ts: TrigSignal::new(),
+            level: 0.5,
+            hysteresis: 0.01,
+            direction: CrossDirection::Rising,
+            above: None,
+        }
+    }
+
+    /// Reset internal state.
+    pub fn reset(&mut self) {
+        self.ts.reset();
+        self.above = None;
+    }
+
+    /// Set the sample rate for the trigger signal generator
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.ts.set_sample_rate(srate);
+    }
+
+    /// Sets the level the signal has to cross.
+    pub fn set_level(&mut self, level: f32) {
+        self.level = level;
+    }
+
+    /// Sets the width of the hysteresis band around the level.
+    pub fn set_hysteresis(&mut self, hysteresis: f32) {
+        self.hysteresis = hysteresis.abs();
+    }
+
+    /// Sets the crossing direction that emits triggers.
+    pub fn set_direction(&mut self, direction: CrossDirection) {
+        self.direction = direction;
+    }
+
+    /// Feed a new input sample.
+    ///
+    /// The return value is the trigger signal.
+    #[inline]
+    pub fn next(&mut self, input: f32) -> f32 {
+        let half = self.hysteresis * 0.5;
+
+        match self.above {
+            None => {
+                self.above = Some(input > self.level);
+            }
+            Some(false) if input > self.level + half => {
+                self.above = Some(true);
+                if self.direction != CrossDirection::Falling {
+                    self.ts.trigger();
+                }
+            }
+            Some(true) if input < self.level - half => {
+                self.above = Some(false);
+                if self.direction != CrossDirection::Rising {
+                    self.ts.trigger();
+                }
+            }
+            _ => (),
+        }
+
+        self.ts.next()
+    }
+}
+
+impl Default for LevelCrossTrig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generates a burst of evenly spaced triggers (ratchets), for instance for drum rolls.
+///
+/// The first trigger of a burst is emitted immediately, the following ones are spread over
+/// the duration, so that the burst lasts exactly as long as the duration, including the gap
+/// after the last trigger. Calling [RatchetGenerator::trigger] during a burst restarts it.
+/// Triggers closer than [TRIG_SIGNAL_LENGTH_MS] merge into one long pulse.
+///
+///```
+/// use synfx_dsp::RatchetGenerator;
+///
+/// let mut ratchet = RatchetGenerator::new();
+/// ratchet.set_sample_rate(1000.0);
+///
+/// // 4 triggers within 100ms:
+/// ratchet.trigger(4, 100.0);
+/// let out: Vec<f32> = (0..200).map(|_| ratchet.next()).collect();
+///
+/// let rising_edges: Vec<usize> = (0..out.len())
+///     .filter(|i| out[*i] > 0.5 && (*i == 0 || out[*i - 1] < 0.5))
+///     .collect();
+/// assert_eq!(rising_edges, vec![0, 25, 50, 75]);
+///
+/// // A new burst overrides the running one:
+/// ratchet.trigger(4, 100.0);
+/// ratchet.next();
+/// ratchet.next();
+/// ratchet.next();
+/// ratchet.trigger(2, 10.0);
+/// let out: Vec<f32> = (0..20).map(|_| ratchet.next()).collect();
+/// assert_eq!(&out[0..8], &[1.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+/// assert!(out[8..].iter().all(|t| *t == 0.0));
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct RatchetGenerator {
+    ts: TrigSignal,
+    srate: f32,
+    /// Number of triggers still to emit in the current burst.
+    remaining: u32,
+    /// Index of the next trigger in the current burst.
+    index: u32,
+    /// Distance between two triggers in samples.
+    interval: f64,
+    /// Samples since the start of the current burst.
+    elapsed: u64,
+}
+
+impl RatchetGenerator {
+    /// Create a new, idle, ratchet generator.
+    pub fn new() -> Self {
+        Self {
+            ts: TrigSignal::new(),
+            srate: 44100.0,
+            remaining: 0,
+            index: 0,
+            interval: 0.0,
+            elapsed: 0,
+        }
+    }
+
+    /// Reset internal state, stops any running burst.
+    pub fn reset(&mut self) {
+        self.ts.reset();
+        self.remaining = 0;
+        self.index = 0;
+        self.elapsed = 0;
+    }
+
+    /// Set the sample rate for the burst timing and the trigger signal generator.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.ts.set_sample_rate(srate);
+    }
+
+    /// Starts a burst of `count` triggers over `duration_ms` milliseconds.
+    /// A running burst is stopped, a `count` of 0 just stops it.
+    pub fn trigger(&mut self, count: u32, duration_ms: f32) {
+        self.remaining = count;
+        self.index = 0;
+        self.elapsed = 0;
+        self.interval = if count > 0 {
+            (duration_ms.max(0.0) as f64 * self.srate as f64) / (1000.0 * count as f64)
+        } else {
+            0.0
+        };
+    }
+
+    /// Returns true while a burst is running.
+    pub fn is_running(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Trigger signal output.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        if self.remaining > 0 {
+            let next_at = (self.index as f64 * self.interval).round() as u64;
+            if self.elapsed >= next_at {
+                self.ts.trigger();
+                self.index += 1;
+                self.remaining -= 1;
+            }
+            self.elapsed += 1;
+        }
+
+        self.ts.next()
+    }
+}
+
+impl Default for RatchetGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Trigger signal detector for HexoDSP.
 ///
 /// Whenever you need to detect a trigger on an input you can use this component.
@@ -210,6 +704,104 @@ impl Trigger {
     }
 }
 
+/// Trigger signal detector with retrigger suppression.
+///
+/// Works like [Trigger], and thereby fires at most once per rising edge. Additionally
+/// rising edges that occur within a minimum gap after the last fired trigger are ignored.
+/// This prevents ringing or very fast trigger streams (eg. drum rolls) from double firing.
+///
+///```
+/// use synfx_dsp::MinGapTrigger;
+///
+/// let mut trig = MinGapTrigger::new();
+/// trig.set_sample_rate(1000.0);
+/// trig.set_min_gap_ms(5.0);
+///
+/// assert!(trig.check_trigger(1.0));
+/// assert!(!trig.check_trigger(0.0));
+/// // Within the 5 samples gap:
+/// assert!(!trig.check_trigger(1.0));
+/// assert!(!trig.check_trigger(0.0));
+/// assert!(!trig.check_trigger(0.0));
+/// // Gap is over:
+/// assert!(trig.check_trigger(1.0));
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct MinGapTrigger {
+    triggered: bool,
+    srate_ms: f32,
+    min_gap_ms: f32,
+    min_gap: u32,
+    since_last: u32,
+}
+
+impl MinGapTrigger {
+    /// Create a new trigger detector without any minimum gap.
+    pub fn new() -> Self {
+        Self {
+            triggered: false,
+            srate_ms: 44100.0 / 1000.0,
+            min_gap_ms: 0.0,
+            min_gap: 0,
+            since_last: u32::MAX,
+        }
+    }
+
+    /// Set the sample rate to calculate the amount of samples for the minimum gap.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate_ms = srate / 1000.0;
+        self.set_min_gap_ms(self.min_gap_ms);
+    }
+
+    /// Set the minimum time in milliseconds between two fired triggers.
+    #[inline]
+    pub fn set_min_gap_ms(&mut self, min_gap_ms: f32) {
+        self.min_gap_ms = min_gap_ms.max(0.0);
+        self.min_gap = (self.min_gap_ms * self.srate_ms).round() as u32;
+    }
+
+    /// Reset the internal state of the trigger detector.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.triggered = false;
+        self.since_last = u32::MAX;
+    }
+
+    /// Checks the input signal for a trigger and returns true when the signal
+    /// surpassed [TRIG_HIGH_THRES], has not fallen below [TRIG_LOW_THRES] yet
+    /// and the minimum gap since the last fired trigger has passed.
+    #[inline]
+    pub fn check_trigger(&mut self, input: f32) -> bool {
+        self.since_last = self.since_last.saturating_add(1);
+
+        if self.triggered {
+            if input <= TRIG_LOW_THRES {
+                self.triggered = false;
+            }
+
+            false
+        } else if input > TRIG_HIGH_THRES {
+            // The edge is consumed, even if it is suppressed:
+            self.triggered = true;
+
+            if self.since_last >= self.min_gap {
+                self.since_last = 0;
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+}
+
+impl Default for MinGapTrigger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Trigger signal detector with custom range.
 ///
 /// Whenever you need to detect a trigger with a custom threshold.
@@ -257,6 +849,94 @@ impl CustomTrigger {
     }
 }
 
+/// The result of [Schmitt::process] for one input sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SchmittState {
+    /// The signal went high with this sample.
+    pub rising: bool,
+    /// The signal went low with this sample.
+    pub falling: bool,
+    /// The current logical state of the signal.
+    pub high: bool,
+}
+
+/// Schmitt trigger, which turns a signal into a gate with a configurable hysteresis.
+///
+/// Unlike [CustomTrigger] it reports the current logical state and falling edges too,
+/// which is useful for gate based use cases, like detecting the sustain phase of a gate.
+/// The default thresholds are [TRIG_LOW_THRES] and [TRIG_HIGH_THRES].
+///
+///```
+/// use synfx_dsp::{Schmitt, SchmittState};
+///
+/// let mut gate = Schmitt::new(0.25, 0.5);
+///
+/// let st = gate.process(0.6);
+/// assert_eq!(st, SchmittState { rising: true, falling: false, high: true });
+/// // Within the hysteresis the state stays high:
+/// let st = gate.process(0.3);
+/// assert_eq!(st, SchmittState { rising: false, falling: false, high: true });
+/// let st = gate.process(0.1);
+/// assert_eq!(st, SchmittState { rising: false, falling: true, high: false });
+/// assert!(!gate.is_high());
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct Schmitt {
+    high: bool,
+    low_thres: f32,
+    high_thres: f32,
+}
+
+impl Schmitt {
+    /// Create a new Schmitt trigger, the signal goes high when it surpasses `high_thres`
+    /// and low when it falls to or below `low_thres`.
+    pub fn new(low_thres: f32, high_thres: f32) -> Self {
+        Self { high: false, low_thres, high_thres }
+    }
+
+    pub fn set_threshold(&mut self, low_thres: f32, high_thres: f32) {
+        self.low_thres = low_thres;
+        self.high_thres = high_thres;
+    }
+
+    /// Reset the internal state to low.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.high = false;
+    }
+
+    /// Returns the current logical state.
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.high
+    }
+
+    /// Processes the next input sample and returns the edges and the state.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> SchmittState {
+        let mut state = SchmittState::default();
+
+        if self.high {
+            if input <= self.low_thres {
+                self.high = false;
+                state.falling = true;
+            }
+        } else if input > self.high_thres {
+            self.high = true;
+            state.rising = true;
+        }
+
+        state.high = self.high;
+        state
+    }
+}
+
+impl Default for Schmitt {
+    fn default() -> Self {
+        Self::new(TRIG_LOW_THRES, TRIG_HIGH_THRES)
+    }
+}
+
 /// Generates a phase signal from a trigger/gate input signal.
 ///
 /// This helper allows you to measure the distance between trigger or gate pulses
@@ -290,6 +970,18 @@ impl TriggerPhaseClock {
         self.clock_phase = 0.0;
     }
 
+    /// Returns the measured distance between the last two triggers in samples,
+    /// or `None` if there were not two triggers yet.
+    /// This can be used to sync an [crate::Lfo] to the clock.
+    #[inline]
+    pub fn period_samples<F: crate::Flt>(&self) -> Option<F> {
+        if self.clock_inc > 0.0 {
+            F::from_f64(1.0 / self.clock_inc)
+        } else {
+            None
+        }
+    }
+
     /// Generate the phase signal of this clock.
     ///
     /// * `clock_limit` - The maximum number of samples to detect two trigger signals in.
@@ -354,3 +1046,611 @@ impl TriggerSampleClock {
         self.clock_samples
     }
 }
+
+/// Divides or multiplies an incoming clock signal.
+///
+/// With a division of N every Nth input trigger starts an output group. Within a group
+/// the multiplication number of triggers is spread evenly over the measured length of the
+/// group. The length is measured from the distance between the last two input triggers,
+/// so the multiplied triggers follow tempo changes after one input period. Until the
+/// second input trigger only the first trigger of each group is emitted.
+///
+/// The swing delays every second output trigger (the off-beats) by a fraction of the
+/// distance between two output triggers.
+///
+///```
+/// use synfx_dsp::ClockDivider;
+///
+/// // A clock that triggers every 100 samples:
+/// let clock = |i: usize| if i % 100 < 5 { 1.0 } else { 0.0 };
+/// let rising_edges = |out: &[f32]| -> Vec<usize> {
+///     (0..out.len()).filter(|i| out[*i] > 0.5 && (*i == 0 || out[*i - 1] < 0.5)).collect()
+/// };
+///
+/// let mut div = ClockDivider::new();
+/// div.set_sample_rate(1000.0);
+/// div.set_division(2);
+/// let out: Vec<f32> = (0..500).map(|i| div.next(clock(i))).collect();
+/// assert_eq!(rising_edges(&out), vec![0, 200, 400]);
+///
+/// // Double the clock, with the off-beats delayed by half the output interval:
+/// let mut mul = ClockDivider::new();
+/// mul.set_sample_rate(1000.0);
+/// mul.set_multiplication(2);
+/// mul.set_swing(0.5);
+/// let out: Vec<f32> = (0..300).map(|i| mul.next(clock(i))).collect();
+/// assert_eq!(rising_edges(&out), vec![0, 100, 175, 200, 275]);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct ClockDivider {
+    trig: Trigger,
+    ts: TrigSignal,
+    division: u32,
+    multiplication: u32,
+    swing: f32,
+    /// Samples since the last input trigger, `None` before the first one.
+    counter: Option<u64>,
+    /// Measured distance between the last two input triggers in samples, 0 if unknown.
+    period: u64,
+    /// Number of input triggers since the start of the current group.
+    in_count: u32,
+    /// Toggles with every group, to determine the off-beats for odd multiplications.
+    odd_group: bool,
+    /// Samples since the start of the current group.
+    elapsed: u64,
+    /// Index of the next output trigger within the group.
+    index: u32,
+    /// Number of output triggers in the current group.
+    group_len: u32,
+}
+
+impl ClockDivider {
+    /// Create a new clock divider, that passes the clock through unchanged.
+    pub fn new() -> Self {
+        Self {
+            trig: Trigger::new(),
+            ts: TrigSignal::new(),
+            division: 1,
+            multiplication: 1,
+            swing: 0.0,
+            counter: None,
+            period: 0,
+            in_count: 0,
+            odd_group: true,
+            elapsed: 0,
+            index: 0,
+            group_len: 0,
+        }
+    }
+
+    /// Reset internal state, the next input trigger starts a new group.
+    pub fn reset(&mut self) {
+        self.trig.reset();
+        self.ts.reset();
+        self.counter = None;
+        self.period = 0;
+        self.in_count = 0;
+        self.odd_group = true;
+        self.elapsed = 0;
+        self.index = 0;
+        self.group_len = 0;
+    }
+
+    /// Set the sample rate for the trigger signal generator.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.ts.set_sample_rate(srate);
+    }
+
+    /// Sets the number of input triggers per output group, at least 1.
+    pub fn set_division(&mut self, division: u32) {
+        self.division = division.max(1);
+        self.in_count %= self.division;
+    }
+
+    /// Sets the number of output triggers per output group, at least 1.
+    pub fn set_multiplication(&mut self, multiplication: u32) {
+        self.multiplication = multiplication.max(1);
+    }
+
+    /// Sets the swing.
+    ///
+    /// * `swing` - Range 0.0 to 0.9. At 0.0 the output is straight, at 0.33 the off-beats
+    ///   are delayed by a third of the output interval, which gives a triplet shuffle.
+    pub fn set_swing(&mut self, swing: f32) {
+        self.swing = swing.clamp(0.0, 0.9);
+    }
+
+    /// Feed the next clock input sample.
+    ///
+    /// * `clock_in` - Clock input signal, will trigger like [Trigger].
+    ///
+    /// The return value is the trigger signal.
+    #[inline]
+    pub fn next(&mut self, clock_in: f32) -> f32 {
+        if let Some(counter) = &mut self.counter {
+            *counter += 1;
+        }
+
+        if self.trig.check_trigger(clock_in) {
+            if let Some(counter) = self.counter {
+                self.period = counter;
+            }
+            self.counter = Some(0);
+
+            if self.in_count == 0 {
+                // Triggers of the previous group, that are still pending, are dropped:
+                self.odd_group = !self.odd_group;
+                self.elapsed = 0;
+                self.index = 0;
+                self.group_len = if self.period > 0 { self.multiplication } else { 1 };
+            }
+            self.in_count = (self.in_count + 1) % self.division;
+        }
+
+        if self.index < self.group_len {
+            let interval = (self.period * self.division as u64) as f64 / self.multiplication as f64;
+            let offbeat_group = self.odd_group && self.multiplication % 2 == 1;
+            let offbeat = (self.index % 2 == 1) != offbeat_group;
+
+            let mut at = self.index as f64 * interval;
+            if offbeat {
+                at += self.swing as f64 * interval;
+            }
+
+            if self.elapsed as f64 >= at.round() {
+                self.ts.trigger();
+                self.index += 1;
+            }
+        }
+        self.elapsed += 1;
+
+        self.ts.next()
+    }
+}
+
+impl Default for ClockDivider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The maximum number of steps of an [EuclideanPattern].
+pub const EUCLID_MAX_STEPS: u32 = 64;
+
+/// Euclidean rhythm generator, driven by an input clock.
+///
+/// The pattern distributes a number of pulses as evenly as possible over the steps.
+/// Every input trigger advances the pattern by one step and emits an output trigger,
+/// if the step is a pulse. The pattern is computed with a Bresenham style algorithm,
+/// which results in the same rhythms as the Bjorklund algorithm, up to a rotation.
+/// The first step is always a pulse, unless the pattern is rotated.
+///
+///```
+/// use synfx_dsp::EuclideanPattern;
+///
+/// let mut euclid = EuclideanPattern::new();
+/// euclid.set_sample_rate(1000.0);
+/// euclid.set_pattern(8, 3, 0);
+///
+/// let mut pulses = vec![];
+/// for _ in 0..16 {
+///     pulses.push(euclid.next(1.0) > 0.5);
+///     for _ in 0..10 {
+///         euclid.next(0.0);
+///     }
+/// }
+///
+/// // x..x..x.
+/// let pattern = [true, false, false, true, false, false, true, false];
+/// assert_eq!(&pulses[0..8], &pattern);
+/// assert_eq!(&pulses[8..16], &pattern);
+///
+/// // Rotated by one step: .x..x..x
+/// euclid.set_pattern(8, 3, 1);
+/// assert!(!euclid.is_pulse(0));
+/// assert!(euclid.is_pulse(1));
+/// assert!(euclid.is_pulse(7));
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct EuclideanPattern {
+    trig: Trigger,
+    ts: TrigSignal,
+    /// One bit per step, the lowest bit is the first step.
+    pattern: u64,
+    steps: u32,
+    /// The step that is played by the next input trigger.
+    step: u32,
+}
+
+impl EuclideanPattern {
+    /// Create a new pattern generator with 4 pulses over 16 steps.
+    pub fn new() -> Self {
+        let mut this =
+            Self { trig: Trigger::new(), ts: TrigSignal::new(), pattern: 0, steps: 1, step: 0 };
+        this.set_pattern(16, 4, 0);
+        this
+    }
+
+    /// Reset internal state, the next input trigger plays the first step.
+    pub fn reset(&mut self) {
+        self.trig.reset();
+        self.ts.reset();
+        self.step = 0;
+    }
+
+    /// Set the sample rate for the trigger signal generator.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.ts.set_sample_rate(srate);
+    }
+
+    /// Restart the pattern. The next input trigger plays the first step again.
+    #[inline]
+    pub fn sync(&mut self) {
+        self.step = 0;
+    }
+
+    /// Computes a new pattern, the current step is kept if it is still within the pattern.
+    ///
+    /// * `steps` - Length of the pattern, range 1 to [EUCLID_MAX_STEPS].
+    /// * `pulses` - Number of pulses, range 0 to `steps`.
+    /// * `rotation` - Number of steps the pattern is rotated to the right.
+    pub fn set_pattern(&mut self, steps: u32, pulses: u32, rotation: u32) {
+        self.steps = steps.clamp(1, EUCLID_MAX_STEPS);
+        let pulses = pulses.min(self.steps);
+        let rotation = rotation % self.steps;
+
+        self.pattern = 0;
+        for i in 0..self.steps {
+            if (i * pulses) % self.steps < pulses {
+                self.pattern |= 1 << ((i + rotation) % self.steps);
+            }
+        }
+
+        self.step %= self.steps;
+    }
+
+    /// Returns true if the step `step` of the pattern is a pulse.
+    #[inline]
+    pub fn is_pulse(&self, step: u32) -> bool {
+        step < self.steps && self.pattern & (1 << step) != 0
+    }
+
+    /// Returns the step that is played by the next input trigger.
+    #[inline]
+    pub fn current_step(&self) -> u32 {
+        self.step
+    }
+
+    /// Feed the next clock input sample.
+    ///
+    /// * `clock_in` - Clock input signal, will trigger like [Trigger].
+    ///
+    /// The return value is the trigger signal.
+    #[inline]
+    pub fn next(&mut self, clock_in: f32) -> f32 {
+        if self.trig.check_trigger(clock_in) {
+            if self.is_pulse(self.step) {
+                self.ts.trigger();
+            }
+            self.step = (self.step + 1) % self.steps;
+        }
+
+        self.ts.next()
+    }
+}
+
+impl Default for EuclideanPattern {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sample and hold, driven by an external trigger signal.
+///
+/// Whenever the trigger input surpasses [TRIG_HIGH_THRES] the current input value is captured
+/// and held until the next trigger. The rate of the sample and hold is thereby defined
+/// by the incoming trigger or clock signal.
+///
+///```
+/// use synfx_dsp::SampleAndHold;
+///
+/// let mut sh = SampleAndHold::new();
+///
+/// assert_eq!(sh.process(0.3, 0.0), 0.0);
+/// assert_eq!(sh.process(0.4, 1.0), 0.4);
+/// assert_eq!(sh.process(0.5, 1.0), 0.4);
+/// assert_eq!(sh.process(0.6, 0.0), 0.4);
+/// assert_eq!(sh.process(0.7, 1.0), 0.7);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct SampleAndHold {
+    trig: Trigger,
+    value: f32,
+}
+
+impl SampleAndHold {
+    /// Create a new sample and hold.
+    pub fn new() -> Self {
+        Self { trig: Trigger::new(), value: 0.0 }
+    }
+
+    /// Reset the trigger detector and the held value.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.trig.reset();
+        self.value = 0.0;
+    }
+
+    /// Returns the currently held value.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Captures `input` if `trigger` triggered, and returns the held value.
+    ///
+    /// * `input` - The signal to sample.
+    /// * `trigger` - Trigger signal input, will trigger like [Trigger].
+    #[inline]
+    pub fn process(&mut self, input: f32, trigger: f32) -> f32 {
+        if self.trig.check_trigger(trigger) {
+            self.value = input;
+        }
+
+        self.value
+    }
+}
+
+impl Default for SampleAndHold {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Track and hold, driven by an external gate signal.
+///
+/// While the gate is high (above [TRIG_HIGH_THRES]) the input is passed through.
+/// When the gate drops below [TRIG_LOW_THRES] the last value is held. When tracking resumes,
+/// the output slews from the held value to the input with the time set by
+/// [TrackAndHold::set_slew_ms], to prevent a jump. See also [SampleAndHold].
+///
+///```
+/// use synfx_dsp::TrackAndHold;
+///
+/// let mut th = TrackAndHold::new();
+/// th.set_sample_rate(1000.0);
+/// th.set_slew_ms(0.0);
+///
+/// assert_eq!(th.process(0.3, 1.0), 0.3);
+/// assert_eq!(th.process(0.4, 1.0), 0.4);
+/// assert_eq!(th.process(0.5, 0.0), 0.4);
+/// assert_eq!(th.process(0.6, 1.0), 0.6);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct TrackAndHold {
+    slew: crate::SlewValue<f32>,
+    slew_ms: f32,
+    gate_high: bool,
+    slewing: bool,
+    value: f32,
+}
+
+impl TrackAndHold {
+    /// Create a new track and hold, without any slew on re-acquisition.
+    pub fn new() -> Self {
+        Self {
+            slew: crate::SlewValue::new(),
+            slew_ms: 0.0,
+            gate_high: false,
+            slewing: false,
+            value: 0.0,
+        }
+    }
+
+    /// Reset the gate detector and the held value.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.slew.reset();
+        self.gate_high = false;
+        self.slewing = false;
+        self.value = 0.0;
+    }
+
+    /// Set the sample rate for the re-acquisition slew.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.slew.set_sample_rate(srate);
+    }
+
+    /// Set the slew time in milliseconds it takes to move by 1.0 when
+    /// tracking resumes. A time of 0.0 switches directly to the input.
+    #[inline]
+    pub fn set_slew_ms(&mut self, slew_ms: f32) {
+        self.slew_ms = slew_ms;
+    }
+
+    /// Returns the current output value.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Tracks or holds the `input`, depending on the `gate`. Returns the output.
+    ///
+    /// * `input` - The signal to track.
+    /// * `gate` - Gate signal input.
+    #[inline]
+    pub fn process(&mut self, input: f32, gate: f32) -> f32 {
+        if self.gate_high {
+            if gate <= TRIG_LOW_THRES {
+                self.gate_high = false;
+            }
+        } else if gate > TRIG_HIGH_THRES {
+            self.gate_high = true;
+            self.slewing = true;
+        }
+
+        if self.gate_high {
+            if self.slewing {
+                self.value = self.slew.next(input, self.slew_ms);
+                if (self.value - input).abs() < 0.000001 {
+                    self.slewing = false;
+                }
+            } else {
+                self.value = input;
+            }
+        } else {
+            // Keep the slew at the held value, so it starts from there once tracking resumes.
+            self.slew.next(self.value, 0.0);
+            self.slewing = false;
+        }
+
+        self.value
+    }
+}
+
+impl Default for TrackAndHold {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Collects timestamped events for sample accurate parameter changes and triggers
+/// in block based processing.
+///
+/// The events are pushed with their sample offset relative to the start of the
+/// current block. [EventScheduler::segments] splits the block at the event offsets
+/// into sub-slices, that can be processed in one go, after the events at their
+/// start were applied. Events behind the end of the block are kept, and
+/// [EventScheduler::advance] moves them into the next block.
+///
+/// The capacity is allocated in [EventScheduler::new], [EventScheduler::push] never
+/// allocates, so it can be used in the audio thread.
+///
+///```
+/// use synfx_dsp::EventScheduler;
+///
+/// let mut sched = EventScheduler::new(16);
+/// sched.push(40, 0.5);
+/// sched.push(10, 0.2);
+/// sched.push(70, 1.0); // Behind the end of the block.
+///
+/// let mut gain = 0.0;
+/// let mut output = [0.0; 64];
+/// for seg in sched.segments(output.len()) {
+///     for (_offset, value) in seg.events {
+///         gain = *value;
+///     }
+///     for out in output[seg.start..seg.end].iter_mut() {
+///         *out = gain;
+///     }
+/// }
+/// assert_eq!(output[9], 0.0);
+/// assert_eq!(output[10], 0.2);
+/// assert_eq!(output[40], 0.5);
+///
+/// sched.advance(output.len());
+/// let offsets: Vec<usize> = sched.segments(64).map(|seg| seg.start).collect();
+/// assert_eq!(offsets, vec![0, 6]);
+///```
+#[derive(Debug, Clone)]
+pub struct EventScheduler<T> {
+    /// The events, sorted by their offset.
+    events: Vec<(usize, T)>,
+}
+
+impl<T> EventScheduler<T> {
+    /// Creates a new event scheduler, that can hold up to `capacity` events.
+    pub fn new(capacity: usize) -> Self {
+        Self { events: Vec::with_capacity(capacity) }
+    }
+
+    /// Removes all events.
+    pub fn reset(&mut self) {
+        self.events.clear();
+    }
+
+    /// Returns the number of scheduled events.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Schedules the `event` at the sample `offset`, relative to the start of the current
+    /// block. Events at the same offset keep the order in which they were pushed.
+    /// Returns false and drops the event if the capacity is exhausted.
+    pub fn push(&mut self, offset: usize, event: T) -> bool {
+        if self.events.len() >= self.events.capacity() {
+            return false;
+        }
+
+        let idx = self.events.partition_point(|(o, _)| *o <= offset);
+        self.events.insert(idx, (offset, event));
+        true
+    }
+
+    /// Returns an iterator over the sub-slices of a block with `block_len` samples.
+    /// Every segment starts at an event offset (or the start of the block) and ends
+    /// at the next event offset (or the end of the block). See also [EventSegment].
+    pub fn segments(&self, block_len: usize) -> EventSegments<'_, T> {
+        EventSegments { events: &self.events, block_len, pos: 0, idx: 0 }
+    }
+
+    /// Removes the events of the current block with `block_len` samples, and moves
+    /// the remaining events into the next block.
+    pub fn advance(&mut self, block_len: usize) {
+        let done = self.events.partition_point(|(o, _)| *o < block_len);
+        self.events.drain(0..done);
+        for (offset, _) in self.events.iter_mut() {
+            *offset -= block_len;
+        }
+    }
+}
+
+/// A sub-slice of a block, returned by [EventScheduler::segments].
+#[derive(Debug, Clone, Copy)]
+pub struct EventSegment<'a, T> {
+    /// The first sample of the segment.
+    pub start: usize,
+    /// One past the last sample of the segment.
+    pub end: usize,
+    /// The events to apply before the segment is processed, with their offset.
+    pub events: &'a [(usize, T)],
+}
+
+/// The iterator returned by [EventScheduler::segments].
+#[derive(Debug, Clone)]
+pub struct EventSegments<'a, T> {
+    events: &'a [(usize, T)],
+    block_len: usize,
+    pos: usize,
+    idx: usize,
+}
+
+impl<'a, T> Iterator for EventSegments<'a, T> {
+    type Item = EventSegment<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.block_len {
+            return None;
+        }
+
+        let first = self.idx;
+        while self.idx < self.events.len() && self.events[self.idx].0 <= self.pos {
+            self.idx += 1;
+        }
+
+        let end = match self.events.get(self.idx) {
+            Some((offset, _)) => (*offset).min(self.block_len),
+            None => self.block_len,
+        };
+
+        let start = self.pos;
+        self.pos = end;
+        Some(EventSegment { start, end, events: &self.events[first..self.idx] })
+    }
+}
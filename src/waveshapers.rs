@@ -4,8 +4,7 @@
 
 //! A collection of wave shaping functions.
 
-use std::simd::f32x4;
-use std::simd::StdFloat;
+use crate::simd::*;
 
 // Ported from LMMS under GPLv2
 // * DspEffectLibrary.h - library with template-based inline-effects
@@ -80,3 +79,1228 @@ pub fn tanh_levien_f64(x: f64) -> f64 {
     a / (1.0 + (a * a)).sqrt()
 }
 
+/// A slew rate limiter used as distortion. Unlike the memoryless wave shapers above
+/// it limits how fast the signal can change per sample, separately for rising and falling
+/// edges. Fast transients turn into triangle like edges, square waves get a soft
+/// "square-ish" timbre and high frequencies lose amplitude.
+///
+///```
+/// use synfx_dsp::SlewDistortion;
+///
+/// let mut slew = SlewDistortion::new();
+/// slew.set_rate(0.25, 0.5);
+///
+/// let out: Vec<f32> = [1.0, 1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0]
+///     .iter()
+///     .map(|s| slew.process(*s))
+///     .collect();
+/// assert_eq!(out, vec![0.25, 0.5, 0.75, 1.0, 1.0, 0.5, 0.0, -0.5]);
+///```
+#[derive(Debug, Clone)]
+pub struct SlewDistortion {
+    srate: f32,
+    /// The rates in Hz, if they were set with [SlewDistortion::set_rate_hz].
+    rate_hz: Option<(f32, f32)>,
+    rise: f32,
+    fall: f32,
+    value: f32,
+}
+
+impl SlewDistortion {
+    pub fn new() -> Self {
+        Self { srate: 44100.0, rate_hz: None, rise: 1.0, fall: 1.0, value: 0.0 }
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        if let Some((rise_hz, fall_hz)) = self.rate_hz {
+            self.set_rate_hz(rise_hz, fall_hz);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.value = 0.0;
+    }
+
+    /// Sets the maximum change of the signal per sample.
+    ///
+    /// * `rise_per_sample` - Maximum increase per sample.
+    /// * `fall_per_sample` - Maximum decrease per sample.
+    pub fn set_rate(&mut self, rise_per_sample: f32, fall_per_sample: f32) {
+        self.rate_hz = None;
+        self.rise = rise_per_sample.abs();
+        self.fall = fall_per_sample.abs();
+    }
+
+    /// Sets the maximum rates of change in terms of a frequency. At `rise_hz` the rising edge
+    /// of a full scale triangle wave (from -1.0 to 1.0 in half a period) just passes unaltered,
+    /// the same goes for `fall_hz` and the falling edge.
+    pub fn set_rate_hz(&mut self, rise_hz: f32, fall_hz: f32) {
+        self.rate_hz = Some((rise_hz, fall_hz));
+        self.rise = 4.0 * rise_hz.abs() / self.srate;
+        self.fall = 4.0 * fall_hz.abs() / self.srate;
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let delta = (input - self.value).clamp(-self.fall, self.rise);
+        self.value += delta;
+        self.value
+    }
+}
+
+impl Default for SlewDistortion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Bias of the asymmetric wave shaper of the [Exciter], it generates the even harmonics.
+const EXCITER_BIAS: f64 = 0.3;
+
+/// An exciter (or enhancer) that adds "air" and presence by generating upper harmonics.
+///
+/// The input is high pass filtered, the high frequencies are distorted with a gentle
+/// asymmetric (tanh based) wave shaper, which generates even and odd harmonics, and the
+/// result is high pass filtered again to remove the DC offset and low intermodulation
+/// products. Then it is mixed back with the dry signal.
+///
+///```
+/// use synfx_dsp::{Exciter, Biquad, BiquadCoefs, rms};
+///
+/// let input: Vec<f32> =
+///     (0..44100).map(|i| (i as f32 * std::f32::consts::TAU * 1000.0 / 44100.0).sin() * 0.5).collect();
+///
+/// let mut exc = Exciter::new();
+/// exc.set_sample_rate(44100.0);
+///
+/// // Without mix the signal is unchanged:
+/// exc.set_params(800.0, 0.8, 0.0);
+/// assert!(input.iter().all(|s| exc.process(*s) == *s));
+///
+/// // Otherwise harmonics above the input frequency are added:
+/// exc.set_params(800.0, 0.8, 0.5);
+/// let mut hpf = Biquad::new();
+/// hpf.set_coefs(BiquadCoefs::butter_highpass(44100.0, 1800.0));
+/// let added: Vec<f32> = input.iter().map(|s| hpf.tick(exc.process(*s) - s)).collect();
+/// assert!(rms(&added[22050..]) > 0.01);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct Exciter {
+    pre_hpf: crate::Biquad,
+    post_hpf: crate::Biquad,
+    srate: f32,
+    freq: f32,
+    drive: f32,
+    mix: f32,
+}
+
+impl Exciter {
+    pub fn new() -> Self {
+        let mut this = Self {
+            pre_hpf: crate::Biquad::new(),
+            post_hpf: crate::Biquad::new(),
+            srate: 44100.0,
+            freq: 3000.0,
+            drive: 10.5,
+            mix: 0.0,
+        };
+        this.update_filters();
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.update_filters();
+    }
+
+    fn update_filters(&mut self) {
+        self.freq = self.freq.clamp(20.0, 0.45 * self.srate);
+        let coefs = crate::BiquadCoefs::butter_highpass(self.srate, self.freq);
+        self.pre_hpf.set_coefs(coefs);
+        self.post_hpf.set_coefs(coefs);
+    }
+
+    pub fn reset(&mut self) {
+        self.pre_hpf.reset();
+        self.post_hpf.reset();
+    }
+
+    /// * `freq_hz` - The cutoff frequency of the high pass filters, only frequencies above
+    ///   are excited.
+    /// * `amount` - The amount of distortion, range 0.0 to 1.0.
+    /// * `mix` - How much of the generated harmonics is added to the dry signal,
+    ///   range 0.0 to 1.0.
+    pub fn set_params(&mut self, freq_hz: f32, amount: f32, mix: f32) {
+        if freq_hz != self.freq {
+            self.freq = freq_hz;
+            self.update_filters();
+        }
+        self.drive = 1.0 + 19.0 * amount.clamp(0.0, 1.0);
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let high = self.pre_hpf.tick(input) as f64 * self.drive as f64;
+        let shaped = tanh_levien_f64(high + EXCITER_BIAS) - tanh_levien_f64(EXCITER_BIAS);
+        let wet = self.post_hpf.tick(shaped as f32);
+        input + wet * self.mix
+    }
+}
+
+impl Default for Exciter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Below this input difference the ADAA formulas are ill-conditioned, and the
+/// wave shaper is evaluated directly at the midpoint instead.
+const ADAA_EPS: f64 = 1e-5;
+
+/// The order of the antiderivative anti-aliasing of [AdaaTanh], [AdaaHardClip] and
+/// [AdaaFolder].
+///
+/// First order ADAA delays the signal by half a sample, second order ADAA
+/// by one sample. The second order suppresses aliasing further, but costs more CPU
+/// and damps the high frequencies a bit more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdaaOrder {
+    First,
+    Second,
+}
+
+/// The shared state of the antiderivative anti-aliasing wave shapers.
+///
+/// The wave shaper `f`, its first antiderivative `f1` and its second antiderivative
+/// `f2` are passed to [AdaaState::process].
+/// See also: Parker, Zavalishin, Le Bivic: "Reducing the Aliasing of Nonlinear
+/// Waveshaping Using Continuous-Time Convolution" (DAFx-16) and Bilbao, Esqueda,
+/// Parker, Välimäki: "Antiderivative Antialiasing for Memoryless Nonlinearities" (2017).
+#[derive(Debug, Clone, Copy)]
+struct AdaaState {
+    order: AdaaOrder,
+    x1: f64,
+    x2: f64,
+    /// First antiderivative at `x1`.
+    f1_x1: f64,
+    /// Second antiderivative at `x1`.
+    f2_x1: f64,
+    /// The first divided difference of the second antiderivative between `x1` and `x2`.
+    d_x1: f64,
+    /// First and second antiderivative at 0.0, for resetting the state.
+    f1_0: f64,
+    f2_0: f64,
+}
+
+impl AdaaState {
+    fn new(order: AdaaOrder, f1: fn(f64) -> f64, f2: fn(f64) -> f64) -> Self {
+        let (f1_0, f2_0) = (f1(0.0), f2(0.0));
+        Self { order, x1: 0.0, x2: 0.0, f1_x1: f1_0, f2_x1: f2_0, d_x1: f1_0, f1_0, f2_0 }
+    }
+
+    /// Resets the state, as if the input was 0.0 forever.
+    fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.f1_x1 = self.f1_0;
+        self.f2_x1 = self.f2_0;
+        self.d_x1 = self.f1_0;
+    }
+
+    #[inline]
+    fn process(
+        &mut self,
+        x: f64,
+        f: fn(f64) -> f64,
+        f1: fn(f64) -> f64,
+        f2: fn(f64) -> f64,
+    ) -> f64 {
+        match self.order {
+            AdaaOrder::First => {
+                let f1_x = f1(x);
+                let dx = x - self.x1;
+                let y = if dx.abs() < ADAA_EPS {
+                    f(0.5 * (x + self.x1))
+                } else {
+                    (f1_x - self.f1_x1) / dx
+                };
+                self.x1 = x;
+                self.f1_x1 = f1_x;
+                y
+            }
+            AdaaOrder::Second => {
+                let f2_x = f2(x);
+                let dx = x - self.x1;
+                let d_x = if dx.abs() < ADAA_EPS {
+                    f1(0.5 * (x + self.x1))
+                } else {
+                    (f2_x - self.f2_x1) / dx
+                };
+
+                let dx2 = x - self.x2;
+                let y = if dx2.abs() < ADAA_EPS {
+                    let xbar = 0.5 * (x + self.x2);
+                    let delta = xbar - self.x1;
+                    if delta.abs() < ADAA_EPS {
+                        f(0.5 * (xbar + self.x1))
+                    } else {
+                        (2.0 / delta) * (f1(xbar) + (self.f2_x1 - f2(xbar)) / delta)
+                    }
+                } else {
+                    (2.0 / dx2) * (d_x - self.d_x1)
+                };
+
+                self.x2 = self.x1;
+                self.x1 = x;
+                self.f2_x1 = f2_x;
+                self.d_x1 = d_x;
+                y
+            }
+        }
+    }
+}
+
+/// The dilogarithm `Li2(z)` for `z` in the range -1.0 to 0.0.
+fn dilog_neg(z: f64) -> f64 {
+    // Landen's identity maps z to w in the range 0.0 to 0.5, where the series converges fast:
+    let w = z / (z - 1.0);
+    let mut sum = 0.0;
+    let mut wk = w;
+    for k in 1..64 {
+        let term = wk / ((k * k) as f64);
+        sum += term;
+        if term < 1e-17 {
+            break;
+        }
+        wk *= w;
+    }
+    let l = (1.0 - z).ln();
+    -sum - 0.5 * l * l
+}
+
+fn adaa_tanh_f(x: f64) -> f64 {
+    x.tanh()
+}
+
+/// `ln(cosh(x))`, written in a way that does not overflow for big `x`.
+fn adaa_tanh_f1(x: f64) -> f64 {
+    let x = x.abs();
+    x + (-2.0 * x).exp().ln_1p() - std::f64::consts::LN_2
+}
+
+fn adaa_tanh_f2(x: f64) -> f64 {
+    let ax = x.abs();
+    let f2 = 0.5 * ax * ax - ax * std::f64::consts::LN_2
+        + std::f64::consts::PI * std::f64::consts::PI / 24.0
+        + 0.5 * dilog_neg(-(-2.0 * ax).exp());
+    f2.copysign(x)
+}
+
+/// A tanh saturator with antiderivative anti-aliasing (ADAA), which aliases a lot
+/// less than a plain `tanh` without the need for oversampling.
+/// See [AdaaOrder] for the difference between first and second order.
+///
+///```
+/// use synfx_dsp::{AdaaOrder, AdaaTanh};
+///
+/// let mut sat = AdaaTanh::new(AdaaOrder::Second);
+///
+/// // A steady input is shaped like by a plain tanh:
+/// let mut out = 0.0;
+/// for _ in 0..10 {
+///     out = sat.process(0.5);
+/// }
+/// assert!((out - 0.5_f32.tanh()).abs() < 0.0001);
+///
+/// // And the output stays within the range of a tanh:
+/// for i in 0..1000 {
+///     let out = sat.process((i as f32 * 0.7).sin() * 10.0);
+///     assert!(out.abs() <= 1.0);
+/// }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct AdaaTanh {
+    state: AdaaState,
+}
+
+impl AdaaTanh {
+    pub fn new(order: AdaaOrder) -> Self {
+        Self { state: AdaaState::new(order, adaa_tanh_f1, adaa_tanh_f2) }
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.state.process(input as f64, adaa_tanh_f, adaa_tanh_f1, adaa_tanh_f2) as f32
+    }
+}
+
+fn adaa_hard_clip_f(x: f64) -> f64 {
+    x.clamp(-1.0, 1.0)
+}
+
+fn adaa_hard_clip_f1(x: f64) -> f64 {
+    let ax = x.abs();
+    if ax <= 1.0 {
+        0.5 * x * x
+    } else {
+        ax - 0.5
+    }
+}
+
+fn adaa_hard_clip_f2(x: f64) -> f64 {
+    let ax = x.abs();
+    let f2 = if ax <= 1.0 { ax * ax * ax / 6.0 } else { 0.5 * ax * ax - 0.5 * ax + 1.0 / 6.0 };
+    f2.copysign(x)
+}
+
+/// A hard clipper (to the range -1.0 to 1.0) with antiderivative anti-aliasing (ADAA).
+/// See [AdaaOrder] for the difference between first and second order.
+///
+///```
+/// use synfx_dsp::{AdaaHardClip, AdaaOrder};
+///
+/// let mut clip = AdaaHardClip::new(AdaaOrder::First);
+///
+/// let mut out = 0.0;
+/// for _ in 0..10 {
+///     out = clip.process(2.0);
+/// }
+/// assert!((out - 1.0).abs() < 0.0001);
+///
+/// for i in 0..1000 {
+///     let out = clip.process((i as f32 * 0.7).sin() * 10.0);
+///     assert!(out.abs() <= 1.0);
+/// }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct AdaaHardClip {
+    state: AdaaState,
+}
+
+impl AdaaHardClip {
+    pub fn new(order: AdaaOrder) -> Self {
+        Self { state: AdaaState::new(order, adaa_hard_clip_f1, adaa_hard_clip_f2) }
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.state.process(input as f64, adaa_hard_clip_f, adaa_hard_clip_f1, adaa_hard_clip_f2)
+            as f32
+    }
+}
+
+/// Maps `x` to the position inside one period of the triangle wave folder,
+/// in the range 0.0 to 4.0.
+#[inline]
+fn adaa_folder_phase(x: f64) -> f64 {
+    (x + 1.0).rem_euclid(4.0)
+}
+
+fn adaa_folder_f(x: f64) -> f64 {
+    1.0 - (adaa_folder_phase(x) - 2.0).abs()
+}
+
+fn adaa_folder_f1(x: f64) -> f64 {
+    let p = adaa_folder_phase(x);
+    if p <= 2.0 {
+        0.5 * p * p - p
+    } else {
+        3.0 * (p - 2.0) - 0.5 * (p * p - 4.0)
+    }
+}
+
+fn adaa_folder_f2(x: f64) -> f64 {
+    let p = adaa_folder_phase(x);
+    if p <= 2.0 {
+        p * p * p / 6.0 - 0.5 * p * p
+    } else {
+        1.5 * p * p - 4.0 * p - p * p * p / 6.0 + 8.0 / 3.0
+    }
+}
+
+/// A triangle wave folder with antiderivative anti-aliasing (ADAA). Inputs above 1.0
+/// (or below -1.0) are reflected back into the range -1.0 to 1.0, as often as necessary.
+/// Scale the input to drive the folder harder.
+/// See [AdaaOrder] for the difference between first and second order.
+///
+///```
+/// use synfx_dsp::{AdaaFolder, AdaaOrder};
+///
+/// let mut folder = AdaaFolder::new(AdaaOrder::Second);
+///
+/// // 1.5 is folded back to 0.5:
+/// let mut out = 0.0;
+/// for _ in 0..10 {
+///     out = folder.process(1.5);
+/// }
+/// assert!((out - 0.5).abs() < 0.0001);
+///
+/// for i in 0..1000 {
+///     let out = folder.process((i as f32 * 0.7).sin() * 10.0);
+///     assert!(out.abs() <= 1.0001);
+/// }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct AdaaFolder {
+    state: AdaaState,
+}
+
+impl AdaaFolder {
+    pub fn new(order: AdaaOrder) -> Self {
+        Self { state: AdaaState::new(order, adaa_folder_f1, adaa_folder_f2) }
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.state.process(input as f64, adaa_folder_f, adaa_folder_f1, adaa_folder_f2) as f32
+    }
+}
+
+/// The maximum number of cascaded stages of the [Wavefolder].
+pub const WAVEFOLDER_MAX_STAGES: usize = 4;
+
+/// The gain between the cascaded stages of the [Wavefolder], a full scale output
+/// of one stage is folded once more by the next stage.
+const WAVEFOLDER_STAGE_GAIN: f64 = 2.0;
+
+/// The cutoff of the DC blocking high pass of the [Wavefolder].
+const WAVEFOLDER_DC_BLOCK_HZ: f64 = 5.0;
+
+/// Thresholds of the parallel folding cells of [WavefolderMode::Buchla].
+const BUCHLA_CELL_THRESHOLDS: [f64; 5] = [1.0, 3.0, 5.0, 7.0, 9.0];
+/// Gains of the parallel folding cells of [WavefolderMode::Buchla]. They fold
+/// to the peaks 1.0, -0.8, 0.65, -0.5 and 0.4, and flatten the curve above the last cell.
+const BUCHLA_CELL_GAINS: [f64; 5] = [-1.9, 1.625, -1.3, 1.025, -0.45];
+
+/// The folding curve of a [Wavefolder] stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavefolderMode {
+    /// Smooth sine shaped folds, like the ones of the Lockhart and Serge wave folders.
+    Serge,
+    /// Sharp folds of decreasing height from parallel folding cells, loosely
+    /// modeled after the timbre section of the Buchla 259.
+    Buchla,
+}
+
+fn wavefolder_serge_f(x: f64) -> f64 {
+    (std::f64::consts::FRAC_PI_2 * x).sin()
+}
+
+fn wavefolder_serge_f1(x: f64) -> f64 {
+    -std::f64::consts::FRAC_2_PI * (std::f64::consts::FRAC_PI_2 * x).cos()
+}
+
+fn wavefolder_serge_f2(x: f64) -> f64 {
+    -std::f64::consts::FRAC_2_PI
+        * std::f64::consts::FRAC_2_PI
+        * (std::f64::consts::FRAC_PI_2 * x).sin()
+}
+
+fn wavefolder_buchla_f(x: f64) -> f64 {
+    let mut y = x;
+    for (t, g) in BUCHLA_CELL_THRESHOLDS.iter().zip(BUCHLA_CELL_GAINS.iter()) {
+        y += g * (x.abs() - t).max(0.0).copysign(x);
+    }
+    y
+}
+
+fn wavefolder_buchla_f1(x: f64) -> f64 {
+    let mut y = 0.5 * x * x;
+    for (t, g) in BUCHLA_CELL_THRESHOLDS.iter().zip(BUCHLA_CELL_GAINS.iter()) {
+        let d = (x.abs() - t).max(0.0);
+        y += g * 0.5 * d * d;
+    }
+    y
+}
+
+fn wavefolder_buchla_f2(x: f64) -> f64 {
+    let ax = x.abs();
+    let mut y = ax * ax * ax / 6.0;
+    for (t, g) in BUCHLA_CELL_THRESHOLDS.iter().zip(BUCHLA_CELL_GAINS.iter()) {
+        let d = (ax - t).max(0.0);
+        y += g * d * d * d / 6.0;
+    }
+    y.copysign(x)
+}
+
+/// A wave shaping curve, or one of its antiderivatives.
+type ShapeFn = fn(f64) -> f64;
+
+/// Returns the folding curve and its first and second antiderivative.
+fn wavefolder_fns(mode: WavefolderMode) -> (ShapeFn, ShapeFn, ShapeFn) {
+    match mode {
+        WavefolderMode::Serge => (wavefolder_serge_f, wavefolder_serge_f1, wavefolder_serge_f2),
+        WavefolderMode::Buchla => (wavefolder_buchla_f, wavefolder_buchla_f1, wavefolder_buchla_f2),
+    }
+}
+
+/// A West Coast style wave folder with multiple cascaded stages.
+///
+/// The input is multiplied by the drive and shifted by the symmetry offset, then
+/// it passes the stages, which fold it with the curve of the [WavefolderMode].
+/// Each further stage folds the output of the previous one again, which adds
+/// more and more upper harmonics. The DC offset, that an asymmetric setting
+/// introduces, is removed by a DC blocking filter.
+///
+/// Folding generates a lot of aliasing, enable the antiderivative anti-aliasing
+/// with [Wavefolder::set_adaa] or oversample the wave folder.
+///
+///```
+/// use synfx_dsp::{Wavefolder, WavefolderMode};
+///
+/// let mut folder = Wavefolder::new();
+/// folder.set_sample_rate(44100.0);
+/// folder.set_mode(WavefolderMode::Serge);
+///
+/// let mut count_zero_crossings = |folder: &mut Wavefolder| {
+///     let out: Vec<f32> = (0..44100)
+///         .map(|i| (i as f32 * std::f32::consts::TAU * 100.0 / 44100.0).sin())
+///         .map(|s| folder.process(s))
+///         .collect();
+///     assert!(out.iter().all(|s| s.abs() < 1.1));
+///     out[4410..].windows(2).filter(|w| w[0].signum() != w[1].signum()).count()
+/// };
+///
+/// // Without drive the sine is only shaped, but not folded:
+/// folder.set_drive(1.0);
+/// assert_eq!(count_zero_crossings(&mut folder), 180);
+///
+/// // Driving the folder harder folds it back twice per half period:
+/// folder.reset();
+/// folder.set_drive(3.0);
+/// assert_eq!(count_zero_crossings(&mut folder), 540);
+///```
+#[derive(Debug, Clone)]
+pub struct Wavefolder {
+    mode: WavefolderMode,
+    adaa: Option<AdaaOrder>,
+    stages: usize,
+    drive: f32,
+    symmetry: f32,
+    states: [AdaaState; WAVEFOLDER_MAX_STAGES],
+    dc_block: crate::OnePoleHPF<f64>,
+}
+
+impl Wavefolder {
+    /// Creates a single stage [WavefolderMode::Serge] wave folder without anti-aliasing.
+    pub fn new() -> Self {
+        let (_, f1, f2) = wavefolder_fns(WavefolderMode::Serge);
+        let mut this = Self {
+            mode: WavefolderMode::Serge,
+            adaa: None,
+            stages: 1,
+            drive: 1.0,
+            symmetry: 0.0,
+            states: [AdaaState::new(AdaaOrder::First, f1, f2); WAVEFOLDER_MAX_STAGES],
+            dc_block: crate::OnePoleHPF::new(),
+        };
+        this.dc_block.set_freq(WAVEFOLDER_DC_BLOCK_HZ);
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.dc_block.set_sample_rate(srate as f64);
+    }
+
+    pub fn reset(&mut self) {
+        for state in self.states.iter_mut() {
+            state.reset();
+        }
+        self.dc_block.reset();
+    }
+
+    /// Sets the folding curve of all stages.
+    pub fn set_mode(&mut self, mode: WavefolderMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.init_states();
+        }
+    }
+
+    /// Enables the antiderivative anti-aliasing with `Some(order)`, or disables it
+    /// with `None`. See [AdaaOrder] for the added latency.
+    pub fn set_adaa(&mut self, adaa: Option<AdaaOrder>) {
+        if adaa != self.adaa {
+            self.adaa = adaa;
+            self.init_states();
+        }
+    }
+
+    /// Sets the number of cascaded folding stages, from 1 to [WAVEFOLDER_MAX_STAGES].
+    pub fn set_stages(&mut self, stages: usize) {
+        let stages = stages.clamp(1, WAVEFOLDER_MAX_STAGES);
+        for state in self.states[self.stages.min(stages)..].iter_mut() {
+            state.reset();
+        }
+        self.stages = stages;
+    }
+
+    /// Sets the input gain. At 1.0 a full scale input is not folded yet, every
+    /// further 2.0 fold it once more.
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.max(0.0);
+    }
+
+    /// Sets the offset, that is added to the driven input, range -1.0 to 1.0.
+    /// Asymmetric folding adds even harmonics.
+    pub fn set_symmetry(&mut self, symmetry: f32) {
+        self.symmetry = symmetry.clamp(-1.0, 1.0);
+    }
+
+    fn init_states(&mut self) {
+        let (_, f1, f2) = wavefolder_fns(self.mode);
+        let order = self.adaa.unwrap_or(AdaaOrder::First);
+        self.states = [AdaaState::new(order, f1, f2); WAVEFOLDER_MAX_STAGES];
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let (f, f1, f2) = wavefolder_fns(self.mode);
+
+        let mut x = (input * self.drive + self.symmetry) as f64;
+        for (i, state) in self.states[..self.stages].iter_mut().enumerate() {
+            if i > 0 {
+                x *= WAVEFOLDER_STAGE_GAIN;
+            }
+            x = if self.adaa.is_some() { state.process(x, f, f1, f2) } else { f(x) };
+        }
+
+        self.dc_block.process(x) as f32
+    }
+}
+
+impl Default for Wavefolder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The transfer curve of a [SoftClipper]. All curves have a slope of 1.0
+/// around zero and saturate at -1.0 and 1.0, except for the asymmetric
+/// [SoftClipCurve::AsymTanh].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoftClipCurve {
+    /// The hyperbolic tangent, the classic smooth saturation.
+    Tanh,
+    /// The arc tangent, which saturates slower than tanh and keeps more of the peaks.
+    Atan,
+    /// A cubic curve, which reaches 1.0 at an input of 1.5 and clips hard above.
+    /// Mostly third harmonics for moderate levels.
+    Cubic,
+    /// A fifth order polynomial, shaped like sine clipping, which reaches 1.0
+    /// at an input of 1.875 with a flat slope and clips hard above.
+    SineClip,
+    /// A tanh, that saturates at -0.5 on the negative side, like a single ended
+    /// tube stage. Adds even harmonics and a DC offset, which the DC blocker
+    /// of the [SoftClipper] removes.
+    AsymTanh,
+}
+
+impl SoftClipCurve {
+    /// Returns the output of the curve for the input `x`.
+    #[inline]
+    pub fn shape(&self, x: f32) -> f32 {
+        match self {
+            SoftClipCurve::Tanh => x.tanh(),
+            SoftClipCurve::Atan => {
+                std::f32::consts::FRAC_2_PI * (std::f32::consts::FRAC_PI_2 * x).atan()
+            }
+            SoftClipCurve::Cubic => {
+                let x = x.clamp(-1.5, 1.5);
+                x - (4.0 / 27.0) * x * x * x
+            }
+            SoftClipCurve::SineClip => {
+                let t = (x * (8.0 / 15.0)).clamp(-1.0, 1.0);
+                let t2 = t * t;
+                t * (15.0 - 10.0 * t2 + 3.0 * t2 * t2) * 0.125
+            }
+            SoftClipCurve::AsymTanh => {
+                if x >= 0.0 {
+                    x.tanh()
+                } else {
+                    0.5 * (2.0 * x).tanh()
+                }
+            }
+        }
+    }
+}
+
+/// A soft clipper with selectable transfer curves, input drive, output trim and an
+/// optional DC blocker.
+///
+/// The input is multiplied by the drive, shaped by the [SoftClipCurve] and multiplied
+/// by the output trim. The optional [crate::DCBlockFilter] after the curve removes
+/// the DC offset of the output, that an offset in the input or an asymmetric curve
+/// like [SoftClipCurve::AsymTanh] produces.
+///
+///```
+/// use synfx_dsp::{SoftClipper, SoftClipCurve};
+///
+/// let sine: Vec<f32> =
+///     (0..44100).map(|i| (i as f32 * std::f32::consts::TAU * 100.0 / 44100.0).sin()).collect();
+///
+/// for curve in [SoftClipCurve::Tanh, SoftClipCurve::Atan, SoftClipCurve::Cubic, SoftClipCurve::SineClip] {
+///     let mut clip = SoftClipper::new();
+///     clip.set_curve(curve);
+///
+///     // Small signals pass almost unchanged:
+///     assert!((clip.process(0.01) - 0.01).abs() < 0.0001);
+///
+///     // The output never exceeds the trim:
+///     clip.set_drive(10.0);
+///     clip.set_trim_db(-6.0);
+///     assert!(sine.iter().all(|s| clip.process(*s).abs() <= 0.502));
+/// }
+///
+/// // The DC blocker removes the offset of the input from the output:
+/// let mut clip = SoftClipper::new();
+/// clip.set_sample_rate(44100.0);
+/// clip.set_dc_block(true);
+/// let out: Vec<f32> = sine.iter().map(|s| clip.process(s * 0.5 + 0.3)).collect();
+/// let mean = out[22050..].iter().sum::<f32>() / 22050.0;
+/// assert!(mean.abs() < 0.01);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct SoftClipper {
+    curve: SoftClipCurve,
+    drive: f32,
+    trim: f32,
+    dc_block: Option<crate::DCBlockFilter<f32>>,
+    srate: f32,
+}
+
+impl SoftClipper {
+    /// Creates a [SoftClipCurve::Tanh] soft clipper with a drive and trim of 1.0
+    /// and without DC blocker.
+    pub fn new() -> Self {
+        Self { curve: SoftClipCurve::Tanh, drive: 1.0, trim: 1.0, dc_block: None, srate: 44100.0 }
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        if let Some(dc_block) = &mut self.dc_block {
+            dc_block.set_sample_rate(srate);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        if let Some(dc_block) = &mut self.dc_block {
+            dc_block.reset();
+        }
+    }
+
+    pub fn set_curve(&mut self, curve: SoftClipCurve) {
+        self.curve = curve;
+    }
+
+    /// Sets the input gain, higher values saturate more.
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.max(0.0);
+    }
+
+    /// Sets the output gain in decibels.
+    pub fn set_trim_db(&mut self, trim_db: f32) {
+        self.trim = crate::gain_db2coef(trim_db);
+    }
+
+    /// Enables or disables the DC blocker after the curve and trim.
+    pub fn set_dc_block(&mut self, enabled: bool) {
+        if enabled != self.dc_block.is_some() {
+            self.dc_block = if enabled {
+                let mut dc_block = crate::DCBlockFilter::new();
+                dc_block.set_sample_rate(self.srate);
+                Some(dc_block)
+            } else {
+                None
+            };
+        }
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let out = self.curve.shape(input * self.drive) * self.trim;
+        if let Some(dc_block) = &mut self.dc_block {
+            dc_block.next(out)
+        } else {
+            out
+        }
+    }
+}
+
+impl Default for SoftClipper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The highest harmonic of a [ChebyshevShaper].
+pub const CHEBYSHEV_MAX_HARMONIC: usize = 8;
+
+/// A wave shaper, that mixes harmonics directly with a weighted sum of the
+/// Chebyshev polynomials T1 to T8.
+///
+/// The Chebyshev polynomial `Tn` turns a full scale cosine into its `n`th harmonic,
+/// `Tn(cos(x)) = cos(n * x)`. So for a full scale sine input, the weights are the
+/// relative amplitudes of the harmonics in the output. The weights are normalized:
+/// The output is divided by the sum of the absolute weights, so it stays within
+/// -1.0 to 1.0, and each harmonic has the amplitude of its weight divided by that sum.
+/// Lower input levels produce fewer upper harmonics, like a real saturator.
+/// The input is clipped to -1.0 to 1.0.
+///
+/// The even harmonics come with a DC offset for inputs below full scale,
+/// put a DC blocker behind the shaper if you use them.
+///
+///```
+/// use synfx_dsp::ChebyshevShaper;
+///
+/// let mut cheb = ChebyshevShaper::new();
+/// cheb.set_weights(&[0.5, 0.0, 0.25, 0.0, 0.25]);
+///
+/// // The fundamental plus the 3rd and 5th harmonic, the weights already sum up to 1.0:
+/// for i in 0..100 {
+///     let x = i as f32 * std::f32::consts::TAU / 100.0;
+///     let expected = 0.5 * x.cos() + 0.25 * (3.0 * x).cos() + 0.25 * (5.0 * x).cos();
+///     assert!((cheb.process(x.cos()) - expected).abs() < 0.0001);
+/// }
+///
+/// // The weights are relative, this mixes the fundamental and the 3rd harmonic at 0.5 each:
+/// cheb.set_weights(&[1.0, 0.0, 1.0]);
+/// let x = 0.3_f32;
+/// let expected = 0.5 * x.cos() + 0.5 * (3.0 * x).cos();
+/// assert!((cheb.process(x.cos()) - expected).abs() < 0.0001);
+///
+/// // The output is normalized:
+/// cheb.set_weights(&[1.0, -1.0, 1.0, 0.5]);
+/// assert!((0..=100).all(|i| cheb.process(i as f32 * 0.04 - 2.0).abs() <= 1.0001));
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct ChebyshevShaper {
+    weights: [f32; CHEBYSHEV_MAX_HARMONIC],
+    /// The normalization gain, the inverse of the sum of the absolute weights.
+    norm: f32,
+}
+
+impl ChebyshevShaper {
+    /// Creates a shaper, that passes the input unchanged (only T1 has a weight of 1.0).
+    pub fn new() -> Self {
+        let mut weights = [0.0; CHEBYSHEV_MAX_HARMONIC];
+        weights[0] = 1.0;
+        Self { weights, norm: 1.0 }
+    }
+
+    /// Sets the weight of one harmonic, from 1 to [CHEBYSHEV_MAX_HARMONIC].
+    /// Other harmonics are ignored.
+    pub fn set_weight(&mut self, harmonic: usize, weight: f32) {
+        if (1..=CHEBYSHEV_MAX_HARMONIC).contains(&harmonic) {
+            self.weights[harmonic - 1] = weight;
+            self.update_norm();
+        }
+    }
+
+    /// Sets the weights of the harmonics, starting with the fundamental.
+    /// Missing weights are set to 0.0, more than [CHEBYSHEV_MAX_HARMONIC] are ignored.
+    pub fn set_weights(&mut self, weights: &[f32]) {
+        for (i, w) in self.weights.iter_mut().enumerate() {
+            *w = weights.get(i).copied().unwrap_or(0.0);
+        }
+        self.update_norm();
+    }
+
+    /// Returns the weight of the `harmonic`, from 1 to [CHEBYSHEV_MAX_HARMONIC].
+    pub fn weight(&self, harmonic: usize) -> f32 {
+        if (1..=CHEBYSHEV_MAX_HARMONIC).contains(&harmonic) {
+            self.weights[harmonic - 1]
+        } else {
+            0.0
+        }
+    }
+
+    fn update_norm(&mut self) {
+        let sum: f32 = self.weights.iter().map(|w| w.abs()).sum();
+        self.norm = if sum > 0.0 { 1.0 / sum } else { 0.0 };
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let x = input.clamp(-1.0, 1.0);
+
+        // The recurrence T(n + 1) = 2x * T(n) - T(n - 1):
+        let (mut t_prev, mut t) = (1.0, x);
+        let mut out = 0.0;
+        for w in self.weights.iter() {
+            out += w * t;
+            let t_next = 2.0 * x * t - t_prev;
+            t_prev = t;
+            t = t_next;
+        }
+
+        out * self.norm
+    }
+}
+
+impl Default for ChebyshevShaper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The polyBLAMP residual of a corner in the output of a piecewise linear wave shaper,
+/// which is crossed by the input between two samples. Returns the corrections for the
+/// sample before and the sample after the corner.
+///
+/// * `x0`, `x1` - The previous and the current input sample.
+/// * `corner` - The input value, where the slope of the wave shaper changes.
+/// * `jump` - The slope of the wave shaper above the corner minus the slope below it.
+///
+/// The input is approximated as a straight line between the samples, the output slope
+/// then changes by `jump * |x1 - x0|` per sample at the crossing, which is smoothed by the
+/// two sample polyBLAMP (see also [crate::poly_blamp]).
+/// See also: Esqueda, Välimäki, Bilbao: "Aliasing Reduction in Soft-Clipping
+/// Algorithms" (EUSIPCO 2015) and "Rounding Corners with BLAMP" (DAFx-16).
+#[inline]
+fn blamp_corner(x0: f32, x1: f32, corner: f32, jump: f32) -> (f32, f32) {
+    if (x0 < corner) == (x1 < corner) || x0 == x1 {
+        return (0.0, 0.0);
+    }
+
+    let slope = x1 - x0;
+    // The fraction of the sample, at which the input crosses the corner:
+    let d = (corner - x0) / slope;
+    let change = jump * slope.abs() / 6.0;
+    let before = 1.0 - d;
+    (change * before * before * before, change * d * d * d)
+}
+
+/// The corners of a hard clipper at `limit`, as input value and jump of the slope.
+#[inline]
+fn blamp_hard_clip_corners(limit: f32) -> [(f32, f32); 2] {
+    [(-limit, 1.0), (limit, -1.0)]
+}
+
+/// The corner of a half-wave rectifier.
+const BLAMP_HALF_RECTIFY_CORNERS: [(f32, f32); 1] = [(0.0, 1.0)];
+/// The corner of a full-wave rectifier.
+const BLAMP_FULL_RECTIFY_CORNERS: [(f32, f32); 1] = [(0.0, 2.0)];
+
+/// Applies the piecewise linear wave shaper `f` with the `corners` to a whole block.
+fn blamp_block(input: &[f32], output: &mut [f32], f: impl Fn(f32) -> f32, corners: &[(f32, f32)]) {
+    let len = input.len().min(output.len());
+    for (o, i) in output.iter_mut().zip(input.iter()) {
+        *o = f(*i);
+    }
+
+    for n in 1..len {
+        for (corner, jump) in corners {
+            let (before, after) = blamp_corner(input[n - 1], input[n], *corner, *jump);
+            output[n - 1] += before;
+            output[n] += after;
+        }
+    }
+}
+
+/// Hard clips a block of samples to the range `-limit` to `limit`, with the corners
+/// band limited by polyBLAMP residuals. This aliases a lot less than a plain clamp,
+/// at about the cost of a plain clamp. Only `min(input.len(), output.len())` samples
+/// are processed. For processing a continuous stream sample by sample, use [BlampHardClip].
+///
+///```
+/// use synfx_dsp::blamp_hard_clip;
+///
+/// let input: Vec<f32> = (0..100).map(|i| (i as f32 * 0.3).sin() * 2.0).collect();
+/// let mut output = vec![0.0; 100];
+/// blamp_hard_clip(&input[..], &mut output[..], 1.0);
+///
+/// // The corrections only round the corners:
+/// for (i, o) in input.iter().zip(output.iter()) {
+///     assert!((o - i.clamp(-1.0, 1.0)).abs() < 0.1);
+/// }
+///```
+pub fn blamp_hard_clip(input: &[f32], output: &mut [f32], limit: f32) {
+    let limit = limit.abs();
+    blamp_block(input, output, |x| x.clamp(-limit, limit), &blamp_hard_clip_corners(limit));
+}
+
+/// Half-wave rectifies a block of samples (negative samples become 0.0), with the corner
+/// band limited by polyBLAMP residuals. Only `min(input.len(), output.len())` samples
+/// are processed. For processing a continuous stream, use [BlampHalfRectifier].
+pub fn blamp_half_rectify(input: &[f32], output: &mut [f32]) {
+    blamp_block(input, output, |x| x.max(0.0), &BLAMP_HALF_RECTIFY_CORNERS);
+}
+
+/// Full-wave rectifies a block of samples (the absolute value), with the corner
+/// band limited by polyBLAMP residuals. Only `min(input.len(), output.len())` samples
+/// are processed. For processing a continuous stream, use [BlampFullRectifier].
+pub fn blamp_full_rectify(input: &[f32], output: &mut [f32]) {
+    blamp_block(input, output, |x| x.abs(), &BLAMP_FULL_RECTIFY_CORNERS);
+}
+
+/// The shared state of the sample by sample polyBLAMP wave shapers.
+/// The correction of a corner reaches one sample back, so the output is delayed by one sample.
+#[derive(Debug, Clone, Copy)]
+struct BlampState {
+    x1: f32,
+    /// The correction of the sample, that is returned by the next call.
+    next: f32,
+}
+
+impl BlampState {
+    fn new() -> Self {
+        Self { x1: 0.0, next: 0.0 }
+    }
+
+    fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.next = 0.0;
+    }
+
+    #[inline]
+    fn process(&mut self, x: f32, f: impl Fn(f32) -> f32, corners: &[(f32, f32)]) -> f32 {
+        let mut out = f(self.x1) + self.next;
+        self.next = 0.0;
+        for (corner, jump) in corners {
+            let (before, after) = blamp_corner(self.x1, x, *corner, *jump);
+            out += before;
+            self.next += after;
+        }
+        self.x1 = x;
+        out
+    }
+}
+
+/// A hard clipper (to the range `-limit` to `limit`) with polyBLAMP anti-aliasing.
+/// Cheaper than [AdaaHardClip] and without its high frequency damping, but the
+/// output is delayed by one sample. See also [blamp_hard_clip] for whole blocks.
+///
+///```
+/// use synfx_dsp::BlampHardClip;
+///
+/// let mut clip = BlampHardClip::new();
+/// clip.set_limit(0.5);
+///
+/// let mut out = 0.0;
+/// for _ in 0..10 {
+///     out = clip.process(2.0);
+/// }
+/// assert_eq!(out, 0.5);
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct BlampHardClip {
+    state: BlampState,
+    limit: f32,
+}
+
+impl BlampHardClip {
+    /// Creates a new hard clipper with a limit of 1.0.
+    pub fn new() -> Self {
+        Self { state: BlampState::new(), limit: 1.0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    /// Sets the level, at which the input is clipped.
+    pub fn set_limit(&mut self, limit: f32) {
+        self.limit = limit.abs();
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let limit = self.limit;
+        self.state.process(input, |x| x.clamp(-limit, limit), &blamp_hard_clip_corners(limit))
+    }
+}
+
+impl Default for BlampHardClip {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A half-wave rectifier (negative samples become 0.0) with polyBLAMP anti-aliasing,
+/// for example for envelope followers or wave folders running at audio rate.
+/// The output is delayed by one sample. See also [blamp_half_rectify] for whole blocks.
+#[derive(Debug, Clone, Copy)]
+pub struct BlampHalfRectifier {
+    state: BlampState,
+}
+
+impl BlampHalfRectifier {
+    pub fn new() -> Self {
+        Self { state: BlampState::new() }
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.state.process(input, |x| x.max(0.0), &BLAMP_HALF_RECTIFY_CORNERS)
+    }
+}
+
+impl Default for BlampHalfRectifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A full-wave rectifier (the absolute value) with polyBLAMP anti-aliasing,
+/// for example for octave up effects or envelope followers running at audio rate.
+/// The output is delayed by one sample. See also [blamp_full_rectify] for whole blocks.
+///
+///```
+/// use synfx_dsp::{blamp_full_rectify, BlampFullRectifier};
+///
+/// let input: Vec<f32> = (0..200).map(|i| (i as f32 * 0.37).sin()).collect();
+/// let mut block = vec![0.0; 200];
+/// blamp_full_rectify(&input[..], &mut block[..]);
+///
+/// // Sample by sample processing gives the same, just one sample later:
+/// let mut rect = BlampFullRectifier::new();
+/// let stream: Vec<f32> = input.iter().map(|s| rect.process(*s)).collect();
+/// for (b, s) in block.iter().zip(stream[1..].iter()) {
+///     assert!((b - s).abs() < 1e-6);
+/// }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct BlampFullRectifier {
+    state: BlampState,
+}
+
+impl BlampFullRectifier {
+    pub fn new() -> Self {
+        Self { state: BlampState::new() }
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.state.process(input, |x| x.abs(), &BLAMP_FULL_RECTIFY_CORNERS)
+    }
+}
+
+impl Default for BlampFullRectifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
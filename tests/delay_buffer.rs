@@ -252,3 +252,56 @@ fn check_delaybuffer_cubic_interpolation() {
         ]
     );
 }
+
+#[test]
+fn check_delaybuffer_sinc_interpolation() {
+    let table = synfx_dsp::SincTable::new(16, 128);
+    let mut buf = synfx_dsp::DelayBuffer::new();
+
+    for i in 0..20 {
+        buf.feed(i as f32 * 0.1);
+    }
+
+    // Integer offsets return the samples exactly:
+    let mut samples_out = vec![];
+    for offs in 7..12 {
+        samples_out.push(buf.sinc_interpolate_at_s(offs as f32, &table));
+    }
+    assert_vec_feq!(samples_out, vec![1.2, 1.1, 1.0, 0.9, 0.8]);
+
+    // On a ramp the interpolated value lies in between:
+    let v = buf.sinc_interpolate_at_s(9.5, &table);
+    assert!((v - 0.95).abs() < 0.001);
+}
+
+#[test]
+fn check_delaybuffer_allpass_interpolation() {
+    let mut buf = synfx_dsp::DelayBuffer::new_with_size(64);
+    let mut tap = synfx_dsp::AllpassTap::new();
+
+    // An impulse keeps its energy and arrives around the delay time:
+    let mut samples_out = vec![];
+    for i in 0..60 {
+        samples_out.push(buf.next_allpass(
+            3.5 * 1000.0 / 44100.0,
+            if i == 0 { 1.0 } else { 0.0 },
+            &mut tap,
+        ));
+    }
+
+    let energy: f32 = samples_out.iter().map(|s| s * s).sum();
+    assert!((energy - 1.0).abs() < 0.001);
+
+    let peak = (0..samples_out.len())
+        .max_by(|a, b| samples_out[*a].abs().total_cmp(&samples_out[*b].abs()))
+        .unwrap();
+    assert!(peak == 4 || peak == 5);
+
+    // Integer offsets with a fractional part of 1.0 are exact:
+    buf.reset();
+    tap.reset();
+    for i in 0..20 {
+        buf.feed(i as f32 * 0.1);
+    }
+    assert!((buf.allpass_interpolate_at_s(9.0, &mut tap) - 1.0).abs() < 0.0001);
+}
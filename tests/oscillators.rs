@@ -0,0 +1,104 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use synfx_dsp::{assert_fft_peak_at, assert_spectrum_feq, fft_magnitudes, spectrum_level_db};
+
+fn run_saw(freq: f32, samples: usize) -> Vec<f32> {
+    let mut osc = synfx_dsp::PolyBlepOscillator::new(0.0);
+    (0..samples).map(|_| osc.next_saw(freq, 1.0 / 44100.0)).collect()
+}
+
+#[test]
+fn check_polyblep_saw_harmonics() {
+    let out = run_saw(441.0, 8192);
+
+    assert_fft_peak_at!(out, 44100.0, 441.0, 6.0);
+
+    // The harmonics of a saw fall with 1/n:
+    let harmonic_db = |n: f32| 20.0 * (2.0 / (std::f32::consts::PI * n)).log10();
+    assert_spectrum_feq!(
+        out,
+        44100.0,
+        [(441.0, harmonic_db(1.0)), (882.0, harmonic_db(2.0)), (1764.0, harmonic_db(4.0))],
+        0.3
+    );
+}
+
+#[test]
+fn check_polyblep_saw_aliasing() {
+    let out = run_saw(4000.0, 8192);
+
+    // The 7th harmonic (28kHz) is mirrored down to 16.1kHz,
+    // a naive saw has it at about -21dB:
+    assert_spectrum_feq!(out, 44100.0, [(16100.0, -200.0)], 170.0);
+
+    let mags = fft_magnitudes(&out);
+    let fundamental = spectrum_level_db(&mags, 44100.0, 4000.0);
+    let alias = spectrum_level_db(&mags, 44100.0, 16100.0);
+    assert!(fundamental - alias > 25.0);
+}
+
+fn run_tri_blamp(freq: f32, samples: usize) -> Vec<f32> {
+    let mut osc = synfx_dsp::PolyBlepOscillator::new(0.0);
+    (0..samples).map(|_| osc.next_tri_blamp(freq, 1.0 / 44100.0)).collect()
+}
+
+#[test]
+fn check_polyblamp_tri_harmonics() {
+    let out = run_tri_blamp(441.0, 8192);
+
+    // The odd harmonics of a triangle fall with 1/n^2:
+    let harmonic_db =
+        |n: f32| 20.0 * (8.0 / (std::f32::consts::PI * std::f32::consts::PI * n * n)).log10();
+    assert_spectrum_feq!(
+        out,
+        44100.0,
+        [(441.0, harmonic_db(1.0)), (1323.0, harmonic_db(3.0)), (2205.0, harmonic_db(5.0))],
+        0.3
+    );
+}
+
+#[test]
+fn check_polyblamp_tri_aliasing() {
+    let out = run_tri_blamp(4000.0, 8192);
+
+    // The 7th harmonic (28kHz) is mirrored down to 16.1kHz,
+    // a naive triangle has it at about 34dB below the fundamental:
+    let mags = fft_magnitudes(&out);
+    let fundamental = spectrum_level_db(&mags, 44100.0, 4000.0);
+    let alias = spectrum_level_db(&mags, 44100.0, 16100.0);
+    assert!(fundamental - alias > 45.0);
+}
+
+fn run_blit_saw(freq: f32, samples: usize) -> Vec<f32> {
+    let mut osc = synfx_dsp::BlitOscillator::new(0.0);
+    (0..samples).map(|_| osc.next(freq, 1.0 / 44100.0).saw).collect()
+}
+
+#[test]
+fn check_blit_saw_harmonics() {
+    let out = run_blit_saw(441.0, 8192);
+
+    assert_fft_peak_at!(out, 44100.0, 441.0, 6.0);
+
+    let harmonic_db = |n: f32| 20.0 * (2.0 / (std::f32::consts::PI * n)).log10();
+    assert_spectrum_feq!(
+        out,
+        44100.0,
+        [(441.0, harmonic_db(1.0)), (882.0, harmonic_db(2.0)), (1764.0, harmonic_db(4.0))],
+        0.3
+    );
+}
+
+#[test]
+fn check_blit_saw_aliasing() {
+    let out = run_blit_saw(4000.0, 8192);
+
+    // The windowed sinc impulses suppress the alias at 16.1kHz far more than
+    // the polyblep saw does:
+    let mags = fft_magnitudes(&out);
+    let fundamental = spectrum_level_db(&mags, 44100.0, 4000.0);
+    let alias = spectrum_level_db(&mags, 44100.0, 16100.0);
+    assert!(fundamental - alias > 45.0);
+}
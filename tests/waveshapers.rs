@@ -0,0 +1,79 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of synfx-dsp. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use synfx_dsp::{fft_magnitudes, spectrum_level_db};
+
+fn sine(freq: f64, amp: f32, samples: usize) -> Vec<f32> {
+    (0..samples)
+        .map(|i| amp * (std::f64::consts::TAU * freq * i as f64 / 44100.0).sin() as f32)
+        .collect()
+}
+
+/// Returns the level of the fundamental over the level of the `alias` frequency in dB.
+fn alias_rejection_db(out: &[f32], fundamental: f32, alias: f32) -> f32 {
+    let mags = fft_magnitudes(out);
+    spectrum_level_db(&mags, 44100.0, fundamental) - spectrum_level_db(&mags, 44100.0, alias)
+}
+
+#[test]
+fn check_blamp_full_rectify_aliasing() {
+    let input = sine(4000.0, 1.0, 8192);
+    let naive: Vec<f32> = input.iter().map(|s| s.abs()).collect();
+    let mut out = vec![0.0; 8192];
+    synfx_dsp::blamp_full_rectify(&input[..], &mut out[..]);
+
+    // The 10th harmonic (40kHz) is mirrored down to 4.1kHz, the 2nd harmonic
+    // is the strongest one of a full-wave rectified sine:
+    let naive_rej = alias_rejection_db(&naive[..], 8000.0, 4100.0);
+    let blamp_rej = alias_rejection_db(&out[..], 8000.0, 4100.0);
+    assert!(naive_rej < 35.0, "naive={}", naive_rej);
+    assert!(blamp_rej > naive_rej + 15.0, "naive={} blamp={}", naive_rej, blamp_rej);
+}
+
+#[test]
+fn check_blamp_half_rectify_aliasing() {
+    let input = sine(4000.0, 1.0, 8192);
+    let naive: Vec<f32> = input.iter().map(|s| s.max(0.0)).collect();
+    let mut out = vec![0.0; 8192];
+    synfx_dsp::blamp_half_rectify(&input[..], &mut out[..]);
+
+    let naive_rej = alias_rejection_db(&naive[..], 4000.0, 4100.0);
+    let blamp_rej = alias_rejection_db(&out[..], 4000.0, 4100.0);
+    assert!(blamp_rej > naive_rej + 15.0, "naive={} blamp={}", naive_rej, blamp_rej);
+}
+
+#[test]
+fn check_blamp_hard_clip_aliasing() {
+    let input = sine(4000.0, 2.0, 8192);
+    let naive: Vec<f32> = input.iter().map(|s| s.clamp(-1.0, 1.0)).collect();
+
+    let mut clip = synfx_dsp::BlampHardClip::new();
+    let out: Vec<f32> = input.iter().map(|s| clip.process(*s)).collect();
+
+    // The 7th harmonic (28kHz) is mirrored down to 16.1kHz,
+    // the 9th harmonic (36kHz) down to 8.1kHz:
+    for alias in [16100.0, 8100.0] {
+        let naive_rej = alias_rejection_db(&naive[..], 4000.0, alias);
+        let blamp_rej = alias_rejection_db(&out[..], 4000.0, alias);
+        assert!(blamp_rej > naive_rej + 12.0, "naive={} blamp={}", naive_rej, blamp_rej);
+    }
+}
+
+#[test]
+fn check_soft_clipper_dc_block_asym_curve() {
+    let input = sine(100.0, 1.0, 44100);
+
+    let mut clip = synfx_dsp::SoftClipper::new();
+    clip.set_curve(synfx_dsp::SoftClipCurve::AsymTanh);
+    clip.set_drive(4.0);
+    let out: Vec<f32> = input.iter().map(|s| clip.process(*s)).collect();
+    let mean = out[22050..].iter().sum::<f32>() / 22050.0;
+    assert!(mean > 0.1, "mean={}", mean);
+
+    clip.set_sample_rate(44100.0);
+    clip.set_dc_block(true);
+    let out: Vec<f32> = input.iter().map(|s| clip.process(*s)).collect();
+    let mean = out[22050..].iter().sum::<f32>() / 22050.0;
+    assert!(mean.abs() < 0.01, "mean={}", mean);
+}